@@ -0,0 +1,354 @@
+//! # Control Flow Graph
+//!
+//! This module builds the control flow graph of a Baby program, with each
+//! node a basic block, a run of words that always execute one after another,
+//! and each edge a way execution can move from the end of one block to the
+//! start of another.
+//!
+//! The main part of this module is [ControlFlowGraph][crate::analysis::cfg::ControlFlowGraph],
+//! built from a main store with [ControlFlowGraph::from_store][crate::analysis::cfg::ControlFlowGraph::from_store]
+//! or from [LinkerData] with [ControlFlowGraph::from_linker_data][crate::analysis::cfg::ControlFlowGraph::from_linker_data],
+//! and exported to Graphviz DOT with [ControlFlowGraph::to_dot][crate::analysis::cfg::ControlFlowGraph::to_dot].
+//!
+//! The Baby's jumps and stores are all indirect, `jmp` and `jrp` take their
+//! target from the word at their operand, and `sto` stores to the address
+//! held in the word at its operand, these are followed using the words'
+//! values before the program runs, see [successors][crate::analysis::cfg::successors].
+//! Anywhere this may not be what happens once the program runs is reported
+//! as a [Finding][crate::analysis::cfg::Finding], along with any words that
+//! are never used at all.
+//!
+//! # Example
+//! ```
+//! use baby_emulator::assembler::assemble;
+//! use baby_emulator::analysis::cfg::{ControlFlowGraph, EdgeKind};
+//!
+//! let asm = ":loop\nldn $x\ncmp\nstp\njmp $to_loop\n:to_loop\nabs $loop\n:x\nabs 0d1";
+//! let cfg = ControlFlowGraph::from_linker_data(&assemble(asm, false).ok().unwrap());
+//! assert_eq!(cfg.blocks.len(), 3);
+//! assert_eq!(cfg.successors(0).len(), 2);
+//! assert_eq!(cfg.successors(3)[0].kind, EdgeKind::Jump);
+//! assert!(cfg.to_dot().contains("b3 -> b0 [label=\"jump\"];"));
+//! ```
+//!
+
+use std::collections::{BTreeSet, HashMap};
+use crate::core::{MEMORY_WORDS, WORD, instructions::BabyInstruction};
+use crate::assembler::disassembler::{tag_names, with_tag};
+use crate::assembler::linker::LinkerData;
+use crate::assembler::parser::LineType;
+use super::decode;
+
+
+#[cfg(test)]
+mod tests;
+
+
+/// The ways execution can move from one word to another.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EdgeKind {
+    /// On to the next word.
+    Fallthrough,
+    /// Past the next word, from a test when the accumulator is negative.
+    Skip,
+    /// To the target of an absolute or relative jump.
+    Jump,
+}
+
+impl EdgeKind {
+
+    /// Returns the name of the edge kind, as used to label DOT edges.
+    pub fn name(&self) -> &'static str {
+        match self {
+            EdgeKind::Fallthrough => "fallthrough",
+            EdgeKind::Skip => "skip",
+            EdgeKind::Jump => "jump",
+        }
+    }
+}
+
+/// An edge between two basic blocks.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Edge {
+    /// The address of the first word of the block execution moves from.
+    pub from: usize,
+    /// The address of the first word of the block execution moves to.
+    pub to: usize,
+    /// How execution moves along the edge.
+    pub kind: EdgeKind,
+}
+
+/// A run of words that always execute one after another, only entered at
+/// the first word and only left from the last.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BasicBlock {
+    /// The address of the first word.
+    pub start: usize,
+    /// The address of the last word.
+    pub end: usize,
+}
+
+impl BasicBlock {
+
+    /// Returns true if the block contains an address.
+    pub fn contains(&self, addr: usize) -> bool {
+        (self.start..=self.end).contains(&addr)
+    }
+}
+
+/// Something found building a control flow graph that may be a mistake,
+/// or that means the graph may not be what happens once the program runs.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Finding {
+    /// A word of the program that is never executed, read or written (address).
+    Unreachable(usize),
+    /// A word that is both executed and written to by the program (address).
+    SelfModifying(usize),
+    /// A jump through a word the program writes to, so it may jump somewhere
+    /// other than the graph shows (jump address, operand address).
+    ModifiedJump(usize, usize),
+    /// A store through a word the program writes to, so it may store anywhere
+    /// (store address, operand address).
+    ModifiedStore(usize, usize),
+}
+
+impl Finding {
+
+    /// Returns a short string describing the finding.
+    pub fn describe(&self) -> String {
+        match self {
+            Finding::Unreachable(a) => format!("The word at `{}` is never executed, read or written. ", a),
+            Finding::SelfModifying(a) => format!("The word at `{}` is executed and also written to by the program. ", a),
+            Finding::ModifiedJump(a, o) => format!(
+                "The jump at `{}` is through the word at `{}`, which the program writes to, so may jump elsewhere. ", a, o
+            ),
+            Finding::ModifiedStore(a, o) => format!(
+                "The store at `{}` is through the word at `{}`, which the program writes to, so may store anywhere. ", a, o
+            ),
+        }
+    }
+}
+
+/// The control flow graph of a program.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ControlFlowGraph {
+    /// The basic blocks, in the order of their first word.
+    pub blocks: Vec<BasicBlock>,
+    /// The edges between the blocks, in the order of the blocks they leave.
+    pub edges: Vec<Edge>,
+    /// The addresses of every word that can be executed.
+    pub executed: BTreeSet<usize>,
+    /// The addresses of every word that can be written to.
+    pub written: BTreeSet<usize>,
+    /// Everything found building the graph, in the order of the addresses.
+    pub findings: Vec<Finding>,
+    store: [WORD; MEMORY_WORDS],
+    names: HashMap<usize, Vec<String>>,
+}
+
+impl ControlFlowGraph {
+
+    /// Builds the control flow graph of a main store.
+    ///
+    /// Only words up to the last word that is not zero, or has a tag, are
+    /// checked for [Finding::Unreachable].
+    ///
+    /// # Parameters
+    /// * `store` - The main store.
+    /// * `tags` - Tag names and values to label the graph with.
+    ///
+    pub fn from_store(store: &[WORD; MEMORY_WORDS], tags: &HashMap<String, WORD>) -> ControlFlowGraph {
+        let names = tag_names(tags);
+        let len = store.iter().rposition(|w| *w != 0)
+            .into_iter()
+            .chain(names.keys().cloned())
+            .max()
+            .map_or(0, |v| v + 1);
+        Self::build(store, len, names)
+    }
+
+    /// Builds the control flow graph of a linked program, labelled with
+    /// its tags.
+    ///
+    /// # Parameters
+    /// * `data` - The linked program and its tags.
+    ///
+    pub fn from_linker_data(data: &LinkerData) -> ControlFlowGraph {
+        let LinkerData(program, tags) = data;
+        let store = BabyInstruction::to_numbers(program.clone());
+        Self::build(&store, program.len().min(MEMORY_WORDS), tag_names(tags))
+    }
+
+    /// Builds the graph, checking words up to `len` for [Finding::Unreachable].
+    fn build(store: &[WORD; MEMORY_WORDS], len: usize, names: HashMap<usize, Vec<String>>) -> ControlFlowGraph {
+        let mut executed = BTreeSet::new();
+        let mut word_edges = vec![];
+        let mut pending = vec![0usize];
+        while let Some(addr) = pending.pop() {
+            if !executed.insert(addr) { continue; }
+            for (to, kind) in successors(store, addr) {
+                word_edges.push((addr, to, kind));
+                pending.push(to);
+            }
+        }
+
+        let mut accessed = BTreeSet::new();
+        let mut written = BTreeSet::new();
+        for addr in &executed {
+            match decode(store[*addr]) {
+                BabyInstruction::Store(o) => {
+                    accessed.insert(o as usize);
+                    written.insert(store[o as usize] as usize & 0x1F);
+                },
+                BabyInstruction::Jump(o) | BabyInstruction::RelativeJump(o) |
+                BabyInstruction::Negate(o) | BabyInstruction::Subtract(o) => { accessed.insert(o as usize); },
+                _ => (),
+            }
+        }
+
+        let mut findings = vec![];
+        for (addr, word) in store.iter().enumerate() {
+            let is_executed = executed.contains(&addr);
+            if addr < len && !is_executed && !accessed.contains(&addr) && !written.contains(&addr) {
+                findings.push(Finding::Unreachable(addr));
+            }
+            if is_executed && written.contains(&addr) {
+                findings.push(Finding::SelfModifying(addr));
+            }
+            match decode(*word) {
+                BabyInstruction::Jump(o) | BabyInstruction::RelativeJump(o) if is_executed && written.contains(&(o as usize)) =>
+                    findings.push(Finding::ModifiedJump(addr, o as usize)),
+                BabyInstruction::Store(o) if is_executed && written.contains(&(o as usize)) =>
+                    findings.push(Finding::ModifiedStore(addr, o as usize)),
+                _ => (),
+            }
+        }
+
+        let ends_block = |a: usize| matches!(
+            decode(store[a]),
+            BabyInstruction::Jump(_) | BabyInstruction::RelativeJump(_) | BabyInstruction::SkipNextIfNegative | BabyInstruction::Stop
+        );
+        let leaders: BTreeSet<usize> = executed.iter()
+            .filter(|a| **a == 0 || word_edges.iter().any(|(f, t, _)| t == *a && ends_block(*f)))
+            .cloned()
+            .collect();
+        let blocks: Vec<BasicBlock> = leaders.iter()
+            .map(|start| {
+                let mut end = *start;
+                while !ends_block(end) && !leaders.contains(&((end + 1) % MEMORY_WORDS)) { end += 1; }
+                BasicBlock { start: *start, end }
+            })
+            .collect();
+        let edges = blocks.iter()
+            .flat_map(|b| word_edges.iter()
+                .filter(|(f, _, _)| *f == b.end)
+                .map(|(_, to, kind)| Edge { from: b.start, to: *to, kind: *kind }))
+            .collect();
+
+        ControlFlowGraph { blocks, edges, executed, written, findings, store: *store, names }
+    }
+
+    /// Returns the block containing an address, if it is executed.
+    ///
+    /// # Parameters
+    /// * `addr` - The address.
+    ///
+    pub fn block_at(&self, addr: usize) -> Option<&BasicBlock> {
+        self.blocks.iter().find(|b| b.contains(addr))
+    }
+
+    /// Returns the edges leaving the block starting at an address.
+    ///
+    /// # Parameters
+    /// * `start` - The address of the first word of the block.
+    ///
+    pub fn successors(&self, start: usize) -> Vec<&Edge> {
+        self.edges.iter().filter(|e| e.from == start).collect()
+    }
+
+    /// Exports the graph to Graphviz DOT.
+    ///
+    /// Each block is a node listing its words as modern notation asm,
+    /// with tag names for tag declarations and operands, blocks with a
+    /// word in a finding are outlined in red, and the findings are listed
+    /// in comments at the top.
+    pub fn to_dot(&self) -> String {
+        let mut dot: String = self.findings.iter()
+            .map(|f| format!("// {}\n", f.describe().trim_end()))
+            .collect();
+        dot.push_str("digraph program {\n    node [shape=box, fontname=\"monospace\"];\n");
+        let flagged: BTreeSet<usize> = self.findings.iter()
+            .filter_map(|f| match f {
+                Finding::SelfModifying(a) | Finding::ModifiedJump(a, _) | Finding::ModifiedStore(a, _) => Some(*a),
+                Finding::Unreachable(_) => None,
+            })
+            .collect();
+        for block in &self.blocks {
+            let label: String = (block.start..=block.end)
+                .map(|a| {
+                    let tags: String = self.names.get(&a).into_iter().flatten()
+                        .map(|t| format!(":{}\\l", escape(t)))
+                        .collect();
+                    let instr = LineType::Instruction(with_tag(decode(self.store[a]), &self.names));
+                    format!("{}{:02}  {}\\l", tags, a, escape(&instr.to_asm_string(false)))
+                })
+                .collect();
+            let colour = if flagged.iter().any(|a| block.contains(*a)) { ", color=red" } else { "" };
+            dot.push_str(&format!("    b{} [label=\"{}\"{}];\n", block.start, label, colour));
+        }
+        for edge in &self.edges {
+            let label = match edge.kind {
+                EdgeKind::Fallthrough => String::new(),
+                k => format!(" [label=\"{}\"]", k.name()),
+            };
+            dot.push_str(&format!("    b{} -> b{}{};\n", edge.from, edge.to, label));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Returns where execution can move to after the word at an address, and
+/// how, taking jump targets from the words' current values.
+///
+/// A stop has nowhere to go, a test can go on or skip the next word, and
+/// a jump goes to its target, which for an absolute jump is the value of
+/// the word at its operand, and for a relative jump is its own address
+/// plus that value, each kept within the store as the emulator does.
+///
+/// # Parameters
+/// * `store` - The main store.
+/// * `addr` - The address of the word.
+///
+/// # Example
+/// ```
+/// use baby_emulator::analysis::cfg::{successors, EdgeKind};
+/// use baby_emulator::core::instructions::BabyInstruction;
+///
+/// let store = BabyInstruction::to_numbers(vec![
+///     BabyInstruction::SkipNextIfNegative,
+///     BabyInstruction::Jump(3),
+///     BabyInstruction::Stop,
+///     BabyInstruction::AbsoluteValue(34),
+/// ]);
+/// assert_eq!(successors(&store, 0), vec![(1, EdgeKind::Fallthrough), (2, EdgeKind::Skip)]);
+/// assert_eq!(successors(&store, 1), vec![(2, EdgeKind::Jump)]);
+/// assert_eq!(successors(&store, 2), vec![]);
+/// ```
+///
+pub fn successors(store: &[WORD; MEMORY_WORDS], addr: usize) -> Vec<(usize, EdgeKind)> {
+    let next = |n: usize| (addr + n) % MEMORY_WORDS;
+    match decode(store[addr]) {
+        BabyInstruction::Stop | BabyInstruction::AbsoluteValue(_) => vec![],
+        BabyInstruction::Jump(o) => vec![(store[o as usize] as usize & 0x1F, EdgeKind::Jump)],
+        BabyInstruction::RelativeJump(o) =>
+            vec![((addr as WORD).wrapping_add(store[o as usize]) as usize & 0x1F, EdgeKind::Jump)],
+        BabyInstruction::SkipNextIfNegative => vec![(next(1), EdgeKind::Fallthrough), (next(2), EdgeKind::Skip)],
+        _ => vec![(next(1), EdgeKind::Fallthrough)],
+    }
+}
+
+/// Escapes a string for a DOT quoted string.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
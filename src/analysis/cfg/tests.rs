@@ -0,0 +1,108 @@
+use super::*;
+use crate::assembler::assemble;
+
+
+fn cfg(asm: &str) -> ControlFlowGraph {
+    ControlFlowGraph::from_linker_data(&assemble(asm, false).ok().unwrap())
+}
+
+const COUNTDOWN: &str =
+":loop
+ldn $count
+sub $one
+sto $to_count
+ldn $count
+sub $one
+cmp
+jmp $to_end
+jmp $to_loop
+:end
+stp
+:to_loop
+abs $loop
+:to_end
+abs $end
+:to_count
+abs $count
+:count
+abs 0d3
+:one
+abs 0d1";
+
+#[test]
+fn test_blocks() {
+    let graph = cfg(COUNTDOWN);
+    assert_eq!(graph.blocks, vec![
+        BasicBlock { start: 0, end: 5 },
+        BasicBlock { start: 6, end: 6 },
+        BasicBlock { start: 7, end: 7 },
+        BasicBlock { start: 8, end: 8 },
+    ]);
+    assert_eq!(graph.edges, vec![
+        Edge { from: 0, to: 6, kind: EdgeKind::Fallthrough },
+        Edge { from: 0, to: 7, kind: EdgeKind::Skip },
+        Edge { from: 6, to: 8, kind: EdgeKind::Jump },
+        Edge { from: 7, to: 0, kind: EdgeKind::Jump },
+    ]);
+    assert_eq!(graph.executed, BTreeSet::from([0, 1, 2, 3, 4, 5, 6, 7, 8]));
+    assert_eq!(graph.written, BTreeSet::from([12]));
+    assert_eq!(graph.findings, vec![]);
+    assert_eq!(graph.block_at(3), Some(&graph.blocks[0]));
+    assert_eq!(graph.block_at(9), None);
+}
+
+#[test]
+fn test_jump_into_block() {
+    let graph = cfg("ldn $x\n:mid\nsub $x\nsub $x\ncmp\njmp $to_mid\nstp\n:to_mid\nabs $mid\n:x\nabs 0d1");
+    let starts: Vec<usize> = graph.blocks.iter().map(|b| b.start).collect();
+    assert_eq!(starts, vec![0, 1, 4, 5]);
+    assert_eq!(graph.successors(0), vec![&Edge { from: 0, to: 1, kind: EdgeKind::Fallthrough }]);
+    assert_eq!(graph.successors(4), vec![&Edge { from: 4, to: 1, kind: EdgeKind::Jump }]);
+}
+
+#[test]
+fn test_relative_jumps() {
+    let graph = cfg("jrp $offset\nstp\nstp\nldn $offset\nstp\n:offset\nabs 0d3");
+    assert_eq!(graph.blocks, vec![BasicBlock { start: 0, end: 0 }, BasicBlock { start: 3, end: 4 }]);
+    assert_eq!(graph.edges, vec![Edge { from: 0, to: 3, kind: EdgeKind::Jump }]);
+    assert_eq!(graph.findings, vec![Finding::Unreachable(1), Finding::Unreachable(2)]);
+}
+
+#[test]
+fn test_findings() {
+    let modifying = "ldn $x\nsto $to_patched\nsto $to_jump\n:patched\nsub $x\njmp $jump\n:end\nstp\n:x\nabs 0d1\n:to_patched\nabs $patched\n:to_jump\nabs $jump\n:jump\nabs $end\n:spare\nabs 0d9";
+    let graph = cfg(modifying);
+    assert_eq!(graph.findings, vec![
+        Finding::SelfModifying(3),
+        Finding::ModifiedJump(4, 9),
+        Finding::Unreachable(10),
+    ]);
+    assert!(graph.findings[1].describe().contains("`4`"));
+
+    let graph = cfg("sto $p\nsto $q\nstp\n:p\nabs $q\n:q\nabs 0d5");
+    assert_eq!(graph.findings, vec![Finding::ModifiedStore(1, 4)]);
+}
+
+#[test]
+fn test_from_store() {
+    let LinkerData(program, tags) = assemble(COUNTDOWN, false).ok().unwrap();
+    let store = BabyInstruction::to_numbers(program);
+    assert_eq!(ControlFlowGraph::from_store(&store, &tags), cfg(COUNTDOWN));
+    let untagged = ControlFlowGraph::from_store(&store, &HashMap::new());
+    assert_eq!(untagged.blocks.len(), 4);
+    assert!(untagged.to_dot().contains("00  ldn 0d12\\l"));
+}
+
+#[test]
+fn test_dot() {
+    let dot = cfg(COUNTDOWN).to_dot();
+    assert!(dot.starts_with("digraph program {\n"));
+    assert!(dot.contains("    b0 [label=\":loop\\l00  ldn $count\\l01  sub $one\\l"));
+    assert!(dot.contains("    b8 [label=\":end\\l08  stp\\l\"];\n"));
+    assert!(dot.contains("    b0 -> b6;\n    b0 -> b7 [label=\"skip\"];\n"));
+    assert!(dot.ends_with("}\n"));
+
+    let dot = cfg("ldn $x\nsto $p\n:x\nstp\n:p\nabs $x").to_dot();
+    assert!(dot.starts_with("// The word at `2` is executed and also written to by the program.\n"));
+    assert!(dot.contains("color=red"));
+}
@@ -0,0 +1,45 @@
+//! # Analysis
+//!
+//! This module contains static analyses of Baby programs, working on a main
+//! store, such as [BabyModel::main_store][crate::core::BabyModel::main_store],
+//! or on [LinkerData][crate::assembler::linker::LinkerData] from the assembler.
+//!
+//! Every word the Baby reaches is run as an instruction, whatever else it was
+//! meant for, so the analyses here decode words exactly as the emulator does,
+//! see [decode].
+//!
+
+use crate::core::{INSTR_MASK, WORD, instructions::BabyInstruction};
+
+
+/// Contains functionality for building the control flow graph of a program.
+pub mod cfg;
+/// Contains an abstract interpreter over the ranges of values a program uses.
+pub mod ranges;
+/// Contains a symbolic executor finding inputs that reach a target.
+pub mod symbolic;
+#[cfg(test)]
+mod tests;
+
+
+/// Decodes a word to the instruction the emulator would run if it reached
+/// the word.
+///
+/// Unlike [decode_instruction][crate::assembler::disassembler::decode_instruction],
+/// this never fails, any bits outside of the opcode and operand are ignored.
+///
+/// # Parameters
+/// * `word` - The word to decode.
+///
+/// # Example
+/// ```
+/// use baby_emulator::analysis::decode;
+/// use baby_emulator::core::instructions::BabyInstruction;
+///
+/// assert_eq!(decode(BabyInstruction::Negate(4).to_number()), BabyInstruction::Negate(4));
+/// assert_eq!(decode(5), BabyInstruction::Jump(5));
+/// ```
+///
+pub fn decode(word: WORD) -> BabyInstruction {
+    BabyInstruction::from_number(word as u16 & INSTR_MASK)
+}
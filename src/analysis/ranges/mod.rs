@@ -0,0 +1,475 @@
+//! # Ranges
+//!
+//! This module is an abstract interpreter for Baby programs, running a
+//! program with the range of values each word and the accumulator could
+//! hold in place of their values, so that a program can be checked for
+//! every value of its inputs at once.
+//!
+//! The main part of this module is [analyse][crate::analysis::ranges::analyse],
+//! which takes a main store, the ranges of any words that are inputs, and a
+//! step limit as would be passed to [BabyModel::run_loop][crate::core::BabyModel::run_loop],
+//! returning a [RangeAnalysis][crate::analysis::ranges::RangeAnalysis] with:
+//!
+//! * A [Prediction][crate::analysis::ranges::Prediction] of whether running the
+//!   program would go past the step limit, with the most steps any run can
+//!   take if not, making a sensible step limit for the program.
+//! * The most times the first word of each loop can be executed.
+//! * Any subtraction or negation that may overflow a [WORD].
+//!
+//! Where a test could go either way, both ways are followed, the accumulator's
+//! range narrowed to the values that would go each way, along with the ranges
+//! of any words the accumulator was loaded from or stored to, as long as it
+//! has only been changed by constants since. Each of these ways is a path, if
+//! the program has more than [MAX_PATHS] the analysis gives up. A path that
+//! jumps back to a state it has been in before will repeat itself forever, so
+//! is known to go past the step limit without following it that far.
+//!
+//! # Example
+//! ```
+//! use baby_emulator::assembler::{assemble, linker::LinkerData};
+//! use baby_emulator::analysis::ranges::{analyse, Interval, Prediction};
+//! use baby_emulator::core::instructions::BabyInstruction;
+//!
+//! let asm = "
+//! :loop
+//! ldn $count
+//! sto $to_negated
+//! ldn $negated
+//! sub $one
+//! sto $to_count
+//! cmp
+//! jmp $to_loop
+//! stp
+//! :to_loop
+//! abs $loop
+//! :to_negated
+//! abs $negated
+//! :to_count
+//! abs $count
+//! :negated
+//! abs 0d0
+//! :one
+//! abs 0d1
+//! :count
+//! abs 0d0
+//! ";
+//! let LinkerData(program, tags) = assemble(asm, false).ok().unwrap();
+//! let store = BabyInstruction::to_numbers(program);
+//! let inputs = [(tags["count"] as usize, Interval::new(0, 4))];
+//! let analysis = analyse(&store, &inputs, 100);
+//! assert_eq!(analysis.prediction, Prediction::Stops(35));
+//! assert_eq!(analysis.loops[0].iterations, Some(5));
+//! ```
+//!
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use crate::core::{MEMORY_WORDS, WORD, instructions::BabyInstruction};
+use super::decode;
+
+
+#[cfg(test)]
+mod tests;
+
+
+/// The most paths [analyse] follows before giving up.
+pub const MAX_PATHS: usize = 4096;
+
+/// The most states each path records to find when it repeats itself.
+const MAX_STATES: usize = 65_536;
+
+/// A range of values, from `min` to `max` inclusive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Interval {
+    /// The smallest value.
+    pub min: WORD,
+    /// The largest value.
+    pub max: WORD,
+}
+
+impl Interval {
+
+    /// Creates a new range, swapping the bounds if `min` is larger.
+    ///
+    /// # Parameters
+    /// * `min` - The smallest value.
+    /// * `max` - The largest value.
+    ///
+    pub fn new(min: WORD, max: WORD) -> Interval {
+        Interval { min: min.min(max), max: max.max(min) }
+    }
+
+    /// Creates a range of a single value.
+    pub fn constant(value: WORD) -> Interval {
+        Interval { min: value, max: value }
+    }
+
+    /// Creates a range of every value of a [WORD].
+    pub fn full() -> Interval {
+        Interval { min: WORD::MIN, max: WORD::MAX }
+    }
+
+    /// Returns the value if the range only holds one.
+    pub fn as_constant(&self) -> Option<WORD> {
+        if self.min == self.max { Some(self.min) } else { None }
+    }
+
+    /// Returns true if the range holds a value.
+    pub fn contains(&self, value: WORD) -> bool {
+        (self.min..=self.max).contains(&value)
+    }
+
+    /// Returns the smallest range holding both ranges.
+    pub fn join(&self, other: &Interval) -> Interval {
+        Interval { min: self.min.min(other.min), max: self.max.max(other.max) }
+    }
+
+    /// Returns the range of values held by both ranges, if there are any.
+    pub fn meet(&self, other: &Interval) -> Option<Interval> {
+        let (min, max) = (self.min.max(other.min), self.max.min(other.max));
+        if min <= max { Some(Interval { min, max }) } else { None }
+    }
+
+    /// Returns the range of the negated values, and true if negating may
+    /// overflow, in which case the range is every value.
+    pub fn negate(&self) -> (Interval, bool) {
+        if let Some(v) = self.as_constant() {
+            return (Interval::constant(v.wrapping_neg()), v.checked_neg().is_none());
+        }
+        if self.min == WORD::MIN { (Interval::full(), true) }
+        else { (Interval { min: -self.max, max: -self.min }, false) }
+    }
+
+    /// Returns the range of the values of this range minus those of
+    /// another, and true if subtracting may overflow, in which case the
+    /// range is every value.
+    ///
+    /// # Parameters
+    /// * `other` - The range being subtracted.
+    ///
+    pub fn subtract(&self, other: &Interval) -> (Interval, bool) {
+        if let (Some(a), Some(b)) = (self.as_constant(), other.as_constant()) {
+            return (Interval::constant(a.wrapping_sub(b)), a.checked_sub(b).is_none());
+        }
+        let min = self.min as i64 - other.max as i64;
+        let max = self.max as i64 - other.min as i64;
+        match (WORD::try_from(min), WORD::try_from(max)) {
+            (Ok(min), Ok(max)) => (Interval { min, max }, false),
+            _ => (Interval::full(), true),
+        }
+    }
+
+    /// Returns the addresses execution could move to through a word in
+    /// this range, each added to `base` and kept within the store.
+    fn addresses(&self, base: usize) -> BTreeSet<usize> {
+        if self.max as i64 - self.min as i64 >= MEMORY_WORDS as i64 - 1 {
+            return (0..MEMORY_WORDS).collect();
+        }
+        (self.min..=self.max)
+            .map(|v| (base as WORD).wrapping_add(v) as usize & 0x1F)
+            .collect()
+    }
+}
+
+impl From<WORD> for Interval {
+    fn from(value: WORD) -> Interval {
+        Interval::constant(value)
+    }
+}
+
+/// Whether running a program would go past a step limit.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Prediction {
+    /// Every run stops within the limit (the most steps any run takes,
+    /// including the stop).
+    Stops(usize),
+    /// Every run goes past the limit.
+    Exceeds,
+    /// Some runs may go past the limit, or the program could not be followed.
+    MayExceed,
+}
+
+/// The most times the first word of a loop can be executed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoopBound {
+    /// The address of the first word of the loop, the target of a jump back.
+    pub head: usize,
+    /// The most times the first word can be executed on any run, [None] if
+    /// a run through the loop could not be followed until it stopped.
+    pub iterations: Option<usize>,
+}
+
+/// Possible problems found analysing a program.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RangeWarning {
+    /// A subtraction may overflow a [WORD] (address).
+    SubtractOverflow(usize),
+    /// A negation may overflow a [WORD], negating the smallest value (address).
+    NegateOverflow(usize),
+    /// A word executed may hold more than one value, so the instruction
+    /// cannot be known (address).
+    UnknownInstruction(usize),
+    /// The program has more than [MAX_PATHS] paths, so was not fully followed.
+    TooManyPaths,
+}
+
+impl RangeWarning {
+
+    /// Returns a short string describing the warning.
+    pub fn describe(&self) -> String {
+        match self {
+            RangeWarning::SubtractOverflow(a) => format!("The subtraction at `{}` may overflow. ", a),
+            RangeWarning::NegateOverflow(a) => format!("The negation at `{}` may overflow. ", a),
+            RangeWarning::UnknownInstruction(a) => format!("The word at `{}` may be executed holding more than one value. ", a),
+            RangeWarning::TooManyPaths => format!("The program has more than {} paths. ", MAX_PATHS),
+        }
+    }
+}
+
+/// The result of analysing a program.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RangeAnalysis {
+    /// Whether running the program would go past the step limit.
+    pub prediction: Prediction,
+    /// The bound of each loop, in the order of their first words.
+    pub loops: Vec<LoopBound>,
+    /// Any problems found, each once, in the order of their kind then address.
+    pub warnings: Vec<RangeWarning>,
+    /// The range of the accumulator once stopped, over every run that
+    /// stops, [None] if none do.
+    pub accumulator: Option<Interval>,
+    /// The number of paths followed.
+    pub paths: usize,
+}
+
+/// Analyses a program for every value of its inputs, see [ranges][crate::analysis::ranges].
+///
+/// # Parameters
+/// * `store` - The main store holding the program.
+/// * `inputs` - The address and range of each word that is an input, all
+///   other words take their values from `store`.
+/// * `max_iter` - The step limit, as would be passed to [BabyModel::run_loop][crate::core::BabyModel::run_loop].
+///
+pub fn analyse(store: &[WORD; MEMORY_WORDS], inputs: &[(usize, Interval)], max_iter: usize) -> RangeAnalysis {
+    let mut start = Path {
+        pc: 0,
+        acc: Interval::constant(0),
+        store: store.map(Interval::constant),
+        links: vec![],
+        steps: 0,
+        visits: [0; MEMORY_WORDS],
+        seen: HashSet::new(),
+    };
+    for (addr, range) in inputs { start.store[*addr % MEMORY_WORDS] = *range; }
+
+    let mut explorer = Explorer { pending: vec![start], paths: 1, heads: BTreeSet::new(), warnings: BTreeSet::new() };
+    let mut ends = vec![];
+    let mut accumulator: Option<Interval> = None;
+    while let Some(path) = explorer.pending.pop() {
+        if let Some((end, path)) = explorer.follow(path, max_iter) {
+            if matches!(end, End::Stopped(_)) { accumulator = Some(accumulator.map_or(path.acc, |a| a.join(&path.acc))); }
+            ends.push((end, path.visits));
+        }
+    }
+    if explorer.warnings.contains(&RangeWarning::TooManyPaths) { ends.push((End::Unfollowed, [0; MEMORY_WORDS])); }
+
+    let mut bounds: BTreeMap<usize, Option<usize>> = explorer.heads.iter().map(|h| (*h, Some(0))).collect();
+    for (end, visits) in &ends {
+        for (head, bound) in bounds.iter_mut() {
+            if visits[*head] == 0 { continue; }
+            *bound = match end {
+                End::Stopped(_) => bound.map(|b| b.max(visits[*head])),
+                _ => None,
+            };
+        }
+    }
+    let unfollowed = ends.iter().any(|(e, _)| *e == End::Unfollowed) || ends.is_empty();
+    let exceeded = ends.iter().filter(|(e, _)| *e == End::Exceeded).count();
+    let prediction = if unfollowed { Prediction::MayExceed }
+        else if exceeded == ends.len() { Prediction::Exceeds }
+        else if exceeded > 0 { Prediction::MayExceed }
+        else { Prediction::Stops(ends.iter().filter_map(|(e, _)| e.steps()).max().unwrap_or(0)) };
+
+    RangeAnalysis {
+        prediction,
+        loops: bounds.into_iter().map(|(head, iterations)| LoopBound { head, iterations }).collect(),
+        warnings: explorer.warnings.into_iter().collect(),
+        accumulator,
+        paths: explorer.paths,
+    }
+}
+
+/// How a path ended.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum End {
+    /// Stopped (steps taken).
+    Stopped(usize),
+    /// Went past the step limit.
+    Exceeded,
+    /// Could not be followed.
+    Unfollowed,
+}
+
+impl End {
+
+    /// Returns the steps taken if the path stopped.
+    fn steps(&self) -> Option<usize> {
+        match self { End::Stopped(s) => Some(*s), _ => None }
+    }
+}
+
+/// The accumulator is the value of a word, negated if `negated`, plus `offset`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Link {
+    addr: usize,
+    negated: bool,
+    offset: i64,
+}
+
+/// The state of one path through a program.
+#[derive(Clone, Debug)]
+struct Path {
+    pc: usize,
+    acc: Interval,
+    store: [Interval; MEMORY_WORDS],
+    links: Vec<Link>,
+    steps: usize,
+    visits: [usize; MEMORY_WORDS],
+    seen: HashSet<State>,
+}
+
+/// The state of a path at an address, without the steps taken to get there.
+type State = (usize, Interval, [Interval; MEMORY_WORDS], Vec<Link>);
+
+impl Path {
+
+    /// Narrows the accumulator to a range, and the words linked to it,
+    /// returning false if a word can hold no values.
+    fn narrow(&mut self, acc: Interval) -> bool {
+        self.acc = acc;
+        let clamp = |v: i64| v.clamp(WORD::MIN as i64, WORD::MAX as i64) as WORD;
+        for link in &self.links {
+            let (min, max) = (acc.min as i64 - link.offset, acc.max as i64 - link.offset);
+            let (min, max) = if link.negated { (-max, -min) } else { (min, max) };
+            match self.store[link.addr].meet(&Interval::new(clamp(min), clamp(max))) {
+                Some(v) => self.store[link.addr] = v,
+                None => return false,
+            }
+        }
+        true
+    }
+
+    /// Returns a copy of the path to follow another way from here, which
+    /// starts recording its states afresh rather than copying them.
+    fn fork(&self) -> Path {
+        Path { links: self.links.clone(), seen: HashSet::new(), ..*self }
+    }
+
+    /// Records the state of the path, returning false if it has been in
+    /// the same state before, so will repeat the same steps forever.
+    fn record(&mut self) -> bool {
+        self.seen.len() >= MAX_STATES || self.seen.insert((self.pc, self.acc, self.store, self.links.clone()))
+    }
+}
+
+/// Follows paths through a program, gathering what is found.
+struct Explorer {
+    pending: Vec<Path>,
+    paths: usize,
+    heads: BTreeSet<usize>,
+    warnings: BTreeSet<RangeWarning>,
+}
+
+impl Explorer {
+
+    /// Adds a path to follow, unless there are already [MAX_PATHS], in
+    /// which case [RangeWarning::TooManyPaths] is noted and it is dropped.
+    fn push(&mut self, path: Path) {
+        if self.paths >= MAX_PATHS {
+            self.warnings.insert(RangeWarning::TooManyPaths);
+            return;
+        }
+        self.paths += 1;
+        self.pending.push(path);
+    }
+
+    /// Follows a path until it ends, adding any new paths where it splits
+    /// to `pending`, returns [None] if the path turns out not to be possible.
+    fn follow(&mut self, mut path: Path, max_iter: usize) -> Option<(End, Path)> {
+        loop {
+            if path.steps == max_iter { return Some((End::Exceeded, path)); }
+            let pc = path.pc;
+            let Some(word) = path.store[pc].as_constant() else {
+                self.warnings.insert(RangeWarning::UnknownInstruction(pc));
+                return Some((End::Unfollowed, path));
+            };
+            path.steps += 1;
+            path.visits[pc] += 1;
+            let next = |n: usize| (pc + n) % MEMORY_WORDS;
+            match decode(word) {
+                BabyInstruction::Stop | BabyInstruction::AbsoluteValue(_) => return Some((End::Stopped(path.steps), path)),
+                BabyInstruction::Negate(o) => {
+                    let (acc, overflow) = path.store[o as usize].negate();
+                    if overflow { self.warnings.insert(RangeWarning::NegateOverflow(pc)); }
+                    path.links = if overflow { vec![] } else { vec![Link { addr: o as usize, negated: true, offset: 0 }] };
+                    path.acc = acc;
+                    path.pc = next(1);
+                },
+                BabyInstruction::Subtract(o) => {
+                    let value = path.store[o as usize];
+                    let (acc, overflow) = path.acc.subtract(&value);
+                    if overflow { self.warnings.insert(RangeWarning::SubtractOverflow(pc)); }
+                    path.links = match (value.as_constant(), path.acc.as_constant()) {
+                        _ if overflow => vec![],
+                        (Some(c), _) => path.links.iter().map(|l| Link { offset: l.offset - c as i64, ..*l }).collect(),
+                        (None, Some(k)) => vec![Link { addr: o as usize, negated: true, offset: k as i64 }],
+                        _ => vec![],
+                    };
+                    path.acc = acc;
+                    path.pc = next(1);
+                },
+                BabyInstruction::Store(o) => {
+                    let targets = path.store[o as usize].addresses(0);
+                    if targets.len() == 1 {
+                        let target = *targets.first()?;
+                        path.store[target] = path.acc;
+                        path.links.retain(|l| l.addr != target);
+                        path.links.push(Link { addr: target, negated: false, offset: 0 });
+                    } else {
+                        for t in &targets { path.store[*t] = path.store[*t].join(&path.acc); }
+                        path.links.retain(|l| !targets.contains(&l.addr));
+                    }
+                    path.pc = next(1);
+                },
+                BabyInstruction::SkipNextIfNegative => {
+                    let negative = path.acc.meet(&Interval::new(WORD::MIN, -1));
+                    let positive = path.acc.meet(&Interval::new(0, WORD::MAX));
+                    match (negative, positive) {
+                        (Some(n), Some(p)) => {
+                            let mut skipped = path.fork();
+                            if skipped.narrow(n) {
+                                skipped.pc = next(2);
+                                self.push(skipped);
+                            }
+                            if !path.narrow(p) { return None; }
+                            path.pc = next(1);
+                        },
+                        (Some(_), None) => path.pc = next(2),
+                        _ => path.pc = next(1),
+                    }
+                },
+                BabyInstruction::Jump(o) | BabyInstruction::RelativeJump(o) => {
+                    let base = if matches!(decode(word), BabyInstruction::Jump(_)) { 0 } else { pc };
+                    let targets = path.store[o as usize].addresses(base);
+                    self.heads.extend(targets.iter().filter(|t| **t <= pc));
+                    for t in targets.iter().skip(1) {
+                        let mut other = path.fork();
+                        other.pc = *t;
+                        self.push(other);
+                    }
+                    path.pc = *targets.first()?;
+                    if path.pc <= pc && !path.record() { return Some((End::Exceeded, path)); }
+                },
+            }
+        }
+    }
+}
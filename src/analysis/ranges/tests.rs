@@ -0,0 +1,118 @@
+use super::*;
+use crate::assembler::linker::LinkerData;
+use crate::compiler::compile_and_link;
+use super::super::tests::store_and_tags;
+use crate::core::{BabyModel, errors::BabyErrors};
+
+
+/// Returns the fewest steps a store needs to stop when run.
+fn steps_to_stop(store: [WORD; MEMORY_WORDS]) -> usize {
+    let model = BabyModel::new_with_program(store);
+    (1..10_000).find(|n| matches!(model.run_loop(*n).1, BabyErrors::Stop(_))).unwrap()
+}
+
+const SPIN_IF_POSITIVE: &str = "ldn $x\ncmp\nstp\n:spin\njmp $to_spin\n:to_spin\nabs $spin\n:x\nabs 0d0";
+
+#[test]
+fn test_intervals() {
+    assert_eq!(Interval::new(3, -2), Interval { min: -2, max: 3 });
+    assert_eq!(Interval::new(-2, 3).negate(), (Interval::new(-3, 2), false));
+    assert_eq!(Interval::new(WORD::MIN, 0).negate(), (Interval::full(), true));
+    assert_eq!(Interval::constant(WORD::MIN).negate(), (Interval::constant(WORD::MIN), true));
+    assert_eq!(Interval::new(0, 5).subtract(&Interval::new(-1, 2)), (Interval::new(-2, 6), false));
+    assert_eq!(Interval::new(-5, 5).subtract(&Interval::constant(WORD::MAX)), (Interval::full(), true));
+    assert_eq!(Interval::new(0, 5).meet(&Interval::new(6, 9)), None);
+    assert_eq!(Interval::new(0, 5).join(&Interval::new(7, 9)), Interval::new(0, 9));
+    assert!(Interval::from(4).contains(4));
+    assert_eq!(Interval::new(30, 33).addresses(0), BTreeSet::from([0, 1, 30, 31]));
+    assert_eq!(Interval::new(-100, 100).addresses(3).len(), MEMORY_WORDS);
+}
+
+#[test]
+fn test_predictions() {
+    let (store, tags) = store_and_tags(SPIN_IF_POSITIVE);
+    let x = tags["x"] as usize;
+    assert_eq!(analyse(&store, &[(x, Interval::new(-5, 0))], 100).prediction, Prediction::Stops(3));
+    assert_eq!(analyse(&store, &[(x, Interval::new(-2, 2))], 100).prediction, Prediction::MayExceed);
+    let spinning = analyse(&store, &[(x, Interval::new(1, 5))], 1_000_000);
+    assert_eq!(spinning.prediction, Prediction::Exceeds);
+    assert_eq!(spinning.loops, vec![LoopBound { head: 3, iterations: None }]);
+    assert_eq!(spinning.paths, 1);
+    assert_eq!(analyse(&store, &[], 2).prediction, Prediction::Exceeds);
+    assert_eq!(analyse(&store, &[], 3).accumulator, Some(Interval::constant(0)));
+}
+
+#[test]
+fn test_loop_bounds() {
+    let source = "var a = 0\nvar b = 0\nvar product\nwhile a > 0 {\n    product = product + b\n    a = a - 1\n}";
+    let LinkerData(program, tags) = compile_and_link(source).ok().unwrap();
+    let store = BabyInstruction::to_numbers(program);
+    let (a, b) = (tags["a"] as usize, tags["b"] as usize);
+    let analysis = analyse(&store, &[(a, Interval::new(0, 6)), (b, Interval::new(-3, 3))], 10_000);
+    let most = (0..=6)
+        .map(|v| { let mut s = store; s[a] = v; steps_to_stop(s) })
+        .max()
+        .unwrap();
+    assert_eq!(analysis.prediction, Prediction::Stops(most));
+    assert_eq!(analysis.loops, vec![LoopBound { head: 0, iterations: Some(7) }]);
+    assert_eq!(analysis.warnings, vec![]);
+    assert_eq!(analysis.paths, 7);
+}
+
+#[test]
+fn test_overflow() {
+    let (store, tags) = store_and_tags("ldn $x\nsub $y\nstp\n:x\nabs 0d0\n:y\nabs 0d0");
+    let (x, y) = (tags["x"] as usize, tags["y"] as usize);
+    let analysis = analyse(&store, &[(x, Interval::new(WORD::MIN, 0))], 10);
+    assert_eq!(analysis.warnings, vec![RangeWarning::NegateOverflow(0)]);
+    let analysis = analyse(&store, &[(x, Interval::new(-5, 5)), (y, Interval::constant(WORD::MAX))], 10);
+    assert_eq!(analysis.warnings, vec![RangeWarning::SubtractOverflow(1)]);
+    assert_eq!(analysis.accumulator, Some(Interval::full()));
+    assert_eq!(analyse(&store, &[(y, Interval::new(-5, 5))], 10).warnings, vec![]);
+}
+
+#[test]
+fn test_unknown_instruction() {
+    let (store, tags) = store_and_tags("ldn $x\nsto $to_patched\n:patched\nabs 0d0\nstp\n:to_patched\nabs $patched\n:x\nabs 0d0");
+    let x = tags["x"] as usize;
+    let analysis = analyse(&store, &[(x, Interval::new(0, 1))], 10);
+    assert_eq!(analysis.warnings, vec![RangeWarning::UnknownInstruction(2)]);
+    assert_eq!(analysis.prediction, Prediction::MayExceed);
+    assert_eq!(analyse(&store, &[], 10).prediction, Prediction::Exceeds);
+}
+
+#[test]
+#[cfg(not(feature = "i8"))]
+fn test_wide_inputs() {
+    let countdown = "
+:loop
+ldn $count
+sto $to_negated
+ldn $negated
+sub $one
+sto $to_count
+cmp
+jmp $to_loop
+stp
+:to_loop
+abs $loop
+:to_negated
+abs $negated
+:to_count
+abs $count
+:negated
+abs 0d0
+:one
+abs 0d1
+:count
+abs 0d0";
+    let (store, tags) = store_and_tags(countdown);
+    let count = tags["count"] as usize;
+    let analysis = analyse(&store, &[(count, Interval::new(0, 20_000))], 1_000_000);
+    assert_eq!(analysis.prediction, Prediction::MayExceed);
+    assert_eq!(analysis.warnings, vec![RangeWarning::TooManyPaths]);
+    assert_eq!(analysis.paths, MAX_PATHS);
+    let analysis = analyse(&store, &[(count, Interval::new(0, 3000))], 1_000_000);
+    assert_eq!(analysis.prediction, Prediction::Stops(7 * 3001));
+    assert_eq!(analysis.paths, 3001);
+}
@@ -0,0 +1,398 @@
+//! # Symbolic
+//!
+//! This module finds values for the inputs of a Baby program that make it
+//! reach an address, stop, or stop with an accumulator breaking an assertion,
+//! for testing programs against inputs that are hard to guess.
+//!
+//! The main part of this module is [find_inputs][crate::analysis::symbolic::find_inputs],
+//! which runs a program with some of its words marked as inputs, each
+//! holding a variable in place of a value, so the accumulator and each word
+//! hold a sum of the inputs multiplied by coefficients, plus a constant, see
+//! [Linear][crate::analysis::symbolic::solver::Linear]. Tests split a program
+//! into paths as in [ranges][crate::analysis::ranges], up to the same [MAX_PATHS][crate::analysis::ranges::MAX_PATHS],
+//! but each way adds a constraint that the accumulator is negative or not
+//! rather than narrowing its range. The constraints of a path that reaches
+//! the [Target][crate::analysis::symbolic::Target] are then solved with
+//! [solve][crate::analysis::symbolic::solver::solve], a built in solver for
+//! linear integer constraints, giving the values of the inputs.
+//!
+//! Each path also has constraints that no subtraction or negation overflows
+//! a [WORD], so only inputs that do not overflow are found. Every set of inputs
+//! found is checked by running the program with them before it is returned.
+//!
+//! # Example
+//! ```
+//! use baby_emulator::assembler::{assemble, linker::LinkerData};
+//! use baby_emulator::analysis::ranges::Interval;
+//! use baby_emulator::analysis::symbolic::{find_inputs, Target, SearchResult};
+//! use baby_emulator::core::instructions::BabyInstruction;
+//!
+//! // Only reaches `secret` if `x` is more than 3 and less than 10
+//! let asm = "
+//! ldn $x
+//! sub $minus_three
+//! cmp
+//! stp
+//! sto $to_negated
+//! ldn $negated
+//! sub $seven
+//! cmp
+//! stp
+//! :secret
+//! stp
+//! :x
+//! abs 0d0
+//! :minus_three
+//! abs 0d-3
+//! :seven
+//! abs 0d7
+//! :to_negated
+//! abs $negated
+//! :negated
+//! abs 0d0
+//! ";
+//! let LinkerData(program, tags) = assemble(asm, false).ok().unwrap();
+//! let store = BabyInstruction::to_numbers(program);
+//! let x = tags["x"] as usize;
+//! let target = Target::Address(tags["secret"] as usize);
+//! match find_inputs(&store, &[(x, Interval::new(-100, 100))], &target, 100) {
+//!     SearchResult::Found(witness) => assert_eq!(witness.inputs, vec![(x, 4)]),
+//!     _ => panic!(),
+//! }
+//! ```
+//!
+
+use crate::core::{BabyModel, MEMORY_WORDS, WORD, errors::BabyErrors, instructions::BabyInstruction};
+use super::decode;
+use super::ranges::{Interval, MAX_PATHS};
+use solver::{solve, Constraint, Linear, Solution};
+
+
+/// Contains a solver for linear integer constraints.
+pub mod solver;
+#[cfg(test)]
+mod tests;
+
+
+/// A comparison of the accumulator with a value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    /// The accumulator is less than the value.
+    Less,
+    /// The accumulator is at most the value.
+    LessEqual,
+    /// The accumulator is the value.
+    Equal,
+    /// The accumulator is not the value.
+    NotEqual,
+    /// The accumulator is at least the value.
+    GreaterEqual,
+    /// The accumulator is more than the value.
+    Greater,
+}
+
+/// Something that should be true of the accumulator once a program stops.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Assertion {
+    /// How the accumulator is compared with the value.
+    pub comparison: Comparison,
+    /// The value.
+    pub value: WORD,
+}
+
+impl Assertion {
+
+    /// Returns true if the assertion holds for an accumulator.
+    ///
+    /// # Parameters
+    /// * `acc` - The accumulator.
+    ///
+    pub fn holds(&self, acc: WORD) -> bool {
+        match self.comparison {
+            Comparison::Less => acc < self.value,
+            Comparison::LessEqual => acc <= self.value,
+            Comparison::Equal => acc == self.value,
+            Comparison::NotEqual => acc != self.value,
+            Comparison::GreaterEqual => acc >= self.value,
+            Comparison::Greater => acc > self.value,
+        }
+    }
+
+    /// Returns the ways the assertion can be broken by an accumulator, each
+    /// a constraint, any of which breaks it, [None] if one overflows.
+    fn violations(&self, acc: &Linear) -> Option<Vec<Constraint>> {
+        let value = Linear::constant(self.value as i64);
+        let (below, above) = (Linear::constant(self.value as i64 - 1), Linear::constant(self.value as i64 + 1));
+        Some(match self.comparison {
+            Comparison::Less => vec![Constraint::at_most(&value, acc)?],
+            Comparison::LessEqual => vec![Constraint::at_most(&above, acc)?],
+            Comparison::Equal => vec![Constraint::at_most(acc, &below)?, Constraint::at_most(&above, acc)?],
+            Comparison::NotEqual => vec![Constraint::Zero(acc.subtract(&value)?)],
+            Comparison::GreaterEqual => vec![Constraint::at_most(acc, &below)?],
+            Comparison::Greater => vec![Constraint::at_most(acc, &value)?],
+        })
+    }
+}
+
+/// What inputs are searched for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// Inputs that make the program reach the word at an address, about to
+    /// execute it.
+    Address(usize),
+    /// Inputs that make the program stop.
+    Stop,
+    /// Inputs that make the program stop with an accumulator breaking an assertion.
+    Violation(Assertion),
+}
+
+/// Inputs found that reach a target.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Witness {
+    /// The address and value of each input, in the order given.
+    pub inputs: Vec<(usize, WORD)>,
+    /// The steps taken to reach the target, including the stop if stopping.
+    pub steps: usize,
+    /// The accumulator once the target is reached.
+    pub accumulator: WORD,
+}
+
+/// Why a search may have missed inputs that reach its target.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Incomplete {
+    /// A word executed depends on the inputs (address).
+    InputInstruction(usize),
+    /// A jump or store is through a word that depends on the inputs (address).
+    InputAddress(usize),
+    /// The program has more than [MAX_PATHS] paths.
+    TooManyPaths,
+    /// The solver gave up on a path's constraints.
+    SolverGaveUp,
+    /// A coefficient of the inputs grew past an [i64], such as by a loop
+    /// doubling a word each pass (address).
+    TooLarge(usize),
+}
+
+impl Incomplete {
+
+    /// Returns a short string describing why the search was incomplete.
+    pub fn describe(&self) -> String {
+        match self {
+            Incomplete::InputInstruction(a) => format!("The word at `{}` is executed but depends on the inputs. ", a),
+            Incomplete::InputAddress(a) => format!("The jump or store at `{}` is through a word that depends on the inputs. ", a),
+            Incomplete::TooManyPaths => format!("The program has more than {} paths. ", MAX_PATHS),
+            Incomplete::SolverGaveUp => "The solver gave up on the constraints of a path. ".to_owned(),
+            Incomplete::TooLarge(a) => format!("The value of the accumulator at `{}` grew too large to follow. ", a),
+        }
+    }
+}
+
+/// The result of searching for inputs.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SearchResult {
+    /// Inputs that reach the target.
+    Found(Witness),
+    /// No inputs reach the target within the step limit, without overflowing.
+    Unreachable,
+    /// No inputs were found, but not every path could be followed.
+    Unknown(Vec<Incomplete>),
+}
+
+/// Searches for values of the inputs of a program that reach a target, see
+/// [symbolic][crate::analysis::symbolic].
+///
+/// # Parameters
+/// * `store` - The main store holding the program.
+/// * `inputs` - The address and range of values of each word that is an input.
+/// * `target` - What to reach.
+/// * `max_iter` - The most steps to take, as would be passed to [BabyModel::run_loop].
+///
+pub fn find_inputs(store: &[WORD; MEMORY_WORDS], inputs: &[(usize, Interval)], target: &Target, max_iter: usize) -> SearchResult {
+    let ranges: Vec<(i64, i64)> = inputs.iter().map(|(_, r)| (r.min as i64, r.max as i64)).collect();
+    let mut start = Path {
+        pc: 0,
+        acc: Linear::constant(0),
+        store: store.map(|w| Linear::constant(w as i64)),
+        constraints: vec![],
+        steps: 0,
+    };
+    for (i, (addr, _)) in inputs.iter().enumerate() { start.store[*addr % MEMORY_WORDS] = Linear::variable(i); }
+
+    let mut search = Search { ranges, pending: vec![start], incomplete: vec![] };
+    let mut paths = 0;
+    while let Some(path) = search.pending.pop() {
+        paths += 1;
+        if paths > MAX_PATHS {
+            search.note(Incomplete::TooManyPaths);
+            break;
+        }
+        let Some((path, stopped)) = search.follow(path, target, max_iter) else { continue };
+        let alternatives = match target {
+            Target::Violation(a) if stopped => match a.violations(&path.acc) {
+                Some(alternatives) => alternatives,
+                None => { search.note(Incomplete::TooLarge(path.pc)); continue },
+            },
+            Target::Violation(_) => continue,
+            _ => vec![Constraint::AtMostZero(Linear::constant(0))],
+        };
+        for alternative in alternatives {
+            let constraints = [path.constraints.clone(), vec![alternative]].concat();
+            let Some(values) = search.solve(&constraints) else { continue };
+            let inputs: Vec<(usize, WORD)> = inputs.iter().zip(values).map(|((a, _), v)| (*a, v as WORD)).collect();
+            if let Some(witness) = check(store, inputs, target, max_iter) { return SearchResult::Found(witness); }
+        }
+    }
+    if search.incomplete.is_empty() { SearchResult::Unreachable } else { SearchResult::Unknown(search.incomplete) }
+}
+
+/// Runs a program with inputs, returning what was found if it reaches the target.
+fn check(store: &[WORD; MEMORY_WORDS], inputs: Vec<(usize, WORD)>, target: &Target, max_iter: usize) -> Option<Witness> {
+    let mut store = *store;
+    for (addr, value) in &inputs { store[*addr % MEMORY_WORDS] = *value; }
+    let mut model = BabyModel::new_with_program(store);
+    for steps in 0..=max_iter {
+        if let Target::Address(a) = target {
+            if model.instruction_address as usize == *a { return Some(Witness { inputs, steps, accumulator: model.accumulator }); }
+        }
+        if steps == max_iter { return None; }
+        model = match model.execute() {
+            Ok(m) => m,
+            Err(BabyErrors::Stop(_)) => {
+                let reached = match target {
+                    Target::Address(_) => false,
+                    Target::Stop => true,
+                    Target::Violation(a) => !a.holds(model.accumulator),
+                };
+                return Some(Witness { inputs, steps: steps + 1, accumulator: model.accumulator }).filter(|_| reached);
+            },
+            Err(_) => return None,
+        };
+    }
+    None
+}
+
+/// The state of one path through a program.
+#[derive(Clone, Debug)]
+struct Path {
+    pc: usize,
+    acc: Linear,
+    store: [Linear; MEMORY_WORDS],
+    constraints: Vec<Constraint>,
+    steps: usize,
+}
+
+/// Follows paths through a program, noting why any cannot be followed.
+struct Search {
+    ranges: Vec<(i64, i64)>,
+    pending: Vec<Path>,
+    incomplete: Vec<Incomplete>,
+}
+
+impl Search {
+
+    /// Notes why a path could not be followed, if not already noted.
+    fn note(&mut self, incomplete: Incomplete) {
+        if !self.incomplete.contains(&incomplete) { self.incomplete.push(incomplete); }
+    }
+
+    /// Solves constraints, noting if the solver gave up.
+    fn solve(&mut self, constraints: &[Constraint]) -> Option<Vec<i64>> {
+        match solve(constraints, &self.ranges) {
+            Solution::Satisfiable(v) => Some(v),
+            Solution::Unsatisfiable => None,
+            Solution::Unknown => { self.note(Incomplete::SolverGaveUp); None },
+        }
+    }
+
+    /// Sets the accumulator of a path, wrapping it if it is a constant,
+    /// otherwise adding constraints that it does not overflow, returns [None]
+    /// noting [Incomplete::TooLarge] if the accumulator or a constraint overflows.
+    fn set_acc(&mut self, path: &mut Path, acc: Option<Linear>) -> Option<()> {
+        let acc = self.checked(acc, path.pc)?;
+        if let Some(v) = acc.as_constant() {
+            path.acc = Linear::constant(v as WORD as i64);
+            return Some(());
+        }
+        let (min, max) = acc.bounds(&self.ranges);
+        if min < WORD::MIN as i128 {
+            let constraint = Constraint::at_most(&Linear::constant(WORD::MIN as i64), &acc);
+            path.constraints.push(self.checked(constraint, path.pc)?);
+        }
+        if max > WORD::MAX as i128 {
+            let constraint = Constraint::at_most(&acc, &Linear::constant(WORD::MAX as i64));
+            path.constraints.push(self.checked(constraint, path.pc)?);
+        }
+        path.acc = acc;
+        Some(())
+    }
+
+    /// Returns a value that is [None] if it overflowed, noting [Incomplete::TooLarge].
+    fn checked<T>(&mut self, value: Option<T>, pc: usize) -> Option<T> {
+        if value.is_none() { self.note(Incomplete::TooLarge(pc)); }
+        value
+    }
+
+    /// Follows a path until it reaches the target or stops, returning the
+    /// path and whether it stopped, adding any new paths where it splits to
+    /// `pending`, returns [None] if the path goes past the step limit or
+    /// cannot be followed.
+    fn follow(&mut self, mut path: Path, target: &Target, max_iter: usize) -> Option<(Path, bool)> {
+        loop {
+            let pc = path.pc;
+            if *target == Target::Address(pc) { return Some((path, false)); }
+            if path.steps == max_iter { return None; }
+            let Some(word) = path.store[pc].as_constant() else {
+                self.note(Incomplete::InputInstruction(pc));
+                return None;
+            };
+            path.steps += 1;
+            let next = |n: usize| (pc + n) % MEMORY_WORDS;
+            let instruction = decode(word as WORD);
+            let pointer = |path: &Path, o: u16| path.store[o as usize].as_constant().map(|v| v as WORD);
+            match instruction {
+                BabyInstruction::Stop | BabyInstruction::AbsoluteValue(_) => return Some((path, true)),
+                BabyInstruction::Negate(o) => {
+                    let acc = path.store[o as usize].negate();
+                    self.set_acc(&mut path, acc)?;
+                    path.pc = next(1);
+                },
+                BabyInstruction::Subtract(o) => {
+                    let acc = path.acc.subtract(&path.store[o as usize]);
+                    self.set_acc(&mut path, acc)?;
+                    path.pc = next(1);
+                },
+                BabyInstruction::SkipNextIfNegative => {
+                    if let Some(v) = path.acc.as_constant() {
+                        path.pc = if v < 0 { next(2) } else { next(1) };
+                        continue;
+                    }
+                    let mut skipped = path.clone();
+                    let negative = Constraint::at_most(&path.acc, &Linear::constant(-1));
+                    skipped.constraints.push(self.checked(negative, pc)?);
+                    if self.solve(&skipped.constraints).is_some() {
+                        skipped.pc = next(2);
+                        self.pending.push(skipped);
+                    }
+                    let positive = path.acc.negate().map(Constraint::AtMostZero);
+                    path.constraints.push(self.checked(positive, pc)?);
+                    self.solve(&path.constraints)?;
+                    path.pc = next(1);
+                },
+                BabyInstruction::Store(o) | BabyInstruction::Jump(o) | BabyInstruction::RelativeJump(o) => {
+                    let Some(value) = pointer(&path, o) else {
+                        self.note(Incomplete::InputAddress(pc));
+                        return None;
+                    };
+                    match instruction {
+                        BabyInstruction::Store(_) => {
+                            path.store[value as usize & 0x1F] = path.acc.clone();
+                            path.pc = next(1);
+                        },
+                        BabyInstruction::Jump(_) => path.pc = value as usize & 0x1F,
+                        _ => path.pc = (pc as WORD).wrapping_add(value) as usize & 0x1F,
+                    }
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,261 @@
+//! # Solver
+//!
+//! This module is a small solver for linear constraints over integer
+//! variables that each have a range of values, finding values for every
+//! variable that meet all of the constraints.
+//!
+//! The main part of this module is [solve][crate::analysis::symbolic::solver::solve],
+//! which narrows the range of each variable to the values the constraints
+//! allow, then splits the range of a variable that is still more than one
+//! value in two, trying the half nearest zero first, until each range is a
+//! single value, so prefers solutions with values near zero.
+//!
+//! # Example
+//! ```
+//! use baby_emulator::analysis::symbolic::solver::{solve, Constraint, Linear, Solution};
+//!
+//! // x - y = 3 and x + y <= -5, i.e. x - y - 3 = 0 and x - (-y) + 5 <= 0
+//! let (x, y) = (Linear::variable(0), Linear::variable(1));
+//! let difference = x.subtract(&y).unwrap();
+//! let sum = x.subtract(&y.negate().unwrap()).unwrap();
+//! let constraints = [
+//!     Constraint::Zero(difference.subtract(&Linear::constant(3)).unwrap()),
+//!     Constraint::AtMostZero(sum.subtract(&Linear::constant(-5)).unwrap()),
+//! ];
+//! assert_eq!(solve(&constraints, &[(-10, 10), (-10, 10)]), Solution::Satisfiable(vec![-1, -4]));
+//! ```
+//!
+
+use std::collections::BTreeMap;
+
+
+/// The most ranges [solve] splits before giving up.
+pub const MAX_SPLITS: usize = 100_000;
+
+/// The most times [solve] narrows the ranges before splitting one.
+const MAX_ROUNDS: usize = 64;
+
+/// A sum of variables each multiplied by a coefficient, plus a constant.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub struct Linear {
+    /// The constant.
+    pub constant: i64,
+    /// The coefficient of each variable, by variable index, none are zero.
+    pub terms: BTreeMap<usize, i64>,
+}
+
+impl Linear {
+
+    /// Creates an expression of a constant.
+    pub fn constant(value: i64) -> Linear {
+        Linear { constant: value, terms: BTreeMap::new() }
+    }
+
+    /// Creates an expression of a single variable.
+    ///
+    /// # Parameters
+    /// * `index` - The index of the variable.
+    ///
+    pub fn variable(index: usize) -> Linear {
+        Linear { constant: 0, terms: BTreeMap::from([(index, 1)]) }
+    }
+
+    /// Returns the value of the expression if it has no variables.
+    pub fn as_constant(&self) -> Option<i64> {
+        if self.terms.is_empty() { Some(self.constant) } else { None }
+    }
+
+    /// Returns the expression negated, [None] if the constant or a
+    /// coefficient overflows.
+    pub fn negate(&self) -> Option<Linear> {
+        Some(Linear {
+            constant: self.constant.checked_neg()?,
+            terms: self.terms.iter().map(|(v, c)| Some((*v, c.checked_neg()?))).collect::<Option<_>>()?,
+        })
+    }
+
+    /// Returns this expression minus another, [None] if the constant or a
+    /// coefficient overflows.
+    ///
+    /// # Parameters
+    /// * `other` - The expression being subtracted.
+    ///
+    pub fn subtract(&self, other: &Linear) -> Option<Linear> {
+        let mut terms = self.terms.clone();
+        for (v, c) in &other.terms {
+            let coefficient = terms.get(v).unwrap_or(&0).checked_sub(*c)?;
+            if coefficient == 0 { terms.remove(v); } else { terms.insert(*v, coefficient); }
+        }
+        Some(Linear { constant: self.constant.checked_sub(other.constant)?, terms })
+    }
+
+    /// Returns the value of the expression for values of its variables.
+    ///
+    /// # Parameters
+    /// * `values` - The value of each variable, by index.
+    ///
+    pub fn evaluate(&self, values: &[i64]) -> i128 {
+        self.terms.iter()
+            .map(|(v, c)| *c as i128 * values[*v] as i128)
+            .sum::<i128>() + self.constant as i128
+    }
+
+    /// Returns the smallest and largest values of the expression for
+    /// variables within ranges.
+    ///
+    /// # Parameters
+    /// * `ranges` - The smallest and largest value of each variable, by index.
+    ///
+    pub fn bounds(&self, ranges: &[(i64, i64)]) -> (i128, i128) {
+        self.terms.iter()
+            .map(|(v, c)| term_bounds(*c, ranges[*v]))
+            .fold((self.constant as i128, self.constant as i128), |(min, max), (a, b)| (min + a, max + b))
+    }
+}
+
+/// A linear constraint.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Constraint {
+    /// The expression is at most zero.
+    AtMostZero(Linear),
+    /// The expression is zero.
+    Zero(Linear),
+}
+
+impl Constraint {
+
+    /// Creates a constraint that one expression is at most another, [None]
+    /// if subtracting them overflows.
+    ///
+    /// # Parameters
+    /// * `left` - The smaller expression.
+    /// * `right` - The larger expression.
+    ///
+    pub fn at_most(left: &Linear, right: &Linear) -> Option<Constraint> {
+        Some(Constraint::AtMostZero(left.subtract(right)?))
+    }
+
+    /// Returns true if the constraint holds for values of its variables.
+    ///
+    /// # Parameters
+    /// * `values` - The value of each variable, by index.
+    ///
+    pub fn holds(&self, values: &[i64]) -> bool {
+        match self {
+            Constraint::AtMostZero(e) => e.evaluate(values) <= 0,
+            Constraint::Zero(e) => e.evaluate(values) == 0,
+        }
+    }
+}
+
+/// The result of solving constraints.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Solution {
+    /// Values meeting every constraint, by variable index.
+    Satisfiable(Vec<i64>),
+    /// No values meet every constraint.
+    Unsatisfiable,
+    /// The solver gave up after [MAX_SPLITS] splits, or as negating an
+    /// expression overflows.
+    Unknown,
+}
+
+/// Solves linear constraints over variables within ranges, see [solver][crate::analysis::symbolic::solver].
+///
+/// # Parameters
+/// * `constraints` - The constraints.
+/// * `ranges` - The smallest and largest value of each variable, by index.
+///
+pub fn solve(constraints: &[Constraint], ranges: &[(i64, i64)]) -> Solution {
+    let divisible = constraints.iter().all(|c| match c {
+        Constraint::Zero(e) => {
+            let divisor = e.terms.values().fold(0, |g, c| gcd(g, c.unsigned_abs()));
+            divisor == 0 || e.constant.unsigned_abs() % divisor == 0
+        },
+        Constraint::AtMostZero(_) => true,
+    });
+    if !divisible { return Solution::Unsatisfiable; }
+    let bounds: Option<Vec<Linear>> = constraints.iter()
+        .map(|c| match c {
+            Constraint::AtMostZero(e) => Some(vec![e.clone()]),
+            Constraint::Zero(e) => Some(vec![e.clone(), e.negate()?]),
+        })
+        .collect::<Option<Vec<Vec<Linear>>>>()
+        .map(|b| b.concat());
+    let Some(bounds) = bounds else { return Solution::Unknown };
+
+    let mut pending = vec![ranges.to_vec()];
+    let mut splits = 0;
+    while let Some(mut ranges) = pending.pop() {
+        if !narrow(&bounds, &mut ranges) { continue; }
+        let Some(v) = ranges.iter().position(|(min, max)| min < max) else {
+            let values: Vec<i64> = ranges.iter().map(|(v, _)| *v).collect();
+            if constraints.iter().all(|c| c.holds(&values)) { return Solution::Satisfiable(values); }
+            continue;
+        };
+        splits += 1;
+        if splits > MAX_SPLITS { return Solution::Unknown; }
+        let (min, max) = ranges[v];
+        let mid = min + ((max as i128 - min as i128) / 2) as i64;
+        let (mut lower, mut upper) = (ranges.clone(), ranges);
+        lower[v] = (min, mid);
+        upper[v] = (mid + 1, max);
+        if mid < 0 { pending.extend([lower, upper]); } else { pending.extend([upper, lower]); }
+    }
+    Solution::Unsatisfiable
+}
+
+/// Narrows the ranges to the values allowed by each expression being at
+/// most zero, returning false if a range is left with no values.
+fn narrow(bounds: &[Linear], ranges: &mut [(i64, i64)]) -> bool {
+    for _ in 0..MAX_ROUNDS {
+        let mut changed = false;
+        for e in bounds {
+            let (least, _) = e.bounds(ranges);
+            if least > 0 { return false; }
+            for (v, c) in &e.terms {
+                let (lo, hi) = ranges[*v];
+                let rest = least - term_bounds(*c, (lo, hi)).0;
+                let limit = -rest;
+                let (lo, hi) = if *c > 0 {
+                    (lo, hi.min(clamp(floor_div(limit, *c as i128))))
+                } else {
+                    (lo.max(clamp(ceil_div(limit, *c as i128))), hi)
+                };
+                if lo > hi { return false; }
+                if (lo, hi) != ranges[*v] { ranges[*v] = (lo, hi); changed = true; }
+            }
+        }
+        if !changed { break; }
+    }
+    true
+}
+
+/// Returns the smallest and largest values of a variable in a range
+/// multiplied by a coefficient.
+fn term_bounds(coefficient: i64, (min, max): (i64, i64)) -> (i128, i128) {
+    let (a, b) = (coefficient as i128 * min as i128, coefficient as i128 * max as i128);
+    (a.min(b), a.max(b))
+}
+
+/// Divides rounding down.
+fn floor_div(a: i128, b: i128) -> i128 {
+    let q = a / b;
+    if a % b != 0 && (a < 0) != (b < 0) { q - 1 } else { q }
+}
+
+/// Divides rounding up.
+fn ceil_div(a: i128, b: i128) -> i128 {
+    let q = a / b;
+    if a % b != 0 && (a < 0) == (b < 0) { q + 1 } else { q }
+}
+
+/// Clamps a value to fit in an [i64].
+fn clamp(v: i128) -> i64 {
+    v.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+/// Returns the greatest common divisor.
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
@@ -0,0 +1,115 @@
+use super::*;
+use crate::assembler::linker::LinkerData;
+use crate::compiler::compile_and_link;
+use super::super::tests::store_and_tags;
+
+
+const SECRET_IF_POSITIVE: &str = "ldn $x\nsub $y\ncmp\nstp\n:secret\nstp\n:x\nabs 0d0\n:y\nabs 0d0";
+
+#[test]
+fn test_solver() {
+    let (x, y) = (Linear::variable(0), Linear::variable(1));
+    let two_x = x.subtract(&x.negate().unwrap()).unwrap();
+    assert_eq!(two_x.bounds(&[(-3, 5)]), (-6, 10));
+    let twice = |v| Constraint::Zero(two_x.subtract(&Linear::constant(v)).unwrap());
+    assert_eq!(solve(&[twice(7)], &[(-100, 100)]), Solution::Unsatisfiable);
+    assert_eq!(solve(&[twice(8)], &[(-100, 100)]), Solution::Satisfiable(vec![4]));
+    let y_plus_one = y.subtract(&Linear::constant(-1)).unwrap();
+    let impossible = [Constraint::at_most(&x, &y).unwrap(), Constraint::at_most(&y_plus_one, &x).unwrap()];
+    assert_eq!(solve(&impossible, &[(-50, 50), (-50, 50)]), Solution::Unsatisfiable);
+    let below = [
+        Constraint::at_most(&x.negate().unwrap(), &Linear::constant(-20)).unwrap(),
+        Constraint::at_most(&y, &two_x.negate().unwrap()).unwrap(),
+    ];
+    assert_eq!(solve(&below, &[(-50, 50), (-50, 50)]), Solution::Satisfiable(vec![20, -40]));
+    assert_eq!(solve(&below, &[(-50, 10), (-50, 50)]), Solution::Unsatisfiable);
+    let huge = Linear { constant: 0, terms: std::collections::BTreeMap::from([(0, i64::MIN)]) };
+    assert_eq!(huge.negate(), None);
+    assert_eq!(x.subtract(&huge), None);
+    assert_eq!(Linear::constant(i64::MIN).subtract(&Linear::constant(1)), None);
+    assert_eq!(solve(&[Constraint::Zero(huge)], &[(-1, 1)]), Solution::Unknown);
+}
+
+#[test]
+fn test_reach_address() {
+    let (store, tags) = store_and_tags(SECRET_IF_POSITIVE);
+    let (x, y, secret) = (tags["x"] as usize, tags["y"] as usize, tags["secret"] as usize);
+    let inputs = [(x, Interval::new(-10, 10)), (y, Interval::new(-10, 10))];
+    let SearchResult::Found(witness) = find_inputs(&store, &inputs, &Target::Address(secret), 10) else { panic!() };
+    let (a, b) = (witness.inputs[0].1, witness.inputs[1].1);
+    assert!(a + b > 0);
+    assert_eq!(witness.steps, 3);
+    assert_eq!(witness.accumulator, -a - b);
+    let inputs = [(x, Interval::new(-10, 0)), (y, Interval::new(-10, 0))];
+    assert_eq!(find_inputs(&store, &inputs, &Target::Address(secret), 10), SearchResult::Unreachable);
+    assert_eq!(find_inputs(&store, &[], &Target::Address(secret), 10), SearchResult::Unreachable);
+    let inputs = [(x, Interval::new(-10, 0))];
+    assert_eq!(find_inputs(&store, &inputs, &Target::Stop, 3), SearchResult::Unreachable);
+    let SearchResult::Found(witness) = find_inputs(&store, &inputs, &Target::Stop, 4) else { panic!() };
+    assert_eq!(witness.steps, 4);
+}
+
+#[test]
+fn test_assertions() {
+    let (store, tags) = store_and_tags("ldn $x\nsub $x\nstp\n:x\nabs 0d0");
+    let x = tags["x"] as usize;
+    let inputs = [(x, Interval::new(-30, 30))];
+    let violation = |comparison, value| Target::Violation(Assertion { comparison, value });
+    assert_eq!(find_inputs(&store, &inputs, &violation(Comparison::NotEqual, 7), 10), SearchResult::Unreachable);
+    let SearchResult::Found(witness) = find_inputs(&store, &inputs, &violation(Comparison::NotEqual, 8), 10) else { panic!() };
+    assert_eq!(witness, Witness { inputs: vec![(x, -4)], steps: 3, accumulator: 8 });
+    let SearchResult::Found(witness) = find_inputs(&store, &inputs, &violation(Comparison::Equal, 0), 10) else { panic!() };
+    assert_ne!(witness.accumulator, 0);
+    let SearchResult::Found(witness) = find_inputs(&store, &inputs, &violation(Comparison::Less, 50), 10) else { panic!() };
+    assert!(witness.accumulator >= 50);
+    assert_eq!(find_inputs(&store, &inputs, &violation(Comparison::LessEqual, 60), 10), SearchResult::Unreachable);
+    assert_eq!(find_inputs(&store, &inputs, &violation(Comparison::Greater, -61), 10), SearchResult::Unreachable);
+}
+
+#[test]
+fn test_overflow() {
+    let (store, tags) = store_and_tags("ldn $x\nsub $y\nstp\n:x\nabs 0d0\n:y\nabs 0d0");
+    let (x, y) = (tags["x"] as usize, tags["y"] as usize);
+    let inputs = [(x, Interval::new(WORD::MIN, WORD::MIN + 10)), (y, Interval::new(-10, 10))];
+    let target = Target::Violation(Assertion { comparison: Comparison::LessEqual, value: WORD::MAX - 5 });
+    let SearchResult::Found(witness) = find_inputs(&store, &inputs, &target, 10) else { panic!() };
+    assert_eq!(witness.inputs[0].1.checked_neg().unwrap().checked_sub(witness.inputs[1].1), Some(witness.accumulator));
+}
+
+#[test]
+fn test_loops() {
+    let source = "var a = 0\nvar b = 0\nvar product\nwhile a > 0 {\n    product = product + b\n    a = a - 1\n}";
+    let LinkerData(program, tags) = compile_and_link(source).ok().unwrap();
+    let store = BabyInstruction::to_numbers(program);
+    let (a, b) = (tags["a"] as usize, tags["b"] as usize);
+    let mut twice = store;
+    twice[a] = 2;
+    let model = BabyModel::new_with_program(twice);
+    let steps = (1..1000).find(|n| matches!(model.run_loop(*n).1, BabyErrors::Stop(_))).unwrap();
+    let inputs = [(a, Interval::new(2, 10)), (b, Interval::new(-10, 10))];
+    let SearchResult::Found(witness) = find_inputs(&store, &inputs, &Target::Stop, steps) else { panic!() };
+    assert_eq!(witness.inputs[0], (a, 2));
+    assert_eq!(witness.steps, steps);
+    let inputs = [(a, Interval::new(3, 10)), (b, Interval::new(-10, 10))];
+    assert_eq!(find_inputs(&store, &inputs, &Target::Stop, steps), SearchResult::Unreachable);
+}
+
+#[test]
+fn test_incomplete() {
+    let (store, tags) = store_and_tags("ldn $x\nsto $to_patched\n:patched\nabs 0d0\nstp\n:to_patched\nabs $patched\n:x\nabs 0d0");
+    let x = tags["x"] as usize;
+    let result = find_inputs(&store, &[(x, Interval::new(0, 1))], &Target::Stop, 10);
+    assert_eq!(result, SearchResult::Unknown(vec![Incomplete::InputInstruction(2)]));
+    let (store, tags) = store_and_tags("jmp $x\nstp\n:x\nabs 0d0");
+    let x = tags["x"] as usize;
+    let result = find_inputs(&store, &[(x, Interval::new(0, 1))], &Target::Stop, 10);
+    assert_eq!(result, SearchResult::Unknown(vec![Incomplete::InputAddress(0)]));
+}
+
+#[test]
+fn test_growing_coefficients() {
+    let (store, tags) = store_and_tags(":loop\nldn $t\nsub $t\nsto $pt\njmp $ploop\n:t\nabs 0d0\n:pt\nabs $t\n:ploop\nabs $loop");
+    let t = tags["t"] as usize;
+    let result = find_inputs(&store, &[(t, Interval::new(-10, 10))], &Target::Stop, 1000);
+    assert_eq!(result, SearchResult::Unknown(vec![Incomplete::TooLarge(1)]));
+}
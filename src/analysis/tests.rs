@@ -0,0 +1,10 @@
+use std::collections::HashMap;
+use crate::assembler::{assemble, linker::LinkerData};
+use crate::core::{MEMORY_WORDS, WORD, instructions::BabyInstruction};
+
+
+/// Assembles asm, returning the main store and the address of each tag.
+pub fn store_and_tags(asm: &str) -> ([WORD; MEMORY_WORDS], HashMap<String, WORD>) {
+    let LinkerData(program, tags) = assemble(asm, false).ok().unwrap();
+    (BabyInstruction::to_numbers(program), tags)
+}
@@ -0,0 +1,140 @@
+//! # Diagnostics
+//!
+//! This module contains [SourceSpan][crate::assembler::diagnostics::SourceSpan],
+//! which records where in the original asm source a line or part of a line
+//! came from, so errors found during assembling can point the user at the
+//! exact place in their file.
+//!
+//! Line numbers count every line of the source, including blank and comment
+//! only lines, so they match what is shown in a text editor.
+//!
+//! # Example
+//! ```
+//! use baby_emulator::assembler::assemble;
+//!
+//! match assemble("ldn $foo\nstp\n\nfoo 0d1", false) {
+//!     Ok(_) => panic!(),
+//!     Err(e) => println!("{}", e.render(Some("prog.asm"))),
+//! }
+//! ```
+//!
+//! Which prints the following:
+//! ```text
+//! error: Error parsing a instruction line. The specified instruction foo 0d1 is not known.
+//!  --> prog.asm:4:1
+//!   |
+//! 4 | foo 0d1
+//!   | ^^^^^^^
+//! ```
+//!
+
+
+#[cfg(test)]
+mod tests;
+
+
+/// A span of columns on a single line of asm source.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SourceSpan {
+    /// The line number, starting at 1.
+    pub line: usize,
+    /// The column of the first character of the span, starting at 0.
+    pub start: usize,
+    /// The column after the last character of the span.
+    pub end: usize,
+    /// The full text of the source line.
+    pub text: String,
+}
+
+impl SourceSpan {
+
+    /// Creates a span covering an entire source line.
+    ///
+    /// # Parameters
+    /// * `line` - The line number, starting at 1.
+    /// * `text` - The full text of the source line.
+    ///
+    pub fn new(line: usize, text: &str) -> SourceSpan {
+        SourceSpan { line, start: 0, end: text.chars().count(), text: text.to_owned() }
+    }
+
+    /// Returns the text covered by the span.
+    pub fn source(&self) -> String {
+        self.text.chars().skip(self.start).take(self.end - self.start).collect()
+    }
+
+    /// Returns the column the span starts at, starting at 1 as shown
+    /// by text editors.
+    pub fn column(&self) -> usize {
+        self.start + 1
+    }
+
+    /// Returns a copy of the span with the start and end moved inwards
+    /// past any whitespace.
+    pub fn trim(&self) -> SourceSpan {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut res = self.clone();
+        while res.start < res.end && chars[res.start].is_whitespace() { res.start += 1; }
+        while res.end > res.start && chars[res.end - 1].is_whitespace() { res.end -= 1; }
+        res
+    }
+
+    /// Narrows the span to the token holding the last occurrence of
+    /// `needle`, ignoring case, where tokens are separated by whitespace
+    /// or commas.
+    ///
+    /// Returns a copy of the span unchanged if `needle` cannot be found.
+    ///
+    /// # Example
+    /// ```
+    /// use baby_emulator::assembler::diagnostics::SourceSpan;
+    ///
+    /// let span = SourceSpan::new(1, "ldn $Foo ; comment").narrow("foo");
+    /// assert_eq!(span.source(), "$Foo");
+    /// ```
+    ///
+    pub fn narrow(&self, needle: &str) -> SourceSpan {
+        let chars: Vec<char> = self.text.chars().map(|c| c.to_ascii_lowercase()).collect();
+        let needle: Vec<char> = needle.chars().map(|c| c.to_ascii_lowercase()).collect();
+        if needle.is_empty() || needle.len() > self.end - self.start { return self.clone(); }
+        let found = (self.start..=self.end - needle.len()).rev()
+            .find(|i| chars[*i..*i + needle.len()] == needle[..]);
+        let (mut start, mut end) = match found {
+            Some(i) => (i, i + needle.len()),
+            None => return self.clone(),
+        };
+        let is_break = |c: char| c.is_whitespace() || c == ',';
+        while start > self.start && !is_break(chars[start - 1]) { start -= 1; }
+        while end < self.end && !is_break(chars[end]) { end += 1; }
+        SourceSpan { start, end, ..self.clone() }
+    }
+
+    /// Returns a string describing the location of the span.
+    pub fn describe(&self) -> String {
+        format!("line {}, column {}", self.line, self.column())
+    }
+
+    /// Renders a diagnostic message showing the source line with carets
+    /// under the span.
+    ///
+    /// # Parameters
+    /// * `heading` - The first line of the diagnostic, E.G. `error: ...`.
+    /// * `file` - The name of the source file, if there is one.
+    ///
+    pub fn render(&self, heading: &str, file: Option<&str>) -> String {
+        let number = self.line.to_string();
+        let gutter = " ".repeat(number.len());
+        let location = match file {
+            Some(f) => format!("{}:{}:{}", f, self.line, self.column()),
+            None => self.describe(),
+        };
+        let padding: String = self.text.chars().take(self.start)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let carets = "^".repeat((self.end - self.start).max(1));
+        format!(
+            "{}\n{gutter}--> {}\n{gutter} |\n{} | {}\n{gutter} | {}{}",
+            heading.trim_end(), location, number, self.text, padding, carets
+        )
+    }
+}
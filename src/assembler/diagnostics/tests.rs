@@ -0,0 +1,36 @@
+use super::*;
+
+
+#[test]
+fn test_trim_and_source() {
+    let span = SourceSpan::new(3, "  ldn $foo  ").trim();
+    assert_eq!((span.start, span.end), (2, 10));
+    assert_eq!(span.source(), "ldn $foo");
+    assert_eq!(span.column(), 3);
+    assert_eq!(span.describe(), "line 3, column 3");
+}
+
+#[test]
+fn test_narrow() {
+    let span = SourceSpan::new(1, "-0d5, C");
+    assert_eq!(span.narrow("5").source(), "-0d5");
+    assert_eq!(span.narrow("bar"), span);
+    assert_eq!(span.narrow(""), span);
+    let span = SourceSpan { end: 5, ..SourceSpan::new(1, "stp ; stp") };
+    assert_eq!(span.narrow("stp").source(), "stp");
+}
+
+#[test]
+fn test_render() {
+    let span = SourceSpan::new(12, "\tsub 0xZZ").narrow("zz");
+    let expected = 
+"error: bad
+  --> line 12, column 6
+   |
+12 | \tsub 0xZZ
+   | \t    ^^^^";
+    assert_eq!(span.render("error: bad", None), expected);
+    assert!(span.render("error: bad", Some("a.asm")).contains("--> a.asm:12:6"));
+    let empty = SourceSpan { start: 0, end: 0, ..span };
+    assert!(empty.render("", None).ends_with("| ^"));
+}
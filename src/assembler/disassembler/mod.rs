@@ -0,0 +1,184 @@
+//! # Disassembler
+//!
+//! This module turns a Baby main store back into asm source that can be
+//! fed back into [assemble][crate::assembler::assemble], giving back the
+//! identical store.
+//!
+//! The main part of this module is [disassemble][crate::assembler::disassembler::disassemble],
+//! this takes a `[WORD; MEMORY_WORDS]`, such as [BabyModel::main_store][crate::core::BabyModel::main_store],
+//! and optionally a collection of tag names and values, such as
+//! [LinkerData][crate::assembler::linker::LinkerData]`.1`, to restore tag
+//! declarations and references.
+//!
+//! Since the Baby stores program code and data in the same memory, a word
+//! is only disassembled as an instruction if it can be reached by execution
+//! from address 0 (see [find_code][crate::assembler::disassembler::find_code]),
+//! all other words are written as `abs` absolute values.
+//!
+//! # Example
+//! ```
+//! use baby_emulator::assembler::{assemble, linker::LinkerData};
+//! use baby_emulator::assembler::disassembler::disassemble;
+//! use baby_emulator::core::instructions::BabyInstruction;
+//!
+//! let asm = "ldn $value\nstp\n:value\nabs 0d-5";
+//! let (store, tags) = match assemble(asm, false) {
+//!     Ok(LinkerData(v, t)) => (BabyInstruction::to_numbers(v), t),
+//!     Err(_) => panic!()
+//! };
+//! let source = disassemble(&store, false, Some(&tags));
+//! assert_eq!(source, "ldn $value\nstp\n:value\nabs 0d-5\n");
+//! ```
+//!
+
+use std::collections::{BTreeSet, HashMap};
+use crate::core::{instructions::BabyInstruction, MEMORY_WORDS, WORD};
+use super::parser::{LineType, Instruction, Value};
+
+
+#[cfg(test)]
+mod tests;
+
+
+/// Disassembles a main store into asm source.
+///
+/// The returned source will assemble to the identical main store,
+/// trailing zero words are left out as the assembler pads the store
+/// with zeros.
+///
+/// # Parameters
+/// * `store` - The main store to be disassembled.
+/// * `og_notation` - If true, will use original notation.
+/// * `tags` - Optional tag names and values to restore.
+///
+pub fn disassemble(store: &[WORD; MEMORY_WORDS], og_notation: bool, tags: Option<&HashMap<String, WORD>>) -> String {
+    let empty = HashMap::new();
+    decode_store(store, tags.unwrap_or(&empty)).iter()
+        .map(|l| format!("{}\n", l.to_asm_string(og_notation)))
+        .collect()
+}
+
+/// Decodes a main store into parsed asm lines, as would be returned by
+/// the [parser][crate::assembler::parser] for the disassembled source.
+///
+/// Every tag at an address is declared before it in alphabetical order,
+/// operands use the first, tags outside of the main store are ignored.
+///
+/// # Parameters
+/// * `store` - The main store to be decoded.
+/// * `tags` - Tag names and values to restore.
+///
+pub fn decode_store(store: &[WORD; MEMORY_WORDS], tags: &HashMap<String, WORD>) -> Vec<LineType> {
+    let names = tag_names(tags);
+    let code = find_code(store);
+    let end = store.iter().rposition(|w| *w != 0)
+        .into_iter()
+        .chain(names.keys().cloned())
+        .max()
+        .map_or(0, |v| v + 1);
+
+    let mut lines = vec![];
+    for addr in 0..end {
+        if let Some(n) = names.get(&addr) {
+            lines.extend(n.iter().map(|t| LineType::Tag(t.clone())));
+        }
+        let word = store[addr];
+        let line = match decode_instruction(word) {
+            Some(i) if code.contains(&addr) => LineType::Instruction(with_tag(i, &names)),
+            _ => LineType::Absolute(jump_target_value(store, &code, addr, &names)),
+        };
+        lines.push(line);
+    }
+    lines
+}
+
+/// Decodes a single word into a parsed asm line, using tag names for any
+/// operand with the same value as a tag.
+///
+/// Words are decoded as instructions wherever they have an exact encoding
+/// as one, so unlike [decode_store] this cannot tell code from data.
+pub fn decode_word(word: WORD, tags: &HashMap<String, WORD>) -> LineType {
+    match decode_instruction(word) {
+        Some(i) => LineType::Instruction(with_tag(i, &tag_names(tags))),
+        None => LineType::Absolute(Value::Value(word)),
+    }
+}
+
+/// Decodes a word into an instruction, only if encoding the instruction
+/// gives back the exact same word, so any word with bits set outside of
+/// the opcode and operand fields is not an instruction.
+pub fn decode_instruction(word: WORD) -> Option<BabyInstruction> {
+    let instr = BabyInstruction::from_number(word as u16);
+    if instr.to_number() == word { Some(instr) }
+    else { None }
+}
+
+/// Finds the addresses of all the words that can be executed, starting
+/// from address 0.
+///
+/// Follows each instruction to the next, both possible next instructions
+/// after a test, and for jumps, the address held in the operand word,
+/// stopping at any stop instruction or word that is not an instruction.
+pub fn find_code(store: &[WORD; MEMORY_WORDS]) -> BTreeSet<usize> {
+    let mut code = BTreeSet::new();
+    let mut pending = vec![0usize];
+    while let Some(addr) = pending.pop() {
+        if !code.insert(addr) { continue; }
+        let next = (addr + 1) % MEMORY_WORDS;
+        match decode_instruction(store[addr]) {
+            None => { code.remove(&addr); },
+            Some(BabyInstruction::Stop) => (),
+            Some(BabyInstruction::Jump(o)) => pending.push(store[o as usize] as usize & 0x1F),
+            Some(BabyInstruction::RelativeJump(o)) =>
+                pending.push((addr as WORD).wrapping_add(store[o as usize]) as usize & 0x1F),
+            Some(BabyInstruction::SkipNextIfNegative) => {
+                pending.push(next);
+                pending.push((addr + 2) % MEMORY_WORDS);
+            },
+            Some(_) => pending.push(next),
+        }
+    }
+    code
+}
+
+/// Maps each address to the tag names at that address in alphabetical order.
+pub(crate) fn tag_names(tags: &HashMap<String, WORD>) -> HashMap<usize, Vec<String>> {
+    let mut names: HashMap<usize, Vec<String>> = HashMap::new();
+    for (name, value) in tags {
+        if *value < 0 || *value as usize >= MEMORY_WORDS { continue; }
+        names.entry(*value as usize).or_default().push(name.clone());
+    }
+    names.values_mut().for_each(|n| n.sort());
+    names
+}
+
+/// Converts an instruction to a parsed instruction, using a tag
+/// reference for the operand if a tag is at the operand's address.
+pub(crate) fn with_tag(instr: BabyInstruction, names: &HashMap<usize, Vec<String>>) -> Instruction {
+    let operand = instr.get_operand();
+    let value = match names.get(&operand).and_then(|n| n.first()) {
+        Some(n) => Value::Tag(n.clone()),
+        None => Value::Value(operand as WORD),
+    };
+    match instr {
+        BabyInstruction::Jump(_) => Instruction::Jump(value),
+        BabyInstruction::RelativeJump(_) => Instruction::RelativeJump(value),
+        BabyInstruction::Negate(_) => Instruction::Negate(value),
+        BabyInstruction::Store(_) => Instruction::Store(value),
+        BabyInstruction::Subtract(_) => Instruction::Subtract(value),
+        BabyInstruction::SkipNextIfNegative => Instruction::Test,
+        _ => Instruction::Stop,
+    }
+}
+
+/// Gets the value for an absolute word, this is a tag reference if the
+/// word is the operand of an absolute jump and a tag is at its address.
+fn jump_target_value(store: &[WORD; MEMORY_WORDS], code: &BTreeSet<usize>, addr: usize, names: &HashMap<usize, Vec<String>>) -> Value {
+    let word = store[addr];
+    let is_target = code.iter()
+        .any(|c| decode_instruction(store[*c]) == Some(BabyInstruction::Jump(addr as u16)));
+    match names.get(&(word as usize)).and_then(|n| n.first()) {
+        Some(n) if is_target && (0..MEMORY_WORDS as WORD).contains(&word) => Value::Tag(n.clone()),
+        _ => Value::Value(word),
+    }
+}
@@ -0,0 +1,128 @@
+use super::*;
+use crate::assembler::{assemble, linker::LinkerData};
+
+
+const EXAMPLE: &str =
+"
+ldn $start_value
+:loop_start_value
+sub $subtract_val
+cmp
+jmp $loop_start
+stp
+:loop_start
+abs $loop_start_value
+:subtract_val
+abs 0d1
+:start_value
+abs 0d-10
+";
+
+fn assemble_store(asm: &str, og_notation: bool) -> ([WORD; MEMORY_WORDS], HashMap<String, WORD>) {
+    match assemble(asm, og_notation) {
+        Ok(LinkerData(v, t)) => (BabyInstruction::to_numbers(v), t),
+        Err(e) => panic!("{}\n{}", e.describe(true), asm)
+    }
+}
+
+/// Generates pseudo random stores, mixing instructions and data.
+fn random_stores() -> Vec<[WORD; MEMORY_WORDS]> {
+    let mut seed: u64 = 0x5EED;
+    let mut next = move || {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (seed >> 33) as u32
+    };
+    (0..50).map(|_| {
+        let mut store = [0; MEMORY_WORDS];
+        for word in store.iter_mut() {
+            let r = next();
+            *word = match r % 3 {
+                0 => BabyInstruction::from_number((r >> 8) as u16).to_number(),
+                1 => (r >> 4) as WORD,
+                _ => ((r >> 4) as WORD).wrapping_neg(),
+            };
+        }
+        store
+    }).collect()
+}
+
+fn assert_round_trip(store: [WORD; MEMORY_WORDS], tags: Option<&HashMap<String, WORD>>) {
+    for og_notation in [false, true] {
+        let source = disassemble(&store, og_notation, tags);
+        let (res, _) = assemble_store(&source, og_notation);
+        assert_eq!(res, store, "\n{}", source);
+    }
+}
+
+#[test]
+fn test_decode_instruction() {
+    let negate = BabyInstruction::Negate(5).to_number();
+    assert_eq!(decode_instruction(negate), Some(BabyInstruction::Negate(5)));
+    assert_eq!(decode_instruction(BabyInstruction::Stop.to_number()), Some(BabyInstruction::Stop));
+    assert_eq!(decode_instruction(negate + 0x20), None);
+    assert_eq!(decode_instruction(-1), None);
+}
+
+#[test]
+fn test_find_code() {
+    let (store, _) = assemble_store(EXAMPLE, false);
+    let code: Vec<usize> = find_code(&store).into_iter().collect();
+    assert_eq!(code, vec![0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn test_disassemble_example() {
+    let (store, tags) = assemble_store(EXAMPLE, false);
+    assert_eq!(disassemble(&store, false, Some(&tags)), EXAMPLE.trim_start());
+    let og = disassemble(&store, true, None);
+    assert_eq!(og.lines().next(), Some("-0d7, C"));
+    assert_eq!(og.lines().nth(3), Some("0d5, Cl"));
+}
+
+#[test]
+fn test_decode_word() {
+    let tags = HashMap::from([("foo".to_owned(), 3)]);
+    let store = |o| BabyInstruction::Store(o).to_number();
+    assert_eq!(decode_word(store(3), &tags), LineType::Instruction(Instruction::Store(Value::Tag("foo".to_owned()))));
+    assert_eq!(decode_word(store(4), &tags), LineType::Instruction(Instruction::Store(Value::Value(4))));
+    assert_eq!(decode_word(-3, &tags), LineType::Absolute(Value::Value(-3)));
+}
+
+#[test]
+fn test_round_trip_examples() {
+    let (store, tags) = assemble_store(EXAMPLE, false);
+    assert_round_trip(store, Some(&tags));
+    assert_round_trip(store, None);
+    assert_round_trip([0; MEMORY_WORDS], None);
+    let (store, tags) = assemble_store("jrp $rel\nstp\n:rel\nabs 0d-1\n:a\n:b\nabs 0d9", false);
+    assert_round_trip(store, Some(&tags));
+    let asm = "ldn $a\nsto $a\nstp\n:a\n:b\nabs 0d9\n";
+    let (store, tags) = assemble_store(asm, false);
+    assert_eq!(disassemble(&store, false, Some(&tags)), asm);
+}
+
+#[test]
+fn test_round_trip_random() {
+    let tags = HashMap::from([
+        ("low".to_owned(), 0),
+        ("mid".to_owned(), 16),
+        ("high".to_owned(), 31),
+        ("outside".to_owned(), 40),
+    ]);
+    for store in random_stores() {
+        assert_round_trip(store, None);
+        assert_round_trip(store, Some(&tags));
+    }
+}
+
+#[test]
+#[cfg(feature = "i16")]
+fn test_round_trip_negative_instructions() {
+    let asm = "jrp $rel\nstp\n:rel\nabs 0d1\n";
+    let (store, tags) = assemble_store(asm, false);
+    assert!(store[0] < 0 && store[1] < 0);
+    assert_eq!(decode_instruction(store[1]), Some(BabyInstruction::Stop));
+    assert_eq!(find_code(&store).into_iter().collect::<Vec<usize>>(), vec![0, 1]);
+    assert_eq!(disassemble(&store, false, Some(&tags)), asm);
+    assert_round_trip(store, None);
+}
@@ -5,13 +5,16 @@
 //! process. 
 //! 
 //! Since this assembler is quite simple and simply takes the 
-//! output of the parser and feeds it into the linker, returning
-//! the resultant machine code, this enum just has 2 values, 
-//! [AssemblyError::ParserError][crate::assembler::errors::AssemblyError::LinkerError] & [AssemblyError::LinkerError][crate::assembler::errors::AssemblyError::LinkerError].
+//! output of the parser, expands any macros and feeds it into the linker, 
+//! returning the resultant machine code, this enum just has 4 values, 
+//! [AssemblyError::ParserError][crate::assembler::errors::AssemblyError::ParserError], 
+//! [AssemblyError::MacroError][crate::assembler::errors::AssemblyError::MacroError], [AssemblyError::LinkerError][crate::assembler::errors::AssemblyError::LinkerError] 
+//! & [AssemblyError::Warning][crate::assembler::errors::AssemblyError::Warning], a warning that has been made into an error.
 //! 
 //! These are wrappers for the overarching error types exported by 
-//! [assembler::parser::errors][crate::assembler::parser::errors] & [assembler::linker::errors][crate::assembler::linker::errors].
-//! These represent the errors thrown by the parsing and linking. 
+//! [assembler::parser::errors][crate::assembler::parser::errors], [assembler::expander::errors][crate::assembler::expander::errors] 
+//! & [assembler::linker::errors][crate::assembler::linker::errors].
+//! These represent the errors thrown by the parsing, macro expansion and linking. 
 //! 
 //! For simple debug purposes, [crate::assembler::errors::AssemblyError::describe]
 //! can be used to simply log any error to the console. 
@@ -29,16 +32,23 @@
 //! ```
 //! 
 
+use super::diagnostics::SourceSpan;
+use super::expander::errors::{ExpansionError, ExpanderError};
 use super::linker::errors::{LinkingError, LinkerError};
 use super::parser::errors::{LineParseError, ParseError}; 
+use super::warnings::Warning;
 
 
 /// Possble errors thrown at parts of the assembly process. 
 pub enum AssemblyError {
-    /// Error parsing a line (line no, error). 
-    ParserError(usize, LineParseError),
-    /// Error thrown during linking. 
-    LinkerError(LinkingError),
+    /// Error parsing a line (source location, error). 
+    ParserError(SourceSpan, LineParseError),
+    /// Error thrown during macro expansion (source location, error). 
+    MacroError(SourceSpan, ExpansionError),
+    /// Error thrown during linking (source location, error). 
+    LinkerError(SourceSpan, LinkingError),
+    /// A warning made into an error (source location, warning). 
+    Warning(SourceSpan, Warning),
 }
 
 impl AssemblyError {
@@ -50,8 +60,41 @@ impl AssemblyError {
     /// 
     pub fn describe(&self, line_breaks: bool) -> String {
         match self {
-            AssemblyError::ParserError(i, p) => format!("An error was thrown during parsing at line {}. {}", i, p.describe(line_breaks)),
-            AssemblyError::LinkerError(l) => format!("An error was thrown during linking. {}", l.describe(line_breaks)),
+            AssemblyError::ParserError(s, p) => format!("An error was thrown during parsing at {}. {}", s.describe(), p.describe(line_breaks)),
+            AssemblyError::MacroError(s, m) => format!("An error was thrown during macro expansion at {}. {}", s.describe(), m.describe(line_breaks)),
+            AssemblyError::LinkerError(s, l) => format!("An error was thrown during linking at {}. {}", s.describe(), l.describe(line_breaks)),
+            AssemblyError::Warning(s, w) => format!("A warning was denied at {}. {}", s.describe(), w.describe()),
         }
     }
+
+    /// Returns where in the asm source the error was found. 
+    pub fn span(&self) -> &SourceSpan {
+        match self {
+            AssemblyError::ParserError(s, _) => s,
+            AssemblyError::MacroError(s, _) => s,
+            AssemblyError::LinkerError(s, _) => s,
+            AssemblyError::Warning(s, _) => s,
+        }
+    }
+
+    /// Renders the error as a diagnostic showing the offending source 
+    /// line with carets underneath the part that caused the error. 
+    /// 
+    /// # Parameters
+    /// * `file` - The name of the source file, if there is one. 
+    /// 
+    pub fn render(&self, file: Option<&str>) -> String {
+        self.span().render(&format!("error: {}", self.message()), file)
+    }
+
+    /// Returns a single line describing the error, without where it was found. 
+    pub fn message(&self) -> String {
+        let message = match self {
+            AssemblyError::ParserError(_, p) => p.describe(false),
+            AssemblyError::MacroError(_, m) => m.describe(false),
+            AssemblyError::LinkerError(_, l) => l.describe(false),
+            AssemblyError::Warning(_, w) => w.describe(),
+        };
+        message.split_whitespace().collect::<Vec<&str>>().join(" ")
+    }
 }
@@ -0,0 +1,56 @@
+//! # Expander Errors
+//!
+//! This module includes all the possible errors that can be thrown
+//! whilst expanding macros, all error types implement [ExpanderError][crate::assembler::expander::errors::ExpanderError].
+//!
+//! The main type in this module is [ExpansionError][crate::assembler::expander::errors::ExpansionError]
+//! which is an enum that has a branch for each possible error that can be thrown.
+//!
+
+use super::MAX_MACRO_DEPTH;
+
+
+/// Defines common behaviour for any error thrown by the expander.
+pub trait ExpanderError {
+    /// Returns a short string describing the error.
+    ///
+    /// # Parameters
+    /// * `line_breaks` - Add in line breaks between each embedded error.
+    ///
+    fn describe(&self, line_breaks: bool) -> String;
+}
+
+/// Possible errors thrown during the macro expansion process.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExpansionError {
+    /// A macro definition has no `endm` (macro name).
+    UnterminatedMacro(String),
+    /// An `endm` with no macro definition to end.
+    UnexpectedEndMacro,
+    /// A macro definition inside another macro definition (macro name).
+    NestedMacro(String),
+    /// A macro with the same name has already been defined (macro name).
+    DuplicateMacro(String),
+    /// A macro is used but not defined (macro name).
+    UnknownMacro(String),
+    /// A macro is used with the wrong number of arguments (macro name, expected, found).
+    ArgumentCount(String, usize, usize),
+    /// Macros use each other more than [MAX_MACRO_DEPTH] deep (macro name).
+    RecursionLimit(String),
+}
+
+impl ExpanderError for ExpansionError {
+    fn describe(&self, _line_breaks: bool) -> String {
+        match self {
+            ExpansionError::UnterminatedMacro(n) => format!("The macro `{}` has no `endm`. ", n),
+            ExpansionError::UnexpectedEndMacro => "The `endm` has no macro definition to end. ".to_owned(),
+            ExpansionError::NestedMacro(n) => format!("The macro `{}` is defined inside another macro. ", n),
+            ExpansionError::DuplicateMacro(n) => format!("The macro `{}` is already defined. ", n),
+            ExpansionError::UnknownMacro(n) => format!("The macro `{}` is not defined. ", n),
+            ExpansionError::ArgumentCount(n, e, f) =>
+                format!("The macro `{}` takes {} arguments but was given {}. ", n, e, f),
+            ExpansionError::RecursionLimit(n) =>
+                format!("The macro `{}` is used more than {} macros deep. ", n, MAX_MACRO_DEPTH),
+        }
+    }
+}
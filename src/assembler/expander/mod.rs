@@ -0,0 +1,274 @@
+//! # Expander
+//!
+//! This module expands macros in the output of the [parser][crate::assembler::parser],
+//! before it is fed into the [linker][crate::assembler::linker].
+//!
+//! The main part of this module is [expand_macros][crate::assembler::expander::expand_macros],
+//! this takes a vector of [LineType][crate::assembler::parser::LineType], removes all the
+//! macro definitions, and replaces every use of a macro with the lines in the
+//! body of the macro.
+//!
+//! Any tag reference to a parameter of the macro is replaced with the value
+//! given for it in the macro use, and any tags declared inside the macro are
+//! local to each use, being renamed to `<macro>.<use>.<tag>` where `<use>`
+//! counts up for each macro use in the program, so the same macro can be used
+//! more than once without tags clashing.
+//!
+//! Macros can use other macros, up to [MAX_MACRO_DEPTH][crate::assembler::expander::MAX_MACRO_DEPTH]
+//! deep, but cannot be defined inside other macros.
+//!
+//! # Example
+//! ```
+//! use baby_emulator::assembler::{assemble, linker::LinkerData};
+//!
+//! const ASM: &str =
+//! "
+//! macro copy from, to  ; Copies the value at `from` to `to`
+//! ldn $from
+//! sto $neg
+//! ldn $neg
+//! sto $to
+//! endm
+//!
+//! copy $a, $b
+//! stp
+//! :a
+//! abs 0d5
+//! :b
+//! abs 0d0
+//! :neg
+//! abs 0d0
+//! ";
+//!
+//! let (program, tags) = match assemble(ASM, false) {
+//!     Ok(LinkerData(v, t)) => (v, t),
+//!     Err(e) => panic!("{}", e.describe(true))
+//! };
+//! assert_eq!(program.len(), 8);
+//! assert_eq!(tags.get("a"), Some(&5));
+//! ```
+//!
+
+use std::collections::{HashMap, HashSet};
+use super::diagnostics::SourceSpan;
+use super::parser::{LineType, Value};
+use errors::ExpansionError;
+
+
+/// Contains types for handling errors found during macro expansion.
+pub mod errors;
+#[cfg(test)]
+mod tests;
+
+
+/// The maximum depth macros can use other macros, stopping macros that
+/// use themselves from expanding forever.
+pub const MAX_MACRO_DEPTH: usize = 16;
+
+/// A macro definition, collected from the lines between a
+/// [LineType::Macro] and a [LineType::EndMacro].
+#[derive(Clone, Debug, PartialEq)]
+pub struct MacroDefinition {
+    /// The name of the macro.
+    pub name: String,
+    /// The names of the parameters of the macro.
+    pub params: Vec<String>,
+    /// The lines in the body of the macro.
+    pub body: Vec<LineType>,
+}
+
+impl MacroDefinition {
+
+    /// Returns the names of all the tags and constants declared in the body
+    /// of the macro, these are local to each use of the macro.
+    pub fn local_tags(&self) -> HashSet<&str> {
+        self.body.iter()
+            .filter_map(|l| match l {
+                LineType::Tag(t) | LineType::Constant(t, _) => Some(t.as_str()),
+                _ => None
+            })
+            .collect()
+    }
+}
+
+/// Parsed lines each paired with a location, and the errors found
+/// expanding them each paired with the location of the line that caused it.
+pub type ExpandedLines<L> = (Vec<(L, LineType)>, Vec<(L, ExpansionError)>);
+
+/// Expands all the macros in a vector of parsed asm lines.
+///
+/// Returns the lines with the macro definitions removed and every
+/// macro use replaced with the body of the macro.
+///
+/// Returns the first [ExpansionError] if a macro definition is malformed,
+/// or a macro use cannot be expanded.
+///
+/// # Example
+/// ```
+/// use baby_emulator::assembler::parser::{parse_asm_string, LineType, Instruction, Value};
+/// use baby_emulator::assembler::expander::expand_macros;
+///
+/// let lines = parse_asm_string("macro clear x\nsto $x\nendm\nclear 0d9", false).ok().unwrap();
+/// assert_eq!(expand_macros(lines), Ok(vec![
+///     LineType::Instruction(Instruction::Store(Value::Value(9)))
+/// ]));
+/// ```
+///
+pub fn expand_macros(lines: Vec<LineType>) -> Result<Vec<LineType>, ExpansionError> {
+    let located: Vec<(usize, LineType)> = lines.into_iter().enumerate().collect();
+    match expand_located_lines(located) {
+        (res, errors) if errors.is_empty() => Ok(res.into_iter().map(|(_, l)| l).collect()),
+        (_, mut errors) => Err(errors.swap_remove(0).1),
+    }
+}
+
+/// Expands all the macros in parsed lines along with where in the source each
+/// line came from, as returned by [parse_asm_source][crate::assembler::parser::parse_asm_source].
+///
+/// Works the same as [expand_macros], each line from the expansion of a macro
+/// use takes the source span of the macro use, so any errors found in it later
+/// point back to where the macro was used.
+///
+/// Returns a tuple of [SourceSpan] and [ExpansionError] for the first error.
+///
+/// # Parameters
+/// * `lines` - The source spans and parsed lines.
+///
+pub fn expand_source_macros(lines: Vec<(SourceSpan, LineType)>) -> Result<Vec<(SourceSpan, LineType)>, (SourceSpan, ExpansionError)> {
+    match expand_source_macros_all(lines) {
+        (res, errors) if errors.is_empty() => Ok(res),
+        (_, mut errors) => Err(errors.swap_remove(0)),
+    }
+}
+
+/// Expands all the macros in parsed lines along with where in the source each
+/// line came from, as per [expand_source_macros], but carries on past any errors.
+///
+/// Returns a tuple of the expanded lines, leaving out any macro uses that could
+/// not be expanded, and every error found in the order of the lines.
+///
+/// # Parameters
+/// * `lines` - The source spans and parsed lines.
+///
+pub fn expand_source_macros_all(lines: Vec<(SourceSpan, LineType)>) -> ExpandedLines<SourceSpan> {
+    expand_located_lines(lines)
+}
+
+/// Expands all the macros in parsed lines each paired with a location,
+/// returning the expanded lines and every error with the location of the
+/// line that caused it.
+fn expand_located_lines<L: Clone>(lines: Vec<(L, LineType)>) -> ExpandedLines<L> {
+    let (macros, lines, mut errors) = collect_macros(lines);
+    let mut res: Vec<(L, LineType)> = vec![];
+    let mut uses = 0;
+    for (i, location, line) in lines {
+        match line {
+            LineType::MacroCall(name, args) => match expand_use(&name, &args, &macros, &mut uses, 0) {
+                Ok(v) => res.extend(v.into_iter().map(|l| (location.clone(), l))),
+                Err(e) => errors.push((i, location, e)),
+            },
+            l => res.push((location, l)),
+        }
+    }
+    errors.sort_by_key(|(i, _, _)| *i);
+    (res, errors.into_iter().map(|(_, l, e)| (l, e)).collect())
+}
+
+/// The lines left after removing macro definitions along with their index.
+type IndexedLines<L> = Vec<(usize, L, LineType)>;
+
+/// Errors found collecting macro definitions along with the line index.
+type IndexedErrors<L> = Vec<(usize, L, ExpansionError)>;
+
+/// Removes all the macro definitions from parsed lines.
+///
+/// Returns the macro definitions by name, the remaining lines, and any
+/// errors found, each of these paired with the index of the line.
+fn collect_macros<L: Clone>(lines: Vec<(L, LineType)>) ->
+    (HashMap<String, MacroDefinition>, IndexedLines<L>, IndexedErrors<L>) {
+    let mut macros: HashMap<String, MacroDefinition> = HashMap::new();
+    let mut res: IndexedLines<L> = vec![];
+    let mut errors: IndexedErrors<L> = vec![];
+    let mut current: Option<(usize, L, MacroDefinition)> = None;
+
+    for (i, (location, line)) in lines.into_iter().enumerate() {
+        match (line, &mut current) {
+            (LineType::Macro(name, _), Some(_)) =>
+                errors.push((i, location, ExpansionError::NestedMacro(name))),
+            (LineType::Macro(name, params), None) =>
+                current = Some((i, location, MacroDefinition { name, params, body: vec![] })),
+            (LineType::EndMacro, None) =>
+                errors.push((i, location, ExpansionError::UnexpectedEndMacro)),
+            (LineType::EndMacro, Some(_)) => {
+                let (start, start_location, def) = current.take().unwrap();
+                if macros.contains_key(&def.name) {
+                    errors.push((start, start_location, ExpansionError::DuplicateMacro(def.name)));
+                } else {
+                    macros.insert(def.name.clone(), def);
+                }
+            },
+            (line, Some((_, _, def))) => def.body.push(line),
+            (line, None) => res.push((i, location, line)),
+        }
+    }
+    if let Some((i, location, def)) = current {
+        errors.push((i, location, ExpansionError::UnterminatedMacro(def.name)));
+    }
+    (macros, res, errors)
+}
+
+/// Expands a single macro use, including any macros used inside it.
+///
+/// # Parameters
+/// * `name` - The name of the macro.
+/// * `args` - The argument values given to the macro.
+/// * `macros` - All the macro definitions by name.
+/// * `uses` - The count of macro uses so far, for naming local tags.
+/// * `depth` - How many macros deep this use is.
+///
+fn expand_use(name: &str, args: &[Value], macros: &HashMap<String, MacroDefinition>, uses: &mut usize, depth: usize) ->
+    Result<Vec<LineType>, ExpansionError> {
+    let def = match macros.get(name) {
+        Some(d) => d,
+        None => return Err(ExpansionError::UnknownMacro(name.to_owned()))
+    };
+    if def.params.len() != args.len() {
+        return Err(ExpansionError::ArgumentCount(name.to_owned(), def.params.len(), args.len()));
+    }
+    if depth >= MAX_MACRO_DEPTH {
+        return Err(ExpansionError::RecursionLimit(name.to_owned()));
+    }
+    *uses += 1;
+    let id = *uses;
+    let locals = def.local_tags();
+    let substitute = |v: &Value| v.map_tags(&|t| match def.params.iter().position(|p| p.eq_ignore_ascii_case(t)) {
+        Some(i) => args[i].clone(),
+        None if locals.contains(t) => Value::Tag(local_tag(name, id, t)),
+        None => Value::Tag(t.to_owned()),
+    });
+
+    let mut res: Vec<LineType> = vec![];
+    for line in &def.body {
+        match line {
+            LineType::Tag(t) => res.push(LineType::Tag(local_tag(name, id, t))),
+            LineType::Absolute(v) => res.push(LineType::Absolute(substitute(v))),
+            LineType::Constant(t, v) => res.push(LineType::Constant(local_tag(name, id, t), substitute(v))),
+            LineType::Origin(v) => res.push(LineType::Origin(substitute(v))),
+            LineType::Fill(n, v) => res.push(LineType::Fill(substitute(n), substitute(v))),
+            LineType::Words(v) => res.push(LineType::Words(v.iter().map(substitute).collect())),
+            LineType::Reserve(n) => res.push(LineType::Reserve(substitute(n))),
+            LineType::Instruction(i) => res.push(LineType::Instruction(i.with_operand(substitute(&i.get_operand())))),
+            LineType::MacroCall(n, a) => {
+                let a: Vec<Value> = a.iter().map(substitute).collect();
+                res.extend(expand_use(n, &a, macros, uses, depth + 1)?);
+            },
+            l => res.push(l.clone()),
+        }
+    }
+    Ok(res)
+}
+
+/// Returns the name a local tag is renamed to for a single macro use.
+fn local_tag(name: &str, id: usize, tag: &str) -> String {
+    format!("{}.{}.{}", name, id, tag)
+}
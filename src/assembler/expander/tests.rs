@@ -0,0 +1,124 @@
+use super::*;
+use crate::assembler::parser::{Instruction, parse_asm_source, parse_asm_string};
+use crate::assembler::parser::expression::{BinaryOperator, UnaryOperator};
+use crate::assembler::{assemble, assemble_all, errors::AssemblyError, linker::LinkerData};
+
+
+fn expand(asm: &str) -> Result<Vec<LineType>, ExpansionError> {
+    match parse_asm_string(asm, false) {
+        Ok(v) => expand_macros(v),
+        Err(_) => panic!("Failed to parse correct source. ")
+    }
+}
+
+fn tag(t: &str) -> Value {
+    Value::Tag(t.to_owned())
+}
+
+#[test]
+fn test_expand_simple() {
+    let res = expand("macro halt\nstp\nendm\nhalt\nldn 0d1\nhalt");
+    assert_eq!(res, Ok(vec![
+        LineType::Instruction(Instruction::Stop),
+        LineType::Instruction(Instruction::Negate(Value::Value(1))),
+        LineType::Instruction(Instruction::Stop),
+    ]));
+}
+
+#[test]
+fn test_expand_parameters() {
+    let res = expand("macro move From, $to\nldn $from\nsto $TO\nabs $other\nendm\nmove $a, 0d4");
+    assert_eq!(res, Ok(vec![
+        LineType::Instruction(Instruction::Negate(tag("a"))),
+        LineType::Instruction(Instruction::Store(Value::Value(4))),
+        LineType::Absolute(tag("other")),
+    ]));
+}
+
+#[test]
+fn test_expand_local_tags() {
+    let res = expand("macro wait\n:loop\njmp $loop\nendm\nwait\nwait");
+    assert_eq!(res, Ok(vec![
+        LineType::Tag("wait.1.loop".to_owned()),
+        LineType::Instruction(Instruction::Jump(tag("wait.1.loop"))),
+        LineType::Tag("wait.2.loop".to_owned()),
+        LineType::Instruction(Instruction::Jump(tag("wait.2.loop"))),
+    ]));
+}
+
+#[test]
+fn test_expand_nested_use() {
+    let res = expand("macro inner x\n:here\nsub $x\nendm\nmacro outer y\ninner $y\nendm\nouter 0d3");
+    assert_eq!(res, Ok(vec![
+        LineType::Tag("inner.2.here".to_owned()),
+        LineType::Instruction(Instruction::Subtract(Value::Value(3))),
+    ]));
+}
+
+#[test]
+fn test_expand_errors() {
+    let errors = vec![
+        ("macro foo\nstp", ExpansionError::UnterminatedMacro("foo".to_owned())),
+        ("stp\nendm", ExpansionError::UnexpectedEndMacro),
+        ("macro foo\nmacro bar\nendm\nendm", ExpansionError::NestedMacro("bar".to_owned())),
+        ("macro foo\nendm\nmacro foo\nendm", ExpansionError::DuplicateMacro("foo".to_owned())),
+        ("macro foo a\nendm\nfoo 0d1, 0d2", ExpansionError::ArgumentCount("foo".to_owned(), 1, 2)),
+        ("macro foo\nfoo\nendm\nfoo", ExpansionError::RecursionLimit("foo".to_owned())),
+    ];
+    for (asm, err) in errors {
+        assert_eq!(expand(asm), Err(err), "{}", asm);
+    }
+    let call = vec![LineType::MacroCall("foo".to_owned(), vec![])];
+    assert_eq!(expand_macros(call), Err(ExpansionError::UnknownMacro("foo".to_owned())));
+}
+
+#[test]
+fn test_expand_source_macros() {
+    let lines = parse_asm_source("macro clear x\nsto $x\nendm\n\nclear 0d9\nclear 0d8", false).ok().unwrap();
+    let res = expand_source_macros(lines).ok().unwrap();
+    let spans: Vec<usize> = res.iter().map(|(s, _)| s.line).collect();
+    assert_eq!(spans, vec![5, 6]);
+
+    let lines = parse_asm_source("stp\nmacro foo a\nendm\nfoo\nfoo 0d1, 0d2", false).ok().unwrap();
+    let (res, errors) = expand_source_macros_all(lines);
+    assert_eq!(res.len(), 1);
+    let errors: Vec<usize> = errors.iter().map(|(s, _)| s.line).collect();
+    assert_eq!(errors, vec![4, 5]);
+}
+
+#[test]
+fn test_assemble_macros() {
+    let asm = "macro add a, b\nldn $a\nsub $b\nendm\nadd $one, $one\nmacro wait n\n:loop\nsub $n\ncmp\njmp $loop_ptr\nendm\nwait $one\nstp\n:loop_ptr\nabs 0d0\n:one\nabs 0d1";
+    let (program, tags) = match assemble(asm, false) {
+        Ok(LinkerData(v, t)) => (v, t),
+        Err(e) => panic!("{}", e.describe(true))
+    };
+    assert_eq!(program.len(), 8);
+    assert_eq!(tags.get("wait.2.loop"), Some(&2));
+    assert_eq!(tags.get("one"), Some(&7));
+}
+
+#[test]
+fn test_assemble_macro_errors() {
+    let asm = "macro foo\nsub $missing\nendm\nfoo\nendm\nstp";
+    let errors = match assemble_all(asm, false) {
+        Ok(_) => panic!("Incorrect source assembled correctly. "),
+        Err(e) => e
+    };
+    assert!(matches!(errors[0], AssemblyError::LinkerError(_, _)));
+    assert_eq!(errors[0].span().line, 4);
+    assert!(matches!(errors[1], AssemblyError::MacroError(_, ExpansionError::UnexpectedEndMacro)));
+    assert_eq!(errors[1].span().line, 5);
+}
+
+#[test]
+fn test_expand_expressions() {
+    let res = expand("macro point table, n\nequ at $table+$n\nldn -$at\nendm\npoint $t, 0d2");
+    let at = Value::Tag("point.1.at".to_owned());
+    assert_eq!(res, Ok(vec![
+        LineType::Constant("point.1.at".to_owned(), Value::Binary(
+            BinaryOperator::Add, Box::new(tag("t")), Box::new(Value::Value(2))
+        )),
+        LineType::Instruction(Instruction::Negate(Value::Unary(UnaryOperator::Negate, Box::new(at)))),
+    ]));
+}
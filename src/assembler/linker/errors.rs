@@ -39,6 +39,113 @@ impl LinkerError for TagError {
     }
 }
 
+/// Possible errors thrown when declaring a tag. 
+#[derive(Clone, Debug, PartialEq)]
+pub enum TagDeclarationError {
+    /// A tag with the same name has already been declared. 
+    DuplicateTagName(String),
+    /// A tag is not followed by anything for it to mark. 
+    DanglingTag(String),
+}
+
+impl LinkerError for TagDeclarationError {
+    fn describe(&self, _line_breaks: bool) -> String {
+        match self {
+            TagDeclarationError::DuplicateTagName(s) => format!("The tag `{}` is already declared. ", s),
+            TagDeclarationError::DanglingTag(s) => format!("The tag `{}` is not followed by an instruction or absolute value. ", s),
+        }
+    }
+}
+
+/// Possible errors thrown when evaluating a value expression. 
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExpressionError {
+    /// An expression divides by zero (the expression). 
+    DivideByZero(String),
+    /// An expression shifts by a negative amount or by at least the 
+    /// number of bits in a word (the expression). 
+    ShiftOutOfRange(String),
+    /// A constant's value depends on itself (constant name). 
+    CircularConstant(String),
+}
+
+impl LinkerError for ExpressionError {
+    fn describe(&self, _line_breaks: bool) -> String {
+        match self {
+            ExpressionError::DivideByZero(s) => format!("The expression `{}` divides by zero. ", s),
+            ExpressionError::ShiftOutOfRange(s) => format!("The expression `{}` shifts out of range. ", s),
+            ExpressionError::CircularConstant(s) => format!("The value of the constant `{}` depends on itself. ", s),
+        }
+    }
+}
+
+/// Possible errors thrown when placing the program in memory. 
+#[derive(Clone, Debug, PartialEq)]
+pub enum PlacementError {
+    /// A placement directive's value depends on a tag, so cannot be known 
+    /// before the program is placed (the value). 
+    NotConstant(String),
+    /// A placement directive's count or address is negative (the value). 
+    NegativeValue(String),
+    /// A word is placed at an address already used by an earlier line (the address). 
+    Overlap(usize),
+}
+
+impl LinkerError for PlacementError {
+    fn describe(&self, _line_breaks: bool) -> String {
+        match self {
+            PlacementError::NotConstant(s) => format!("The placement value `{}` depends on a tag. ", s),
+            PlacementError::NegativeValue(s) => format!("The placement value `{}` is negative. ", s),
+            PlacementError::Overlap(a) => format!("The address `{}` is already used by an earlier line. ", a),
+        }
+    }
+}
+
+/// Possible errors thrown assembling or linking object files. 
+#[derive(Clone, Debug, PartialEq)]
+pub enum ObjectError {
+    /// A name is exported but not declared in the object (the name). 
+    UnknownExport(String),
+    /// A name is imported but also declared in the object (the name). 
+    ImportDeclared(String),
+    /// A name is exported by more than one object (the name). 
+    DuplicateExport(String),
+    /// An imported name is not exported by any object (the name). 
+    UnresolvedImport(String),
+    /// A line of an object file is malformed (line number, line). 
+    InvalidLine(usize, String),
+    /// A word of an object file is placed outside of the store (line number, offset). 
+    WordOutOfStore(usize, usize),
+}
+
+impl LinkerError for ObjectError {
+    fn describe(&self, _line_breaks: bool) -> String {
+        match self {
+            ObjectError::UnknownExport(s) => format!("The exported name `{}` is not declared. ", s),
+            ObjectError::ImportDeclared(s) => format!("The imported name `{}` is also declared. ", s),
+            ObjectError::DuplicateExport(s) => format!("The name `{}` is exported by more than one object. ", s),
+            ObjectError::UnresolvedImport(s) => format!("The imported name `{}` is not exported by any object. ", s),
+            ObjectError::InvalidLine(n, s) => format!("Line {} of the object file `{}` is not valid. ", n, s),
+            ObjectError::WordOutOfStore(n, o) => format!("Line {} of the object file places a word at `{}`, outside of the store. ", n, o),
+        }
+    }
+}
+
+/// A line of a symbol file is malformed. 
+#[derive(Clone, Debug, PartialEq)]
+pub struct SymbolFileError {
+    /// The line number, starting at 1. 
+    pub line: usize,
+    /// The malformed line. 
+    pub text: String,
+}
+
+impl LinkerError for SymbolFileError {
+    fn describe(&self, _line_breaks: bool) -> String {
+        format!("Line {} of the symbol file `{}` is not valid. ", self.line, self.text)
+    }
+}
+
 /// The linked program stack is greater than the Baby's memory. 
 #[derive(Clone, Debug, PartialEq)]
 pub struct MemoryExceedingError {
@@ -53,10 +160,18 @@ impl LinkerError for MemoryExceedingError {
 }
 
 /// Possible errors thrown during the linking process. 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum LinkingError {
     /// Error thrown during resolving a tag. 
     TagError(TagError),
+    /// Error thrown evaluating a value expression. 
+    ExpressionError(ExpressionError),
+    /// Error thrown declaring a tag. 
+    TagDeclarationError(TagDeclarationError),
+    /// Error thrown placing the program in memory. 
+    PlacementError(PlacementError),
+    /// Error thrown assembling or linking object files. 
+    ObjectError(ObjectError),
     /// The linked program stack is greater than the Baby's memory. 
     MemoryExceedingError(MemoryExceedingError)
 }
@@ -66,6 +181,10 @@ impl LinkerError for LinkingError {
         let line_break = if line_breaks { "\n" } else { "" };
         match self {
             LinkingError::TagError(t) => format!("There was an error linking a tag. {line_break} {}", t.describe(line_breaks)),
+            LinkingError::ExpressionError(e) => format!("There was an error evaluating an expression. {line_break} {}", e.describe(line_breaks)),
+            LinkingError::TagDeclarationError(t) => format!("There was an error declaring a tag. {line_break} {}", t.describe(line_breaks)),
+            LinkingError::PlacementError(p) => format!("There was an error placing the program. {line_break} {}", p.describe(line_breaks)),
+            LinkingError::ObjectError(o) => format!("There was an error linking objects. {line_break} {}", o.describe(line_breaks)),
             LinkingError::MemoryExceedingError(m) => format!("There was an error positiong the program. {line_break} {}", m.describe(line_breaks))
         }
     }
@@ -14,9 +14,12 @@
 //! This output can used with the [core][crate::core] module to make a runnable 
 //! emulation model. 
 //! 
+//! Any value expressions are evaluated once the tag values are known, along 
+//! with the values of any constants, which can be referenced the same as tags. 
+//! 
 //! This will return [LinkingError][crate::assembler::linker::errors::LinkingError] 
-//! if a tag reference cannot be bound or if the passed program stack is greater 
-//! than the total available memory. 
+//! if a tag reference cannot be bound, an expression cannot be evaluated, or if 
+//! the passed program stack is greater than the total available memory. 
 //! 
 //! # Example
 //! ```
@@ -25,27 +28,31 @@
 //! 
 //! 
 //! pub fn assemble(asm: &String) -> Result<Vec<BabyInstruction>, AssemblyError> {
-//!     let parse_result = match parser::parse_asm_string(asm, false) {
+//!     let parse_result = match parser::parse_asm_source(asm, false) {
 //!         Ok(v) => v,
-//!         Err((l, e)) => return Err(AssemblyError::ParserError(l, e))
+//!         Err((s, e)) => return Err(AssemblyError::ParserError(s, e))
 //!     };
-//!     match linker::link_parsed_lines(parse_result) {
+//!     match linker::link_source_lines(parse_result) {
 //!         Ok(LinkerData(v, _)) => Ok(v),
-//!         Err(e) => Err(AssemblyError::LinkerError(e))
+//!         Err((s, e)) => Err(AssemblyError::LinkerError(s, e))
 //!     }
 //! }
 //! ```
 //! 
 
-use std::collections::HashMap;
-use std::convert::identity;
+use std::collections::{HashMap, HashSet};
 use crate::core::{instructions::BabyInstruction, MEMORY_WORDS, WORD};
-use super::parser::{LineType, Value, Instruction};
-use errors::{LinkingError, TagError, MemoryExceedingError};
+use super::parser::{LineType, Value, Instruction, expression::BinaryOperator};
+use super::diagnostics::SourceSpan;
+use errors::{LinkingError, TagError, TagDeclarationError, ExpressionError, PlacementError, MemoryExceedingError};
 
 
 /// Contains types for handling errors found during linking. 
 pub mod errors;
+/// Contains relocatable object files and the linker that combines them. 
+pub mod object;
+/// Contains functionality for saving and loading symbol tables. 
+pub mod symbols;
 #[cfg(test)]
 mod tests;
 
@@ -63,16 +70,359 @@ pub struct LinkerData(pub Vec<BabyInstruction>, pub HashMap<String, WORD>);
 /// If the all the contained value expressions can be resolved without error it will
 /// return an [Ok] with a [LinkerData]. 
 /// 
-/// Returns a [LinkingError] if an error is encountered resolving the values. 
+/// Returns the first [LinkingError] if an error is encountered resolving the values. 
 pub fn link_parsed_lines(lines: Vec<LineType>) -> Result<LinkerData, LinkingError> {
-    let inlined_tags = inline_tags(lines);
-    let tag_values = position_tags(&inlined_tags);
-    let preprocessed_lines: Vec<UnlinkedData> = inlined_tags.iter()
-        .map(|(_, t)| t.clone())
+    let located: Vec<(usize, LineType)> = lines.into_iter().enumerate().collect();
+    link_located_lines(located).map_err(|mut e| e.swap_remove(0).1)
+}
+
+/// Links parsed lines along with where in the source each line came from, 
+/// as returned by [parse_asm_source][crate::assembler::parser::parse_asm_source]. 
+/// 
+/// Works the same as [link_parsed_lines], but returns a tuple of [SourceSpan] 
+/// and [LinkingError] if an error is encountered, the span pointing at the 
+/// unknown tag reference, or the first line that does not fit in memory. 
+/// 
+/// # Parameters
+/// * `lines` - The source spans and parsed lines. 
+/// 
+pub fn link_source_lines(lines: Vec<(SourceSpan, LineType)>) -> Result<LinkerData, (SourceSpan, LinkingError)> {
+    link_source_lines_all(lines).map_err(|mut e| e.swap_remove(0))
+}
+
+/// Links parsed lines along with where in the source each line came from, 
+/// as per [link_source_lines], but returns every error found rather than 
+/// just the first. 
+/// 
+/// If the program does not fit in memory this will be the first error, 
+/// followed by every unknown tag reference in the order of the lines. 
+/// 
+/// # Parameters
+/// * `lines` - The source spans and parsed lines. 
+/// 
+pub fn link_source_lines_all(lines: Vec<(SourceSpan, LineType)>) -> Result<LinkerData, Vec<(SourceSpan, LinkingError)>> {
+    link_located_lines(lines).map_err(narrow_errors)
+}
+
+/// Narrows the source span of each unknown tag error to the tag reference. 
+fn narrow_errors(errors: Vec<(SourceSpan, LinkingError)>) -> Vec<(SourceSpan, LinkingError)> {
+    errors.into_iter()
+        .map(|(s, e)| match &e {
+            LinkingError::TagError(TagError::UnknownTagName(t)) => (s.narrow(&format!("${}", t)), e),
+            _ => (s, e),
+        })
+        .collect()
+}
+
+/// Links parsed lines each paired with a location, returning the location 
+/// of each line that caused an error. 
+fn link_located_lines<L: Clone>(lines: Vec<(L, LineType)>) -> Result<LinkerData, Vec<(L, LinkingError)>> {
+    let (locations, lines): (Vec<L>, Vec<LineType>) = lines.into_iter().unzip();
+    let mut errors: Vec<(usize, LinkingError)> = find_tag_errors(&lines).into_iter()
+        .map(|(i, e)| (i, LinkingError::TagDeclarationError(e)))
+        .collect();
+    let constants = collect_constants(&lines);
+
+    let (placed, placement_errors) = place_lines(&lines, &layout_symbols(&constants));
+    errors.extend(placement_errors);
+    let (placed, beyond): (Vec<PlacedData>, Vec<PlacedData>) = placed.into_iter()
+        .partition(|(a, _, _, _)| *a < MEMORY_WORDS);
+    let size = placed.iter().map(|(a, _, _, _)| a + 1).max().unwrap_or(0);
+    let mut inlined_tags = vec![(vec![], UnlinkedData::Absolute(Value::Value(0))); size];
+    let mut placed_lines: Vec<Option<usize>> = vec![None; size];
+    for (address, tags, data, i) in placed {
+        inlined_tags[address].0.extend(tags);
+        if placed_lines[address].is_none() {
+            inlined_tags[address].1 = data;
+            placed_lines[address] = Some(i);
+        }
+    }
+
+    let mut tag_values = position_tags(&inlined_tags);
+    tag_values.extend(beyond.iter().flat_map(|(a, t, _, _)| t.iter().map(|t| (t.clone(), *a as WORD))));
+    let (symbols, constant_errors) = resolve_constants(&constants, &tag_values);
+    errors.extend(constant_errors);
+    // Anything that does not fit in memory is still checked for errors 
+    errors.extend(beyond.iter().filter_map(|(_, _, d, i)| d.resolve(&symbols).err().map(|e| (*i, e))));
+    let preprocessed_lines: Vec<UnlinkedData> = inlined_tags.into_iter()
+        .map(|(_, t)| t)
+        .collect();
+    let linked = match link_tags(preprocessed_lines, &symbols) {
+        Ok(v) => Some(v),
+        Err(e) => { errors.extend(e.into_iter().filter_map(|(a, e)| Some((placed_lines.get(a).copied().flatten()?, e)))); None }
+    };
+
+    match linked {
+        Some(v) if errors.is_empty() => Ok(LinkerData(v, tag_values)),
+        _ => {
+            errors.sort_by_key(|(i, e)| (!matches!(e, LinkingError::MemoryExceedingError(_)), *i));
+            Err(errors.into_iter().map(|(i, e)| (locations[i].clone(), e)).collect())
+        }
+    }
+}
+
+/// Finds the address of every word placed in memory by parsed lines each 
+/// paired with a location, such as the [SourceSpan] of the line. 
+/// 
+/// Returns each address in memory along with the location of the line that 
+/// placed it, in the order of the lines, words that do not fit in memory 
+/// are left out. 
+/// 
+/// # Example 
+/// ```
+/// use baby_emulator::assembler::parser::{LineType, Value};
+/// use baby_emulator::assembler::linker::word_locations;
+/// 
+/// let lines = vec![
+///     ("a", LineType::Absolute(Value::Value(1))),
+///     ("b", LineType::Origin(Value::Value(5))),
+///     ("c", LineType::Words(vec![Value::Value(2), Value::Value(3)])),
+/// ];
+/// assert_eq!(word_locations(&lines), vec![(0, "a"), (5, "c"), (6, "c")]);
+/// ```
+/// 
+pub fn word_locations<L: Clone>(lines: &[(L, LineType)]) -> Vec<(usize, L)> {
+    let parsed: Vec<LineType> = lines.iter().map(|(_, l)| l.clone()).collect();
+    let (placed, _) = place_lines(&parsed, &layout_symbols(&collect_constants(&parsed)));
+    placed.into_iter()
+        .filter(|(a, _, _, _)| *a < MEMORY_WORDS)
+        .map(|(a, _, _, i)| (a, lines[i].0.clone()))
+        .collect()
+}
+
+/// Collects every constant declared in parsed lines along with the 
+/// index of the line declaring it, as taken by [resolve_constants]. 
+pub fn collect_constants(lines: &[LineType]) -> Vec<(usize, String, Value)> {
+    lines.iter().enumerate()
+        .filter_map(|(i, l)| match l {
+            LineType::Constant(n, v) => Some((i, n.clone(), v.clone())),
+            _ => None
+        })
+        .collect()
+}
+
+/// Data placed in memory, holds the address, the names of the tags marking 
+/// it, the unlinked data and the index of the line it came from. 
+pub type PlacedData = (usize, Vec<String>, UnlinkedData, usize);
+
+/// Works out the address of every word of data in a vector of parsed lines, 
+/// following any placement directives. 
+/// 
+/// Lines are placed one after another from address 0, `.org` moves the 
+/// address the next line is placed at, `.fill` and `.reserve` take up 
+/// their count of words, and `.word` one word for each value. The counts 
+/// and addresses of placement directives are evaluated against `symbols`, 
+/// which cannot include tags as these are not known until everything is placed. 
+/// 
+/// Returns the placed data in the order of the lines, including anything 
+/// past the end of memory, along with any errors paired with the index of 
+/// the line that caused them: a word placed over an earlier one (every word 
+/// is still returned), placement values that cannot be evaluated or are 
+/// negative, and [LinkingError::MemoryExceedingError] at the first line that 
+/// does not fit in memory. 
+/// 
+/// A `.fill` or `.reserve` is never returned as more words than fit in memory. 
+/// 
+/// # Parameters
+/// * `lines` - The parsed lines. 
+/// * `symbols` - The constant names and values that can be used in placement directives. 
+/// 
+/// # Example 
+/// ```
+/// use std::collections::HashMap;
+/// use baby_emulator::assembler::parser::{LineType, Value};
+/// use baby_emulator::assembler::linker::place_lines;
+/// 
+/// let lines = vec![
+///     LineType::Absolute(Value::Value(1)),
+///     LineType::Origin(Value::Value(20)),
+///     LineType::Tag("table".to_owned()),
+///     LineType::Words(vec![Value::Value(2), Value::Value(3)]),
+/// ];
+/// let (placed, errors) = place_lines(&lines, &HashMap::new());
+/// let addresses: Vec<usize> = placed.iter().map(|(a, _, _, _)| *a).collect();
+/// assert_eq!(addresses, vec![0, 20, 21]);
+/// assert_eq!(placed[1].1, vec!["table".to_owned()]);
+/// assert!(errors.is_empty());
+/// ```
+/// 
+pub fn place_lines(lines: &[LineType], symbols: &HashMap<String, WORD>) -> (Vec<PlacedData>, Vec<(usize, LinkingError)>) {
+    let mut placed: Vec<PlacedData> = vec![];
+    let mut errors: Vec<(usize, LinkingError)> = vec![];
+    let mut used: HashSet<usize> = HashSet::new();
+    let mut tags: Vec<String> = vec![];
+    let mut address = 0;
+    let mut end = 0;
+    let mut overflow: Option<usize> = None;
+
+    for (i, line) in lines.iter().enumerate() {
+        let count = |n: &Value| placement_value(n, symbols).map_err(|e| (i, e));
+        let (size, data) = match line {
+            LineType::Tag(t) => { tags.push(t.clone()); continue; },
+            LineType::Absolute(v) => (1, vec![UnlinkedData::Absolute(v.clone())]),
+            LineType::Instruction(v) => (1, vec![UnlinkedData::Instruction(v.clone())]),
+            LineType::Words(v) => (v.len(), v.iter().map(|v| UnlinkedData::Absolute(v.clone())).collect()),
+            LineType::Fill(n, v) => match count(n) {
+                Ok(n) => (n, vec![UnlinkedData::Absolute(v.clone()); n.min(MEMORY_WORDS)]),
+                Err(e) => { errors.push(e); continue; }
+            },
+            LineType::Reserve(n) => match count(n) {
+                Ok(n) => (n, vec![UnlinkedData::Absolute(Value::Value(0)); n.min(MEMORY_WORDS)]),
+                Err(e) => { errors.push(e); continue; }
+            },
+            LineType::Origin(n) => {
+                match count(n) { Ok(n) => address = n, Err(e) => errors.push(e) };
+                continue;
+            },
+            _ => continue,
+        };
+        if size == 0 { continue; }
+        end = end.max(address.saturating_add(size));
+        if address.saturating_add(size) > MEMORY_WORDS { overflow.get_or_insert(i); }
+
+        let mut overlapped = false;
+        for (j, data) in data.into_iter().enumerate() {
+            let at = address.saturating_add(j);
+            if !used.insert(at) && !overlapped {
+                errors.push((i, LinkingError::PlacementError(PlacementError::Overlap(at))));
+                overlapped = true;
+            }
+            placed.push((at, std::mem::take(&mut tags), data, i));
+        }
+        address = address.saturating_add(size);
+    }
+    if let Some(i) = overflow {
+        let error = MemoryExceedingError { linked_size: end };
+        errors.push((i, LinkingError::MemoryExceedingError(error)));
+    }
+    (placed, errors)
+}
+
+/// Evaluates the count or address of a placement directive, these cannot 
+/// reference tags, and cannot be negative. 
+fn placement_value(value: &Value, symbols: &HashMap<String, WORD>) -> Result<usize, LinkingError> {
+    match UnlinkedData::resolve_value(value, symbols) {
+        Ok(v) => usize::try_from(v)
+            .map_err(|_| LinkingError::PlacementError(PlacementError::NegativeValue(value.to_asm_string()))),
+        Err(LinkingError::TagError(_)) => 
+            Err(LinkingError::PlacementError(PlacementError::NotConstant(value.to_asm_string()))),
+        Err(e) => Err(e),
+    }
+}
+
+/// Evaluates the constants that can be used in placement directives, those 
+/// that do not depend on any tags, either directly or through other constants. 
+fn layout_symbols(constants: &[(usize, String, Value)]) -> HashMap<String, WORD> {
+    let mut known: HashSet<&str> = HashSet::new();
+    loop {
+        let count = known.len();
+        for (_, name, value) in constants {
+            if value.tags().iter().all(|t| known.contains(t)) { known.insert(name); }
+        }
+        if known.len() == count { break; }
+    }
+    let layout: Vec<(usize, String, Value)> = constants.iter()
+        .filter(|(_, n, _)| known.contains(n.as_str()))
+        .cloned()
         .collect();
-    let processed_lines = link_tags(preprocessed_lines, &tag_values)
-        .map_err(|e| e)?;
-    Ok(LinkerData(processed_lines, tag_values))
+    resolve_constants(&layout, &HashMap::new()).0
+}
+
+/// Finds any problems with the tag declarations in a vector of parsed lines, 
+/// tags declared more than once, and tags with no value after them to mark. 
+/// 
+/// Returns each error along with the index of the offending tag line, every 
+/// declaration after the first is reported for a duplicate tag, constants 
+/// share names with tags so are counted as declarations too. 
+/// 
+/// # Example 
+/// ```
+/// use baby_emulator::assembler::parser::{LineType, Value};
+/// use baby_emulator::assembler::linker::find_tag_errors;
+/// use baby_emulator::assembler::linker::errors::TagDeclarationError;
+/// 
+/// let parsed_lines = vec![
+///     LineType::Tag("foo".to_owned()),
+///     LineType::Absolute(Value::Value(23)),
+///     LineType::Tag("foo".to_owned()),
+/// ];
+/// assert_eq!(find_tag_errors(&parsed_lines), vec![
+///     (2, TagDeclarationError::DuplicateTagName("foo".to_owned())),
+///     (2, TagDeclarationError::DanglingTag("foo".to_owned())),
+/// ]);
+/// ```
+/// 
+pub fn find_tag_errors(lines: &[LineType]) -> Vec<(usize, TagDeclarationError)> {
+    let last_data = lines.iter().rposition(|l| l.is_data());
+    let mut declared: HashSet<&str> = HashSet::new();
+    let mut errors = vec![];
+    for (i, line) in lines.iter().enumerate() {
+        let tag = match line {
+            LineType::Tag(t) | LineType::Constant(t, _) => t,
+            _ => continue,
+        };
+        if !declared.insert(tag) {
+            errors.push((i, TagDeclarationError::DuplicateTagName(tag.clone())));
+        }
+        if matches!(line, LineType::Tag(_)) && last_data.is_none_or(|d| i > d) {
+            errors.push((i, TagDeclarationError::DanglingTag(tag.clone())));
+        }
+    }
+    errors
+}
+
+/// Evaluates the values of constants, given the values of all the tags, 
+/// constants can reference tags and each other in any order. 
+/// 
+/// Returns the values of all the tags and constants together, along with 
+/// any errors evaluating the constants paired with the index of the line 
+/// they were declared on, a constant that depends on itself is reported 
+/// with [ExpressionError::CircularConstant]. 
+/// 
+/// # Parameters
+/// * `constants` - The line index, name and value of each constant. 
+/// * `tags` - The tag names and corresponding values. 
+/// 
+/// # Example 
+/// ```
+/// use std::collections::HashMap;
+/// use baby_emulator::assembler::parser::Value;
+/// use baby_emulator::assembler::linker::resolve_constants;
+/// 
+/// let tags = HashMap::from([("table".to_owned(), 10)]);
+/// let constants = vec![
+///     (0, "end".to_owned(), Value::parse("$table+$size").unwrap()),
+///     (1, "size".to_owned(), Value::parse("0d4").unwrap()),
+/// ];
+/// let (symbols, errors) = resolve_constants(&constants, &tags);
+/// assert!(errors.is_empty());
+/// assert_eq!(symbols.get("end"), Some(&14));
+/// ```
+/// 
+pub fn resolve_constants(constants: &[(usize, String, Value)], tags: &HashMap<String, WORD>) -> 
+    (HashMap<String, WORD>, Vec<(usize, LinkingError)>) {
+    let names: HashSet<&str> = constants.iter().map(|(_, n, _)| n.as_str()).collect();
+    let mut symbols = tags.clone();
+    let mut errors: Vec<(usize, LinkingError)> = vec![];
+    let mut pending: Vec<&(usize, String, Value)> = constants.iter().collect();
+    loop {
+        let count = pending.len();
+        pending.retain(|(i, name, value)| {
+            if value.tags().iter().any(|t| names.contains(t) && !symbols.contains_key(*t)) {
+                return true;
+            }
+            // Failed constants still get a value so nothing depending on them is reported 
+            let resolved = UnlinkedData::resolve_value(value, &symbols)
+                .unwrap_or_else(|e| { errors.push((*i, e)); 0 });
+            symbols.entry(name.clone()).or_insert(resolved);
+            false
+        });
+        if pending.len() == count { break; }
+    }
+    errors.extend(pending.into_iter()
+        .map(|(i, n, _)| (*i, LinkingError::ExpressionError(ExpressionError::CircularConstant(n.clone())))));
+    errors.sort_by_key(|(i, _)| *i);
+    (symbols, errors)
 }
 
 /// Takes a list of unlinked values and a collection of tag names and values, 
@@ -84,33 +434,37 @@ pub fn link_parsed_lines(lines: Vec<LineType>) -> Result<LinkerData, LinkingErro
 /// If the all the contained value expressions can be resolved without error it will
 /// return an [Ok] with a vector of each instruction [BabyInstruction]. 
 /// 
-/// Returns a [LinkingError] if an error is encountered resolving the values or if 
-/// the program stack is greater than the total Baby memory. 
+/// Returns every [LinkingError] along with the index of the value it was found 
+/// at if any values cannot be resolved, or if the program stack is greater than 
+/// the total Baby memory, which comes first at the index of the first value 
+/// that does not fit. 
 /// 
 /// # Parameters
 /// * `preprocessed_lines` - The unlinked data. 
 /// * `tag_values` - The tag names and corresponding values. 
 /// 
 fn link_tags(preprocessed_lines: Vec<UnlinkedData>, tag_values: &HashMap<String, WORD>) -> 
-    Result<Vec<BabyInstruction>, LinkingError> {
+    Result<Vec<BabyInstruction>, Vec<(usize, LinkingError)>> {
     let mut instructions: Vec<BabyInstruction> = vec![];
+    let mut errors: Vec<(usize, LinkingError)> = vec![];
 
     if preprocessed_lines.len() > MEMORY_WORDS { 
-        return Err(LinkingError::MemoryExceedingError(MemoryExceedingError { linked_size: preprocessed_lines.len() }));
-     }
+        let error = MemoryExceedingError { linked_size: preprocessed_lines.len() };
+        errors.push((MEMORY_WORDS, LinkingError::MemoryExceedingError(error)));
+    }
 
-    for line in preprocessed_lines {
-        let val = match line.resolve(tag_values) {
-            Ok(v) => v,
-            Err(e) => return Err(LinkingError::TagError(e))
+    for (i, line) in preprocessed_lines.iter().enumerate() {
+        match line.resolve(tag_values) {
+            Ok(v) => instructions.push(v),
+            Err(e) => errors.push((i, e))
         };
-        instructions.push(val);
     }
-    Ok(instructions)
+    if errors.is_empty() { Ok(instructions) }
+    else { Err(errors) }
 }
 
-/// Takes a vector of tuples of unlinked machine code values and any tag names specified,
-/// generates a collection of tag names plus their index in the supplied vector. 
+/// Takes a vector of tuples of unlinked machine code values and the names of any tags 
+/// specified, generates a collection of tag names plus their index in the supplied vector. 
 /// 
 /// # Example 
 /// ```
@@ -121,19 +475,21 @@ fn link_tags(preprocessed_lines: Vec<UnlinkedData>, tag_values: &HashMap<String,
 /// use baby_emulator::assembler::linker::UnlinkedData;
 /// 
 /// let parsed_lines = vec![
-///     LineType::Tag("foo".to_owned()), // tag "foo" at index 0
+///     LineType::Absolute(Value::Value(5)),
+///     LineType::Tag("foo".to_owned()), // tags "foo" and "bar" at index 1
+///     LineType::Tag("bar".to_owned()),
 ///     LineType::Absolute(Value::Value(23))
 /// ];
 /// let inlined = inline_tags(parsed_lines);
 /// let tags = position_tags(&inlined);
-/// assert_eq!(tags.get("foo"), Some(&0));
+/// assert_eq!(tags.get("foo"), Some(&1));
+/// assert_eq!(tags.get("bar"), Some(&1));
 /// ```
 /// 
-pub fn position_tags(lines: &Vec<(Option<String>, UnlinkedData)>) -> HashMap<String, WORD> {
-    lines.iter().enumerate().filter_map(|(i, (t, _))| match t {
-        Some(v) => Some((v.clone(), i as WORD)),
-        None => None
-    }).collect()
+pub fn position_tags(lines: &[(Vec<String>, UnlinkedData)]) -> HashMap<String, WORD> {
+    lines.iter().enumerate()
+        .flat_map(|(i, (t, _))| t.iter().map(move |v| (v.clone(), i as WORD)))
+        .collect()
 }
 
 /// Takes a vector of parsed asm lines, converts them to [UnlinkedData]
@@ -141,12 +497,22 @@ pub fn position_tags(lines: &Vec<(Option<String>, UnlinkedData)>) -> HashMap<Str
 /// representing positions in the program stack being placed in a tuple 
 /// with the correspoding value it references. 
 /// 
+/// Any number of tags can be stacked up before a value, tags after the 
+/// last value have nothing to reference and are dropped, these are 
+/// reported by [find_tag_errors]. 
+/// 
+/// This places every value one after another, [place_lines] also 
+/// handles placement directives, which are skipped here. 
+/// 
+/// Macros should be expanded with [expand_macros][crate::assembler::expander::expand_macros] 
+/// first, any macro lines left are skipped. 
+/// 
 /// # Parameters 
 /// * `lines` - A vector of parsed asm lines. 
 /// 
 /// # Returns
 /// - A vector of tuples of all the unlinked machine code values plus 
-/// a `Some(String)` name of a tag if one was specified. 
+///   the names of all the tags specified before it. 
 /// 
 /// # Example 
 /// ```
@@ -161,31 +527,28 @@ pub fn position_tags(lines: &Vec<(Option<String>, UnlinkedData)>) -> HashMap<Str
 /// ];
 /// let inlined = inline_tags(parsed_lines);
 /// match &inlined[0] {
-///     (Some(tag_name), UnlinkedData::Absolute(Value::Value(line))) => {
-///         assert_eq!(tag_name, "foo");
+///     (tag_names, UnlinkedData::Absolute(Value::Value(line))) => {
+///         assert_eq!(tag_names, &vec!["foo".to_owned()]);
 ///         assert_eq!(*line, 23);
 ///     },
 ///     _ => panic!()
 /// }
 /// ```
 /// 
-pub fn inline_tags(lines: Vec<LineType>) -> Vec<(Option<String>, UnlinkedData)> {
-    lines.iter().enumerate().map(|(i, v)| {
-        let i = if i == 0 { 1 } else { i };
-        match &lines.get(i - 1) {
-            Some(LineType::Tag(tag)) => (Some(tag.clone()), v.clone()),
-            _ => (None::<String>, v.clone())
-        }
-    })
-    .map(|(t, l)| match l {
-        LineType::Absolute(v) => Some((t, UnlinkedData::Absolute(v))),
-        LineType::Instruction(v) => Some((t, UnlinkedData::Instruction(v))),
-        _ => None
-    })
-    .filter_map(identity)
-    .collect()
+pub fn inline_tags(lines: Vec<LineType>) -> Vec<(Vec<String>, UnlinkedData)> {
+    let mut tags: Vec<String> = vec![];
+    let mut res = vec![];
+    for line in lines {
+        let data = match line {
+            LineType::Tag(t) => { tags.push(t); continue; },
+            LineType::Absolute(v) => UnlinkedData::Absolute(v),
+            LineType::Instruction(v) => UnlinkedData::Instruction(v),
+            _ => continue,
+        };
+        res.push((std::mem::take(&mut tags), data));
+    }
+    res
 }
-
 /// Represents either an instruction or an absolute value. 
 /// 
 /// Both of these are "real" values (I.E. convert to real machine code values),
@@ -195,7 +558,7 @@ pub fn inline_tags(lines: Vec<LineType>) -> Vec<(Option<String>, UnlinkedData)>
 /// This represents the expression that have not yet had their values fully
 /// determined, anc contain say unverified references to tags that need
 /// verifiying and resolving to a concrete value. 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum UnlinkedData {
     Absolute(Value),
     Instruction(Instruction),
@@ -209,37 +572,51 @@ impl UnlinkedData {
     /// If the contained value expression is already a concrete value it will just
     /// return that, if the contained value expression is a tag reference it 
     /// will lookup the tag name in the supplied hashmap and try to find it's value
-    /// returning [TagError] if it cannot be found. 
+    /// returning [LinkingError::TagError] if it cannot be found, or evaluate the
+    /// expression, see [UnlinkedData::resolve_value]. 
     /// 
     /// # Parameters
     /// 
     /// * `tags` - A hashmap pf tag names and corresponding values. 
     /// 
-    pub fn resolve(&self, tags: &HashMap<String, WORD>) -> Result<BabyInstruction, TagError> {
+    pub fn resolve(&self, tags: &HashMap<String, WORD>) -> Result<BabyInstruction, LinkingError> {
         match self {
             UnlinkedData::Absolute(v) => Self::resolve_absolute_value(v, tags),
             UnlinkedData::Instruction(c) => Self::resolve_instruction(c, tags)
         }
     }
 
+    /// Returns the value expression held by the data, the operand of an instruction. 
+    pub fn get_value(&self) -> Value {
+        match self {
+            UnlinkedData::Absolute(v) => v.clone(),
+            UnlinkedData::Instruction(i) => i.get_operand(),
+        }
+    }
+
+    /// Returns the data as a line of modern notation asm. 
+    pub fn to_asm_string(&self) -> String {
+        match self {
+            UnlinkedData::Absolute(v) => format!("abs {}", v.to_asm_string()),
+            UnlinkedData::Instruction(i) => i.to_asm_string(false),
+        }
+    }
+
     /// Converts an [Instruction] object to [BabyInstruction], resolving the inner 
     /// operand value expression to a concrete value. 
     /// 
     /// If the inner value expression can be determined, it will return [BabyInstruction]. 
     /// 
-    /// Will return [TagError] if the value expresion is a tag reference that 
-    /// cannot be determined. 
+    /// Will return [LinkingError] if the value expresion references a tag that 
+    /// cannot be determined or cannot be evaluated. 
     /// 
     /// # Parameters 
     /// 
     /// * `instr` - The instruction to be resolved. 
     /// * `tags` - A collection of tag names and values to be looked up. 
     /// 
-    pub fn resolve_instruction(instr: &Instruction, tags: &HashMap<String, WORD>) -> Result<BabyInstruction, TagError> {
-        let val = match Self::resolve_value(&instr.get_operand(), tags) {
-            Ok(v) => v,
-            Err(v) => return Err(TagError::UnknownTagName(v))
-        } as u16;
+    pub fn resolve_instruction(instr: &Instruction, tags: &HashMap<String, WORD>) -> Result<BabyInstruction, LinkingError> {
+        let val = Self::resolve_value(&instr.get_operand(), tags)? as u16;
         match instr {
             Instruction::Jump(_) => Ok(BabyInstruction::Jump(val)),
             Instruction::RelativeJump(_) => Ok(BabyInstruction::RelativeJump(val)),
@@ -254,30 +631,37 @@ impl UnlinkedData {
     /// Tries to resolve an absolute value. 
     /// 
     /// Wrapper for [UnlinkedData::resolve_value], returns [BabyInstruction::AbsoluteValue]
-    /// if successful, and [TagError::UnknownTagName] if a tag name reference cannot 
-    /// be determined. 
+    /// if successful, and [LinkingError] if a tag name reference cannot be 
+    /// determined or the expression cannot be evaluated. 
     /// 
     /// # Parameters 
     /// * `val` - The value to be resolved. 
     /// * `tags` - A collection of tag names and values to be looked up. 
     /// 
-    pub fn resolve_absolute_value(val: &Value, tags: &HashMap<String, WORD>) -> Result<BabyInstruction, TagError> {
-        match Self::resolve_value(val, tags) {
-            Ok(v) => Ok(BabyInstruction::AbsoluteValue(v)),
-            Err(v) => Err(TagError::UnknownTagName(v))
-        }
+    pub fn resolve_absolute_value(val: &Value, tags: &HashMap<String, WORD>) -> Result<BabyInstruction, LinkingError> {
+        Self::resolve_value(val, tags).map(BabyInstruction::AbsoluteValue)
     }
 
     /// Helper function, tries to resolve a value expression. 
     /// 
     /// If the value expression is just [Value::Value] then it will just return
     /// the inner concrete value, if it's a tag reference, it will try to lookup the 
-    /// tag value in the supplied hashmap, returning the tag name if it can't be 
-    /// found. 
-    pub fn resolve_value(val: &Value, tags: &HashMap<String, WORD>) -> Result<WORD, String> {
+    /// tag value in the supplied hashmap, returning [TagError::UnknownTagName] if 
+    /// it can't be found, and operators are applied to the resolved values, 
+    /// returning an [ExpressionError] if dividing by zero or shifting out of range. 
+    pub fn resolve_value(val: &Value, tags: &HashMap<String, WORD>) -> Result<WORD, LinkingError> {
         match val {
-            Value::Tag(tag) => Self::get_tag(tag, tags),
-            Value::Value(v) => return Ok(*v),
+            Value::Tag(tag) => Self::get_tag(tag, tags)
+                .map_err(|t| LinkingError::TagError(TagError::UnknownTagName(t))),
+            Value::Value(v) => Ok(*v),
+            Value::Unary(o, v) => Ok(o.apply(Self::resolve_value(v, tags)?)),
+            Value::Binary(o, l, r) => {
+                let (l, r) = (Self::resolve_value(l, tags)?, Self::resolve_value(r, tags)?);
+                o.apply(l, r).ok_or_else(|| LinkingError::ExpressionError(match o {
+                    BinaryOperator::Divide => ExpressionError::DivideByZero(val.to_asm_string()),
+                    _ => ExpressionError::ShiftOutOfRange(val.to_asm_string()),
+                }))
+            },
         }
     }
 
@@ -0,0 +1,427 @@
+//! # Object Files
+//!
+//! This module lets a program be assembled in parts, each source file is
+//! assembled to a relocatable [ObjectFile][crate::assembler::linker::object::ObjectFile]
+//! that can be saved and reused, then any number of objects are combined
+//! into a single program by [link_objects][crate::assembler::linker::object::link_objects].
+//!
+//! An object holds its words still unresolved, as [UnlinkedData][crate::assembler::linker::UnlinkedData],
+//! with the address of each tag relative to the start of the object, as it
+//! is not known where the object will be placed until the final link.
+//! Any `.org` in an object is also relative to the start of the object.
+//!
+//! Names are local to an object unless they are exported with `.export`,
+//! another object can then reference them once they are imported with `.import`.
+//! Every word that references a tag or constant has a [Relocation][crate::assembler::linker::object::Relocation]
+//! entry, these are the only words changed by linking.
+//!
+//! The size of the program is only checked against the Baby's memory once
+//! every object is linked, apart from an object that does not fit in memory
+//! on its own, which is reported straight away as it can never be linked.
+//!
+//! # Example
+//! ```
+//! use baby_emulator::assembler::assemble_object;
+//! use baby_emulator::assembler::linker::{LinkerData, object::link_objects};
+//!
+//! let main = assemble_object(".import negate\nldn $negate\nstp", false).ok().unwrap();
+//! let library = assemble_object(".export negate\n:negate\nabs 0d-5", false).ok().unwrap();
+//! match link_objects(&[main, library]) {
+//!     Ok(LinkerData(program, tags)) => {
+//!         assert_eq!(program.len(), 3);
+//!         assert_eq!(tags.get("negate"), Some(&2));
+//!     },
+//!     Err(_) => panic!(),
+//! }
+//! ```
+//!
+//! # Format
+//! Objects are saved as text, one entry per line after a `babyobj 1` header,
+//! words are written as modern notation asm:
+//!
+//! ```text
+//! babyobj 1
+//! export negate
+//! import print
+//! tag negate 2
+//! equ limit 0d40
+//! reloc 0 negate
+//! word 0 ldn $negate
+//! word 1 stp
+//! word 2 abs 0d-5
+//! ```
+//!
+
+use std::collections::{HashMap, HashSet};
+use crate::core::{instructions::BabyInstruction, MEMORY_WORDS, WORD};
+use super::super::diagnostics::SourceSpan;
+use super::super::parser::{LineType, Instruction, Value};
+use super::errors::{LinkingError, ObjectError, TagError, MemoryExceedingError};
+use super::{LinkerData, UnlinkedData, PlacedData};
+use super::{collect_constants, find_tag_errors, layout_symbols, narrow_errors, place_lines, resolve_constants};
+
+
+/// The first line of every object file.
+pub const OBJECT_HEADER: &str = "babyobj 1";
+
+/// A word in an object that references tags or constants, so has to be
+/// resolved at the final link.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Relocation {
+    /// The address of the word relative to the start of the object.
+    pub offset: usize,
+    /// The names the word references.
+    pub symbols: Vec<String>,
+}
+
+/// A relocatable object, a part of a program assembled on its own that
+/// can be linked with other objects.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjectFile {
+    /// Every word of the object in order, unused space is zero.
+    pub words: Vec<UnlinkedData>,
+    /// The address of every tag relative to the start of the object.
+    pub tags: HashMap<String, usize>,
+    /// Every constant in the order they are declared.
+    pub constants: Vec<(String, Value)>,
+    /// The names visible to other objects.
+    pub exports: Vec<String>,
+    /// The names declared in other objects.
+    pub imports: Vec<String>,
+    /// The words that reference tags or constants.
+    pub relocations: Vec<Relocation>,
+}
+
+impl ObjectFile {
+
+    /// Writes the object as text, as described in the [module][crate::assembler::linker::object]
+    /// documentation, which can be read back with [ObjectFile::parse].
+    pub fn to_text(&self) -> String {
+        let mut tags: Vec<(&String, &usize)> = self.tags.iter().collect();
+        tags.sort_by(|(a, x), (b, y)| x.cmp(y).then(a.cmp(b)));
+
+        let mut lines = vec![OBJECT_HEADER.to_owned()];
+        lines.extend(self.exports.iter().map(|n| format!("export {}", n)));
+        lines.extend(self.imports.iter().map(|n| format!("import {}", n)));
+        lines.extend(tags.into_iter().map(|(n, a)| format!("tag {} {}", n, a)));
+        lines.extend(self.constants.iter().map(|(n, v)| format!("equ {} {}", n, v.to_asm_string())));
+        lines.extend(self.relocations.iter().map(|r| format!("reloc {} {}", r.offset, r.symbols.join(", "))));
+        lines.extend(self.words.iter().enumerate().map(|(i, w)| format!("word {} {}", i, w.to_asm_string())));
+        lines.join("\n") + "\n"
+    }
+
+    /// Reads an object written by [ObjectFile::to_text].
+    ///
+    /// Returns [ObjectError::InvalidLine] for the first line that cannot be
+    /// read, or [ObjectError::WordOutOfStore] for a word placed outside of
+    /// the store, blank lines and comments starting with `;` are ignored.
+    ///
+    /// # Example
+    /// ```
+    /// use baby_emulator::assembler::linker::object::ObjectFile;
+    ///
+    /// let object = ObjectFile::parse("babyobj 1\nexport a\ntag a 0\nword 0 abs 0d5").ok().unwrap();
+    /// assert_eq!(object.tags.get("a"), Some(&0));
+    /// assert!(ObjectFile::parse("word 0 abs 0d5").is_err());
+    /// ```
+    ///
+    pub fn parse(text: &str) -> Result<ObjectFile, ObjectError> {
+        let mut object = ObjectFile {
+            words: vec![], tags: HashMap::new(), constants: vec![],
+            exports: vec![], imports: vec![], relocations: vec![],
+        };
+        let mut lines = text.lines().enumerate()
+            .map(|(i, l)| (i + 1, l.trim()))
+            .filter(|(_, l)| !l.is_empty() && !l.starts_with(';'));
+        match lines.next() {
+            Some((_, OBJECT_HEADER)) => (),
+            Some((n, l)) => return Err(ObjectError::InvalidLine(n, l.to_owned())),
+            None => return Err(ObjectError::InvalidLine(1, String::new())),
+        }
+        for (n, line) in lines {
+            parse_object_line(&mut object, n, line)?;
+        }
+        Ok(object)
+    }
+}
+
+/// Reads a single line of an object file into the object, returns
+/// [ObjectError::InvalidLine] if the line is malformed.
+fn parse_object_line(object: &mut ObjectFile, n: usize, line: &str) -> Result<(), ObjectError> {
+    let invalid = || ObjectError::InvalidLine(n, line.to_owned());
+    let (kind, rest) = line.split_once(char::is_whitespace).ok_or_else(invalid)?;
+    let rest = rest.trim();
+    let (first, second) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    match kind {
+        "export" => object.exports.push(rest.to_owned()),
+        "import" => object.imports.push(rest.to_owned()),
+        "tag" => { object.tags.insert(first.to_owned(), second.trim().parse().map_err(|_| invalid())?); },
+        "equ" => object.constants.push((first.to_owned(), Value::parse(second).map_err(|_| invalid())?)),
+        "reloc" => object.relocations.push(Relocation {
+            offset: first.parse().map_err(|_| invalid())?,
+            symbols: second.split(',').map(|s| s.trim().to_owned()).collect(),
+        }),
+        "word" => {
+            let offset: usize = first.parse().map_err(|_| invalid())?;
+            if offset >= MEMORY_WORDS { return Err(ObjectError::WordOutOfStore(n, offset)); }
+            if object.words.len() <= offset {
+                object.words.resize(offset + 1, UnlinkedData::Absolute(Value::Value(0)));
+            }
+            object.words[offset] = parse_word(second.trim()).ok_or_else(invalid)?;
+        },
+        _ => return Err(invalid()),
+    }
+    Ok(())
+}
+
+/// Reads a word of an object file, written as an instruction or `abs`
+/// value in modern notation, keeping the case of any tag names.
+fn parse_word(word: &str) -> Option<UnlinkedData> {
+    let (op, operand) = word.split_once(char::is_whitespace).unwrap_or((word, ""));
+    let value = || Value::parse(operand).ok();
+    let instr = match op {
+        "abs" => return Some(UnlinkedData::Absolute(value()?)),
+        "jmp" => Instruction::Jump(value()?),
+        "jrp" => Instruction::RelativeJump(value()?),
+        "ldn" => Instruction::Negate(value()?),
+        "sto" => Instruction::Store(value()?),
+        "sub" => Instruction::Subtract(value()?),
+        "cmp" => Instruction::Test,
+        "stp" => Instruction::Stop,
+        _ => return None,
+    };
+    Some(UnlinkedData::Instruction(instr))
+}
+
+/// Assembles parsed lines into an [ObjectFile], each line paired with
+/// a location, such as its [SourceSpan].
+///
+/// Works the same as [link_parsed_lines][super::link_parsed_lines] but leaves
+/// every reference unresolved, returns every error found along with the
+/// location of the line that caused it, the object only has to fit in memory
+/// on its own, the linked program is checked by [link_objects].
+///
+/// # Parameters
+/// * `lines` - The parsed lines each paired with a location.
+///
+/// # Example
+/// ```
+/// use baby_emulator::assembler::parser::{LineType, Instruction, Value};
+/// use baby_emulator::assembler::linker::object::{object_from_lines, Relocation};
+///
+/// let lines = vec![
+///     (1, LineType::Import(vec!["value".to_owned()])),
+///     (2, LineType::Instruction(Instruction::Negate(Value::Tag("value".to_owned())))),
+/// ];
+/// let object = object_from_lines(lines).ok().unwrap();
+/// assert_eq!(object.relocations, vec![Relocation { offset: 0, symbols: vec!["value".to_owned()] }]);
+/// ```
+///
+pub fn object_from_lines<L: Clone>(lines: Vec<(L, LineType)>) -> Result<ObjectFile, Vec<(L, LinkingError)>> {
+    let (locations, lines): (Vec<L>, Vec<LineType>) = lines.into_iter().unzip();
+    let mut errors: Vec<(usize, LinkingError)> = find_tag_errors(&lines).into_iter()
+        .map(|(i, e)| (i, LinkingError::TagDeclarationError(e)))
+        .collect();
+    let constants = collect_constants(&lines);
+    let (placed, placement_errors) = place_lines(&lines, &layout_symbols(&constants));
+    errors.extend(placement_errors);
+
+    let (words, tags) = object_words(placed);
+    let declared: HashSet<&str> = tags.keys().map(|t| t.as_str())
+        .chain(constants.iter().map(|(_, n, _)| n.as_str()))
+        .collect();
+    let mut exports: Vec<String> = vec![];
+    let mut imports: Vec<String> = vec![];
+    for (i, line) in lines.iter().enumerate() {
+        match line {
+            LineType::Export(names) => for n in names {
+                if !declared.contains(n.as_str()) {
+                    errors.push((i, LinkingError::ObjectError(ObjectError::UnknownExport(n.clone()))));
+                } else if !exports.contains(n) { exports.push(n.clone()); }
+            },
+            LineType::Import(names) => for n in names {
+                if declared.contains(n.as_str()) {
+                    errors.push((i, LinkingError::ObjectError(ObjectError::ImportDeclared(n.clone()))));
+                } else if !imports.contains(n) { imports.push(n.clone()); }
+            },
+            _ => (),
+        }
+    }
+    for (i, line) in lines.iter().enumerate() {
+        for tag in line_values(line).iter().flat_map(|v| v.tags()) {
+            if !declared.contains(tag) && !imports.iter().any(|n| n == tag) {
+                errors.push((i, LinkingError::TagError(TagError::UnknownTagName(tag.to_owned()))));
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        errors.sort_by_key(|(i, _)| *i);
+        return Err(errors.into_iter().map(|(i, e)| (locations[i].clone(), e)).collect());
+    }
+    let relocations = words.iter().enumerate()
+        .filter_map(|(offset, w)| {
+            let mut symbols: Vec<String> = vec![];
+            for t in w.get_value().tags() {
+                if !symbols.iter().any(|s| s == t) { symbols.push(t.to_owned()); }
+            }
+            if symbols.is_empty() { None } else { Some(Relocation { offset, symbols }) }
+        })
+        .collect();
+    Ok(ObjectFile {
+        words,
+        tags,
+        constants: constants.into_iter().map(|(_, n, v)| (n, v)).collect(),
+        exports,
+        imports,
+        relocations,
+    })
+}
+
+/// Assembles parsed lines along with where in the source each line came
+/// from into an [ObjectFile], as per [object_from_lines], with the span of
+/// any unknown tag error narrowed to the tag reference.
+///
+/// # Parameters
+/// * `lines` - The source spans and parsed lines.
+///
+pub fn object_from_source_lines(lines: Vec<(SourceSpan, LineType)>) -> Result<ObjectFile, Vec<(SourceSpan, LinkingError)>> {
+    object_from_lines(lines).map_err(narrow_errors)
+}
+
+/// Lays out placed data as the dense words of an object, returning the words
+/// and the address of each tag, where words overlap the first is kept.
+fn object_words(placed: Vec<PlacedData>) -> (Vec<UnlinkedData>, HashMap<String, usize>) {
+    let size = placed.iter().map(|(a, _, _, _)| a + 1).max().unwrap_or(0);
+    let mut words = vec![UnlinkedData::Absolute(Value::Value(0)); size];
+    let mut used: HashSet<usize> = HashSet::new();
+    let mut tags: HashMap<String, usize> = HashMap::new();
+    for (address, names, data, _) in placed {
+        tags.extend(names.into_iter().map(|t| (t, address)));
+        if used.insert(address) { words[address] = data; }
+    }
+    (words, tags)
+}
+
+/// Returns every value expression in a line that is resolved when linking.
+fn line_values(line: &LineType) -> Vec<&Value> {
+    match line {
+        LineType::Absolute(v) | LineType::Constant(_, v) | LineType::Fill(_, v) => vec![v],
+        LineType::Instruction(Instruction::Jump(v) | Instruction::RelativeJump(v) | Instruction::Negate(v) |
+            Instruction::Store(v) | Instruction::Subtract(v)) => vec![v],
+        LineType::Words(v) => v.iter().collect(),
+        _ => vec![],
+    }
+}
+
+/// Links objects into a single program, each object is placed straight
+/// after the one before, starting from address 0.
+///
+/// Each import is bound to the object exporting the name, then every
+/// relocated word is resolved. Returns every error found along with the
+/// index of the object that caused it, a [MemoryExceedingError] if the
+/// linked program does not fit in memory, names exported by more than one
+/// object, imports no object exports, and any errors evaluating values.
+///
+/// The tag values returned are every tag in the first object, as this is
+/// usually the main program, along with every exported tag.
+///
+/// # Parameters
+/// * `objects` - The objects to link in order.
+///
+pub fn link_objects(objects: &[ObjectFile]) -> Result<LinkerData, Vec<(usize, LinkingError)>> {
+    let mut errors: Vec<(usize, LinkingError)> = vec![];
+    let bases: Vec<usize> = objects.iter()
+        .scan(0, |base, o| { let b = *base; *base += o.words.len(); Some(b) })
+        .collect();
+    let size: usize = objects.iter().map(|o| o.words.len()).sum();
+    if size > MEMORY_WORDS {
+        let first = (0..objects.len()).find(|o| bases[*o] + objects[*o].words.len() > MEMORY_WORDS).unwrap_or(0);
+        errors.push((first, LinkingError::MemoryExceedingError(MemoryExceedingError { linked_size: size })));
+    }
+
+    let mut owners: HashMap<&str, usize> = HashMap::new();
+    for (o, object) in objects.iter().enumerate() {
+        for name in &object.exports {
+            if owners.insert(name, o).is_some_and(|p| p != o) {
+                errors.push((o, LinkingError::ObjectError(ObjectError::DuplicateExport(name.clone()))));
+            }
+        }
+    }
+    let local_tags: Vec<HashMap<String, WORD>> = objects.iter().zip(&bases)
+        .map(|(o, base)| o.tags.iter().map(|(t, a)| (t.clone(), (base + a) as WORD)).collect())
+        .collect();
+    let exported_tags: HashMap<String, WORD> = owners.iter()
+        .filter_map(|(n, o)| Some((n.to_string(), *local_tags[*o].get(*n)?)))
+        .collect();
+    let mut exported = exported_tags.clone();
+
+    // Exported constants can depend on imports, so keep resolving until every chain is known
+    for _ in 0..objects.len() {
+        for (o, object) in objects.iter().enumerate() {
+            let (values, failed) = object_constants(object, &local_tags[o], &exported);
+            exported.extend(object.exports.iter()
+                .filter(|n| !failed.contains(n.as_str()))
+                .filter_map(|n| Some((n.clone(), *values.get(n)?))));
+        }
+    }
+
+    let mut program: Vec<BabyInstruction> = vec![];
+    for (o, object) in objects.iter().enumerate() {
+        // Names already reported, references to them would only repeat the error
+        let mut reported: HashSet<&str> = HashSet::new();
+        for name in object.imports.iter().filter(|n| !exported.contains_key(*n)) {
+            errors.push((o, LinkingError::ObjectError(ObjectError::UnresolvedImport(name.clone()))));
+            reported.insert(name);
+        }
+        let indexed: Vec<(usize, String, Value)> = object.constants.iter().enumerate()
+            .map(|(i, (n, v))| (i, n.clone(), v.clone()))
+            .collect();
+        let mut symbols = object_symbols(object, &local_tags[o], &exported);
+        let (values, constant_errors) = resolve_constants(&indexed, &symbols);
+        let repeated = |e: &LinkingError, reported: &HashSet<&str>|
+            matches!(e, LinkingError::TagError(TagError::UnknownTagName(n)) if reported.contains(n.as_str()));
+        for (i, e) in constant_errors {
+            if !repeated(&e, &reported) { errors.push((o, e)); }
+            reported.insert(&object.constants[i].0);
+        }
+        symbols.extend(values);
+
+        let relocated: HashSet<usize> = object.relocations.iter().map(|r| r.offset).collect();
+        let empty = HashMap::new();
+        for (offset, word) in object.words.iter().enumerate() {
+            match word.resolve(if relocated.contains(&offset) { &symbols } else { &empty }) {
+                Ok(v) => program.push(v),
+                Err(e) => if !repeated(&e, &reported) { errors.push((o, e)) },
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        errors.sort_by_key(|(o, e)| (!matches!(e, LinkingError::MemoryExceedingError(_)), *o));
+        return Err(errors);
+    }
+    let mut tags = exported_tags;
+    if let Some(main) = local_tags.first() { tags.extend(main.clone()); }
+    Ok(LinkerData(program, tags))
+}
+
+/// Returns the names an object can reference before its constants are
+/// resolved, its own tags and any imports that have been exported.
+fn object_symbols(object: &ObjectFile, tags: &HashMap<String, WORD>, exported: &HashMap<String, WORD>) -> HashMap<String, WORD> {
+    let mut symbols = tags.clone();
+    symbols.extend(object.imports.iter().filter_map(|n| Some((n.clone(), *exported.get(n)?))));
+    symbols
+}
+
+/// Resolves the constants of an object with the imports exported so far,
+/// returning the values and the names of any that could not be resolved.
+fn object_constants<'a>(object: &'a ObjectFile, tags: &HashMap<String, WORD>, exported: &HashMap<String, WORD>) ->
+    (HashMap<String, WORD>, HashSet<&'a str>) {
+    let indexed: Vec<(usize, String, Value)> = object.constants.iter().enumerate()
+        .map(|(i, (n, v))| (i, n.clone(), v.clone()))
+        .collect();
+    let (values, errors) = resolve_constants(&indexed, &object_symbols(object, tags, exported));
+    let failed = errors.iter().map(|(i, _)| object.constants[*i].0.as_str()).collect();
+    (values, failed)
+}
@@ -0,0 +1,86 @@
+//! # Symbol Files
+//!
+//! This module saves the tags of a linked program, the second field of
+//! [LinkerData][crate::assembler::linker::LinkerData], to a symbol file and
+//! loads them back, so a main store can be read in terms of the names in the
+//! asm source long after it was assembled, for example by
+//! [BabyModel::core_dump_with_symbols][crate::core::BabyModel::core_dump_with_symbols].
+//!
+//! # Example
+//! ```
+//! use baby_emulator::assembler::assemble;
+//! use baby_emulator::assembler::linker::{LinkerData, symbols::{to_symbol_file, parse_symbol_file}};
+//!
+//! let LinkerData(_, tags) = assemble("ldn $counter\nstp\n:counter\nabs 0d3", false).ok().unwrap();
+//! let text = to_symbol_file(&tags);
+//! assert_eq!(parse_symbol_file(&text), Ok(tags));
+//! ```
+//!
+//! # Format
+//! Symbol files are text, one symbol per line as its name and then its value
+//! in decimal, ordered by value then name, blank lines and comments starting
+//! with `;` are ignored:
+//!
+//! ```text
+//! ; Baby symbols
+//! loop 0
+//! counter 5
+//! ```
+//!
+
+use std::collections::HashMap;
+use crate::core::WORD;
+use super::errors::SymbolFileError;
+
+
+/// The comment written at the top of every symbol file.
+const SYMBOL_HEADER: &str = "; Baby symbols";
+
+/// Writes symbol names and values as a symbol file.
+///
+/// # Parameters
+/// * `symbols` - The symbol names and values, such as the tags returned in [LinkerData][crate::assembler::linker::LinkerData].
+///
+pub fn to_symbol_file(symbols: &HashMap<String, WORD>) -> String {
+    let mut sorted: Vec<(&String, &WORD)> = symbols.iter().collect();
+    sorted.sort_by(|(a, x), (b, y)| x.cmp(y).then(a.cmp(b)));
+    let mut res = format!("{}\n", SYMBOL_HEADER);
+    for (name, value) in sorted {
+        res += &format!("{} {}\n", name, value);
+    }
+    res
+}
+
+/// Reads a symbol file written by [to_symbol_file].
+///
+/// Returns a [SymbolFileError] for the first line that is not a name
+/// followed by a decimal value, or that repeats a name.
+///
+/// # Parameters
+/// * `text` - The contents of the symbol file.
+///
+/// # Example
+/// ```
+/// use baby_emulator::assembler::linker::symbols::parse_symbol_file;
+///
+/// let symbols = parse_symbol_file("; Baby symbols\nloop 0\ncounter 5\n").ok().unwrap();
+/// assert_eq!(symbols.get("counter"), Some(&5));
+/// assert!(parse_symbol_file("counter five").is_err());
+/// ```
+///
+pub fn parse_symbol_file(text: &str) -> Result<HashMap<String, WORD>, SymbolFileError> {
+    let mut symbols = HashMap::new();
+    let lines = text.lines().enumerate()
+        .map(|(i, l)| (i + 1, l.trim()))
+        .filter(|(_, l)| !l.is_empty() && !l.starts_with(';'));
+    for (line, text) in lines {
+        let error = || SymbolFileError { line, text: text.to_owned() };
+        let mut parts = text.split_whitespace();
+        let (Some(name), Some(value), None) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(error());
+        };
+        let value: WORD = value.parse().map_err(|_| error())?;
+        if symbols.insert(name.to_owned(), value).is_some() { return Err(error()); }
+    }
+    Ok(symbols)
+}
@@ -1,8 +1,13 @@
 use super::*;
+use crate::assembler::parser::parse_asm_source;
 
 
 #[cfg(test)]
 pub mod unlinked_data_tests;
+#[cfg(test)]
+pub mod object_tests;
+#[cfg(test)]
+pub mod symbols_tests;
 
 #[test]
 fn test_inline_tags() {
@@ -14,25 +19,27 @@ fn test_inline_tags() {
     let res = inline_tags(vec);
     assert_eq!(res.len(), 2);
     match &res[0] {
-        (Some(tag), UnlinkedData::Instruction(Instruction::Negate(Value::Tag(tag_ref)))) => {
-            assert_eq!(tag, "foo");
+        (tags, UnlinkedData::Instruction(Instruction::Negate(Value::Tag(tag_ref)))) => {
+            assert_eq!(tags, &vec!["foo".to_owned()]);
             assert_eq!(tag_ref, "foo")
         },
         _ => assert!(false)
     }
     match &res[1] {
-        (None, UnlinkedData::Instruction(Instruction::Negate(Value::Tag(tag_ref)))) => 
-            assert_eq!(tag_ref, "foo"),
+        (tags, UnlinkedData::Instruction(Instruction::Negate(Value::Tag(tag_ref)))) => {
+            assert!(tags.is_empty());
+            assert_eq!(tag_ref, "foo")
+        },
         _ => assert!(false)
     }
 }
 
 #[test]
 fn test_position_tags() {
-    let vec: Vec<(Option<String>, UnlinkedData)> = vec![
-        (Some(format!("foo1")), UnlinkedData::Instruction(Instruction::Negate(Value::Value(5)))),
-        (None, UnlinkedData::Instruction(Instruction::Negate(Value::Value(5)))),
-        (Some(format!("foo2")), UnlinkedData::Instruction(Instruction::Negate(Value::Value(5)))),
+    let vec: Vec<(Vec<String>, UnlinkedData)> = vec![
+        (vec!["foo1".to_owned()], UnlinkedData::Instruction(Instruction::Negate(Value::Value(5)))),
+        (vec![], UnlinkedData::Instruction(Instruction::Negate(Value::Value(5)))),
+        (vec!["foo2".to_owned()], UnlinkedData::Instruction(Instruction::Negate(Value::Value(5)))),
     ];
 
     let tags = position_tags(&vec);
@@ -67,7 +74,7 @@ fn test_link_tags_fail() {
     ];
     match link_tags(lines, &tags) {
         Err(e) => {
-            assert_eq!(e, LinkingError::TagError(TagError::UnknownTagName("bar".to_owned())))
+            assert_eq!(e, vec![(0, LinkingError::TagError(TagError::UnknownTagName("bar".to_owned())))])
         },
         Ok(_) => assert!(false),
     }
@@ -79,7 +86,7 @@ fn test_link_tags_beyond() {
     let lines: Vec<UnlinkedData> = vec![UnlinkedData::Instruction(Instruction::Jump(Value::Value(5))); 33];
     match link_tags(lines, &tags) {
         Err(e) => {
-            assert_eq!(e, LinkingError::MemoryExceedingError(MemoryExceedingError { linked_size: 33 }))
+            assert_eq!(e, vec![(MEMORY_WORDS, LinkingError::MemoryExceedingError(MemoryExceedingError { linked_size: 33 }))])
         },
         Ok(_) => assert!(false),
     }
@@ -118,3 +125,290 @@ fn test_link_parsed_lines_fail() {
         Ok(_) => assert!(false),
     }
 }
+
+#[test]
+fn test_link_source_lines_fail() {
+    let asm = ":start\nldn 0d2\n\njmp $Foo ; comment\nabs 0d5";
+    let lines = match parse_asm_source(asm, false) {
+        Ok(v) => v,
+        Err(_) => panic!("Failed to parse correct lines. ")
+    };
+    match link_source_lines(lines) {
+        Err((s, e)) => {
+            assert_eq!(e, LinkingError::TagError(TagError::UnknownTagName("foo".to_owned())));
+            assert_eq!(s.line, 4);
+            assert_eq!(s.source(), "$Foo");
+        },
+        Ok(_) => panic!("Unknown tag linked correctly. "),
+    }
+    let asm = vec!["stp"; MEMORY_WORDS + 2].join("\n");
+    let lines = parse_asm_source(&asm, false).ok().unwrap();
+    match link_source_lines(lines) {
+        Err((s, _)) => assert_eq!(s.line, MEMORY_WORDS + 1),
+        Ok(_) => panic!("Oversized program linked correctly. "),
+    }
+}
+
+#[test]
+fn test_link_source_lines_all() {
+    let asm = "jmp $foo\n:foo\nsub $bar\nldn $baz\nstp";
+    let lines = parse_asm_source(asm, false).ok().unwrap();
+    match link_source_lines_all(lines) {
+        Err(e) => {
+            let res: Vec<(usize, LinkingError)> = e.into_iter().map(|(s, e)| (s.line, e)).collect();
+            assert_eq!(res, vec![
+                (3, LinkingError::TagError(TagError::UnknownTagName("bar".to_owned()))),
+                (4, LinkingError::TagError(TagError::UnknownTagName("baz".to_owned()))),
+            ]);
+        },
+        Ok(_) => panic!("Unknown tags linked correctly. "),
+    }
+}
+
+#[test]
+fn test_inline_stacked_tags() {
+    let vec = vec![
+        LineType::Tag("foo".to_owned()),
+        LineType::Tag("bar".to_owned()),
+        LineType::Absolute(Value::Value(1)),
+        LineType::Absolute(Value::Value(2)),
+        LineType::Tag("baz".to_owned()),
+    ];
+    let res = inline_tags(vec);
+    assert_eq!(res.len(), 2);
+    assert_eq!(res[0].0, vec!["foo".to_owned(), "bar".to_owned()]);
+    assert!(res[1].0.is_empty());
+    let tags = position_tags(&res);
+    assert_eq!(tags, HashMap::from([("foo".to_owned(), 0), ("bar".to_owned(), 0)]));
+}
+
+#[test]
+fn test_link_stacked_tags() {
+    let lines: Vec<LineType> = vec![
+        LineType::Instruction(Instruction::Jump(Value::Tag("a".to_owned()))),
+        LineType::Tag("a".to_owned()),
+        LineType::Tag("b".to_owned()),
+        LineType::Instruction(Instruction::Negate(Value::Tag("b".to_owned()))),
+    ];
+    match link_parsed_lines(lines) {
+        Ok(LinkerData(v, tags)) => {
+            assert_eq!(v, vec![BabyInstruction::Jump(1), BabyInstruction::Negate(1)]);
+            assert_eq!(tags.get("a"), Some(&1));
+            assert_eq!(tags.get("b"), Some(&1));
+        },
+        Err(_) => panic!("Failed to link stacked tags. ")
+    }
+}
+
+#[test]
+fn test_find_tag_errors() {
+    let lines: Vec<LineType> = vec![
+        LineType::Tag("a".to_owned()),
+        LineType::Absolute(Value::Value(1)),
+        LineType::Tag("b".to_owned()),
+        LineType::Tag("a".to_owned()),
+        LineType::Absolute(Value::Value(2)),
+        LineType::Tag("c".to_owned()),
+        LineType::Tag("c".to_owned()),
+    ];
+    assert_eq!(find_tag_errors(&lines), vec![
+        (3, TagDeclarationError::DuplicateTagName("a".to_owned())),
+        (5, TagDeclarationError::DanglingTag("c".to_owned())),
+        (6, TagDeclarationError::DuplicateTagName("c".to_owned())),
+        (6, TagDeclarationError::DanglingTag("c".to_owned())),
+    ]);
+    assert_eq!(find_tag_errors(&[LineType::Tag("a".to_owned())]), vec![
+        (0, TagDeclarationError::DanglingTag("a".to_owned())),
+    ]);
+    assert!(find_tag_errors(&lines[..5]).len() == 1);
+}
+
+#[test]
+fn test_link_duplicate_tag() {
+    let lines: Vec<LineType> = vec![
+        LineType::Tag("a".to_owned()),
+        LineType::Absolute(Value::Value(1)),
+        LineType::Tag("a".to_owned()),
+        LineType::Absolute(Value::Value(2)),
+    ];
+    match link_parsed_lines(lines) {
+        Err(e) => assert_eq!(e, LinkingError::TagDeclarationError(TagDeclarationError::DuplicateTagName("a".to_owned()))),
+        Ok(_) => panic!("Duplicate tag linked correctly. ")
+    }
+}
+
+#[test]
+fn test_link_dangling_tag() {
+    let lines: Vec<LineType> = vec![
+        LineType::Absolute(Value::Value(1)),
+        LineType::Tag("end".to_owned()),
+    ];
+    match link_parsed_lines(lines) {
+        Err(e) => assert_eq!(e, LinkingError::TagDeclarationError(TagDeclarationError::DanglingTag("end".to_owned()))),
+        Ok(_) => panic!("Dangling tag linked correctly. ")
+    }
+}
+
+#[test]
+fn test_link_source_tag_errors() {
+    let asm = ":a
+abs 0d1
+:a
+
+sub $nowhere
+:end ; trailing";
+    let lines = parse_asm_source(asm, false).ok().unwrap();
+    match link_source_lines_all(lines) {
+        Err(e) => {
+            let res: Vec<(usize, String)> = e.iter().map(|(s, _)| (s.line, s.source())).collect();
+            assert_eq!(res, vec![
+                (3, ":a".to_owned()),
+                (5, "$nowhere".to_owned()),
+                (6, ":end".to_owned()),
+            ]);
+        },
+        Ok(_) => panic!("Tag errors linked correctly. ")
+    }
+}
+
+#[test]
+fn test_resolve_constants() {
+    let tags: HashMap<String, WORD> = HashMap::from([("table".to_owned(), 3)]);
+    let constants = vec![
+        (0, "a".to_owned(), Value::parse("$b+1").unwrap()),
+        (1, "b".to_owned(), Value::parse("$table*2").unwrap()),
+        (2, "c".to_owned(), Value::parse("$d").unwrap()),
+        (3, "d".to_owned(), Value::parse("$c").unwrap()),
+        (4, "e".to_owned(), Value::parse("$nope").unwrap()),
+        (5, "f".to_owned(), Value::parse("$e").unwrap()),
+    ];
+    let (symbols, errors) = resolve_constants(&constants, &tags);
+    assert_eq!(symbols.get("a"), Some(&7));
+    assert_eq!(symbols.get("table"), Some(&3));
+    assert_eq!(errors, vec![
+        (2, LinkingError::ExpressionError(ExpressionError::CircularConstant("c".to_owned()))),
+        (3, LinkingError::ExpressionError(ExpressionError::CircularConstant("d".to_owned()))),
+        (4, LinkingError::TagError(TagError::UnknownTagName("nope".to_owned()))),
+    ]);
+}
+
+#[test]
+fn link_constants_and_expressions() {
+    let asm = "equ size 2\nequ last $table+$size-1\nldn $table+1\nsto $last\nstp\n:table\nabs $size\nabs -$size";
+    let lines = parse_asm_source(asm, false).ok().unwrap();
+    match link_source_lines(lines) {
+        Ok(LinkerData(v, tags)) => {
+            assert_eq!(v, vec![
+                BabyInstruction::Negate(4),
+                BabyInstruction::Store(4),
+                BabyInstruction::Stop,
+                BabyInstruction::AbsoluteValue(2),
+                BabyInstruction::AbsoluteValue(-2),
+            ]);
+            assert_eq!(tags.get("table"), Some(&3));
+            assert_eq!(tags.get("size"), None);
+        },
+        Err((s, e)) => panic!("{} {:?}", s.describe(), e),
+    }
+}
+
+#[test]
+fn link_constant_errors() {
+    let asm = ":size\nstp\nequ size 2\nequ loop $loop\nabs 0d1/0";
+    let lines = parse_asm_source(asm, false).ok().unwrap();
+    let errors: Vec<(usize, LinkingError)> = match link_source_lines_all(lines) {
+        Err(e) => e.into_iter().map(|(s, e)| (s.line, e)).collect(),
+        Ok(_) => panic!("Incorrect source linked correctly. "),
+    };
+    assert_eq!(errors, vec![
+        (3, LinkingError::TagDeclarationError(TagDeclarationError::DuplicateTagName("size".to_owned()))),
+        (4, LinkingError::ExpressionError(ExpressionError::CircularConstant("loop".to_owned()))),
+        (5, LinkingError::ExpressionError(ExpressionError::DivideByZero("0d1/0d0".to_owned()))),
+    ]);
+}
+
+#[test]
+fn test_place_lines() {
+    let lines = vec![
+        LineType::Constant("base".to_owned(), Value::Value(20)),
+        LineType::Instruction(Instruction::Stop),
+        LineType::Origin(Value::Tag("base".to_owned())),
+        LineType::Tag("table".to_owned()),
+        LineType::Fill(Value::Value(2), Value::Value(7)),
+        LineType::Reserve(Value::Value(3)),
+        LineType::Tag("end".to_owned()),
+        LineType::Words(vec![Value::Value(1), Value::Value(2)]),
+    ];
+    let symbols = HashMap::from([("base".to_owned(), 20)]);
+    let (placed, errors) = place_lines(&lines, &symbols);
+    let placed: Vec<(usize, Vec<String>, usize)> = placed.into_iter().map(|(a, t, _, i)| (a, t, i)).collect();
+    assert_eq!(placed, vec![
+        (0, vec![], 1),
+        (20, vec!["table".to_owned()], 4),
+        (21, vec![], 4),
+        (22, vec![], 5),
+        (23, vec![], 5),
+        (24, vec![], 5),
+        (25, vec!["end".to_owned()], 7),
+        (26, vec![], 7),
+    ]);
+    assert!(errors.is_empty());
+}
+
+#[test]
+fn test_place_lines_errors() {
+    let lines = vec![
+        LineType::Reserve(Value::Value(4)),
+        LineType::Origin(Value::Value(2)),
+        LineType::Words(vec![Value::Value(1), Value::Value(2), Value::Value(3), Value::Value(4), Value::Value(5)]),
+        LineType::Origin(Value::Tag("tag".to_owned())),
+        LineType::Fill(Value::Value(-1), Value::Value(0)),
+        LineType::Origin(Value::Value(30)),
+        LineType::Fill(Value::Value(100), Value::Value(0)),
+    ];
+    let (placed, errors) = place_lines(&lines, &HashMap::new());
+    assert_eq!(placed.len(), 4 + 5 + MEMORY_WORDS);
+    assert_eq!(errors, vec![
+        (2, LinkingError::PlacementError(PlacementError::Overlap(2))),
+        (3, LinkingError::PlacementError(PlacementError::NotConstant("$tag".to_owned()))),
+        (4, LinkingError::PlacementError(PlacementError::NegativeValue("0d-1".to_owned()))),
+        (6, LinkingError::MemoryExceedingError(MemoryExceedingError { linked_size: 130 })),
+    ]);
+}
+
+#[test]
+fn link_placement_directives() {
+    let asm = "equ top 0d20\nldn $values\nsto $out\nstp\n:out\n.reserve 1\n.org $top\n:values\n.word 0d-5, 0d6\n.fill 2 $out";
+    let lines = parse_asm_source(asm, false).ok().unwrap();
+    match link_source_lines(lines) {
+        Ok(LinkerData(v, tags)) => {
+            assert_eq!(v.len(), 24);
+            assert_eq!(v[0], BabyInstruction::Negate(20));
+            assert_eq!(v[1], BabyInstruction::Store(3));
+            assert_eq!(v[3], BabyInstruction::AbsoluteValue(0));
+            assert_eq!(v[19], BabyInstruction::AbsoluteValue(0));
+            assert_eq!(v[20..], [
+                BabyInstruction::AbsoluteValue(-5),
+                BabyInstruction::AbsoluteValue(6),
+                BabyInstruction::AbsoluteValue(3),
+                BabyInstruction::AbsoluteValue(3),
+            ]);
+            assert_eq!(tags.get("values"), Some(&20));
+        },
+        Err((s, e)) => panic!("{} {:?}", s.describe(), e),
+    }
+}
+
+#[test]
+fn link_placement_overlap() {
+    let asm = "stp\nstp\n.org 1\nabs 0d5\n.org 40\n:far\nabs $far";
+    let lines = parse_asm_source(asm, false).ok().unwrap();
+    let errors: Vec<(usize, LinkingError)> = match link_source_lines_all(lines) {
+        Err(e) => e.into_iter().map(|(s, e)| (s.line, e)).collect(),
+        Ok(_) => panic!("Overlapping source linked correctly. "),
+    };
+    assert_eq!(errors, vec![
+        (7, LinkingError::MemoryExceedingError(MemoryExceedingError { linked_size: 41 })),
+        (4, LinkingError::PlacementError(PlacementError::Overlap(1))),
+    ]);
+}
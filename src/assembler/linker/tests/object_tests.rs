@@ -0,0 +1,145 @@
+use super::*;
+use crate::assembler::{assemble, assemble_object, errors::AssemblyError};
+use errors::ObjectError;
+use object::{ObjectFile, Relocation, link_objects};
+
+
+const LIBRARY: &str =
+"
+.export negate, one, limit
+equ limit $one+0d39
+:negate         ; Negates the accumulator
+sto $temp
+ldn $temp
+jmp $return
+:temp
+abs 0d0
+:one
+abs 0d1
+:return
+abs 0d0
+";
+
+const MAIN: &str =
+"
+.import negate, one
+ldn $value
+sub $one
+stp
+:value
+abs 0d-10
+";
+
+fn object(asm: &str) -> ObjectFile {
+    match assemble_object(asm, false) {
+        Ok(o) => o,
+        Err(e) => panic!("{}", e[0].describe(true))
+    }
+}
+
+fn link_errors(objects: &[ObjectFile]) -> Vec<(usize, LinkingError)> {
+    match link_objects(objects) {
+        Err(e) => e,
+        Ok(_) => panic!("Objects linked. ")
+    }
+}
+
+#[test]
+fn test_object_from_lines() {
+    let library = object(LIBRARY);
+    assert_eq!(library.words.len(), 6);
+    assert_eq!(library.tags.get("one"), Some(&4));
+    assert_eq!(library.exports, vec!["negate".to_owned(), "one".to_owned(), "limit".to_owned()]);
+    assert_eq!(library.constants, vec![("limit".to_owned(), Value::parse("$one+0d39").unwrap())]);
+    assert_eq!(library.relocations, vec![
+        Relocation { offset: 0, symbols: vec!["temp".to_owned()] },
+        Relocation { offset: 1, symbols: vec!["temp".to_owned()] },
+        Relocation { offset: 2, symbols: vec!["return".to_owned()] },
+    ]);
+    let main = object(MAIN);
+    assert_eq!(main.imports, vec!["negate".to_owned(), "one".to_owned()]);
+}
+
+#[test]
+fn test_object_errors() {
+    let errors = match assemble_object(".export foo\n.import bar\n:bar\nabs $baz\nequ x $qux", false) {
+        Err(e) => e,
+        Ok(_) => panic!()
+    };
+    let found: Vec<(usize, String)> = errors.iter().map(|e| (e.span().line, e.span().source())).collect();
+    assert_eq!(found, vec![
+        (1, ".export foo".to_owned()),
+        (2, ".import bar".to_owned()),
+        (4, "$baz".to_owned()),
+        (5, "$qux".to_owned()),
+    ]);
+    let errors = match assemble_object(".fill 0d40 0d1", false) {
+        Err(e) => e,
+        Ok(_) => panic!()
+    };
+    assert!(matches!(errors[0], AssemblyError::LinkerError(_, LinkingError::MemoryExceedingError(_))));
+}
+
+#[test]
+fn test_link_objects() {
+    let LinkerData(program, tags) = match link_objects(&[object(MAIN), object(LIBRARY)]) {
+        Ok(v) => v,
+        Err(e) => panic!("{:?}", e)
+    };
+    assert_eq!(program.len(), 10);
+    assert_eq!(program[1], BabyInstruction::Subtract(8));
+    assert_eq!(program[4], BabyInstruction::Store(7));
+    assert_eq!(program[6], BabyInstruction::Jump(9));
+    assert_eq!(tags.get("value"), Some(&3));
+    assert_eq!(tags.get("negate"), Some(&4));
+    assert_eq!(tags.get("temp"), None);
+
+    let user = object(".import limit\nldn $limit\nstp");
+    let LinkerData(program, _) = link_objects(&[user, object(LIBRARY)]).ok().unwrap();
+    assert_eq!(program[0], BabyInstruction::Negate(45));
+}
+
+#[test]
+fn test_link_single_object() {
+    let asm = "ldn $a\n.org 0d5\n:a\n.word 0d3, $a\nequ b $a*2\nsub $b\nstp";
+    let LinkerData(expected, _) = assemble(asm, false).ok().unwrap();
+    let LinkerData(program, _) = link_objects(&[object(asm)]).ok().unwrap();
+    assert_eq!(program, expected);
+}
+
+#[test]
+fn test_link_objects_errors() {
+    let errors = link_errors(&[object(MAIN)]);
+    assert_eq!(errors, vec![
+        (0, LinkingError::ObjectError(ObjectError::UnresolvedImport("negate".to_owned()))),
+        (0, LinkingError::ObjectError(ObjectError::UnresolvedImport("one".to_owned()))),
+    ]);
+    let errors = link_errors(&[object(LIBRARY), object(LIBRARY)]);
+    assert_eq!(errors[0], (1, LinkingError::ObjectError(ObjectError::DuplicateExport("negate".to_owned()))));
+    let big = object(".fill 0d20 0d1");
+    let errors = link_errors(&[object(MAIN), big.clone(), big]);
+    assert_eq!(errors[0], (2, LinkingError::MemoryExceedingError(MemoryExceedingError { linked_size: 44 })));
+    let errors = link_errors(&[object(".import x\nabs 0d1/($x-$x)\nstp"), object(".export x\n:x\nstp")]);
+    assert!(matches!(errors[0], (0, LinkingError::ExpressionError(_))));
+    let unknown = |name: &str| LinkingError::TagError(TagError::UnknownTagName(name.to_owned()));
+    let not_relocated = ObjectFile::parse("babyobj 1\ntag x 1\nword 0 ldn $x\nword 1 stp").ok().unwrap();
+    assert_eq!(link_errors(&[not_relocated]), vec![(0, unknown("x"))]);
+    let undeclared = ObjectFile::parse("babyobj 1\nreloc 0 y\nword 0 ldn $y\nword 1 stp").ok().unwrap();
+    assert_eq!(link_errors(&[undeclared]), vec![(0, unknown("y"))]);
+}
+
+#[test]
+fn test_object_text() {
+    for asm in [LIBRARY, MAIN, "", ".export Foo\n:Foo\nabs $Foo+0d1\nldn $foo\n:foo\nstp"] {
+        let object = object(asm);
+        assert_eq!(ObjectFile::parse(&object.to_text()), Ok(object));
+    }
+    let text = object(MAIN).to_text();
+    assert!(text.starts_with("babyobj 1\nimport negate\nimport one\ntag value 3\n"));
+    assert!(text.contains("reloc 1 one\n") && text.ends_with("word 3 abs 0d-10\n"));
+    assert_eq!(ObjectFile::parse("babyobj 1\n\nword x stp"), Err(ObjectError::InvalidLine(3, "word x stp".to_owned())));
+    assert_eq!(ObjectFile::parse("babyobj 1\nword 0 foo"), Err(ObjectError::InvalidLine(2, "word 0 foo".to_owned())));
+    assert_eq!(ObjectFile::parse(""), Err(ObjectError::InvalidLine(1, "".to_owned())));
+    assert_eq!(ObjectFile::parse("babyobj 1\nword 18446744073709551615 stp"), Err(ObjectError::WordOutOfStore(2, usize::MAX)));
+    assert_eq!(ObjectFile::parse("babyobj 1\nword 32 stp"), Err(ObjectError::WordOutOfStore(2, 32)));
+}
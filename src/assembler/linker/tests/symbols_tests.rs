@@ -0,0 +1,25 @@
+use super::*;
+use errors::SymbolFileError;
+use symbols::{to_symbol_file, parse_symbol_file};
+
+
+#[test]
+fn test_to_symbol_file() {
+    let symbols = HashMap::from([
+        ("loop".to_owned(), 0), ("total".to_owned(), 5), ("counter".to_owned(), 5), ("neg".to_owned(), -1),
+    ]);
+    assert_eq!(to_symbol_file(&symbols), "; Baby symbols\nneg -1\nloop 0\ncounter 5\ntotal 5\n");
+    assert_eq!(parse_symbol_file(&to_symbol_file(&symbols)), Ok(symbols));
+    assert_eq!(to_symbol_file(&HashMap::new()), "; Baby symbols\n");
+}
+
+#[test]
+fn test_parse_symbol_file() {
+    let symbols = parse_symbol_file("\n  ; comment\n  a   3  \n\nb 4").ok().unwrap();
+    assert_eq!(symbols, HashMap::from([("a".to_owned(), 3), ("b".to_owned(), 4)]));
+    let error = |line: usize, text: &str| Err(SymbolFileError { line, text: text.to_owned() });
+    assert_eq!(parse_symbol_file("a 1\nb"), error(2, "b"));
+    assert_eq!(parse_symbol_file("a 1 2"), error(1, "a 1 2"));
+    assert_eq!(parse_symbol_file("a 0x1"), error(1, "a 0x1"));
+    assert_eq!(parse_symbol_file("a 1\n\na 2"), error(3, "a 2"));
+}
@@ -26,7 +26,7 @@ fn test_resolve_value() {
         Err(_) => assert!(false)
     }
     match UnlinkedData::resolve_value(&Value::Tag("bar".to_owned()), &tags) {
-        Err(e) => assert_eq!(e, "bar".to_owned()),
+        Err(e) => assert_eq!(e, LinkingError::TagError(TagError::UnknownTagName("bar".to_owned()))),
         Ok(_) => assert!(false)
     }
 }
@@ -43,12 +43,12 @@ fn test_resolve_absolute_value() {
         Err(_) => assert!(false)
     }
     match UnlinkedData::resolve_absolute_value(&Value::Tag("bar".to_owned()), &tags) {
-        Err(e) => assert_eq!(e, TagError::UnknownTagName("bar".to_owned())),
+        Err(e) => assert_eq!(e, LinkingError::TagError(TagError::UnknownTagName("bar".to_owned()))),
         Ok(_) => assert!(false)
     }
 }
 
-fn get_litteral_value_instruction(value: WORD, result: u16) -> Vec<(Instruction, Result<BabyInstruction, TagError>)> {
+fn get_litteral_value_instruction(value: WORD, result: u16) -> Vec<(Instruction, Result<BabyInstruction, LinkingError>)> {
     vec![
         (Instruction::Jump(Value::Value(value)), Ok(BabyInstruction::Jump(result))),
         (Instruction::RelativeJump(Value::Value(value)), Ok(BabyInstruction::RelativeJump(result))),
@@ -60,7 +60,7 @@ fn get_litteral_value_instruction(value: WORD, result: u16) -> Vec<(Instruction,
     ]
 }
 
-fn get_tag_ref_instructions(value: &str, res: u16) -> Vec<(Instruction, Result<BabyInstruction, TagError>)> {
+fn get_tag_ref_instructions(value: &str, res: u16) -> Vec<(Instruction, Result<BabyInstruction, LinkingError>)> {
     vec![
         (Instruction::Jump(Value::Tag(String::from(value))), Ok(BabyInstruction::Jump(res))),
         (Instruction::RelativeJump(Value::Tag(String::from(value))), Ok(BabyInstruction::RelativeJump(res))),
@@ -70,7 +70,7 @@ fn get_tag_ref_instructions(value: &str, res: u16) -> Vec<(Instruction, Result<B
     ]
 }
 
-fn get_tag_ref_instructions_err(value: &str, err: TagError) -> Vec<(Instruction, Result<BabyInstruction, TagError>)> {
+fn get_tag_ref_instructions_err(value: &str, err: LinkingError) -> Vec<(Instruction, Result<BabyInstruction, LinkingError>)> {
     vec![
         (Instruction::Jump(Value::Tag(String::from(value))), Err(err.clone())),
         (Instruction::RelativeJump(Value::Tag(String::from(value))), Err(err.clone())),
@@ -92,7 +92,21 @@ fn test_resolve_instruction() {
         assert_eq!(UnlinkedData::resolve_instruction(i, &tags), *res);
     });
 
-    get_tag_ref_instructions_err("bar", TagError::UnknownTagName("bar".to_owned())).iter().for_each(|(i, res)| {
+    get_tag_ref_instructions_err("bar", LinkingError::TagError(TagError::UnknownTagName("bar".to_owned()))).iter().for_each(|(i, res)| {
         assert_eq!(UnlinkedData::resolve_instruction(i, &tags), *res);
     });
 }
+
+#[test]
+fn test_resolve_expression() {
+    let tags: HashMap<String, WORD> = HashMap::from([("start".to_owned(), 4), ("end".to_owned(), 10)]);
+    let resolve = |v: &str| UnlinkedData::resolve_value(&Value::parse(v).unwrap(), &tags);
+    assert_eq!(resolve("$end-$start"), Ok(6));
+    assert_eq!(resolve("-$start+0d1"), Ok(-3));
+    assert_eq!(resolve("($end>>1)&0b110"), Ok(4));
+    assert_eq!(resolve("~0|1<<3"), Ok(-1));
+    assert_eq!(resolve("$start*3/2"), Ok(6));
+    assert_eq!(resolve("$end/($start-4)"), Err(LinkingError::ExpressionError(ExpressionError::DivideByZero("$end/($start-0d4)".to_owned()))));
+    assert_eq!(resolve("1<<0d-1"), Err(LinkingError::ExpressionError(ExpressionError::ShiftOutOfRange("0d1<<0d-1".to_owned()))));
+    assert_eq!(resolve("$start+$nope"), Err(LinkingError::TagError(TagError::UnknownTagName("nope".to_owned()))));
+}
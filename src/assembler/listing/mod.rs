@@ -0,0 +1,173 @@
+//! # Listing
+//!
+//! This module builds an assembler listing, a text view of an assembled
+//! program placing each line of the asm source next to the words it put
+//! in memory.
+//!
+//! The main type in this module is [Listing][crate::assembler::listing::Listing],
+//! returned alongside the [LinkerData][crate::assembler::linker::LinkerData] by
+//! [assemble_with][crate::assembler::assemble_with] when asked for, and turned
+//! into text with [Listing::render][crate::assembler::listing::Listing::render].
+//!
+//! Every source line is shown, including blank and comment lines, any line
+//! that places more than one word in memory, such as `.word` or a macro use,
+//! has a row for each word after the first. The listing ends with a table of
+//! every tag and its value. Addresses and tag values are shown in hex, as
+//! in the core dump and the rest of the command line output.
+//!
+//! # Example
+//! ```
+//! use baby_emulator::assembler::{assemble_with, AssemblyOptions};
+//!
+//! let asm = "ldn $value ; Load 5\nstp\n:value\nabs 0d-5";
+//! let options = AssemblyOptions { listing: true, ..Default::default() };
+//! let listing = assemble_with(asm, false, &options).ok().unwrap().listing.unwrap();
+//! println!("{}", listing.render(false));
+//! ```
+//!
+//! Which prints the following:
+//! ```text
+//! ADDR HEX      BINARY (LSB FIRST)               DECODED              LINE SOURCE
+//! 0x00 00004002 01000000000000100000000000000000 ldn $value              1 ldn $value ; Load 5
+//! 0x01 0000e000 00000000000001110000000000000000 stp                     2 stp
+//!                                                                       3 :value
+//! 0x02 fffffffb 11011111111111111111111111111111 abs 0d-5                4 abs 0d-5
+//!
+//! SYMBOLS
+//! value  0x02
+//! ```
+//!
+
+use std::collections::HashMap;
+use crate::core::{instructions::BabyInstruction, WORD};
+use super::diagnostics::SourceSpan;
+use super::disassembler::decode_word;
+use super::linker::LinkerData;
+use super::parser::{LineType, Value};
+
+
+#[cfg(test)]
+mod tests;
+
+
+/// The width of the decoded instruction column.
+const DECODED_WIDTH: usize = 20;
+
+/// A single word placed in memory by a line of asm source.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ListingWord {
+    /// The address of the word in memory.
+    pub address: usize,
+    /// The encoded word.
+    pub word: WORD,
+    /// The word decoded back into asm, either an instruction or an absolute value.
+    pub decoded: LineType,
+}
+
+/// A single line of asm source and the words it placed in memory.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ListingLine {
+    /// The line number, starting at 1.
+    pub line: usize,
+    /// The full text of the source line, including any comment.
+    pub text: String,
+    /// The words placed in memory by the line, in order.
+    pub words: Vec<ListingWord>,
+}
+
+/// An assembler listing, every line of asm source along with the words it
+/// placed in memory, and the values of all the tags.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Listing {
+    /// Every line of the source in order.
+    pub lines: Vec<ListingLine>,
+    /// Every tag name and value, ordered by value then name.
+    pub symbols: Vec<(String, WORD)>,
+}
+
+impl Listing {
+
+    /// Builds a listing for an assembled program.
+    ///
+    /// # Parameters
+    /// * `asm` - The assembly string.
+    /// * `data` - The assembled program.
+    /// * `locations` - The address and source line of each word, as returned by [word_locations][crate::assembler::linker::word_locations].
+    ///
+    pub fn new(asm: &str, data: &LinkerData, locations: &[(usize, SourceSpan)]) -> Listing {
+        let LinkerData(program, tags) = data;
+        let mut words: HashMap<usize, Vec<ListingWord>> = HashMap::new();
+        for (address, span) in locations {
+            let Some(instr) = program.get(*address) else { continue };
+            let word = instr.to_number();
+            let decoded = match instr {
+                BabyInstruction::AbsoluteValue(v) => LineType::Absolute(Value::Value(*v)),
+                _ => decode_word(word, tags),
+            };
+            words.entry(span.line).or_default().push(ListingWord { address: *address, word, decoded });
+        }
+
+        let lines = asm.lines().enumerate()
+            .map(|(i, text)| ListingLine {
+                line: i + 1,
+                text: text.to_owned(),
+                words: words.remove(&(i + 1)).unwrap_or_default(),
+            })
+            .collect();
+        let mut symbols: Vec<(String, WORD)> = tags.iter().map(|(t, v)| (t.clone(), *v)).collect();
+        symbols.sort_by(|(a, x), (b, y)| x.cmp(y).then(a.cmp(b)));
+        Listing { lines, symbols }
+    }
+
+    /// Renders the listing as text, a row for every word showing the address,
+    /// the word in hex and in binary least significant bit first (as shown on
+    /// the Baby's display), the decoded word, and the source line.
+    ///
+    /// # Parameters
+    /// * `og_notation` - If true, decoded words use original notation.
+    ///
+    pub fn render(&self, og_notation: bool) -> String {
+        let hex_width = WORD::BITS as usize / 4;
+        let bin_width = WORD::BITS as usize;
+        let mut rows = vec![format!(
+            "{:<4} {:<hex_width$} {:<bin_width$} {:<DECODED_WIDTH$} LINE SOURCE",
+            "ADDR", "HEX", "BINARY (LSB FIRST)", "DECODED"
+        )];
+        for line in &self.lines {
+            let source = format!("{:>4} {}", line.line, line.text);
+            if line.words.is_empty() {
+                let blank = format!("{:<4} {:<hex_width$} {:<bin_width$} {:<DECODED_WIDTH$}", "", "", "", "");
+                rows.push(format!("{} {}", blank, source).trim_end().to_owned());
+            }
+            for (i, word) in line.words.iter().enumerate() {
+                let row = format!(
+                    "{:#04x} {:0hex_width$x} {} {:<DECODED_WIDTH$} {}",
+                    word.address, word.word, lsb_first(word.word),
+                    word.decoded.to_asm_string(og_notation),
+                    if i == 0 { source.as_str() } else { "" }
+                );
+                rows.push(row.trim_end().to_owned());
+            }
+        }
+
+        rows.push(String::new());
+        rows.push("SYMBOLS".to_owned());
+        let name_width = self.symbols.iter().map(|(t, _)| t.len()).max().unwrap_or(0);
+        rows.extend(self.symbols.iter().map(|(t, v)| format!("{:<name_width$}  {:#04x}", t, v)));
+        rows.join("\n") + "\n"
+    }
+}
+
+/// Formats a word as binary with the least significant bit first,
+/// the order bits are shown on the Baby's display.
+///
+/// # Example
+/// ```
+/// use baby_emulator::assembler::listing::lsb_first;
+///
+/// assert!(lsb_first(0b110).starts_with("0110"));
+/// ```
+///
+pub fn lsb_first(word: WORD) -> String {
+    (0..WORD::BITS).map(|b| if (word >> b) & 1 == 1 { '1' } else { '0' }).collect()
+}
@@ -0,0 +1,63 @@
+use super::*;
+use crate::assembler::{assemble_with, AssemblyOptions};
+
+
+fn listing(asm: &str) -> Listing {
+    match assemble_with(asm, false, &AssemblyOptions { listing: true, ..Default::default() }) {
+        Ok(a) => a.listing.unwrap(),
+        Err(e) => panic!("{}", e[0].describe(true))
+    }
+}
+
+fn addresses(line: &ListingLine) -> Vec<usize> {
+    line.words.iter().map(|w| w.address).collect()
+}
+
+#[test]
+fn test_listing_lines() {
+    let res = listing("; Start\nldn $value ; Load 5\n\nstp\n:value\nabs 0d-5");
+    let texts: Vec<&str> = res.lines.iter().map(|l| l.text.as_str()).collect();
+    assert_eq!(texts, vec!["; Start", "ldn $value ; Load 5", "", "stp", ":value", "abs 0d-5"]);
+    assert_eq!(res.lines[1].words, vec![ListingWord {
+        address: 0,
+        word: BabyInstruction::Negate(2).to_number(),
+        decoded: decode_word(BabyInstruction::Negate(2).to_number(), &HashMap::from([("value".to_owned(), 2)])),
+    }]);
+    assert_eq!(res.lines[5].words[0].decoded, LineType::Absolute(Value::Value(-5)));
+    assert!(res.lines[0].words.is_empty());
+    assert_eq!(res.symbols, vec![("value".to_owned(), 2)]);
+}
+
+#[test]
+fn test_listing_multiple_words() {
+    let asm = "macro twice\nstp\nstp\nendm\ntwice\n.org 0d10\n:b\n:a\n.word 0d1, 0d2\n.fill 0d2 0d7";
+    let res = listing(asm);
+    assert_eq!(addresses(&res.lines[4]), vec![0, 1]);
+    assert_eq!(addresses(&res.lines[8]), vec![10, 11]);
+    assert_eq!(addresses(&res.lines[9]), vec![12, 13]);
+    assert_eq!(res.symbols, vec![("a".to_owned(), 10), ("b".to_owned(), 10)]);
+}
+
+#[test]
+fn test_render() {
+    let text = listing("ldn $value ; Load 5\n.word 0d1, 0d2\n:value\nabs 0d-5").render(false);
+    let rows: Vec<&str> = text.lines().collect();
+    assert!(rows[0].starts_with("ADDR HEX"));
+    assert!(rows[1].starts_with("0x00 "));
+    assert!(rows[1].contains(&lsb_first(BabyInstruction::Negate(3).to_number())));
+    assert!(rows[1].ends_with("   1 ldn $value ; Load 5"));
+    assert!(rows[2].contains("abs 0d1") && rows[2].ends_with("   2 .word 0d1, 0d2"));
+    assert!(rows[3].starts_with("0x02 ") && rows[3].ends_with("abs 0d2"));
+    assert_eq!(rows[4].trim_start(), "3 :value");
+    assert!(rows[5].contains(&format!("{:0w$x}", -5 as WORD, w = WORD::BITS as usize / 4)));
+    assert_eq!(&rows[6..], &["", "SYMBOLS", "value  0x03"]);
+    let og = listing("ldn $value\nstp\n:value\nabs 0d-5").render(true);
+    assert!(og.lines().nth(1).is_some_and(|r| r.contains("-$value, C")));
+}
+
+#[test]
+fn test_lsb_first() {
+    let res = lsb_first(0b110);
+    assert!(res.starts_with("0110"));
+    assert_eq!(res.len(), WORD::BITS as usize);
+}
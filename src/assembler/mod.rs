@@ -84,16 +84,68 @@
 //! * All instructions with a value operand `#` the value operand is always 
 //! a memory address. 
 //! 
+//! ## Original notation
+//! Original notation follows the notation of the 1948 notebooks, so programs 
+//! can be transcribed as they were written: 
+//! 
+//! * Keywords are case insensitive, and the register names can follow `to` 
+//! as well as a comma, so `-24 to C`, `c to 26`, `20 to CI` and `Add 20 to CI` 
+//! are all accepted, `CI` can also be written `Cl`. 
+//! * Subtract can be written `Sub. #`, test `CMP` and stop `STP`. 
+//! * Operands can be plain decimal line numbers. 
+//! * A line can start with its store line number, which places it at that line 
+//! as per `.org`, and a line that is just a number, such as `-3`, stores it. 
+//! 
+//! ```text
+//! 1  -24 to C
+//! 2  Sub. 27
+//! 3  Test
+//! 4  Add 20 to CI
+//! 5  STP
+//! ```
+//! 
 //! ## Values 
 //! The following value formats are accepted: 
 //! 
-//! | Format        | Example |
-//! |---------------|---------|
-//! | Hex           | 0xA     |
-//! | Decimal       | 0d10    |
-//! | Octal         | 0o12    |
-//! | Binary        | 0b1010  |
-//! | Tag Reference | $start  |
+//! | Format           | Example |
+//! |------------------|---------|
+//! | Hex              | 0xA     |
+//! | Decimal          | 0d10    |
+//! | Octal            | 0o12    |
+//! | Binary           | 0b1010  |
+//! | LSB first binary | 0r0101  |
+//! | Character        | 'A'     |
+//! | Tag Reference    | $start  |
+//! 
+//! Plain numbers such as `10` are read as decimal. Literals can be signed with 
+//! a `-` after the prefix (`0x-A`), and hex, octal and binary without a sign are 
+//! read as a bit pattern filling the word, so `0xFFFFFFFF` is -1 in a 32 bit word. 
+//! Digits can be grouped with `_` (`0b1010_1010`). LSB first binary is written 
+//! least significant bit first, as words appear on the Baby's display and in the 
+//! 1948 notes. Characters give their character code, with the escapes `\n`, `\t`, 
+//! `\0`, `\\` and `\'`. 
+//! 
+//! ## Expressions
+//! Anywhere a value is used it can be an expression, combining values with the 
+//! operators `+`, `-`, `*`, `/`, `&`, `|`, `^`, `<<`, `>>`, unary `-` & `~`, and 
+//! parentheses, these are worked out once the positions of all the tags are known, 
+//! see [parser::expression][crate::assembler::parser::expression] for the order of operations. 
+//! 
+//! ```text
+//! ldn $table+3        ; The fourth word of the table 
+//! abs $end-$start     ; The length of a block 
+//! abs ($flags>>2)&0b11
+//! ```
+//! 
+//! ## Constants
+//! A value can be given a name with `equ <name> <value>`, it is referenced the same 
+//! as a tag but does not take up any memory, the value can be an expression using 
+//! tags and other constants. 
+//! 
+//! ```text
+//! equ limit 0d40
+//! sub $limit
+//! ```
 //! 
 //! ## Tags
 //! You can tag a position in the program stack and reference it's memory address 
@@ -107,6 +159,10 @@
 //! stp
 //! ```
 //! 
+//! Several tags can be stacked up to mark the same position, each tag name can 
+//! only be declared once, and a tag must be followed by an instruction or 
+//! absolute value for it to mark. 
+//! 
 //! ## Absolute values
 //! Often you will need to include values in your program code in, you will have to 
 //! add these to your program stack in memory, to do this you can use the `abs #` directive, 
@@ -126,6 +182,51 @@
 //! abs 0d-10
 //! ```
 //! 
+//! ## Placement directives
+//! Lines are placed in memory one after another from address 0, the following 
+//! directives change where lines are placed and add blocks of data: 
+//! 
+//! | Directive         | Description                                                  |
+//! |-------------------|--------------------------------------------------------------|
+//! | `.org N`          | Places the next line at address `N`                          |
+//! | `.fill N value`   | `N` words all holding `value`                                |
+//! | `.word a, b, c`   | A word for each value, one after another                     |
+//! | `.reserve N`      | `N` words of space left as zero                              |
+//! 
+//! Any space skipped over by `.org` is left as zero, placing anything over an 
+//! address that is already used is an error. `N` can be an expression using 
+//! constants, but not tags, as these are only known once everything is placed. 
+//! 
+//! ```text
+//! ldn $values
+//! stp
+//! 
+//! .org 0d20
+//! :values
+//! .word 0d-5, 0d6
+//! ```
+//! 
+//! ## Macros
+//! A sequence of lines can be named as a macro between `macro <name> <params>` 
+//! and `endm`, then used anywhere by writing the name followed by the comma 
+//! separated values for each parameter, the lines of the macro are put in place 
+//! of the use with `$param` replaced by the value given. 
+//! 
+//! Tags declared inside a macro are local to each use, so a macro can be used 
+//! several times without its tags clashing, macros can also use other macros 
+//! but cannot be defined inside each other. 
+//! 
+//! ```text
+//! macro copy from, to
+//! ldn $from
+//! sto $neg
+//! ldn $neg
+//! sto $to
+//! endm
+//! 
+//! copy $a, $b
+//! ```
+//! 
 //! ## Comments 
 //! Comments can be used with a `;` as with other assembly languages. 
 //! 
@@ -134,6 +235,25 @@
 //! JMP 0d1 ; And so is this 
 //! ```
 //! 
+//! ## Object files
+//! A program can be split over several source files, each assembled on its own 
+//! to an object with [assemble_object][crate::assembler::assemble_object] and then 
+//! linked together, see [linker::object][crate::assembler::linker::object]. Tags and 
+//! constants are local to their file unless exported, and can only be used in another 
+//! file once imported: 
+//! 
+//! ```text
+//! .export negate   ; In the library
+//! .import negate   ; In the program using it
+//! ```
+//! 
+//! ## Warnings
+//! [assemble_with][crate::assembler::assemble_with] can also check for code 
+//! that assembles but is probably a mistake: operands outside of the main store, which 
+//! are masked to fit (`sub 0d40` is `sub 0d8`), values that wrap as they do not fit in a 
+//! word, instructions straight after a `stp` that nothing can reach, and programs with 
+//! no `stp` at all. These can be made into errors with `deny_warnings`. 
+//! 
 //! ## Full example 
 //! This shows a simple program in both modern and original notation that 
 //! loads negative a -10 into the accumulator (or, loads 10 into the accumulator),
@@ -183,7 +303,11 @@
 //! 
 
 use errors::AssemblyError;
-use linker::LinkerData;
+use linker::{LinkerData, object::ObjectFile};
+use listing::Listing;
+use warnings::AssemblyWarning;
+use diagnostics::SourceSpan;
+use parser::{LineType, Value, errors::{LineParseError, ConstantError}};
 
 
 /// Contains types and functionality for parsing Baby asm. 
@@ -193,6 +317,26 @@ pub mod linker;
 /// Contains types for handling errors found during the overall 
 /// assembling process. 
 pub mod errors;
+/// Contains functionality for turning a main store back into asm. 
+pub mod disassembler;
+/// Contains types for pointing diagnostics at the asm source. 
+pub mod diagnostics;
+/// Contains types and functionality for expanding macros post parsing. 
+pub mod expander;
+/// Contains types for building a listing of an assembled program. 
+pub mod listing;
+/// Contains functionality for finding likely mistakes in assembled programs. 
+pub mod warnings;
+/// Contains a lossless syntax tree of asm source, for formatting and refactoring. 
+pub mod syntax;
+/// Contains functionality for converting asm between modern and original notation. 
+pub mod notation;
+/// Contains a peephole optimiser for assembled programs. 
+pub mod optimiser;
+/// Contains a superoptimiser searching for the shortest programs meeting a specification. 
+pub mod superoptimiser;
+#[cfg(test)]
+mod tests;
 
 /// Assembles a string of Baby asm to a [LinkerData]. 
 /// 
@@ -216,13 +360,191 @@ pub mod errors;
 /// * `asm` - The assembly string. 
 /// * `og_notation` - If true, will use original notation. 
 /// 
-pub fn assemble(asm: &String, og_notation: bool) -> Result<LinkerData, AssemblyError> {
-    let parse_result = match parser::parse_asm_string(asm, og_notation) {
+pub fn assemble(asm: &str, og_notation: bool) -> Result<LinkerData, AssemblyError> {
+    let parse_result = match parser::parse_asm_source(asm, og_notation) {
         Ok(v) => v,
-        Err((l, e)) => return Err(AssemblyError::ParserError(l, e))
+        Err((s, e)) => return Err(AssemblyError::ParserError(s, e))
     };
-    match linker::link_parsed_lines(parse_result) {
+    let expanded = match expander::expand_source_macros(parse_result) {
+        Ok(v) => v,
+        Err((s, e)) => return Err(AssemblyError::MacroError(s, e))
+    };
+    match linker::link_source_lines(expanded) {
         Ok(v) => Ok(v),
-        Err(e) => Err(AssemblyError::LinkerError(e))
+        Err((s, e)) => Err(AssemblyError::LinkerError(s, e))
+    }
+}
+
+/// Assembles a string of Baby asm to a [LinkerData] as per [assemble], 
+/// but rather than stopping at the first error, carries on past any lines 
+/// that fail to parse, returning every [AssemblyError] found in the order 
+/// they appear in the source. 
+/// 
+/// Instruction and absolute value lines that fail to parse still take up 
+/// a word of memory so the positions of the following tags are not thrown 
+/// off, constants with values that fail to parse are still declared, macros are still expanded and tag references are still checked, so 
+/// every macro error and unknown tag is reported alongside the parse errors. 
+/// 
+/// # Parameters
+/// * `asm` - The assembly string. 
+/// * `og_notation` - If true, will use original notation. 
+/// 
+/// # Example
+/// ```
+/// use baby_emulator::assembler::assemble_all;
+/// 
+/// let asm = "ldn $foo\nfoo 0d1\nsub 0xZZ\nstp";
+/// match assemble_all(asm, false) {
+///     Err(errors) => {
+///         let lines: Vec<usize> = errors.iter().map(|e| e.span().line).collect();
+///         assert_eq!(lines, vec![1, 2, 3]);
+///     },
+///     Ok(_) => panic!()
+/// }
+/// ```
+/// 
+pub fn assemble_all(asm: &str, og_notation: bool) -> Result<LinkerData, Vec<AssemblyError>> {
+    let (lines, errors) = expand_source_all(asm, og_notation);
+    link_all(lines, errors)
+}
+
+/// What [assemble_with] makes on top of the assembled program, by 
+/// default nothing, the same as [assemble_all]. 
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AssemblyOptions {
+    /// If true, a [Listing] of the assembled program is made. 
+    pub listing: bool,
+    /// If true, the program is checked for anything that is probably a 
+    /// mistake, see [warnings] for what is checked. 
+    pub warnings: bool,
+    /// If true, any warnings are returned as [AssemblyError::Warning] errors. 
+    pub deny_warnings: bool,
+}
+
+/// A program assembled by [assemble_with]. 
+pub struct Assembled {
+    /// The assembled program and the values of all the tags. 
+    pub data: LinkerData,
+    /// The listing of the program, if one was asked for. 
+    pub listing: Option<Listing>,
+    /// Any warnings found, always empty unless warnings were asked for. 
+    pub warnings: Vec<AssemblyWarning>,
+}
+
+/// Assembles a string of Baby asm to a [LinkerData] as per [assemble_all], 
+/// also making a [Listing] and checking for warnings as set in `options`, 
+/// all from assembling the source once. 
+/// 
+/// Warnings are only looked for once the program assembles without errors. 
+/// 
+/// # Parameters
+/// * `asm` - The assembly string. 
+/// * `og_notation` - If true, will use original notation. 
+/// * `options` - What to make on top of the assembled program. 
+/// 
+/// # Example
+/// ```
+/// use baby_emulator::assembler::{assemble_with, AssemblyOptions};
+/// use baby_emulator::assembler::warnings::Warning;
+/// 
+/// let options = AssemblyOptions { listing: true, warnings: true, ..Default::default() };
+/// let assembled = assemble_with("stp\nldn $foo\n:foo\nabs 0d5", false, &options).ok().unwrap();
+/// let listing = assembled.listing.unwrap();
+/// assert_eq!(listing.lines[1].words[0].address, 1);
+/// assert_eq!(listing.symbols, vec![("foo".to_owned(), 2)]);
+/// assert_eq!(assembled.warnings[0].span.line, 2);
+/// assert_eq!(assembled.warnings[0].warning, Warning::UnreachableCode);
+/// ```
+/// 
+pub fn assemble_with(asm: &str, og_notation: bool, options: &AssemblyOptions) -> Result<Assembled, Vec<AssemblyError>> {
+    let (lines, errors) = expand_source_all(asm, og_notation);
+    let locations = if options.listing { linker::word_locations(&lines) } else { vec![] };
+    let (data, warnings) = if options.warnings { link_with_warnings(lines, errors, options.deny_warnings)? }
+        else { (link_all(lines, errors)?, vec![]) };
+    let listing = if options.listing { Some(Listing::new(asm, &data, &locations)) } else { None };
+    Ok(Assembled { data, listing, warnings })
+}
+
+/// Assembles a string of Baby asm to a relocatable [ObjectFile], to be linked 
+/// with other objects by [link_objects][crate::assembler::linker::object::link_objects]. 
+/// 
+/// Works the same as [assemble_all], returning every error found, but leaves 
+/// references unresolved, so names declared in other objects can be used once 
+/// imported with `.import`, and the program only has to fit in memory once linked. 
+/// 
+/// # Parameters
+/// * `asm` - The assembly string. 
+/// * `og_notation` - If true, will use original notation. 
+/// 
+/// # Example
+/// ```
+/// use baby_emulator::assembler::assemble_object;
+/// 
+/// let object = assemble_object(".export value\n.import other\n:value\nabs $other+0d1", false).ok().unwrap();
+/// assert_eq!(object.exports, vec!["value".to_owned()]);
+/// assert_eq!(object.relocations[0].symbols, vec!["other".to_owned()]);
+/// assert!(assemble_object("abs $other", false).is_err());
+/// ```
+/// 
+pub fn assemble_object(asm: &str, og_notation: bool) -> Result<ObjectFile, Vec<AssemblyError>> {
+    let (lines, mut errors) = expand_source_all(asm, og_notation);
+    match linker::object::object_from_source_lines(lines) {
+        Ok(v) if errors.is_empty() => return Ok(v),
+        Ok(_) => (),
+        Err(e) => errors.extend(e.into_iter().map(|(s, e)| AssemblyError::LinkerError(s, e))),
+    }
+    errors.sort_by_key(|e| (e.span().line, e.span().start));
+    Err(errors)
+}
+
+/// Parses and expands the macros in a string of Baby asm, carrying on past 
+/// any errors as per [assemble_all]. 
+/// 
+/// Returns the expanded lines, with placeholders for lines that failed to 
+/// parse, and every error found. 
+fn expand_source_all(asm: &str, og_notation: bool) -> (Vec<(SourceSpan, LineType)>, Vec<AssemblyError>) {
+    let mut errors: Vec<AssemblyError> = vec![];
+    let mut lines = vec![];
+    for line in parser::parse_source_lines(asm, og_notation) {
+        match line {
+            Ok(l) => lines.push(l),
+            Err((s, e)) => {
+                match &e {
+                    LineParseError::AbsoluteError(_) | LineParseError::InstructionError(_) =>
+                        lines.push((s.clone(), LineType::Absolute(Value::Value(0)))),
+                    LineParseError::ConstantError(ConstantError::ValueError(n, _)) =>
+                        lines.push((s.clone(), LineType::Constant(n.clone(), Value::Value(0)))),
+                    _ => (),
+                }
+                errors.push(AssemblyError::ParserError(s, e));
+            }
+        }
+    }
+    let (lines, macro_errors) = expander::expand_source_macros_all(lines);
+    errors.extend(macro_errors.into_iter().map(|(s, e)| AssemblyError::MacroError(s, e)));
+    (lines, errors)
+}
+
+/// Links expanded lines as per [link_all], then looks for warnings in the 
+/// lines, returning them as errors if `deny_warnings`. 
+fn link_with_warnings(lines: Vec<(SourceSpan, LineType)>, errors: Vec<AssemblyError>, deny_warnings: bool) -> 
+    Result<(LinkerData, Vec<AssemblyWarning>), Vec<AssemblyError>> {
+    let data = link_all(lines.clone(), errors)?;
+    let warnings = warnings::find_source_warnings(&lines, &data.1);
+    if deny_warnings && !warnings.is_empty() {
+        return Err(warnings.into_iter().map(|w| AssemblyError::Warning(w.span, w.warning)).collect());
+    }
+    Ok((data, warnings))
+}
+
+/// Links expanded lines, returning every error found linking along with 
+/// any errors already found, sorted in the order they appear in the source. 
+fn link_all(lines: Vec<(SourceSpan, LineType)>, mut errors: Vec<AssemblyError>) -> Result<LinkerData, Vec<AssemblyError>> {
+    match linker::link_source_lines_all(lines) {
+        Ok(v) if errors.is_empty() => return Ok(v),
+        Ok(_) => (),
+        Err(e) => errors.extend(e.into_iter().map(|(s, e)| AssemblyError::LinkerError(s, e))),
     }
+    errors.sort_by_key(|e| (e.span().line, e.span().start));
+    Err(errors)
 }
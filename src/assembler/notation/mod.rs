@@ -0,0 +1,198 @@
+//! # Notation
+//!
+//! This module converts asm source between modern notation (`ldn $x`) and
+//! the original notation used by Kilburn and Tootill (`-x, C`), built on the
+//! lossless [SyntaxTree][crate::assembler::syntax::SyntaxTree] so tags,
+//! comments and layout are kept, only the code on each line is rewritten.
+//!
+//! The main part of this module is [convert_notation][crate::assembler::notation::convert_notation],
+//! which returns the converted source along with a [ConversionIssue] for
+//! anything that does not map to the other notation.
+//!
+//! | Modern      | Original      |
+//! |-------------|---------------|
+//! | `jmp x`     | `x, Cl`       |
+//! | `jrp x`     | `Add x, Cl`   |
+//! | `ldn x`     | `-x, C`       |
+//! | `sto x`     | `c, x`        |
+//! | `sub x`     | `SUB x`       |
+//! | `cmp`       | `Test`        |
+//! | `stp`       | `Stop`        |
+//! | `abs 0d-3`  | `-3`          |
+//!
+//! Operands are written out as parsed, so literals become decimal. Modern
+//! notation has no line numbers, so an original notation line number becomes
+//! a `.org` directive before the line, only where the line would not already
+//! be placed at that address.
+//!
+//! # Example
+//! ```
+//! use baby_emulator::assembler::notation::convert_notation;
+//!
+//! let (og, issues) = convert_notation("ldn $x ; Load\nstp\n:x\nabs 0d-3", false);
+//! assert!(issues.is_empty());
+//! assert_eq!(og, "-$x, C ; Load\nStop\n:x\n-3");
+//! let (modern, _) = convert_notation(&og, true);
+//! assert_eq!(modern, "ldn $x ; Load\nstp\n:x\nabs 0d-3");
+//! ```
+//!
+
+use super::diagnostics::SourceSpan;
+use super::parser::{LineType, Value, RESERVED_NAMES, OG_RESERVED_NAMES, expression::UnaryOperator};
+use super::parser::errors::{LineParseError, ParseError};
+use super::syntax::{SyntaxLine, SyntaxTree, Token, TokenKind};
+
+
+#[cfg(test)]
+mod tests;
+
+
+/// Something in the source that cannot be converted to the other notation.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NotationIssue {
+    /// The line does not parse in the source notation, so is left as it is.
+    ParseError(LineParseError),
+    /// A macro name is reserved in the target notation (macro name).
+    ReservedMacroName(String),
+}
+
+impl NotationIssue {
+
+    /// Returns a short string describing the issue.
+    pub fn describe(&self) -> String {
+        match self {
+            NotationIssue::ParseError(e) => format!("The line could not be parsed so was not converted. {}", e.describe(false)),
+            NotationIssue::ReservedMacroName(n) => format!(
+                "The macro name `{}` is reserved in the target notation, rename the macro before converting. ", n
+            ),
+        }
+    }
+}
+
+/// An issue along with where in the asm source it was found.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConversionIssue {
+    /// Where in the asm source the issue was found.
+    pub span: SourceSpan,
+    /// The issue.
+    pub issue: NotationIssue,
+}
+
+impl ConversionIssue {
+
+    /// Returns a string describing the issue and where it was found.
+    pub fn describe(&self) -> String {
+        format!("An issue was found converting {}. {}", self.span.describe(), self.issue.describe())
+    }
+
+    /// Renders the issue as a diagnostic showing the offending source
+    /// line with carets underneath it.
+    ///
+    /// # Parameters
+    /// * `file` - The name of the source file, if there is one.
+    ///
+    pub fn render(&self, file: Option<&str>) -> String {
+        let message: Vec<String> = self.issue.describe().split_whitespace().map(str::to_owned).collect();
+        self.span.render(&format!("error: {}", message.join(" ")), file)
+    }
+}
+
+/// Converts asm source to the other notation, keeping tags, comments and
+/// layout, returning the converted source and any issues found.
+///
+/// Lines with an issue are left as they are, so the source is converted
+/// as far as it can be even when there are issues.
+///
+/// # Parameters
+/// * `asm` - The asm source.
+/// * `og_notation` - If true, the source is in original notation and is
+///   converted to modern, otherwise it is converted to original notation.
+///
+pub fn convert_notation(asm: &str, og_notation: bool) -> (String, Vec<ConversionIssue>) {
+    let tree = SyntaxTree::parse(asm, og_notation);
+    let reserved = if og_notation { RESERVED_NAMES.as_slice() } else { OG_RESERVED_NAMES.as_slice() };
+    let mut res = String::new();
+    let mut issues = vec![];
+    let mut address = Some(0);
+    let mut in_macro = false;
+    for (i, line) in tree.lines.iter().enumerate() {
+        let span = || SourceSpan::new(i + 1, line.text().trim_end_matches(['\r', '\n']));
+        let parsed = match &line.parsed {
+            None => { res.push_str(&line.text()); continue; },
+            Some(Err(e)) => {
+                issues.push(ConversionIssue { span: span().trim(), issue: NotationIssue::ParseError(e.clone()) });
+                res.push_str(&line.text());
+                continue;
+            },
+            Some(Ok(p)) => p,
+        };
+        if let LineType::Macro(n, _) = parsed {
+            if reserved.contains(&n.as_str()) {
+                issues.push(ConversionIssue { span: span().trim().narrow(n), issue: NotationIssue::ReservedMacroName(n.clone()) });
+                res.push_str(&line.text());
+                continue;
+            }
+        }
+
+        let number = line.token(TokenKind::LineNumber).and_then(|n| n.parse::<usize>().ok());
+        if let Some(n) = number.filter(|n| address != Some(*n)) {
+            let indent = line.tokens.first().filter(|t| t.kind == TokenKind::Whitespace).map_or("", |t| t.text.as_str());
+            let newline = line.token(TokenKind::Newline).unwrap_or("\n");
+            res.push_str(&format!("{}.org 0d{}{}", indent, n, newline));
+            address = Some(n);
+        }
+        match parsed {
+            LineType::Macro(..) => in_macro = true,
+            LineType::EndMacro => in_macro = false,
+            l if !in_macro => address = next_address(address, l),
+            _ => (),
+        }
+        res.push_str(&replace_code(line, &convert_line(parsed, !og_notation)));
+    }
+    (res, issues)
+}
+
+/// Writes a parsed line in the target notation, a negated literal such as
+/// an original notation `-3` is written as a single literal.
+fn convert_line(line: &LineType, to_og: bool) -> String {
+    let literal = match line {
+        LineType::Absolute(Value::Value(v)) => Some(*v),
+        LineType::Absolute(Value::Unary(UnaryOperator::Negate, v)) => match **v {
+            Value::Value(v) => Some(v.wrapping_neg()),
+            _ => None,
+        },
+        _ => None,
+    };
+    match literal {
+        Some(v) if to_og => v.to_string(),
+        Some(v) => LineType::Absolute(Value::Value(v)).to_asm_string(false),
+        None => line.to_asm_string(to_og),
+    }
+}
+
+/// Returns the text of a line with the code replaced, keeping any
+/// indentation, comment and line ending.
+fn replace_code(line: &SyntaxLine, code: &str) -> String {
+    let is_code = |k: TokenKind| !matches!(k, TokenKind::Whitespace | TokenKind::Comment | TokenKind::Newline);
+    let first = line.tokens.iter().position(|t| is_code(t.kind)).unwrap_or(0);
+    let last = line.tokens.iter().rposition(|t| is_code(t.kind)).unwrap_or(0);
+    let text = |tokens: &[Token]| tokens.iter().map(|t| t.text.as_str()).collect::<String>();
+    format!("{}{}{}", text(&line.tokens[..first]), code, text(&line.tokens[last + 1..]))
+}
+
+/// Works out the address of the line after a line, [None] once it cannot
+/// be known without linking, such as after a macro use.
+fn next_address(address: Option<usize>, line: &LineType) -> Option<usize> {
+    let literal = |v: &Value| match v {
+        Value::Value(n) => usize::try_from(*n).ok(),
+        _ => None,
+    };
+    match line {
+        LineType::Origin(v) => literal(v),
+        LineType::Instruction(_) | LineType::Absolute(_) => address.map(|a| a + 1),
+        LineType::Words(v) => address.map(|a| a + v.len()),
+        LineType::Fill(n, _) | LineType::Reserve(n) => Some(address? + literal(n)?),
+        LineType::MacroCall(..) => None,
+        _ => address,
+    }
+}
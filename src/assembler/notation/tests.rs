@@ -0,0 +1,95 @@
+use super::*;
+use crate::assembler::assemble;
+
+
+const MODERN: &str =
+"; Counts down from 10
+ldn $start_value  ; Load 10
+:loop
+sub $one ; Subtract
+cmp
+jmp $loop_start
+sto $result
+jrp $offset
+stp
+
+:loop_start
+abs $loop
+:one
+abs 0x1
+:start_value
+abs 0d-10
+:offset
+abs 0d0
+:result
+.reserve 0d1
+";
+
+const OG: &str =
+"; Counts down from 10
+-$start_value, C  ; Load 10
+:loop
+SUB $one ; Subtract
+Test
+$loop_start, Cl
+c, $result
+Add $offset, Cl
+Stop
+
+:loop_start
+abs $loop
+:one
+1
+:start_value
+-10
+:offset
+0
+:result
+.reserve 0d1
+";
+
+fn program(asm: &str, og_notation: bool) -> Vec<crate::core::instructions::BabyInstruction> {
+    match assemble(asm, og_notation) {
+        Ok(d) => d.0,
+        Err(e) => panic!("{}", e.describe(true))
+    }
+}
+
+#[test]
+fn test_modern_to_og() {
+    let (og, issues) = convert_notation(MODERN, false);
+    assert!(issues.is_empty());
+    assert_eq!(og, OG);
+    assert_eq!(program(&og, true), program(MODERN, false));
+}
+
+#[test]
+fn test_og_to_modern() {
+    let (modern, issues) = convert_notation(OG, true);
+    assert!(issues.is_empty());
+    assert!(modern.contains("ldn $start_value  ; Load 10\n") && modern.contains("jrp $offset\n"));
+    assert!(modern.contains(":one\nabs 0d1\n"));
+    assert_eq!(program(&modern, false), program(OG, true));
+    assert_eq!(convert_notation(&modern, false).0, OG);
+}
+
+#[test]
+fn test_line_numbers() {
+    let kilburn = "0 -24 to C\n1 Sub. 25\n2 c to 26\n3 Stop\n24 -3\n25 1";
+    let (modern, issues) = convert_notation(kilburn, true);
+    assert!(issues.is_empty());
+    assert_eq!(modern, "ldn 0d24\nsub 0d25\nsto 0d26\nstp\n.org 0d24\nabs 0d-3\nabs 0d1");
+    assert_eq!(program(&modern, false), program(kilburn, true));
+}
+
+#[test]
+fn test_issues() {
+    let (og, issues) = convert_notation("macro test x\nldn $x\nendm\ntest 0d1\nfoo bar\nstp", false);
+    assert_eq!(issues.len(), 2);
+    assert_eq!(issues[0].issue, NotationIssue::ReservedMacroName("test".to_owned()));
+    assert_eq!((issues[0].span.line, issues[0].span.source()), (1, "test".to_owned()));
+    assert!(matches!(issues[1].issue, NotationIssue::ParseError(_)));
+    assert_eq!(issues[1].span.line, 5);
+    assert_eq!(og, "macro test x\n-$x, C\nendm\ntest 0d1\nfoo bar\nStop");
+    assert!(issues[0].render(Some("prog.asm")).contains("prog.asm:1:7"));
+}
@@ -0,0 +1,565 @@
+//! # Optimiser
+//!
+//! This module is a peephole optimiser over assembled programs, saving
+//! words of the 32 word store without changing what the program does.
+//!
+//! The main part of this module is [optimise][crate::assembler::optimiser::optimise],
+//! which works on the unlinked words of a program with their tags, as returned
+//! by [inline_tags][crate::assembler::linker::inline_tags], while
+//! [optimise_linked][crate::assembler::optimiser::optimise_linked] works on a
+//! linked program, such as [LinkerData][crate::assembler::linker::LinkerData]`.0`.
+//! Each makes the following changes, reported as an [Optimisation]:
+//!
+//! * Jumps to a word that is itself a jump are made to jump straight to the
+//!   final target.
+//! * A negated load straight after another is kept on its own, and a value
+//!   stored, loaded negated, stored and loaded negated again through words
+//!   nothing else uses is left in the accumulator.
+//! * Instructions after a `stp` that nothing can reach or reference are removed.
+//! * Constant words holding the same value that are never written are merged,
+//!   with references and tags moved to the word kept.
+//! * Words only referenced by code that has been removed are removed.
+//!
+//! Each word's references are followed by address, so words can be moved. This
+//! can only be done if every address the program uses is known, so programs
+//! that relatively jump, store through a word they write, write to their own
+//! code or jump pointers, read instructions as data, or use an address outside
+//! of the program, are left as they are.
+//!
+//! The original and optimised programs are run and checked to stop with the
+//! same accumulator and values in each word kept that is not an instruction or
+//! an address before the optimised program is returned, see [check_equivalence][crate::assembler::optimiser::check_equivalence].
+//!
+//! # Example
+//! ```
+//! use baby_emulator::assembler::{assemble, linker::LinkerData};
+//! use baby_emulator::assembler::optimiser::optimise_linked;
+//!
+//! let asm = "ldn $one\nsub $also_one\nstp\nsub $one\n:one\nabs 0d1\n:also_one\nabs 0d1";
+//! let LinkerData(program, _) = assemble(asm, false).ok().unwrap();
+//! let (optimised, changes) = optimise_linked(&program, 100).ok().unwrap();
+//! assert_eq!(program.len(), 6);
+//! assert_eq!(optimised.len(), 4);
+//! assert_eq!(changes.len(), 2);
+//! ```
+//!
+
+use std::collections::{BTreeSet, HashMap};
+use crate::core::{BabyModel, WORD, instructions::BabyInstruction, errors::BabyErrors};
+use super::disassembler::find_code;
+use super::linker::{link_parsed_lines, LinkerData, UnlinkedData, errors::LinkingError};
+use super::parser::{LineType, Instruction, Value};
+
+
+#[cfg(test)]
+mod tests;
+
+
+/// A change made by the optimiser, each with the address of the word
+/// changed or removed in the original program.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Optimisation {
+    /// A jump through a chain of jumps now jumps straight to the end of the chain.
+    ShortenedJump(usize),
+    /// A negated load that had no effect was removed.
+    FoldedNegation(usize),
+    /// An instruction after a stop that could never run was removed.
+    DeadCode(usize),
+    /// A constant was merged with an earlier constant holding the same value
+    /// (the constant, the constant kept).
+    MergedConstant(usize, usize),
+    /// A word only used by removed code was removed.
+    UnusedWord(usize),
+}
+
+impl Optimisation {
+
+    /// Returns a short string describing the change.
+    pub fn describe(&self) -> String {
+        match self {
+            Optimisation::ShortenedJump(a) => format!("The jump at `{}` now jumps straight to the end of a chain of jumps. ", a),
+            Optimisation::FoldedNegation(a) => format!("The negated load at `{}` had no effect and was removed. ", a),
+            Optimisation::DeadCode(a) => format!("The instruction at `{}` can never run and was removed. ", a),
+            Optimisation::MergedConstant(a, k) => format!("The constant at `{}` was merged with the same constant at `{}`. ", a, k),
+            Optimisation::UnusedWord(a) => format!("The word at `{}` was only used by removed code and was removed. ", a),
+        }
+    }
+}
+
+/// A difference found running the original and optimised programs.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Mismatch {
+    /// The programs ended differently, one stopped and the other hit the
+    /// iteration limit (if the original stopped).
+    Outcome(bool),
+    /// The accumulators were different once stopped (original, optimised).
+    Accumulator(WORD, WORD),
+    /// A word was different once stopped (original address, original value, optimised value).
+    Word(usize, WORD, WORD),
+}
+
+impl Mismatch {
+
+    /// Returns a short string describing the difference.
+    pub fn describe(&self) -> String {
+        match self {
+            Mismatch::Outcome(true) => "The original program stops but the optimised program does not. ".to_owned(),
+            Mismatch::Outcome(false) => "The optimised program stops but the original program does not. ".to_owned(),
+            Mismatch::Accumulator(o, n) => format!("The accumulator ends as `{}` but as `{}` once optimised. ", o, n),
+            Mismatch::Word(a, o, n) => format!("The word at `{}` ends as `{}` but as `{}` once optimised. ", a, o, n),
+        }
+    }
+}
+
+/// Possible errors thrown optimising a program.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OptimiserError {
+    /// The program could not be linked.
+    LinkingError(LinkingError),
+    /// The optimised program does not behave the same as the original.
+    NotEquivalent(Mismatch),
+}
+
+impl OptimiserError {
+
+    /// Returns a short string describing the error.
+    pub fn describe(&self) -> String {
+        match self {
+            OptimiserError::LinkingError(e) => format!("The program could not be linked. {}", super::linker::errors::LinkerError::describe(e, false)),
+            OptimiserError::NotEquivalent(m) => format!("The optimised program is not the same as the original. {}", m.describe()),
+        }
+    }
+}
+
+/// An optimised program.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Optimised {
+    /// The optimised words of the program with their tags.
+    pub program: Vec<(Vec<String>, UnlinkedData)>,
+    /// For each address in the original program, the address of the same
+    /// word in the optimised program, [None] if it was removed.
+    pub addresses: Vec<Option<usize>>,
+    /// The changes made, in the order they were made.
+    pub changes: Vec<Optimisation>,
+}
+
+/// Optimises the unlinked words of a program, see [optimiser][crate::assembler::optimiser]
+/// for the changes made.
+///
+/// Returns [OptimiserError::LinkingError] if the program cannot be linked, or
+/// [OptimiserError::NotEquivalent] if running the optimised program does not
+/// end the same as the original.
+///
+/// # Parameters
+/// * `program` - The words of the program with their tags, from address 0.
+/// * `max_iter` - The most instructions to run each program for when checking
+///   they are equivalent.
+///
+/// # Example
+/// ```
+/// use baby_emulator::assembler::{parser::parse_asm_string, linker::inline_tags};
+/// use baby_emulator::assembler::optimiser::{optimise, Optimisation};
+///
+/// let lines = parse_asm_string("ldn $a\nldn $a\nsub $b\nstp\n:a\nabs 0d1\n:b\nabs 0d1", false).ok().unwrap();
+/// let optimised = optimise(&inline_tags(lines), 100).ok().unwrap();
+/// assert_eq!(optimised.changes, vec![Optimisation::FoldedNegation(0), Optimisation::MergedConstant(5, 4)]);
+/// assert_eq!(optimised.program.len(), 4);
+/// assert_eq!(optimised.program[3].0, vec!["a".to_owned(), "b".to_owned()]);
+/// ```
+///
+pub fn optimise(program: &[(Vec<String>, UnlinkedData)], max_iter: usize) -> Result<Optimised, OptimiserError> {
+    let original = link(program)?;
+    let unchanged = Optimised {
+        program: program.to_vec(),
+        addresses: (0..program.len()).map(Some).collect(),
+        changes: vec![],
+    };
+    let mut words = match Words::new(program, &original) {
+        Some(w) => w,
+        None => return Ok(unchanged),
+    };
+    words.shorten_jumps();
+    words.fold_negations();
+    words.remove_dead_code();
+    words.merge_constants();
+    words.remove_unused();
+    let optimised = words.finish();
+    let compared: Vec<Option<usize>> = optimised.addresses.iter()
+        .map(|n| n.filter(|n| matches!(optimised.program[*n].1, UnlinkedData::Absolute(Value::Value(_)))))
+        .collect();
+    check_equivalence(&original, &link(&optimised.program)?, &compared, max_iter)
+        .map_err(OptimiserError::NotEquivalent)?;
+    Ok(optimised)
+}
+
+/// Optimises a linked program as per [optimise], returning the optimised
+/// program and the changes made.
+///
+/// # Parameters
+/// * `program` - The linked program.
+/// * `max_iter` - The most instructions to run each program for when checking
+///   they are equivalent.
+///
+pub fn optimise_linked(program: &[BabyInstruction], max_iter: usize) -> Result<(Vec<BabyInstruction>, Vec<Optimisation>), OptimiserError> {
+    let unlinked: Vec<(Vec<String>, UnlinkedData)> = program.iter()
+        .map(|i| (vec![], unlink(i)))
+        .collect();
+    let optimised = optimise(&unlinked, max_iter)?;
+    Ok((link(&optimised.program)?, optimised.changes))
+}
+
+/// Runs an original and optimised program, checking they end the same.
+///
+/// Both programs must stop, or both hit `max_iter`, and if they stop, the
+/// accumulators must match, as must every word of the original program
+/// with the word at its address in the optimised program.
+///
+/// # Parameters
+/// * `original` - The original program.
+/// * `optimised` - The optimised program.
+/// * `addresses` - For each address in the original program, the address
+///   in the optimised program, [None] if it is not compared.
+/// * `max_iter` - The most instructions to run each program for.
+///
+pub fn check_equivalence(original: &[BabyInstruction], optimised: &[BabyInstruction], addresses: &[Option<usize>], max_iter: usize) -> Result<(), Mismatch> {
+    let run = |p: &[BabyInstruction]| BabyModel::new_with_program(BabyInstruction::to_numbers(p.to_vec())).run_loop(max_iter);
+    let ((original, o_end), (optimised, n_end)) = (run(original), run(optimised));
+    match (matches!(o_end, BabyErrors::Stop(_)), matches!(n_end, BabyErrors::Stop(_))) {
+        (true, true) => (),
+        (false, false) => return Ok(()),
+        (stopped, _) => return Err(Mismatch::Outcome(stopped)),
+    }
+    if original.accumulator != optimised.accumulator {
+        return Err(Mismatch::Accumulator(original.accumulator, optimised.accumulator));
+    }
+    for (a, n) in addresses.iter().enumerate() {
+        let (o, n) = match n { Some(n) => (original.main_store[a], optimised.main_store[*n]), None => continue };
+        if o != n { return Err(Mismatch::Word(a, o, n)); }
+    }
+    Ok(())
+}
+
+/// Links unlinked words.
+fn link(program: &[(Vec<String>, UnlinkedData)]) -> Result<Vec<BabyInstruction>, OptimiserError> {
+    let lines = program.iter()
+        .flat_map(|(t, d)| t.iter().map(|t| LineType::Tag(t.clone())).chain([match d {
+            UnlinkedData::Absolute(v) => LineType::Absolute(v.clone()),
+            UnlinkedData::Instruction(i) => LineType::Instruction(i.clone()),
+        }]))
+        .collect();
+    link_parsed_lines(lines).map(|LinkerData(v, _)| v).map_err(OptimiserError::LinkingError)
+}
+
+/// Converts a linked word back to unlinked data with literal values.
+fn unlink(word: &BabyInstruction) -> UnlinkedData {
+    let operand = Value::Value(word.get_operand() as WORD);
+    match word {
+        BabyInstruction::Jump(_) => UnlinkedData::Instruction(Instruction::Jump(operand)),
+        BabyInstruction::RelativeJump(_) => UnlinkedData::Instruction(Instruction::RelativeJump(operand)),
+        BabyInstruction::Negate(_) => UnlinkedData::Instruction(Instruction::Negate(operand)),
+        BabyInstruction::Store(_) => UnlinkedData::Instruction(Instruction::Store(operand)),
+        BabyInstruction::Subtract(_) => UnlinkedData::Instruction(Instruction::Subtract(operand)),
+        BabyInstruction::SkipNextIfNegative => UnlinkedData::Instruction(Instruction::Test),
+        BabyInstruction::Stop => UnlinkedData::Instruction(Instruction::Stop),
+        BabyInstruction::AbsoluteValue(v) => UnlinkedData::Absolute(Value::Value(*v)),
+    }
+}
+
+/// What happens to the tags of a removed word.
+enum Tags {
+    /// Moved to the next word kept.
+    MoveToNext,
+    /// Moved to another word, which the removed word is merged with.
+    MergeInto(usize),
+    /// Dropped, as nothing references them.
+    Drop,
+}
+
+/// The words of a program being optimised.
+///
+/// Every reference to an address is a tag reference, each word having a
+/// tag, so words can be removed and moved without changing references.
+struct Words {
+    tags: Vec<Vec<String>>,
+    data: Vec<UnlinkedData>,
+    synthetic: Vec<String>,
+    code: BTreeSet<usize>,
+    written: BTreeSet<usize>,
+    removed: Vec<Option<Tags>>,
+    referenced: Vec<bool>,
+    changes: Vec<Optimisation>,
+}
+
+impl Words {
+
+    /// Makes every address the program uses a tag reference, returning
+    /// [None] if the addresses the program uses cannot all be known.
+    fn new(program: &[(Vec<String>, UnlinkedData)], linked: &[BabyInstruction]) -> Option<Words> {
+        let store = BabyInstruction::to_numbers(linked.to_vec());
+        let code = find_code(&store);
+        let len = program.len();
+        let operand = |a: usize| match linked[a] {
+            BabyInstruction::SkipNextIfNegative | BabyInstruction::Stop | BabyInstruction::AbsoluteValue(_) => None,
+            i => Some(i.get_operand()),
+        };
+        let pointee = |a: usize| usize::try_from(store[a]).ok().filter(|v| *v < len);
+
+        let mut pointers = BTreeSet::new();
+        let mut written = BTreeSet::new();
+        for a in &code {
+            if *a >= len { return None; }
+            match linked[*a] {
+                BabyInstruction::RelativeJump(_) | BabyInstruction::AbsoluteValue(_) => return None,
+                BabyInstruction::Negate(o) | BabyInstruction::Subtract(o) =>
+                    if matches!(program.get(o as usize), Some((_, UnlinkedData::Instruction(_)))) { return None; },
+                BabyInstruction::Jump(o) | BabyInstruction::Store(o) => {
+                    let o = o as usize;
+                    if o >= len || code.contains(&o) || pointee(o).is_none() { return None; }
+                    pointers.insert(o);
+                    if matches!(linked[*a], BabyInstruction::Store(_)) { written.insert(pointee(o)?); }
+                },
+                _ => (),
+            }
+        }
+        if written.iter().any(|w| code.contains(w) || pointers.contains(w)) { return None; }
+        if (0..len).filter_map(operand).any(|o| o >= len) { return None; }
+
+        let mut tags: Vec<Vec<String>> = program.iter().map(|(t, _)| t.clone()).collect();
+        let mut synthetic = vec![];
+        for (a, t) in tags.iter_mut().enumerate() {
+            if !t.is_empty() { continue; }
+            let mut name = format!("word.{}", a);
+            while program.iter().any(|(t, _)| t.contains(&name)) { name.push('_'); }
+            t.push(name.clone());
+            synthetic.push(name);
+        }
+        let reference = |a: usize| Value::Tag(tags[a][0].clone());
+        let data: Vec<UnlinkedData> = program.iter().enumerate()
+            .map(|(a, (_, d))| match d {
+                UnlinkedData::Instruction(i) => UnlinkedData::Instruction(match operand(a) {
+                    Some(o) => i.with_operand(reference(o)),
+                    None => i.clone(),
+                }),
+                UnlinkedData::Absolute(_) if pointers.contains(&a) => UnlinkedData::Absolute(reference(store[a] as usize)),
+                UnlinkedData::Absolute(_) => UnlinkedData::Absolute(Value::Value(store[a])),
+            })
+            .collect();
+
+        let mut words = Words { tags, data, synthetic, code, written, removed: (0..len).map(|_| None).collect(), referenced: vec![], changes: vec![] };
+        words.referenced = (0..len).map(|a| !words.references(a).is_empty()).collect();
+        Some(words)
+    }
+
+    /// Returns the address of the word a value references, if it is a tag reference.
+    fn target(&self, value: &Value) -> Option<usize> {
+        match value {
+            Value::Tag(t) => (0..self.tags.len()).find(|a| self.removed[*a].is_none() && self.tags[*a].contains(t)),
+            _ => None,
+        }
+    }
+
+    /// Returns the address of the word referenced by the word at an address.
+    fn referenced_by(&self, a: usize) -> Option<usize> {
+        match &self.data[a] {
+            UnlinkedData::Instruction(Instruction::Test | Instruction::Stop) => None,
+            d => self.target(&d.get_value()),
+        }
+    }
+
+    /// Returns the addresses of the words kept that reference a word.
+    fn references(&self, a: usize) -> Vec<usize> {
+        self.kept().filter(|r| self.referenced_by(*r) == Some(a)).collect()
+    }
+
+    /// Returns the addresses of all the words kept, in order.
+    fn kept(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.data.len()).filter(|a| self.removed[*a].is_none())
+    }
+
+    /// Returns the address of the next word kept after an address.
+    fn next(&self, a: usize) -> Option<usize> {
+        self.kept().find(|n| *n > a)
+    }
+
+    /// Returns the instruction at an address if it is code.
+    fn instruction(&self, a: usize) -> Option<&Instruction> {
+        match &self.data[a] {
+            UnlinkedData::Instruction(i) if self.code.contains(&a) && self.removed[a].is_none() => Some(i),
+            _ => None,
+        }
+    }
+
+    /// Returns true if a word is the target of a jump.
+    fn is_jump_target(&self, a: usize) -> bool {
+        self.kept().any(|j| matches!(self.instruction(j), Some(Instruction::Jump(p)) if self
+            .target(p)
+            .and_then(|p| self.referenced_by(p)) == Some(a)))
+    }
+
+    /// Returns true if a code word can be removed, with execution carrying
+    /// on at the next word, it must not be the instruction after a test,
+    /// which a skip would then land past.
+    fn is_removable(&self, a: usize) -> bool {
+        let after_test = self.kept().filter(|p| *p < a).last()
+            .is_some_and(|p| matches!(self.instruction(p), Some(Instruction::Test)));
+        !after_test && self.next(a).is_some()
+    }
+
+    /// Removes a word.
+    fn remove(&mut self, a: usize, tags: Tags) {
+        let moved = std::mem::take(&mut self.tags[a]);
+        match tags {
+            Tags::MoveToNext => if let Some(n) = self.next(a) { self.tags[n].splice(0..0, moved); },
+            Tags::MergeInto(k) => self.tags[k].extend(moved),
+            Tags::Drop => (),
+        }
+        self.removed[a] = Some(tags);
+    }
+
+    /// Makes each jump to a jump go straight to the end of the chain.
+    fn shorten_jumps(&mut self) {
+        for a in self.code.clone() {
+            let Some(Instruction::Jump(pointer)) = self.instruction(a) else { continue };
+            let (mut pointer, start) = (pointer.clone(), pointer.clone());
+            let mut seen = BTreeSet::from([a]);
+            while let Some(t) = self.target(&pointer).and_then(|p| self.referenced_by(p)) {
+                match self.instruction(t) {
+                    Some(Instruction::Jump(next)) if seen.insert(t) => pointer = next.clone(),
+                    _ => break,
+                }
+            }
+            if pointer != start {
+                self.data[a] = UnlinkedData::Instruction(Instruction::Jump(pointer));
+                self.changes.push(Optimisation::ShortenedJump(a));
+            }
+        }
+    }
+
+    /// Removes negated loads that are straight away replaced, and stores and
+    /// loads through words only they use that leave the accumulator the same.
+    fn fold_negations(&mut self) {
+        for a in self.code.clone() {
+            if self.removed[a].is_some() { continue; }
+            let Some(n) = self.next(a) else { continue };
+            let negates = |w: &Words, x: usize| matches!(w.instruction(x), Some(Instruction::Negate(_)));
+            if negates(self, a) && negates(self, n) && self.is_removable(a) {
+                self.remove(a, Tags::MoveToNext);
+                self.changes.push(Optimisation::FoldedNegation(a));
+                continue;
+            }
+            if let Some(removed) = self.round_trip(a) {
+                for r in &removed[..4] { self.remove(*r, Tags::MoveToNext); }
+                for r in &removed[4..] { if self.removed[*r].is_none() { self.remove(*r, Tags::Drop); } }
+                self.changes.extend(removed[..4].iter().map(|r| Optimisation::FoldedNegation(*r)));
+            }
+        }
+    }
+
+    /// Checks for a store, negated load, store and negated load starting
+    /// at an address, through words nothing else uses, returning the
+    /// addresses of the four instructions followed by the words they use.
+    fn round_trip(&self, a: usize) -> Option<Vec<usize>> {
+        let b = self.next(a)?;
+        let c = self.next(b)?;
+        let d = self.next(c)?;
+        let (first, second) = match (self.instruction(a)?, self.instruction(b)?, self.instruction(c)?, self.instruction(d)?) {
+            (Instruction::Store(p), Instruction::Negate(t), Instruction::Store(q), Instruction::Negate(u)) => {
+                let (p, t, q, u) = (self.target(p)?, self.target(t)?, self.target(q)?, self.target(u)?);
+                if self.referenced_by(p)? != t || self.referenced_by(q)? != u { return None; }
+                ((p, t), (q, u))
+            },
+            _ => None?,
+        };
+        let instructions = [a, b, c, d];
+        let used = [first.0, first.1, second.0, second.1];
+        let only_used_by = |w: usize, by: &[usize]| self.references(w).iter().all(|r| by.contains(r));
+        let removable = self.is_removable(a) && [b, c, d].iter().all(|x| self.is_removable(*x) && !self.is_jump_target(*x));
+        let private = used.iter().all(|w| !self.code.contains(w) && !instructions.contains(w))
+            && only_used_by(first.1, &[b, d, first.0, second.0])
+            && only_used_by(second.1, &[b, d, first.0, second.0])
+            && only_used_by(first.0, &[a, c])
+            && only_used_by(second.0, &[a, c]);
+        if !removable || !private { return None; }
+        Some([instructions.to_vec(), used.to_vec()].concat())
+    }
+
+    /// Removes instructions after each stop that nothing can reach or references.
+    fn remove_dead_code(&mut self) {
+        for a in self.code.clone() {
+            if !matches!(self.instruction(a), Some(Instruction::Stop)) { continue; }
+            let mut next = self.next(a);
+            while let Some(n) = next {
+                let dead = !self.code.contains(&n)
+                    && matches!(self.data[n], UnlinkedData::Instruction(_))
+                    && self.references(n).is_empty();
+                if !dead { break; }
+                next = self.next(n);
+                self.remove(n, Tags::Drop);
+                self.changes.push(Optimisation::DeadCode(n));
+            }
+        }
+    }
+
+    /// Merges constants holding the same value that are never written and
+    /// only read by instructions.
+    fn merge_constants(&mut self) {
+        let mut kept: HashMap<String, usize> = HashMap::new();
+        for a in self.kept().collect::<Vec<usize>>() {
+            let UnlinkedData::Absolute(value) = &self.data[a] else { continue };
+            let references = self.references(a);
+            let read_only = !self.code.contains(&a) && !self.written.contains(&a) && !references.is_empty()
+                && references.iter().all(|r| matches!(self.data[*r], UnlinkedData::Instruction(_)));
+            if !read_only { continue; }
+            match kept.get(&value.to_asm_string()) {
+                Some(k) => {
+                    let k = *k;
+                    self.remove(a, Tags::MergeInto(k));
+                    self.changes.push(Optimisation::MergedConstant(a, k));
+                },
+                None => { kept.insert(value.to_asm_string(), a); },
+            }
+        }
+    }
+
+    /// Removes words that were referenced, but are no longer referenced as
+    /// the code using them has been removed.
+    fn remove_unused(&mut self) {
+        loop {
+            let unused: Vec<usize> = self.kept()
+                .filter(|a| self.referenced[*a] && !self.code.contains(a) && self.references(*a).is_empty())
+                .collect();
+            if unused.is_empty() { return; }
+            for a in unused {
+                self.remove(a, Tags::Drop);
+                self.changes.push(Optimisation::UnusedWord(a));
+            }
+        }
+    }
+
+    /// Returns the optimised program, dropping tags added for words that
+    /// are not referenced.
+    fn finish(self) -> Optimised {
+        let kept: Vec<usize> = self.kept().collect();
+        let referenced: BTreeSet<String> = kept.iter()
+            .filter_map(|a| match self.data[*a].get_value() { Value::Tag(t) => Some(t), _ => None })
+            .collect();
+        let position = |a: usize| kept.iter().position(|k| *k == a);
+        let addresses = self.removed.iter().enumerate()
+            .map(|(a, r)| match r {
+                None => position(a),
+                Some(Tags::MergeInto(k)) => position(*k),
+                Some(_) => None,
+            })
+            .collect();
+        let program = kept.iter()
+            .map(|a| {
+                let tags = self.tags[*a].iter()
+                    .filter(|t| !self.synthetic.contains(t) || referenced.contains(*t))
+                    .cloned()
+                    .collect();
+                (tags, self.data[*a].clone())
+            })
+            .collect();
+        Optimised { program, addresses, changes: self.changes }
+    }
+}
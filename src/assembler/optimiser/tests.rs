@@ -0,0 +1,108 @@
+use super::*;
+use crate::assembler::{assemble, linker::inline_tags, parser::parse_asm_string};
+
+
+fn optimise_asm(asm: &str) -> Optimised {
+    let lines = parse_asm_string(asm, false).unwrap_or_else(|e| panic!("{:?}", e));
+    optimise(&inline_tags(lines), 1000).unwrap_or_else(|e| panic!("{:?}", e))
+}
+
+fn asm_lines(optimised: &Optimised) -> Vec<String> {
+    optimised.program.iter().map(|(_, d)| d.to_asm_string()).collect()
+}
+
+#[test]
+fn test_shortened_jumps() {
+    let optimised = optimise_asm("jmp $p.a\n:a\njmp $p.b\n:b\nstp\n:p.a\nabs $a\n:p.b\nabs $b");
+    assert_eq!(optimised.changes, vec![Optimisation::ShortenedJump(0), Optimisation::UnusedWord(3)]);
+    assert_eq!(asm_lines(&optimised), vec!["jmp $p.b", "jmp $p.b", "stp", "abs $b"]);
+    let looping = optimise_asm("jmp $p\n:a\njmp $p\n:p\nabs $a");
+    assert_eq!(looping.changes, vec![]);
+}
+
+#[test]
+fn test_folded_negations() {
+    let optimised = optimise_asm(
+        "ldn $x\n:keep\nldn $x\nsto $p.t\nldn $t\nsto $p.u\nldn $u\nsto $p.out\nstp\n:x\nabs 0d-5\n:t\nabs 0d0\n:u\nabs 0d0\n:out\nabs 0d0\n:p.t\nabs $t\n:p.u\nabs $u\n:p.out\nabs $out"
+    );
+    assert_eq!(optimised.changes[..5], [
+        Optimisation::FoldedNegation(0),
+        Optimisation::FoldedNegation(2),
+        Optimisation::FoldedNegation(3),
+        Optimisation::FoldedNegation(4),
+        Optimisation::FoldedNegation(5),
+    ]);
+    assert_eq!(asm_lines(&optimised), vec!["ldn $x", "sto $p.out", "stp", "abs 0d-5", "abs 0d0", "abs $out"]);
+    assert_eq!(optimised.program[0].0, vec!["keep".to_owned()]);
+    assert_eq!(optimised.addresses[0], None);
+    assert_eq!(optimised.addresses[11], Some(4));
+
+    let after_test = optimise_asm("cmp\nldn $x\nldn $x\nstp\n:x\nabs 0d1");
+    assert_eq!(after_test.changes, vec![]);
+    let jumped_into = optimise_asm("ldn $x\ncmp\njmp $j\nsto $p\nldn $t\n:in\nsto $p\nldn $t\nstp\n:x\nabs 0d1\n:t\nabs 0d0\n:p\nabs $t\n:j\nabs $in");
+    assert_eq!(jumped_into.changes, vec![]);
+}
+
+#[test]
+fn test_dead_code() {
+    let optimised = optimise_asm("ldn $x\nstp\nsub $y\nldn $y\n:x\nabs 0d1\n:y\nabs 0d2");
+    assert_eq!(optimised.changes, vec![
+        Optimisation::DeadCode(2),
+        Optimisation::DeadCode(3),
+        Optimisation::UnusedWord(5),
+    ]);
+    assert_eq!(asm_lines(&optimised), vec!["ldn $x", "stp", "abs 0d1"]);
+    assert_eq!(optimised.addresses, vec![Some(0), Some(1), None, None, Some(2), None]);
+}
+
+#[test]
+fn test_merged_constants() {
+    let optimised = optimise_asm("ldn $a\nsub $b\nsto $p\nstp\n:a\nabs 0d3\n:b\nabs 0d3\n:c\nabs 0d3\n:p\nabs $c");
+    assert_eq!(optimised.changes, vec![Optimisation::MergedConstant(5, 4)]);
+    assert_eq!(optimised.program[4].0, vec!["a".to_owned(), "b".to_owned()]);
+    assert_eq!(asm_lines(&optimised), vec!["ldn $a", "sub $b", "sto $p", "stp", "abs 0d3", "abs 0d3", "abs $c"]);
+}
+
+#[test]
+fn test_unchanged() {
+    let relative = "jrp $o\nstp\n:o\nabs 0d0\nabs 0d0";
+    assert_eq!(optimise_asm(relative).changes, vec![]);
+    let self_modifying = "ldn $x\nsto $p\nstp\n:x\nabs 0d0\n:p\nabs 0d2\nabs 0d0";
+    assert_eq!(optimise_asm(self_modifying).changes, vec![]);
+    assert!(optimise(&[(vec![], UnlinkedData::Absolute(Value::Tag("x".to_owned())))], 10).is_err());
+}
+
+#[test]
+fn test_linked() {
+    let LinkerData(program, _) = assemble("ldn $x\nldn $x\nstp\nsub $x\n:x\nabs 0d7\nabs 0d7", false).ok().unwrap();
+    let (optimised, changes) = optimise_linked(&program, 100).unwrap_or_else(|e| panic!("{:?}", e));
+    assert_eq!(changes, vec![Optimisation::FoldedNegation(0), Optimisation::DeadCode(3)]);
+    assert_eq!(optimised, vec![
+        BabyInstruction::Negate(2),
+        BabyInstruction::Stop,
+        BabyInstruction::AbsoluteValue(7),
+        BabyInstruction::AbsoluteValue(7),
+    ]);
+}
+
+#[test]
+fn test_check_equivalence() {
+    let program = vec![BabyInstruction::Negate(2), BabyInstruction::Stop, BabyInstruction::AbsoluteValue(4)];
+    let changed = vec![BabyInstruction::Negate(2), BabyInstruction::Stop, BabyInstruction::AbsoluteValue(5)];
+    let looping = vec![BabyInstruction::Jump(1), BabyInstruction::AbsoluteValue(0)];
+    assert_eq!(check_equivalence(&program, &program, &[Some(0), Some(1), Some(2)], 100), Ok(()));
+    assert_eq!(check_equivalence(&program, &changed, &[None, None, None], 100), Err(Mismatch::Accumulator(-4, -5)));
+    let (stops, changed) = (&[BabyInstruction::Stop, BabyInstruction::AbsoluteValue(4)], &[BabyInstruction::Stop, BabyInstruction::AbsoluteValue(5)]);
+    assert_eq!(check_equivalence(stops, changed, &[None, Some(1)], 100), Err(Mismatch::Word(1, 4, 5)));
+    assert_eq!(check_equivalence(&program, &looping, &[], 100), Err(Mismatch::Outcome(true)));
+    assert_eq!(check_equivalence(&looping, &looping, &[], 100), Ok(()));
+}
+
+#[test]
+fn test_compiled() {
+    let source = "var a = 3\nvar b = 4\nvar product\nwhile a > 0 {\n    product = product + b\n    a = a - 1\n}\nhalt\na = 1";
+    let LinkerData(program, _) = crate::compiler::compile_and_link(source).ok().unwrap();
+    let (optimised, changes) = optimise_linked(&program, 1000).unwrap_or_else(|e| panic!("{:?}", e));
+    assert_eq!((program.len(), optimised.len()), (30, 27));
+    assert_eq!(changes, vec![Optimisation::DeadCode(16), Optimisation::DeadCode(17), Optimisation::DeadCode(18)]);
+}
@@ -25,7 +25,7 @@ pub trait ParseError {
 }
 
 /// Thrown when an invalid value is encountered. 
-#[derive(PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub enum ValueParseError {
     /// No discernable value detected where one is expected. 
     InvalidValue(String),
@@ -37,8 +37,16 @@ pub enum ValueParseError {
     InvalidOctal(String),
     /// Invalid binary value. 
     InvalidBinary(String),
+    /// Invalid least significant bit first binary value. 
+    InvalidLsbBinary(String),
+    /// Invalid character literal, or a character that does not fit in a word. 
+    InvalidCharacter(String),
+    /// A valid literal that is too large to fit in a word. 
+    ValueOutOfRange(String),
     /// Invalid tag name. 
     InvalidTagName(String),
+    /// Malformed expression, E.G. a missing operand or parenthesis. 
+    InvalidExpression(String),
 }
 
 impl ParseError for ValueParseError {
@@ -49,13 +57,17 @@ impl ParseError for ValueParseError {
             ValueParseError::InvalidDecimal(v) => format!("The value: {}; is invalid decimal value. ", v),
             ValueParseError::InvalidOctal(v) => format!("The value: {}; is invalid octal value. ", v),
             ValueParseError::InvalidBinary(v) => format!("The value: {}; is invalid binary value. ", v),
+            ValueParseError::InvalidLsbBinary(v) => format!("The value: {}; is invalid LSB first binary value. ", v),
+            ValueParseError::InvalidCharacter(v) => format!("The value: {}; is invalid character. ", v),
+            ValueParseError::ValueOutOfRange(v) => format!("The value: {}; does not fit in a word. ", v),
             ValueParseError::InvalidTagName(v) => format!("The value: {}; is invalid tag name. ", v),
+            ValueParseError::InvalidExpression(v) => format!("The value: {}; is invalid expression. ", v),
         }
     }
 }
 
 /// Thrown when errors are found parsing Baby asm instructions. 
-#[derive(PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub enum InstructionError {
     /// A given instruction isn't correct. 
     UnkownInstruction(String),
@@ -74,7 +86,7 @@ impl ParseError for InstructionError {
 }
 
 /// Thrown when an error was encountered parsing an absolute value. 
-#[derive(PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub enum AbsoluteError {
     /// Attempt to parse a value to an absolute value encountered an error. 
     ValueError(ValueParseError)
@@ -90,7 +102,7 @@ impl ParseError for AbsoluteError {
 }
 
 /// Thrown when an error is encountered trying to parse a tag declaration. 
-#[derive(PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub enum TagError {
     /// Thrown when a delcared tag name has whitespace. 
     TagNameWhitespace(String)
@@ -104,8 +116,75 @@ impl ParseError for TagError {
     }
 }
 
+/// Thrown when an error is encountered parsing a macro definition or use. 
+#[derive(Clone, PartialEq, Debug)]
+pub enum MacroParseError {
+    /// Thrown when a macro name is missing, has invalid characters or is 
+    /// the same as a keyword. 
+    InvalidName(String),
+    /// Thrown when a macro parameter name is empty or has whitespace. 
+    InvalidParameter(String),
+    /// Thrown when an argument to a macro use fails to parse (macro name, error). 
+    ArgumentError(String, ValueParseError),
+}
+
+impl ParseError for MacroParseError {
+    fn describe(&self, line_breaks: bool) -> String {
+        let line_break = if line_breaks { "\n" } else { "" };
+        match self {
+            MacroParseError::InvalidName(v) => format!("The macro name `{}` is invalid. ", v),
+            MacroParseError::InvalidParameter(v) => format!("The macro parameter name `{}` is invalid. ", v),
+            MacroParseError::ArgumentError(m, v) => format!("Failed to parse argument for macro {}. {line_break} {}", m, v.describe(line_breaks)),
+        }
+    }
+}
+
+/// Thrown when an error is encountered parsing a constant declaration. 
+#[derive(Clone, PartialEq, Debug)]
+pub enum ConstantError {
+    /// Thrown when a constant name is missing or has invalid characters. 
+    InvalidName(String),
+    /// Thrown when the value of a constant fails to parse (constant name, error). 
+    ValueError(String, ValueParseError),
+}
+
+impl ParseError for ConstantError {
+    fn describe(&self, line_breaks: bool) -> String {
+        let line_break = if line_breaks { "\n" } else { "" };
+        match self {
+            ConstantError::InvalidName(v) => format!("The constant name `{}` is invalid. ", v),
+            ConstantError::ValueError(c, v) => format!("Failed to parse value for constant {}. {line_break} {}", c, v.describe(line_breaks)),
+        }
+    }
+}
+
+/// Thrown when an error is encountered parsing a placement directive. 
+#[derive(Clone, PartialEq, Debug)]
+pub enum DirectiveError {
+    /// Thrown when a directive name is not known. 
+    UnknownDirective(String),
+    /// Thrown when a directive is missing one of its values (directive name). 
+    MissingValue(String),
+    /// Thrown when a value of a directive fails to parse (directive name, error). 
+    ValueError(String, ValueParseError),
+    /// Thrown when a name given to a directive is empty or has whitespace (directive name, name). 
+    InvalidName(String, String),
+}
+
+impl ParseError for DirectiveError {
+    fn describe(&self, line_breaks: bool) -> String {
+        let line_break = if line_breaks { "\n" } else { "" };
+        match self {
+            DirectiveError::UnknownDirective(v) => format!("The directive `{}` is not known. ", v),
+            DirectiveError::MissingValue(v) => format!("The directive `{}` is missing a value. ", v),
+            DirectiveError::InvalidName(d, n) => format!("The name `{}` given to directive {} is not valid. ", n, d),
+            DirectiveError::ValueError(d, v) => format!("Failed to parse value for directive {}. {line_break} {}", d, v.describe(line_breaks)),
+        }
+    }
+}
+
 /// Thrown when an error is encountered parsing a Baby asm line. 
-#[derive(PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub enum LineParseError {
     /// Thrown when an error is encountered parsing a tag declaration. 
     TagError(TagError),
@@ -113,6 +192,12 @@ pub enum LineParseError {
     AbsoluteError(AbsoluteError),
     /// Thrown when an error is encountered parsing a instruction use. 
     InstructionError(InstructionError),
+    /// Thrown when an error is encountered parsing a macro definition or use. 
+    MacroError(MacroParseError),
+    /// Thrown when an error is encountered parsing a constant declaration. 
+    ConstantError(ConstantError),
+    /// Thrown when an error is encountered parsing a placement directive. 
+    DirectiveError(DirectiveError),
 }
 
 impl ParseError for LineParseError {
@@ -122,6 +207,9 @@ impl ParseError for LineParseError {
             LineParseError::TagError(v) => format!("Error parsing a tag line, {}", v.describe(line_breaks)),
             LineParseError::AbsoluteError(v) => format!("Error parsing an absolute value line. {line_break} {}", v.describe(line_breaks)),
             LineParseError::InstructionError(v) => format!("Error parsing a instruction line. {line_break} {}", v.describe(line_breaks)),
+            LineParseError::MacroError(v) => format!("Error parsing a macro line. {line_break} {}", v.describe(line_breaks)),
+            LineParseError::ConstantError(v) => format!("Error parsing a constant line. {line_break} {}", v.describe(line_breaks)),
+            LineParseError::DirectiveError(v) => format!("Error parsing a directive line. {line_break} {}", v.describe(line_breaks)),
         }
     }
 }
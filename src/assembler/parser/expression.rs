@@ -0,0 +1,330 @@
+//! # Expression
+//!
+//! This module parses value expressions, such as `$table+3`, `$end-$start`,
+//! `-$x` or `($flags>>2)&0b11`, into a tree of [Value][crate::assembler::parser::Value]s.
+//!
+//! Expressions are only evaluated by the [linker][crate::assembler::linker],
+//! once the values of all the tags are known.
+//!
+//! # Operators
+//! From lowest to highest precedence, operators of the same precedence
+//! are evaluated left to right:
+//!
+//! | Operator     | Description               |
+//! |--------------|---------------------------|
+//! | `\|`         | Bitwise or                |
+//! | `^`          | Bitwise exclusive or      |
+//! | `&`          | Bitwise and               |
+//! | `<<` `>>`    | Shift left, shift right   |
+//! | `+` `-`      | Add, subtract             |
+//! | `*` `/`      | Multiply, divide          |
+//! | `-` `~`      | Negate, bitwise not       |
+//!
+//! Parentheses can be used to group parts of an expression, expressions
+//! nesting deeper than [MAX_DEPTH][crate::assembler::parser::expression::MAX_DEPTH]
+//! are rejected.
+//!
+//! # Example
+//! ```
+//! use baby_emulator::assembler::parser::Value;
+//! use baby_emulator::assembler::parser::expression::BinaryOperator;
+//!
+//! assert_eq!(Value::parse("$table+3"), Ok(Value::Binary(
+//!     BinaryOperator::Add,
+//!     Box::new(Value::Tag("table".to_owned())),
+//!     Box::new(Value::Value(3)),
+//! )));
+//! ```
+//!
+
+use crate::core::WORD;
+use super::Value;
+use super::errors::ValueParseError;
+
+
+/// The deepest an expression can nest, counting each parenthesis, unary
+/// operator and binary operator applied to a previous result, any deeper
+/// expression is a [ValueParseError::InvalidExpression].
+pub const MAX_DEPTH: usize = 100;
+
+/// An operator applied to a single value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnaryOperator {
+    /// Two's complement negation, `-`.
+    Negate,
+    /// Bitwise not, `~`.
+    Not,
+}
+
+impl UnaryOperator {
+
+    /// Returns the asm symbol for the operator.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            UnaryOperator::Negate => "-",
+            UnaryOperator::Not => "~",
+        }
+    }
+
+    /// Applies the operator to a value.
+    pub fn apply(&self, value: WORD) -> WORD {
+        match self {
+            UnaryOperator::Negate => value.wrapping_neg(),
+            UnaryOperator::Not => !value,
+        }
+    }
+}
+
+/// An operator applied to two values.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinaryOperator {
+    /// Bitwise or, `|`.
+    Or,
+    /// Bitwise exclusive or, `^`.
+    Xor,
+    /// Bitwise and, `&`.
+    And,
+    /// Shift left, `<<`.
+    ShiftLeft,
+    /// Arithmetic shift right, `>>`.
+    ShiftRight,
+    /// Addition, `+`.
+    Add,
+    /// Subtraction, `-`.
+    Subtract,
+    /// Multiplication, `*`.
+    Multiply,
+    /// Integer division rounding towards zero, `/`.
+    Divide,
+}
+
+impl BinaryOperator {
+
+    /// All the binary operators in order of precedence, lowest first,
+    /// operators in the same group have the same precedence.
+    pub const PRECEDENCE: [&'static [BinaryOperator]; 6] = [
+        &[BinaryOperator::Or],
+        &[BinaryOperator::Xor],
+        &[BinaryOperator::And],
+        &[BinaryOperator::ShiftLeft, BinaryOperator::ShiftRight],
+        &[BinaryOperator::Add, BinaryOperator::Subtract],
+        &[BinaryOperator::Multiply, BinaryOperator::Divide],
+    ];
+
+    /// Returns the asm symbol for the operator.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOperator::Or => "|",
+            BinaryOperator::Xor => "^",
+            BinaryOperator::And => "&",
+            BinaryOperator::ShiftLeft => "<<",
+            BinaryOperator::ShiftRight => ">>",
+            BinaryOperator::Add => "+",
+            BinaryOperator::Subtract => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+        }
+    }
+
+    /// Applies the operator to two values, arithmetic wraps on overflow.
+    ///
+    /// Returns [None] if dividing by zero, or shifting by a negative amount
+    /// or by at least the number of bits in a word.
+    ///
+    /// # Example
+    /// ```
+    /// use baby_emulator::assembler::parser::expression::BinaryOperator;
+    ///
+    /// assert_eq!(BinaryOperator::ShiftLeft.apply(3, 2), Some(12));
+    /// assert_eq!(BinaryOperator::Divide.apply(3, 0), None);
+    /// ```
+    ///
+    pub fn apply(&self, left: WORD, right: WORD) -> Option<WORD> {
+        let shift = u32::try_from(right).ok();
+        match self {
+            BinaryOperator::Or => Some(left | right),
+            BinaryOperator::Xor => Some(left ^ right),
+            BinaryOperator::And => Some(left & right),
+            BinaryOperator::ShiftLeft => shift.and_then(|s| left.checked_shl(s)),
+            BinaryOperator::ShiftRight => shift.and_then(|s| left.checked_shr(s)),
+            BinaryOperator::Add => Some(left.wrapping_add(right)),
+            BinaryOperator::Subtract => Some(left.wrapping_sub(right)),
+            BinaryOperator::Multiply => Some(left.wrapping_mul(right)),
+            BinaryOperator::Divide if right == 0 => None,
+            BinaryOperator::Divide => Some(left.wrapping_div(right)),
+        }
+    }
+
+    /// Returns the precedence of the operator, higher binds tighter.
+    pub fn precedence(&self) -> usize {
+        Self::PRECEDENCE.iter()
+            .position(|g| g.contains(self))
+            .unwrap_or_default()
+    }
+}
+
+/// A single token in a value expression.
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    /// A literal or tag reference, as would be parsed by [Value::parse_term].
+    Term(String),
+    /// An operator symbol.
+    Operator(&'static str),
+    /// An opening parenthesis.
+    Open,
+    /// A closing parenthesis.
+    Close,
+}
+
+/// All the operator symbols, longest first so `<<` is not read as `<`.
+const SYMBOLS: [&str; 10] = ["<<", ">>", "|", "^", "&", "+", "-", "*", "/", "~"];
+
+/// Returns true if the character can start an operator or is a parenthesis.
+fn is_symbol_char(c: char) -> bool {
+    "|^&+-*/~<>()".contains(c)
+}
+
+/// Returns true if the characters start with the given string.
+fn starts_with(chars: &[char], s: &str) -> bool {
+    s.chars().enumerate().all(|(i, c)| chars.get(i) == Some(&c))
+}
+
+/// Splits a value expression into tokens.
+///
+/// Literals can have a `-` straight after their prefix (`0d-10`), this is
+/// part of the literal rather than a subtraction, and character literals
+/// (`'+'`) are a single term whatever they contain.
+fn tokenise(expression: &str) -> Vec<Token> {
+    let chars: Vec<char> = expression.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let rest = &chars[i..];
+        if chars[i].is_whitespace() { i += 1; continue; }
+        if chars[i] == '(' { tokens.push(Token::Open); i += 1; continue; }
+        if chars[i] == ')' { tokens.push(Token::Close); i += 1; continue; }
+        if chars[i] == '\'' {
+            let start = i;
+            i += 1;
+            while i < chars.len() && chars[i] != '\'' { i += if chars[i] == '\\' { 2 } else { 1 }; }
+            i = (i + 1).min(chars.len());
+            tokens.push(Token::Term(chars[start..i].iter().collect()));
+            continue;
+        }
+        if let Some(s) = SYMBOLS.iter().find(|s| starts_with(rest, s)) {
+            tokens.push(Token::Operator(s));
+            i += s.chars().count();
+            continue;
+        }
+        let start = i;
+        if ["0x", "0d", "0o", "0b", "0r"].iter().any(|p| starts_with(rest, p)) {
+            i += 2;
+            if chars.get(i) == Some(&'-') { i += 1; }
+        }
+        while i < chars.len() && !chars[i].is_whitespace() && !is_symbol_char(chars[i]) { i += 1; }
+        if i == start { i += 1; }
+        tokens.push(Token::Term(chars[start..i].iter().collect()));
+    }
+    tokens
+}
+
+/// Parses a value expression into a [Value].
+///
+/// If the expression has no operators or parentheses it is parsed as a
+/// single term with [Value::parse_term], otherwise any error parsing a
+/// term is returned, or [ValueParseError::InvalidExpression] if the
+/// expression is malformed.
+///
+/// # Example
+/// ```
+/// use baby_emulator::assembler::parser::Value;
+/// use baby_emulator::assembler::parser::expression::{parse_expression, UnaryOperator};
+///
+/// assert_eq!(parse_expression("0d-10"), Ok(Value::Value(-10)));
+/// assert_eq!(parse_expression("-$x"), Ok(Value::Unary(
+///     UnaryOperator::Negate,
+///     Box::new(Value::Tag("x".to_owned()))
+/// )));
+/// assert!(parse_expression("($x+1").is_err());
+/// ```
+///
+pub fn parse_expression(expression: &str) -> Result<Value, ValueParseError> {
+    let expression = expression.trim();
+    let tokens = tokenise(expression);
+    if tokens.iter().all(|t| matches!(t, Token::Term(_))) {
+        return Value::parse_term(expression);
+    }
+    let mut parser = ExpressionParser { tokens, position: 0, depth: 0, expression };
+    let value = parser.parse_binary(0)?;
+    match parser.tokens.get(parser.position) {
+        None => Ok(value),
+        Some(_) => Err(parser.invalid()),
+    }
+}
+
+/// Recursive descent parser over the tokens of an expression.
+struct ExpressionParser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    depth: usize,
+    expression: &'a str,
+}
+
+impl ExpressionParser<'_> {
+
+    /// Returns the error for a malformed expression.
+    fn invalid(&self) -> ValueParseError {
+        ValueParseError::InvalidExpression(self.expression.to_owned())
+    }
+
+    /// Parses operators with at least the given precedence, left to right.
+    fn parse_binary(&mut self, precedence: usize) -> Result<Value, ValueParseError> {
+        if precedence >= BinaryOperator::PRECEDENCE.len() {
+            return self.parse_unary();
+        }
+        let depth = self.depth;
+        let mut left = self.parse_binary(precedence + 1)?;
+        while let Some(Token::Operator(s)) = self.tokens.get(self.position) {
+            let op = match BinaryOperator::PRECEDENCE[precedence].iter().find(|o| o.symbol() == *s) {
+                Some(o) => *o,
+                None => break,
+            };
+            self.position += 1;
+            self.nest()?;
+            let right = self.parse_binary(precedence + 1)?;
+            left = Value::Binary(op, Box::new(left), Box::new(right));
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    /// Goes one level deeper, failing if past [MAX_DEPTH].
+    fn nest(&mut self) -> Result<(), ValueParseError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH { Err(self.invalid()) }
+        else { Ok(()) }
+    }
+
+    /// Parses a unary operator, parenthesised expression or single term.
+    fn parse_unary(&mut self) -> Result<Value, ValueParseError> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        let depth = self.depth;
+        if matches!(token, Some(Token::Operator(_)) | Some(Token::Open)) { self.nest()?; }
+        let value = match token {
+            Some(Token::Operator("-")) => Ok(Value::Unary(UnaryOperator::Negate, Box::new(self.parse_unary()?))),
+            Some(Token::Operator("~")) => Ok(Value::Unary(UnaryOperator::Not, Box::new(self.parse_unary()?))),
+            Some(Token::Term(t)) => Value::parse_term(&t),
+            Some(Token::Open) => {
+                let value = self.parse_binary(0)?;
+                match self.tokens.get(self.position) {
+                    Some(Token::Close) => { self.position += 1; Ok(value) },
+                    _ => Err(self.invalid()),
+                }
+            },
+            _ => Err(self.invalid()),
+        };
+        self.depth = depth;
+        value
+    }
+}
@@ -8,10 +8,15 @@
 //! parse it into [LineType][crate::assembler::parser::LineType] that represents 
 //! all the possible types of lines in Baby asm. 
 //! 
+//! [parse_asm_source][crate::assembler::parser::parse_asm_source] does the same 
+//! but keeps a [SourceSpan][crate::assembler::diagnostics::SourceSpan] for each line, 
+//! so errors can be traced back to the exact place in the source. 
+//! 
 //! This can assemble both modern and original notation depending on the 
 //! value passed to `og_notation`. 
 //! 
 //! The output of this can be fed straight into [linker::link_parsed_lines][crate::assembler::linker::link_parsed_lines]
+//! (or [linker::link_source_lines][crate::assembler::linker::link_source_lines] for [parse_asm_source][crate::assembler::parser::parse_asm_source])
 //! to produce a final fully assembled machine code from the asm string. 
 //! 
 //! # Example
@@ -22,31 +27,39 @@
 //! use baby_emulator::core::instructions::BabyInstruction;
 //! 
 //! pub fn assemble(asm: &String) -> Result<Vec<BabyInstruction>, AssemblyError> {
-//!     let parse_result = match parser::parse_asm_string(asm, false) {
+//!     let parse_result = match parser::parse_asm_source(asm, false) {
 //!         Ok(v) => v,
-//!         Err((l, e)) => return Err(AssemblyError::ParserError(l, e))
+//!         Err((s, e)) => return Err(AssemblyError::ParserError(s, e))
 //!     };
-//!     match linker::link_parsed_lines(parse_result) {
+//!     match linker::link_source_lines(parse_result) {
 //!         Ok(LinkerData(v, _)) => Ok(v),
-//!         Err(e) => Err(AssemblyError::LinkerError(e))
+//!         Err((s, e)) => Err(AssemblyError::LinkerError(s, e))
 //!     }
 //! }
 //! ```
 //! 
 
+use std::collections::HashSet;
 use errors::{
     LineParseError,
     TagError,
     AbsoluteError,
     InstructionError,
+    MacroParseError,
+    ConstantError,
+    DirectiveError,
     ValueParseError,
 };
+use expression::{UnaryOperator, BinaryOperator};
 
 use crate::core::WORD;
+use super::diagnostics::SourceSpan;
 
 
 /// Contains types for handling errors during parsing. 
 pub mod errors;
+/// Contains types and functionality for parsing value expressions. 
+pub mod expression;
 #[cfg(test)]
 mod tests;
 
@@ -55,8 +68,12 @@ mod tests;
 pub enum Value {
     /// A literal value. 
     Value(WORD),
-    /// A reference to a [LineType::Tag] value E.G. `$SomeTag`.
+    /// A reference to a [LineType::Tag] or [LineType::Constant] value E.G. `$SomeTag`.
     Tag(String),
+    /// An operator applied to a value E.G. `-$foo`. 
+    Unary(UnaryOperator, Box<Value>),
+    /// An operator applied to two values E.G. `$end-$start`. 
+    Binary(BinaryOperator, Box<Value>, Box<Value>),
 }
 
 impl Value {
@@ -68,61 +85,109 @@ impl Value {
     /// wrong. 
     /// 
     /// # Can parse
-    /// * Hex - `0xA` = 10
-    /// * Decimal - `0d10` = 10
+    /// * Hex - `0xA` = 10, `0x-A` = -10, `0xFFFFFFFF` = -1 
+    /// * Decimal - `0d10` or `10` = 10, `0d-10` = -10 
     /// * Octal - `0o12` = 10
-    /// * Binary - `0b1010` = 10
+    /// * Binary - `0b1010` = 10, `0b-1010` = -10 
+    /// * LSB first binary - `0r0101` = 10, bits as shown on the Baby's display 
+    /// * Characters - `'A'` = 65
     /// * Tags - `$foo` = "foo"
+    /// * Expressions - `$foo+0d2`, see [expression]
+    /// 
+    /// Digits can be separated with `_`, such as `0b1010_1010` or `1_000`, hex, 
+    /// octal and binary without a sign are read as a bit pattern, so can fill 
+    /// the word and set the sign bit. 
     pub fn parse(value: &str) -> Result<Value, ValueParseError> {
+        expression::parse_expression(value)
+    }
+
+    /// Tries to parse a single literal or tag reference, with no operators. 
+    /// 
+    /// Returns a [ValueParseError] if it fails. 
+    pub fn parse_term(value: &str) -> Result<Value, ValueParseError> {
         let value = value.trim();
         match value {
             v if v.starts_with("0x") => Self::parse_hex(v.replace("0x", "")),
             v if v.starts_with("0d") => Self::parse_decimal(v.replace("0d", "")),
             v if v.starts_with("0o") => Self::parse_octal(v.replace("0o", "")),
             v if v.starts_with("0b") => Self::parse_binary(v.replace("0b", "")),
+            v if v.starts_with("0r") => Self::parse_lsb_binary(v.replace("0r", "")),
+            v if v.starts_with('\'') => Self::parse_char(v.to_owned()),
             v if v.starts_with("$") => Self::parse_tag_name(v.replace("$", "")),
+            v if v.starts_with(|c: char| c.is_ascii_digit()) => Self::parse_decimal(v.to_owned()),
             _ => Err(ValueParseError::InvalidValue(value.to_string()))
         }
     }
 
-    /// Tries to parse a hex string. 
+    /// Tries to parse a hex string, without a sign it is read as a bit pattern. 
     /// 
     /// Returns a [ValueParseError] if it fails. 
     pub fn parse_hex(value: String) -> Result<Value, ValueParseError> {
-        let res = match WORD::from_str_radix(&value, 16) {
-            Ok(v) => v,
-            Err(_) => return Err(ValueParseError::InvalidHex(value.to_string()))
-        };
-        Ok(Value::Value(res))
+        parse_literal(&value, 16, true).map(Value::Value).map_err(|e| e.error(value, ValueParseError::InvalidHex))
     }
 
     /// Tries to parse a decimal string. 
     /// 
     /// Returns a [ValueParseError] if it fails. 
     pub fn parse_decimal(value: String) -> Result<Value, ValueParseError> {
-        match WORD::from_str_radix(&value, 10) {
-            Ok(v) => Ok(Value::Value(v)),
-            Err(_) => Err(ValueParseError::InvalidDecimal(value.to_string()))
-        }
+        parse_literal(&value, 10, false).map(Value::Value).map_err(|e| e.error(value, ValueParseError::InvalidDecimal))
     }
 
-    /// Tries to parse an octal string. 
+    /// Tries to parse an octal string, without a sign it is read as a bit pattern. 
     /// 
     /// Returns a [ValueParseError] if it fails. 
     pub fn parse_octal(value: String) -> Result<Value, ValueParseError> {
-        match WORD::from_str_radix(&value, 8) {
-            Ok(v) => Ok(Value::Value(v)),
-            Err(_) => Err(ValueParseError::InvalidOctal(value.to_string()))
-        }
+        parse_literal(&value, 8, true).map(Value::Value).map_err(|e| e.error(value, ValueParseError::InvalidOctal))
     }
 
-    /// Tries to parse a binary string. 
+    /// Tries to parse a binary string, without a sign it is read as a bit pattern. 
     /// 
     /// Returns a [ValueParseError] if it fails. 
     pub fn parse_binary(value: String) -> Result<Value, ValueParseError> {
-        match WORD::from_str_radix(&value, 2) {
-            Ok(v) => Ok(Value::Value(v)),
-            Err(_) => Err(ValueParseError::InvalidBinary(value.to_string()))
+        parse_literal(&value, 2, true).map(Value::Value).map_err(|e| e.error(value, ValueParseError::InvalidBinary))
+    }
+
+    /// Tries to parse a binary string written least significant bit first, 
+    /// the order bits are shown on the Baby's display and in the 1948 notes. 
+    /// 
+    /// Returns a [ValueParseError] if it fails, or if there are more bits 
+    /// than in a word. 
+    /// 
+    /// # Example
+    /// ```
+    /// use baby_emulator::assembler::parser::Value;
+    /// 
+    /// assert_eq!(Value::parse_lsb_binary("0101".to_owned()), Ok(Value::Value(10)));
+    /// ```
+    /// 
+    pub fn parse_lsb_binary(value: String) -> Result<Value, ValueParseError> {
+        let digits = match strip_separators(&value) {
+            Some(d) if d.chars().all(|c| c == '0' || c == '1') => d,
+            _ => return Err(ValueParseError::InvalidLsbBinary(value)),
+        };
+        if digits.len() > WORD::BITS as usize { return Err(ValueParseError::ValueOutOfRange(value)); }
+        let res = digits.chars().rev().fold(0 as WORD, |v, c| (v << 1) | (c == '1') as WORD);
+        Ok(Value::Value(res))
+    }
+
+    /// Tries to parse a character literal such as `'A'`, giving the character's 
+    /// code, the escapes `\n`, `\t`, `\0`, `\\` and `\'` are accepted. 
+    /// 
+    /// Returns a [ValueParseError] if it fails, or if the code does not fit in a word. 
+    pub fn parse_char(value: String) -> Result<Value, ValueParseError> {
+        let inner = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\''));
+        let c = match inner.map(|i| (i, i.chars().count())) {
+            Some((i, 1)) => i.chars().next(),
+            Some(("\\n", _)) => Some('\n'),
+            Some(("\\t", _)) => Some('\t'),
+            Some(("\\0", _)) => Some('\0'),
+            Some(("\\\\", _)) => Some('\\'),
+            Some(("\\'", _)) => Some('\''),
+            _ => None,
+        };
+        match c.and_then(|c| WORD::try_from(c as u32).ok()) {
+            Some(v) => Ok(Value::Value(v)),
+            None => Err(ValueParseError::InvalidCharacter(value)),
         }
     }
 
@@ -136,6 +201,75 @@ impl Value {
         Err(ValueParseError::InvalidTagName(v.to_string()))
     }
 
+    /// Returns the asm source for the value, literals are written 
+    /// as decimal, so parsing the result gives back the same value. 
+    /// 
+    /// Parentheses are only added where they are needed to keep the 
+    /// same order of operations. 
+    /// 
+    /// # Example
+    /// ```
+    /// use baby_emulator::assembler::parser::Value;
+    /// 
+    /// assert_eq!(Value::Value(-10).to_asm_string(), "0d-10");
+    /// assert_eq!(Value::Tag("foo".to_owned()).to_asm_string(), "$foo");
+    /// assert_eq!(Value::parse("($a-$b)-($c-0d1)").unwrap().to_asm_string(), "$a-$b-($c-0d1)");
+    /// ```
+    /// 
+    pub fn to_asm_string(&self) -> String {
+        match self {
+            Value::Value(v) => format!("0d{}", v),
+            Value::Tag(t) => format!("${}", t),
+            Value::Unary(o, v) => match **v {
+                Value::Binary(..) => format!("{}({})", o.symbol(), v.to_asm_string()),
+                _ => format!("{}{}", o.symbol(), v.to_asm_string()),
+            },
+            Value::Binary(o, l, r) => {
+                let wrap = |v: &Value, right: bool| match v {
+                    Value::Binary(c, _, _) if c.precedence() < o.precedence() 
+                        || (right && c.precedence() == o.precedence()) => format!("({})", v.to_asm_string()),
+                    v => v.to_asm_string(),
+                };
+                format!("{}{}{}", wrap(l, false), o.symbol(), wrap(r, true))
+            },
+        }
+    }
+
+    /// Returns the names of all the tags referenced in the value, 
+    /// in the order they are written. 
+    /// 
+    /// # Example
+    /// ```
+    /// use baby_emulator::assembler::parser::Value;
+    /// 
+    /// let value = Value::parse("$end-$start+0d1").unwrap();
+    /// assert_eq!(value.tags(), vec!["end", "start"]);
+    /// ```
+    /// 
+    pub fn tags(&self) -> Vec<&str> {
+        match self {
+            Value::Value(_) => vec![],
+            Value::Tag(t) => vec![t.as_str()],
+            Value::Unary(_, v) => v.tags(),
+            Value::Binary(_, l, r) => l.tags().into_iter().chain(r.tags()).collect(),
+        }
+    }
+
+    /// Returns a copy of the value with every tag reference replaced 
+    /// by the result of `f` for the tag name. 
+    /// 
+    /// # Parameters
+    /// * `f` - Gives the value to replace each tag reference with. 
+    /// 
+    pub fn map_tags(&self, f: &impl Fn(&str) -> Value) -> Value {
+        match self {
+            Value::Value(v) => Value::Value(*v),
+            Value::Tag(t) => f(t),
+            Value::Unary(o, v) => Value::Unary(*o, Box::new(v.map_tags(f))),
+            Value::Binary(o, l, r) => Value::Binary(*o, Box::new(l.map_tags(f)), Box::new(r.map_tags(f))),
+        }
+    }
+
 }
 
 /// Represents all the instructions. 
@@ -175,7 +309,7 @@ impl Instruction {
     /// * `#` is a always a memory address, and will try to be parsed into a [Value]. 
     /// 
     pub fn parse(instruction: &str) -> Result<Instruction, InstructionError> {
-        let instruction = instruction.trim().to_lowercase();
+        let instruction = lowercase_outside_quotes(instruction.trim());
         let v = Value::Value(0);
         match instruction {
 
@@ -200,36 +334,40 @@ impl Instruction {
     /// Parses Baby asm instructions & operands using original notation 
     /// 
     /// # Possible Instructions 
-    /// | Asm       | Description                                                                                                                                           |
-    /// |-----------|-------------------------------------------------------------------------------------------------------------------------------------------------------|
-    /// | #, Cl     | Jump to the instruction at the address obtained from the specified memory address # (absolute unconditional jump)                                     |
-    /// | Add #, Cl | Jump to the instruction at the program counter plus (+) the relative value obtained from the specified memory address # (relative unconditional jump) |
-    /// | -#, C     | Take the number from the specified memory address #, negate it, and load it into the accumulator                                                      |
-    /// | c, #      | Store the number in the accumulator to the specified memory address #                                                                                 |
-    /// | SUB #     | Subtract the number at the specified memory address # from the value in accumulator, and store the result in the accumulator                          |
-    /// | Test      | Skip next instruction if the accumulator contains a negative value                                                                                    |
-    /// | Stop      | Stop                                                                                                                                                  |
-    /// 
-    /// * `#` is a always a memory address 
+    /// | Asm                       | Description                                                                                                                                           |
+    /// |---------------------------|-------------------------------------------------------------------------------------------------------------------------------------------------------|
+    /// | #, CI or # to CI          | Jump to the instruction at the address obtained from the specified memory address # (absolute unconditional jump)                                     |
+    /// | Add #, CI or Add # to CI  | Jump to the instruction at the program counter plus (+) the relative value obtained from the specified memory address # (relative unconditional jump) |
+    /// | -#, C or -# to C          | Take the number from the specified memory address #, negate it, and load it into the accumulator                                                      |
+    /// | c, # or c to #            | Store the number in the accumulator to the specified memory address #                                                                                 |
+    /// | SUB # or Sub. #           | Subtract the number at the specified memory address # from the value in accumulator, and store the result in the accumulator                          |
+    /// | Test or CMP               | Skip next instruction if the accumulator contains a negative value                                                                                    |
+    /// | Stop or STP               | Stop                                                                                                                                                  |
+    /// 
+    /// * `#` is a always a memory address, which can be a plain decimal line number. 
+    /// * Keywords are case insensitive, and `CI` can also be written `Cl`. 
     /// 
     pub fn parse_ogn(instruction: &str) -> Result<Instruction, InstructionError> {
         let instruction = instruction.trim();
         let v = Value::Value(0);
-        match instruction {
-            c if c.ends_with(", Cl") && !c.starts_with("Add ") => 
-                Self::make_instruction(Instruction::Jump(v), c.replace(", Cl", "")),
-            c if c.starts_with("Add ") && c.ends_with(", Cl") => 
-                Self::make_instruction(Instruction::RelativeJump(v), c.replace("Add ", "").replace(", Cl", "")),
-            c if c.starts_with("-") && c.ends_with(", C") => 
-                Self::make_instruction(Instruction::Negate(v), c.replace("-", "").replace(", C", "")),
-            c if c.starts_with("c, ") => 
-                Self::make_instruction(Instruction::Store(v), c.replace("c, ", "")),
-            c if c.starts_with("SUB ") => 
-                Self::make_instruction(Instruction::Subtract(v), c.replace("SUB ", "")),
-
-            c if c.starts_with("Test") => Ok(Instruction::Test),
-            c if c.starts_with("Stop") => Ok(Instruction::Stop),
-
+        if let Some(c) = strip_destination(instruction, &["ci", "cl"]) {
+            return match strip_keyword(c.trim_start(), "add") {
+                Some(o) => Self::make_instruction(Instruction::RelativeJump(v), o.to_owned()),
+                None => Self::make_instruction(Instruction::Jump(v), c.to_owned()),
+            };
+        }
+        if let Some(c) = instruction.strip_prefix('-').and_then(|c| strip_destination(c, &["c"])) {
+            return Self::make_instruction(Instruction::Negate(v), c.to_owned());
+        }
+        if let Some(c) = strip_source(instruction) {
+            return Self::make_instruction(Instruction::Store(v), c.to_owned());
+        }
+        if let Some(c) = strip_keyword(instruction, "sub.").or_else(|| strip_keyword(instruction, "sub")) {
+            return Self::make_instruction(Instruction::Subtract(v), c.to_owned());
+        }
+        match instruction.to_lowercase() {
+            c if c.starts_with("test") || c.starts_with("cmp") => Ok(Instruction::Test),
+            c if c.starts_with("stop") || c.starts_with("stp") => Ok(Instruction::Stop),
             _ => Err(InstructionError::UnkownInstruction(instruction.to_string()))
         }
     }
@@ -247,6 +385,38 @@ impl Instruction {
         }
     }
 
+    /// Returns the asm source for the instruction, in original notation 
+    /// if `og_notation` is true, otherwise modern notation. 
+    /// 
+    /// # Example
+    /// ```
+    /// use baby_emulator::assembler::parser::{Instruction, Value};
+    /// 
+    /// let instr = Instruction::Negate(Value::Tag("foo".to_owned()));
+    /// assert_eq!(instr.to_asm_string(false), "ldn $foo");
+    /// assert_eq!(instr.to_asm_string(true), "-$foo, C");
+    /// ```
+    /// 
+    pub fn to_asm_string(&self, og_notation: bool) -> String {
+        let operand = self.get_operand().to_asm_string();
+        match (self, og_notation) {
+            (Instruction::Jump(_), false) => format!("jmp {}", operand),
+            (Instruction::RelativeJump(_), false) => format!("jrp {}", operand),
+            (Instruction::Negate(_), false) => format!("ldn {}", operand),
+            (Instruction::Store(_), false) => format!("sto {}", operand),
+            (Instruction::Subtract(_), false) => format!("sub {}", operand),
+            (Instruction::Test, false) => "cmp".to_owned(),
+            (Instruction::Stop, false) => "stp".to_owned(),
+            (Instruction::Jump(_), true) => format!("{}, Cl", operand),
+            (Instruction::RelativeJump(_), true) => format!("Add {}, Cl", operand),
+            (Instruction::Negate(_), true) => format!("-{}, C", operand),
+            (Instruction::Store(_), true) => format!("c, {}", operand),
+            (Instruction::Subtract(_), true) => format!("SUB {}", operand),
+            (Instruction::Test, true) => "Test".to_owned(),
+            (Instruction::Stop, true) => "Stop".to_owned(),
+        }
+    }
+
     /// Returns the stored memory address operand of a instruction,
     /// returns a 0 if a stop or test.
     pub fn get_operand(&self) -> Value {
@@ -261,6 +431,19 @@ impl Instruction {
         }
     }
 
+    /// Returns a copy of the instruction with a different operand, a 
+    /// test or stop is returned as it is. 
+    pub fn with_operand(&self, value: Value) -> Instruction {
+        match self {
+            Instruction::Jump(_) => Instruction::Jump(value),
+            Instruction::RelativeJump(_) => Instruction::RelativeJump(value),
+            Instruction::Negate(_) => Instruction::Negate(value),
+            Instruction::Store(_) => Instruction::Store(value),
+            Instruction::Subtract(_) => Instruction::Subtract(value),
+            i => i.clone(),
+        }
+    }
+
     /// Tries to parse an operand value expression, combining it 
     /// with an instruction. 
     /// 
@@ -289,6 +472,102 @@ impl Instruction {
     }
 }
 
+/// The reasons a numeric literal can fail to parse. 
+enum LiteralError {
+    /// The digits are malformed. 
+    Invalid,
+    /// The digits are valid but the value does not fit in a word. 
+    OutOfRange,
+}
+
+impl LiteralError {
+
+    /// Converts into a [ValueParseError], using `invalid` for malformed digits. 
+    fn error(self, value: String, invalid: fn(String) -> ValueParseError) -> ValueParseError {
+        match self {
+            LiteralError::Invalid => invalid(value),
+            LiteralError::OutOfRange => ValueParseError::ValueOutOfRange(value),
+        }
+    }
+}
+
+/// Removes `_` separators from the digits of a literal, returns [None] 
+/// if a separator does not sit between two digits. 
+fn strip_separators(value: &str) -> Option<String> {
+    let digits = value.strip_prefix('-').unwrap_or(value);
+    if digits.starts_with('_') || digits.ends_with('_') || digits.contains("__") { return None; }
+    Some(value.replace('_', ""))
+}
+
+/// Parses the digits of a numeric literal with an optional `-` sign. 
+/// 
+/// If `bit_pattern` is true, a literal without a sign can be as large as 
+/// an unsigned word, taking the two's complement value of its bits. 
+fn parse_literal(value: &str, radix: u32, bit_pattern: bool) -> Result<WORD, LiteralError> {
+    let digits = strip_separators(value).ok_or(LiteralError::Invalid)?;
+    let (negative, magnitude) = match digits.strip_prefix('-') {
+        Some(d) => (true, d),
+        None => (false, digits.as_str()),
+    };
+    if magnitude.starts_with('+') { return Err(LiteralError::Invalid); }
+    let magnitude = u128::from_str_radix(magnitude, radix).map_err(|e| match e.kind() {
+        std::num::IntErrorKind::PosOverflow => LiteralError::OutOfRange,
+        _ => LiteralError::Invalid,
+    })?;
+    let value = if negative { 0i128.checked_sub_unsigned(magnitude) } else { i128::try_from(magnitude).ok() };
+    let value = value.ok_or(LiteralError::OutOfRange)?;
+    if let Ok(v) = WORD::try_from(value) { return Ok(v); }
+    match bit_pattern && !negative && magnitude >> WORD::BITS == 0 {
+        true => Ok(magnitude as WORD),
+        false => Err(LiteralError::OutOfRange),
+    }
+}
+
+/// Lowercases a string apart from any character literals. 
+fn lowercase_outside_quotes(value: &str) -> String {
+    let unquoted: Vec<usize> = unquoted_chars(value).map(|(i, _)| i).collect();
+    value.char_indices()
+        .map(|(i, c)| if unquoted.contains(&i) { c.to_ascii_lowercase() } else { c })
+        .collect()
+}
+
+/// Strips `prefix` from the start of a string, ignoring case. 
+fn strip_prefix_ci<'a>(value: &'a str, prefix: &str) -> Option<&'a str> {
+    value.get(..prefix.len()).filter(|p| p.eq_ignore_ascii_case(prefix))?;
+    value.get(prefix.len()..)
+}
+
+/// Strips `suffix` from the end of a string, ignoring case. 
+fn strip_suffix_ci<'a>(value: &'a str, suffix: &str) -> Option<&'a str> {
+    let split = value.len().checked_sub(suffix.len())?;
+    value.get(split..).filter(|s| s.eq_ignore_ascii_case(suffix))?;
+    value.get(..split)
+}
+
+/// Strips a keyword from the start of an original notation instruction, 
+/// ignoring case, returning the rest if the keyword is a whole word. 
+fn strip_keyword<'a>(instruction: &'a str, keyword: &str) -> Option<&'a str> {
+    let rest = strip_prefix_ci(instruction, keyword)?;
+    let whole = keyword.ends_with('.') || rest.starts_with(char::is_whitespace);
+    if whole && !rest.trim().is_empty() { Some(rest) } else { None }
+}
+
+/// Strips the destination register from the end of an original notation 
+/// instruction, written either as `x, C` or `x to C`, returning the operand. 
+fn strip_destination<'a>(instruction: &'a str, registers: &[&str]) -> Option<&'a str> {
+    let rest = registers.iter().find_map(|r| strip_suffix_ci(instruction, r))?.trim_end();
+    if let Some(operand) = rest.strip_suffix(',') { return Some(operand); }
+    strip_suffix_ci(rest, "to").filter(|o| o.ends_with(char::is_whitespace))
+}
+
+/// Strips the accumulator source from the start of an original notation 
+/// store, written either as `c, x` or `c to x`, returning the operand. 
+fn strip_source(instruction: &str) -> Option<&str> {
+    let rest = strip_prefix_ci(instruction, "c")?.trim_start();
+    if let Some(operand) = rest.strip_prefix(',') { return Some(operand); }
+    strip_prefix_ci(rest, "to").filter(|o| o.starts_with(char::is_whitespace))
+}
+
 /// Represents all the possible syntaxes for a line. 
 #[derive(Clone, Debug, PartialEq)]
 pub enum LineType {
@@ -305,12 +584,143 @@ pub enum LineType {
     /// An actual instruction directive telling the computer to 
     /// perform an action. 
     Instruction(Instruction),
+    /// The start of a macro definition, holds the macro name and the 
+    /// parameter names, all the lines up to [LineType::EndMacro] are 
+    /// the body of the macro. 
+    /// 
+    /// # Asm Example 
+    /// ```text
+    /// macro copy from, to
+    /// ldn $from
+    /// sto $to
+    /// ldn $to
+    /// sto $to
+    /// endm
+    /// ```
+    Macro(String, Vec<String>),
+    /// The end of a macro definition. 
+    EndMacro,
+    /// A named constant, holds the name and the value, it can be 
+    /// referenced like a tag but does not take up any memory. 
+    /// 
+    /// # Asm Example 
+    /// ```text
+    /// equ limit 0d40
+    /// sub $limit
+    /// ```
+    Constant(String, Value),
+    /// A use of a macro, holds the macro name and the argument values. 
+    /// 
+    /// # Asm Example 
+    /// ```text
+    /// copy $foo, $bar
+    /// ```
+    MacroCall(String, Vec<Value>),
+    /// Moves where the next line is placed in memory to an address. 
+    /// 
+    /// # Asm Example 
+    /// ```text
+    /// .org 0d20
+    /// ```
+    Origin(Value),
+    /// A number of words all with the same value, holds the count 
+    /// and the value. 
+    /// 
+    /// # Asm Example 
+    /// ```text
+    /// .fill 0d4 0d-1
+    /// ```
+    Fill(Value, Value),
+    /// A list of absolute values placed one after another. 
+    /// 
+    /// # Asm Example 
+    /// ```text
+    /// .word 0d1, 0d2, $foo
+    /// ```
+    Words(Vec<Value>),
+    /// A number of words of reserved space, left as zero. 
+    /// 
+    /// # Asm Example 
+    /// ```text
+    /// .reserve 0d4
+    /// ```
+    Reserve(Value),
+    /// Tag or constant names made visible to other objects when assembled 
+    /// as an [ObjectFile][crate::assembler::linker::object::ObjectFile]. 
+    /// 
+    /// # Asm Example 
+    /// ```text
+    /// .export print, limit
+    /// ```
+    Export(Vec<String>),
+    /// Tag or constant names declared in other objects, that can be 
+    /// referenced when assembled as an [ObjectFile][crate::assembler::linker::object::ObjectFile]. 
+    /// 
+    /// # Asm Example 
+    /// ```text
+    /// .import print
+    /// ```
+    Import(Vec<String>),
 }
 
+impl LineType {
+
+    /// Returns the asm source for the line, using original notation 
+    /// for instructions if `og_notation` is true. 
+    pub fn to_asm_string(&self, og_notation: bool) -> String {
+        match self {
+            LineType::Tag(t) => format!(":{}", t),
+            LineType::Absolute(v) => format!("abs {}", v.to_asm_string()),
+            LineType::Instruction(i) => i.to_asm_string(og_notation),
+            LineType::Macro(n, p) if p.is_empty() => format!("macro {}", n),
+            LineType::Macro(n, p) => format!("macro {} {}", n, p.join(", ")),
+            LineType::EndMacro => "endm".to_owned(),
+            LineType::Constant(n, v) => format!("equ {} {}", n, v.to_asm_string()),
+            LineType::Origin(v) => format!(".org {}", v.to_asm_string()),
+            LineType::Fill(n, v) => format!(".fill {} {}", n.to_asm_string(), v.to_asm_string()),
+            LineType::Words(v) => format!(".word {}", v.iter()
+                .map(|v| v.to_asm_string())
+                .collect::<Vec<String>>()
+                .join(", ")),
+            LineType::Reserve(n) => format!(".reserve {}", n.to_asm_string()),
+            LineType::Export(n) => format!(".export {}", n.join(", ")),
+            LineType::Import(n) => format!(".import {}", n.join(", ")),
+            LineType::MacroCall(n, a) if a.is_empty() => n.clone(),
+            LineType::MacroCall(n, a) => format!("{} {}", n, a.iter()
+                .map(|v| v.to_asm_string())
+                .collect::<Vec<String>>()
+                .join(", ")),
+        }
+    }
+
+    /// Returns true if the line takes up words in the program stack, 
+    /// I.E. it is an absolute value, an instruction, or a `.fill`, 
+    /// `.word` or `.reserve` directive. 
+    pub fn is_data(&self) -> bool {
+        matches!(self, LineType::Absolute(_) | LineType::Instruction(_) | 
+            LineType::Fill(..) | LineType::Words(_) | LineType::Reserve(_))
+    }
+}
+
+/// Words that cannot be used as macro names in modern notation, as 
+/// they would be mistaken for instructions or directives. 
+pub const RESERVED_NAMES: [&str; 11] = [
+    "jmp", "jrp", "ldn", "sto", "sub", "cmp", "stp", 
+    "abs", "macro", "endm", "equ",
+];
+
+/// Words that cannot be used as macro names in original notation, as 
+/// they would be mistaken for instructions or directives. 
+pub const OG_RESERVED_NAMES: [&str; 11] = [
+    "add", "c", "sub", "test", "stop", "cmp", "stp", 
+    "abs", "macro", "endm", "equ",
+];
+
 /// Splits an asm string into lines, removes the blank lines and
 /// tries to parse each one. 
 /// 
-/// Basically a wrapper for [parse_lines]. 
+/// Basically a wrapper for [parse_asm_source], dropping the source 
+/// locations of each line. 
 /// 
 /// Returns a list of [LineType] corresponding to the line type 
 /// and metadata. Each line can either be an absolute value, a 
@@ -319,18 +729,131 @@ pub enum LineType {
 /// 
 /// Will return a tuple of [usize] and [LineParseError] if an error is 
 /// encountered, containing the metatdata on the error encountered and the
-/// index of the line it was found on. 
+/// index of the line in `asm` it was found on, starting at 0. 
+/// 
+pub fn parse_asm_string(asm: &str, og_notation: bool) -> Result<Vec<LineType>, (usize, LineParseError)> {
+    match parse_asm_source(asm, og_notation) {
+        Ok(v) => Ok(v.into_iter().map(|(_, l)| l).collect()),
+        Err((s, e)) => Err((s.line - 1, e))
+    }
+}
+
+/// Splits an asm string into lines, removes the blank lines and
+/// tries to parse each one, keeping where in the source each line 
+/// came from. 
+/// 
+/// Returns a list of tuples of [SourceSpan] covering the line's 
+/// expression and the parsed [LineType]. 
+/// 
+/// Will return a tuple of [SourceSpan] and [LineParseError] if an error is 
+/// encountered, the span pointing to the part of the line that could not 
+/// be parsed. 
 /// 
-pub fn parse_asm_string(asm: &String, og_notation: bool) -> Result<Vec<LineType>, (usize, LineParseError)> {
-    let lines: Vec<String> = split_filter_lines(asm);
-    parse_lines(lines, og_notation)
+/// # Example
+/// ```
+/// use baby_emulator::assembler::parser::parse_asm_source;
+/// 
+/// match parse_asm_source("stp\n\n; comment\nldn 0xZZ", false) {
+///     Err((span, _)) => {
+///         assert_eq!(span.line, 4);
+///         assert_eq!(span.source(), "0xZZ");
+///     },
+///     Ok(_) => panic!()
+/// }
+/// ```
+/// 
+pub fn parse_asm_source(asm: &str, og_notation: bool) -> Result<Vec<(SourceSpan, LineType)>, (SourceSpan, LineParseError)> {
+    parse_source_lines(asm, og_notation).into_iter().collect()
+}
+
+/// Splits an asm string into lines, removes the blank lines and tries 
+/// to parse every line, carrying on past any lines that fail to parse. 
+/// 
+/// Returns the result of parsing each line, either a tuple of [SourceSpan] 
+/// and the parsed [LineType], or a tuple of [SourceSpan] and [LineParseError] 
+/// with the span pointing to the part of the line that could not be parsed. 
+/// 
+/// # Example
+/// ```
+/// use baby_emulator::assembler::parser::parse_source_lines;
+/// 
+/// let errors: Vec<usize> = parse_source_lines("foo\nstp\nsub 0xZZ", false).into_iter()
+///     .filter_map(|l| l.err())
+///     .map(|(span, _)| span.line)
+///     .collect();
+/// assert_eq!(errors, vec![1, 3]);
+/// ```
+/// 
+pub fn parse_source_lines(asm: &str, og_notation: bool) -> Vec<Result<(SourceSpan, LineType), (SourceSpan, LineParseError)>> {
+    let spans = split_source_lines(asm);
+    let sources: Vec<String> = spans.iter().map(|s| s.source()).collect();
+    let macros = macro_names(&sources, og_notation);
+    let parse = |span: SourceSpan, source: &str| match parse_line_with_macros(source, og_notation, &macros) {
+        Ok(l) => Ok((span, l)),
+        Err(e) => Err((error_span(&span, &e), e))
+    };
+    let mut res = vec![];
+    for (span, source) in spans.into_iter().zip(sources) {
+        let parsed = parse(span.clone(), &source);
+        match split_line_number(&source).filter(|_| og_notation && parsed.is_err()) {
+            Some((n, rest)) => {
+                let end = span.start + source.chars().count() - rest.chars().count();
+                let number = SourceSpan { end, ..span.clone() }.trim();
+                res.push(Ok((number, LineType::Origin(Value::Value(n)))));
+                res.push(parse(SourceSpan { start: end, ..span }.trim(), rest));
+            },
+            None => res.push(parsed),
+        }
+    }
+    res
+}
+
+/// Splits an asm string into lines, returning a [SourceSpan] covering 
+/// the expression on each line that is not blank once comments are 
+/// removed. 
+pub fn split_source_lines(asm: &str) -> Vec<SourceSpan> {
+    asm.lines().enumerate()
+        .map(|(i, l)| {
+            let mut span = SourceSpan::new(i + 1, l);
+            span.end = comment_start(l).map_or(span.end, |i| l[..i].chars().count());
+            span.trim()
+        })
+        .filter(|s| s.start < s.end)
+        .collect()
+}
+
+/// Narrows the span of a line to the part that caused an error parsing it. 
+fn error_span(span: &SourceSpan, error: &LineParseError) -> SourceSpan {
+    let value = match error {
+        LineParseError::TagError(TagError::TagNameWhitespace(v)) => return span.narrow(v),
+        LineParseError::AbsoluteError(AbsoluteError::ValueError(v)) => v,
+        LineParseError::InstructionError(InstructionError::OperandValueParseError(_, v)) => v,
+        LineParseError::InstructionError(InstructionError::UnkownInstruction(_)) => return span.clone(),
+        LineParseError::MacroError(MacroParseError::InvalidName(v)) => return span.narrow(v),
+        LineParseError::MacroError(MacroParseError::InvalidParameter(v)) => return span.narrow(v),
+        LineParseError::MacroError(MacroParseError::ArgumentError(_, v)) => v,
+        LineParseError::ConstantError(ConstantError::InvalidName(v)) => return span.narrow(v),
+        LineParseError::ConstantError(ConstantError::ValueError(_, v)) => v,
+        LineParseError::DirectiveError(DirectiveError::UnknownDirective(v)) => return span.narrow(v),
+        LineParseError::DirectiveError(DirectiveError::MissingValue(_)) => return span.clone(),
+        LineParseError::DirectiveError(DirectiveError::InvalidName(_, n)) if !n.is_empty() => return span.narrow(n),
+        LineParseError::DirectiveError(DirectiveError::InvalidName(_, _)) => return span.clone(),
+        LineParseError::DirectiveError(DirectiveError::ValueError(_, v)) => v,
+    };
+    match value {
+        ValueParseError::InvalidValue(v) | ValueParseError::InvalidHex(v) |
+        ValueParseError::InvalidDecimal(v) | ValueParseError::InvalidOctal(v) |
+        ValueParseError::InvalidBinary(v) | ValueParseError::InvalidTagName(v) |
+        ValueParseError::InvalidLsbBinary(v) | ValueParseError::InvalidCharacter(v) |
+        ValueParseError::ValueOutOfRange(v) | ValueParseError::InvalidExpression(v) => span.narrow(v),
+    }
 }
 
 /// Splits an asm string into lines, removeing comments and blank lines 
 /// returning only parsable expressions (I.E. instructions and tags). 
 /// 
 /// Returns each parsable expression as a string in a vector. 
-pub fn split_filter_lines(asm: &String) -> Vec<String> {
+pub fn split_filter_lines(asm: &str) -> Vec<String> {
     asm.lines()
         .map(strip_comments)
         .map(|l| l.trim().to_owned())
@@ -351,8 +874,17 @@ pub fn split_filter_lines(asm: &String) -> Vec<String> {
 /// 
 pub fn parse_lines(lines: Vec<String>, og_notation: bool) -> Result<Vec<LineType>, (usize, LineParseError)> {
     let mut res: Vec<LineType> = vec![];
+    let macros = macro_names(&lines, og_notation);
     for (index, line) in lines.iter().enumerate() {
-        match parse_line(line, og_notation) {
+        let parsed = parse_line_with_macros(line, og_notation, &macros);
+        let parsed = match split_line_number(line).filter(|_| og_notation && parsed.is_err()) {
+            Some((n, rest)) => {
+                res.push(LineType::Origin(Value::Value(n)));
+                parse_line_with_macros(rest, og_notation, &macros)
+            },
+            None => parsed,
+        };
+        match parsed {
             Ok(l) => res.push(l),
             Err(e) => return Err((index, e))
         }
@@ -360,6 +892,36 @@ pub fn parse_lines(lines: Vec<String>, og_notation: bool) -> Result<Vec<LineType
     Ok(res)
 }
 
+/// Splits the store line number from the start of a line of original 
+/// notation, as programs were written out by hand, returning the line 
+/// number and the rest of the line. 
+/// 
+/// A line is only split if it does not parse without the split, as a 
+/// plain decimal operand such as `20 to CI` could be mistaken for a 
+/// line number, the line is then placed at the line number as per `.org`. 
+/// 
+/// # Example
+/// ```
+/// use baby_emulator::assembler::parser::split_line_number;
+/// 
+/// assert_eq!(split_line_number("6  Sub. 27"), Some((6, "Sub. 27")));
+/// assert_eq!(split_line_number("Sub. 27"), None);
+/// ```
+/// 
+pub fn split_line_number(line: &str) -> Option<(WORD, &str)> {
+    let line = line.trim();
+    let (number, rest) = line.split_once(char::is_whitespace)?;
+    if !number.chars().all(|c| c.is_ascii_digit()) { return None; }
+    Some((number.parse().ok()?, rest.trim_start()))
+}
+
+/// Returns true if a line is just a decimal number with an optional sign, 
+/// which original notation stores as a number. 
+fn is_plain_decimal(line: &str) -> bool {
+    let digits = line.strip_prefix('-').unwrap_or(line);
+    !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+}
+
 /// Tries to parse a line of Baby asm 
 /// 
 /// Returns an instance of [LineType] corresponding to the 
@@ -370,17 +932,216 @@ pub fn parse_lines(lines: Vec<String>, og_notation: bool) -> Result<Vec<LineType
 /// Will return an instance of [LineParseError] if an error is 
 /// encountered, containing metatdata on the error encountered.  
 /// 
-pub fn parse_line(line: &String, og_notation: bool) -> Result<LineType, LineParseError> {
+pub fn parse_line(line: &str, og_notation: bool) -> Result<LineType, LineParseError> {
     let line = line.trim();
-    let line = strip_comments(&line);
+    let line = strip_comments(line);
     match line {
         l if l.starts_with(":") => parse_tag(l.replace(":", "")),
         l if l.starts_with("abs ") => parse_absolute(l.replace("abs ", "")),
+        l if is_keyword(&l, "macro") => parse_macro(&l, og_notation),
+        l if l.trim().eq_ignore_ascii_case("endm") => Ok(LineType::EndMacro),
+        l if is_keyword(&l, "equ") => parse_constant(&l),
+        l if l.starts_with('.') => parse_directive(&l),
+        l if og_notation && is_plain_decimal(&l) => parse_absolute(l),
         l => if og_notation { parse_instruction_ogn(l) } 
             else { parse_instruction(l) },
     }
 }
 
+/// Tries to parse a line of Baby asm as per [parse_line], parsing 
+/// the line as a macro use if it starts with one of the macro names. 
+/// 
+/// # Parameters
+/// * `line` - The line of asm. 
+/// * `og_notation` - If true, will use original notation. 
+/// * `macros` - The names of all the macros, as returned by [macro_names]. 
+/// 
+pub fn parse_line_with_macros(line: &str, og_notation: bool, macros: &HashSet<String>) -> Result<LineType, LineParseError> {
+    let first = strip_comments(line).split_whitespace().next().unwrap_or_default().to_lowercase();
+    if macros.contains(&first) { parse_macro_call(line) }
+    else { parse_line(line, og_notation) }
+}
+
+/// Finds the names of all the macros defined in some lines of asm. 
+/// 
+/// # Example
+/// ```
+/// use baby_emulator::assembler::parser::macro_names;
+/// 
+/// let lines = vec!["macro Clear x".to_owned(), "endm".to_owned()];
+/// assert!(macro_names(&lines, false).contains("clear"));
+/// ```
+/// 
+pub fn macro_names(lines: &[String], og_notation: bool) -> HashSet<String> {
+    lines.iter()
+        .map(|l| strip_comments(l))
+        .filter(|l| is_keyword(l, "macro"))
+        .filter_map(|l| match parse_macro(&l, og_notation) {
+            Ok(LineType::Macro(n, _)) => Some(n),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns true if the first word of a line is a keyword, ignoring case. 
+fn is_keyword(line: &str, keyword: &str) -> bool {
+    line.split_whitespace().next().is_some_and(|w| w.eq_ignore_ascii_case(keyword))
+}
+
+/// Parses a macro definition header, E.G. `macro add a, b`. 
+/// 
+/// Macro and parameter names are not case sensitive, parameters can 
+/// optionally be written as tag references (`$a`). 
+/// 
+/// Returns [LineParseError::MacroError] if the macro name is missing or 
+/// reserved (see [RESERVED_NAMES] & [OG_RESERVED_NAMES]), or any 
+/// parameter name is invalid. 
+/// 
+/// # Parameters
+/// * `line` - The macro definition line. 
+/// * `og_notation` - If true, will reserve original notation names. 
+/// 
+/// # Example
+/// ```
+/// use baby_emulator::assembler::parser::{LineType, parse_macro};
+/// 
+/// assert_eq!(
+///     parse_macro("macro Add a, $b", false), 
+///     Ok(LineType::Macro("add".to_owned(), vec!["a".to_owned(), "b".to_owned()]))
+/// );
+/// ```
+/// 
+pub fn parse_macro(line: &str, og_notation: bool) -> Result<LineType, LineParseError> {
+    let err = |e| Err(LineParseError::MacroError(e));
+    let rest = line.trim().split_at(5).1.trim();
+    let (name, params) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    let name = name.to_lowercase();
+    let valid_name = !name.starts_with('.') && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '.');
+    let reserved = if og_notation { OG_RESERVED_NAMES.as_slice() } else { RESERVED_NAMES.as_slice() };
+    if name.is_empty() || !valid_name || reserved.contains(&name.as_str()) {
+        return err(MacroParseError::InvalidName(name));
+    }
+    let mut res: Vec<String> = vec![];
+    for param in params.split(',').filter(|_| !params.trim().is_empty()) {
+        let param = param.trim();
+        let stripped = param.strip_prefix('$').unwrap_or(param).to_lowercase();
+        if stripped.is_empty() || stripped.contains(char::is_whitespace) {
+            return err(MacroParseError::InvalidParameter(param.to_owned()));
+        }
+        res.push(stripped);
+    }
+    Ok(LineType::Macro(name, res))
+}
+
+/// Parses a macro use, E.G. `add $foo, 0d1`, the macro name followed 
+/// by comma separated argument values. 
+/// 
+/// Returns [LineParseError::MacroError] if any of the arguments cannot 
+/// be parsed. 
+/// 
+/// # Example
+/// ```
+/// use baby_emulator::assembler::parser::{LineType, Value, parse_macro_call};
+/// 
+/// assert_eq!(
+///     parse_macro_call("add $foo, 0d1"), 
+///     Ok(LineType::MacroCall("add".to_owned(), vec![Value::Tag("foo".to_owned()), Value::Value(1)]))
+/// );
+/// ```
+/// 
+pub fn parse_macro_call(line: &str) -> Result<LineType, LineParseError> {
+    let line = strip_comments(line);
+    let line = line.trim();
+    let (name, args) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let name = name.to_lowercase();
+    let mut res: Vec<Value> = vec![];
+    for arg in split_values(args).into_iter().filter(|_| !args.trim().is_empty()) {
+        match Value::parse(arg) {
+            Ok(v) => res.push(v),
+            Err(e) => return Err(LineParseError::MacroError(MacroParseError::ArgumentError(name, e)))
+        }
+    }
+    Ok(LineType::MacroCall(name, res))
+}
+
+/// Parses a constant declaration, E.G. `equ limit 0d40`, the name 
+/// followed by the value expression. 
+/// 
+/// Returns [LineParseError::ConstantError] if the name is missing or 
+/// invalid, or the value cannot be parsed. 
+/// 
+/// # Example
+/// ```
+/// use baby_emulator::assembler::parser::{LineType, Value, parse_constant};
+/// 
+/// assert_eq!(parse_constant("equ limit 0d40"), Ok(LineType::Constant("limit".to_owned(), Value::Value(40))));
+/// ```
+/// 
+pub fn parse_constant(line: &str) -> Result<LineType, LineParseError> {
+    let rest = line.trim().split_at(3).1.trim();
+    let (name, value) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    let valid_name = name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '.');
+    if name.is_empty() || !valid_name {
+        return Err(LineParseError::ConstantError(ConstantError::InvalidName(name.to_owned())));
+    }
+    match Value::parse(value) {
+        Ok(v) => Ok(LineType::Constant(name.to_owned(), v)),
+        Err(e) => Err(LineParseError::ConstantError(ConstantError::ValueError(name.to_owned(), e))),
+    }
+}
+
+/// Parses a placement directive, one of: 
+/// * `.org N` - Places the next line at address `N`. 
+/// * `.fill N value` - `N` words of `value`. 
+/// * `.word a, b, c` - Each value in a word one after another. 
+/// * `.reserve N` - `N` words of reserved space. 
+/// 
+/// Directive names are not case sensitive. 
+/// 
+/// Returns [LineParseError::DirectiveError] if the directive is not known, 
+/// or any of its values are missing or cannot be parsed. 
+/// 
+/// # Example
+/// ```
+/// use baby_emulator::assembler::parser::{LineType, Value, parse_directive};
+/// 
+/// assert_eq!(parse_directive(".fill 0d2 $foo"), Ok(LineType::Fill(Value::Value(2), Value::Tag("foo".to_owned()))));
+/// ```
+/// 
+pub fn parse_directive(line: &str) -> Result<LineType, LineParseError> {
+    let line = line.trim();
+    let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let name = name.to_lowercase();
+    let err = |e| LineParseError::DirectiveError(e);
+    let value = |v: &str| Value::parse(v).map_err(|e| err(DirectiveError::ValueError(name.clone(), e)));
+    match name.as_str() {
+        ".org" => Ok(LineType::Origin(value(rest)?)),
+        ".reserve" => Ok(LineType::Reserve(value(rest)?)),
+        ".fill" => match rest.trim().split_once(char::is_whitespace) {
+            Some((n, v)) => Ok(LineType::Fill(value(n)?, value(v)?)),
+            None => Err(err(DirectiveError::MissingValue(name.clone()))),
+        },
+        ".word" => Ok(LineType::Words(split_values(rest).into_iter().map(value).collect::<Result<Vec<Value>, LineParseError>>()?)),
+        ".export" => Ok(LineType::Export(directive_names(&name, rest)?)),
+        ".import" => Ok(LineType::Import(directive_names(&name, rest)?)),
+        _ => Err(err(DirectiveError::UnknownDirective(name.clone()))),
+    }
+}
+
+/// Parses the comma separated names given to an `.export` or `.import`. 
+/// 
+/// Returns [DirectiveError::InvalidName] if any name is empty or contains whitespace. 
+fn directive_names(directive: &str, names: &str) -> Result<Vec<String>, LineParseError> {
+    names.split(',')
+        .map(|n| match n.trim() {
+            n if n.is_empty() || n.contains(char::is_whitespace) => Err(LineParseError::DirectiveError(
+                DirectiveError::InvalidName(directive.to_owned(), n.to_owned())
+            )),
+            n => Ok(n.to_owned()),
+        })
+        .collect()
+}
+
 /// Strips comments from a line of Baby asm. 
 /// 
 /// # Example
@@ -391,9 +1152,42 @@ pub fn parse_line(line: &String, og_notation: bool) -> Result<LineType, LinePars
 /// ```
 /// 
 pub fn strip_comments(line: &str) -> String {
-    let lines = if let Some(v) = line.split(";").next() { v }
-        else { "" };
-    lines.to_owned()
+    line[..comment_start(line).unwrap_or(line.len())].to_owned()
+}
+
+/// Returns the byte index of the `;` starting a comment on a line, 
+/// ignoring any inside character literals such as `';'`. 
+pub(crate) fn comment_start(line: &str) -> Option<usize> {
+    unquoted_chars(line).find(|(_, c)| *c == ';').map(|(i, _)| i)
+}
+
+/// Splits a list of values on commas, ignoring any inside character literals. 
+fn split_values(values: &str) -> Vec<&str> {
+    let mut res = vec![];
+    let mut start = 0;
+    for (i, _) in unquoted_chars(values).filter(|(_, c)| *c == ',') {
+        res.push(&values[start..i]);
+        start = i + 1;
+    }
+    res.push(&values[start..]);
+    res
+}
+
+/// Returns the characters of a line that are not part of a character 
+/// literal, along with their byte index. 
+fn unquoted_chars(line: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    let mut quoted = false;
+    let mut escaped = false;
+    line.char_indices().filter(move |(_, c)| {
+        let outside = !quoted && *c != '\'';
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '\'' => quoted = !quoted,
+            _ => (),
+        }
+        outside
+    })
 }
 
 /// Parses a tag declaration. 
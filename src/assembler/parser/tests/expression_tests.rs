@@ -0,0 +1,101 @@
+use super::*;
+use expression::{parse_expression, MAX_DEPTH};
+
+
+fn tag(t: &str) -> Box<Value> {
+    Box::new(Value::Tag(t.to_owned()))
+}
+
+fn value(v: WORD) -> Box<Value> {
+    Box::new(Value::Value(v))
+}
+
+#[test]
+fn test_parse_terms() {
+    assert_eq!(parse_expression(" 0d-10 "), Ok(Value::Value(-10)));
+    assert_eq!(parse_expression("12"), Ok(Value::Value(12)));
+    assert_eq!(parse_expression("$loop.1.start"), Ok(Value::Tag("loop.1.start".to_owned())));
+    assert_eq!(parse_expression("$ta g"), Err(ValueParseError::InvalidTagName("ta g".to_owned())));
+}
+
+#[test]
+fn test_parse_operators() {
+    assert_eq!(parse_expression("$table+3"), Ok(Value::Binary(BinaryOperator::Add, tag("table"), value(3))));
+    assert_eq!(parse_expression("$a-0x-A"), Ok(Value::Binary(BinaryOperator::Subtract, tag("a"), value(-10))));
+    assert_eq!(parse_expression("-$x"), Ok(Value::Unary(UnaryOperator::Negate, tag("x"))));
+    assert_eq!(parse_expression("~-1"), Ok(Value::Unary(UnaryOperator::Not, Box::new(
+        Value::Unary(UnaryOperator::Negate, value(1))
+    ))));
+}
+
+#[test]
+fn test_parse_precedence() {
+    assert_eq!(parse_expression("1 + 2 * 3"), Ok(Value::Binary(BinaryOperator::Add, value(1), Box::new(
+        Value::Binary(BinaryOperator::Multiply, value(2), value(3))
+    ))));
+    assert_eq!(parse_expression("1-2-3"), Ok(Value::Binary(BinaryOperator::Subtract, Box::new(
+        Value::Binary(BinaryOperator::Subtract, value(1), value(2))
+    ), value(3))));
+    assert_eq!(parse_expression("(1|2)&3"), Ok(Value::Binary(BinaryOperator::And, Box::new(
+        Value::Binary(BinaryOperator::Or, value(1), value(2))
+    ), value(3))));
+    assert_eq!(parse_expression("$a>>1<<2"), Ok(Value::Binary(BinaryOperator::ShiftLeft, Box::new(
+        Value::Binary(BinaryOperator::ShiftRight, tag("a"), value(1))
+    ), value(2))));
+}
+
+#[test]
+fn test_parse_expression_errors() {
+    let invalid = |v: &str| Err(ValueParseError::InvalidExpression(v.to_owned()));
+    assert_eq!(parse_expression("$a+"), invalid("$a+"));
+    assert_eq!(parse_expression("($a+1"), invalid("($a+1"));
+    assert_eq!(parse_expression("$a+1)"), invalid("$a+1)"));
+    assert_eq!(parse_expression("$a $b+1"), invalid("$a $b+1"));
+    assert_eq!(parse_expression("$a+0xZZ"), Err(ValueParseError::InvalidHex("ZZ".to_owned())));
+}
+
+#[test]
+fn test_expression_depth() {
+    let nested = |n| format!("{}0d1{}", "(".repeat(n), ")".repeat(n));
+    assert_eq!(parse_expression(&nested(MAX_DEPTH)), Ok(Value::Value(1)));
+    assert_eq!(parse_expression(&nested(30_000)), Err(ValueParseError::InvalidExpression(nested(30_000))));
+    assert!(parse_expression(&"-".repeat(MAX_DEPTH + 1)).is_err());
+    let chain = |n| vec!["0d1"; n + 1].join("+");
+    assert!(parse_expression(&chain(MAX_DEPTH)).is_ok());
+    assert!(parse_expression(&chain(100_000)).is_err());
+    assert!(parse_line(&format!("abs {}", nested(30_000)), false).is_err());
+}
+
+#[test]
+fn test_expression_round_trip() {
+    for v in ["$a-$b-($c-0d1)", "-($a+0d1)*0d2", "~$a&(0d1|0d2)", "0d-3/0d2", "($a<<0d1)+0d1", "$a<<0d1+0d1"] {
+        let parsed = parse_expression(v).unwrap();
+        assert_eq!(parsed.to_asm_string(), v);
+        assert_eq!(parse_expression(&parsed.to_asm_string()), Ok(parsed));
+    }
+}
+
+#[test]
+fn test_parse_constant() {
+    assert_eq!(parse_line("equ limit $end-0d1 ; comment", false), Ok(LineType::Constant(
+        "limit".to_owned(), Value::Binary(BinaryOperator::Subtract, tag("end"), value(1))
+    )));
+    assert_eq!(parse_line("equ a-b 1", true), Err(LineParseError::ConstantError(ConstantError::InvalidName("a-b".to_owned()))));
+    assert!(matches!(
+        parse_line("equ limit", false),
+        Err(LineParseError::ConstantError(ConstantError::ValueError(_, _)))
+    ));
+}
+
+#[test]
+fn test_parse_instruction_expressions() {
+    assert_eq!(parse_line("ldn $table+1", false), Ok(LineType::Instruction(Instruction::Negate(
+        Value::Binary(BinaryOperator::Add, tag("table"), value(1))
+    ))));
+    assert_eq!(parse_line("-$a-1, C", true), Ok(LineType::Instruction(Instruction::Negate(
+        Value::Binary(BinaryOperator::Subtract, tag("a"), value(1))
+    ))));
+    assert_eq!(parse_line("--$a, C", true), Ok(LineType::Instruction(Instruction::Negate(
+        Value::Unary(UnaryOperator::Negate, tag("a"))
+    ))));
+}
@@ -67,6 +67,36 @@ fn test_instruction_parse_ogn() {
     });
 }
 
+#[test]
+fn test_instruction_parse_ogn_kilburn() {
+    let v = || Value::Value(20);
+    let forms = vec![
+        ("20 to CI", Ok(Instruction::Jump(v()))),
+        ("20, ci", Ok(Instruction::Jump(v()))),
+        ("20 TO cl", Ok(Instruction::Jump(v()))),
+        ("Add 20 to CI", Ok(Instruction::RelativeJump(v()))),
+        ("ADD 20, CI", Ok(Instruction::RelativeJump(v()))),
+        ("-20 to C", Ok(Instruction::Negate(v()))),
+        ("-20 , c", Ok(Instruction::Negate(v()))),
+        ("c to 20", Ok(Instruction::Store(v()))),
+        ("C, 20", Ok(Instruction::Store(v()))),
+        ("Sub. 20", Ok(Instruction::Subtract(v()))),
+        ("sub.20", Ok(Instruction::Subtract(v()))),
+        ("Sub 20", Ok(Instruction::Subtract(v()))),
+        ("CMP", Ok(Instruction::Test)),
+        ("test", Ok(Instruction::Test)),
+        ("STP", Ok(Instruction::Stop)),
+        ("STOP", Ok(Instruction::Stop)),
+        ("-$Foo to C", Ok(Instruction::Negate(Value::Tag("Foo".to_owned())))),
+        ("20 into CI", Err(InstructionError::UnkownInstruction("20 into CI".to_owned()))),
+        ("Subtract 20", Err(InstructionError::UnkownInstruction("Subtract 20".to_owned()))),
+        ("c to", Err(InstructionError::UnkownInstruction("c to".to_owned()))),
+    ];
+    for (i, r) in forms {
+        assert_eq!(Instruction::parse_ogn(i), r, "{}", i);
+    }
+}
+
 #[test]
 fn test_get_operand() {
     get_instructions_with_operands().iter().for_each(|(i, r)| {
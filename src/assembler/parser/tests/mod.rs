@@ -5,6 +5,8 @@ use super::*;
 mod instruction_test;
 #[cfg(test)]
 mod value_tests;
+#[cfg(test)]
+mod expression_tests;
 
 
 #[test]
@@ -199,3 +201,145 @@ fn test_parse_lines_ogn() {
         Ok(_) => panic!("Incorrect og notation lines parsed correctly. ")
     };
 }
+
+#[test]
+fn test_parse_asm_source() {
+    let asm = "; header\n\n  ldn 0d1 ; comment\n:foo\nstp";
+    let lines = match parse_asm_source(asm, false) {
+        Ok(v) => v,
+        Err(_) => panic!("Failed to parse correct source. ")
+    };
+    let spans: Vec<(usize, String)> = lines.iter()
+        .map(|(s, _)| (s.line, s.source()))
+        .collect();
+    assert_eq!(spans, vec![
+        (3, "ldn 0d1".to_owned()),
+        (4, ":foo".to_owned()),
+        (5, "stp".to_owned()),
+    ]);
+    assert_eq!(lines[1].1, LineType::Tag("foo".to_owned()));
+}
+
+#[test]
+fn test_parse_asm_source_errors() {
+    let errors = vec![
+        ("stp\n\nsub 0xZZ", false, 3, "0xZZ"),
+        ("stp\n; comment\nfoo 0d1 ; bar", false, 3, "foo 0d1"),
+        ("\n\n   abs 0d1x", false, 3, "0d1x"),
+        (":foo bar", false, 1, ":foo bar"),
+        ("Stop\n-0b12, C", true, 2, "-0b12"),
+    ];
+    for (asm, og, line, source) in errors {
+        match parse_asm_source(asm, og) {
+            Err((s, _)) => {
+                assert_eq!(s.line, line);
+                assert_eq!(s.source(), source);
+            },
+            Ok(_) => panic!("Incorrect source parsed correctly. ")
+        }
+        match parse_asm_string(asm, og) {
+            Err((i, _)) => assert_eq!(i, line - 1),
+            Ok(_) => panic!("Incorrect source parsed correctly. ")
+        }
+    }
+}
+
+#[test]
+fn test_parse_source_lines() {
+    let res = parse_source_lines("foo\nstp\n\n:a b\nabs 0d1", false);
+    let lines: Vec<(usize, bool)> = res.iter()
+        .map(|l| match l {
+            Ok((s, _)) => (s.line, true),
+            Err((s, _)) => (s.line, false),
+        })
+        .collect();
+    assert_eq!(lines, vec![(1, false), (2, true), (4, false), (5, true)]);
+}
+
+#[test]
+fn test_parse_macro() {
+    assert_eq!(parse_line("MACRO Copy $From, to ; comment", false), Ok(LineType::Macro(
+        "copy".to_owned(), vec!["from".to_owned(), "to".to_owned()]
+    )));
+    assert_eq!(parse_line("macro halt", true), Ok(LineType::Macro("halt".to_owned(), vec![])));
+    assert_eq!(parse_line("  EndM  ", false), Ok(LineType::EndMacro));
+    assert_eq!(parse_macro("macro sto a", false), Err(LineParseError::MacroError(MacroParseError::InvalidName("sto".to_owned()))));
+    assert_eq!(parse_macro("macro Add a", true), Err(LineParseError::MacroError(MacroParseError::InvalidName("add".to_owned()))));
+    assert!(parse_macro("macro add a", false).is_ok());
+    assert_eq!(parse_macro("macro a-b", false), Err(LineParseError::MacroError(MacroParseError::InvalidName("a-b".to_owned()))));
+    assert_eq!(parse_macro("macro foo a,,b", false), Err(LineParseError::MacroError(MacroParseError::InvalidParameter("".to_owned()))));
+}
+
+#[test]
+fn test_parse_macro_call() {
+    let macros = macro_names(&["macro Clear x".to_owned()], false);
+    assert_eq!(parse_line_with_macros("CLEAR $foo, 0d1", false, &macros), Ok(LineType::MacroCall(
+        "clear".to_owned(), vec![Value::Tag("foo".to_owned()), Value::Value(1)]
+    )));
+    assert_eq!(parse_line_with_macros("clear", false, &macros), Ok(LineType::MacroCall("clear".to_owned(), vec![])));
+    assert_eq!(parse_line_with_macros("stp", false, &macros), Ok(LineType::Instruction(Instruction::Stop)));
+    assert!(matches!(
+        parse_line_with_macros("clear 0xZZ", false, &macros),
+        Err(LineParseError::MacroError(MacroParseError::ArgumentError(_, _)))
+    ));
+}
+
+#[test]
+fn test_parse_directive() {
+    assert_eq!(parse_line(".org 0d20 ; constants", false), Ok(LineType::Origin(Value::Value(20))));
+    assert_eq!(parse_line(".RESERVE 4", true), Ok(LineType::Reserve(Value::Value(4))));
+    assert_eq!(parse_line(".fill 0d3 0d-1", false), Ok(LineType::Fill(Value::Value(3), Value::Value(-1))));
+    assert_eq!(parse_line(".word 0d1, $Foo,0x2", false), Ok(LineType::Words(vec![
+        Value::Value(1), Value::Tag("Foo".to_owned()), Value::Value(2)
+    ])));
+    assert_eq!(parse_line(".align 4", false), Err(LineParseError::DirectiveError(DirectiveError::UnknownDirective(".align".to_owned()))));
+    assert_eq!(parse_line(".fill 4", false), Err(LineParseError::DirectiveError(DirectiveError::MissingValue(".fill".to_owned()))));
+    assert_eq!(parse_line(".word 0d1,", false), Err(LineParseError::DirectiveError(
+        DirectiveError::ValueError(".word".to_owned(), ValueParseError::InvalidValue("".to_owned()))
+    )));
+    assert!(parse_macro("macro .org", false).is_err());
+    assert_eq!(parse_line(".export print, Limit", false), Ok(LineType::Export(vec!["print".to_owned(), "Limit".to_owned()])));
+    assert_eq!(parse_line(".IMPORT print", true), Ok(LineType::Import(vec!["print".to_owned()])));
+    assert_eq!(parse_line(".import a,", false), Err(LineParseError::DirectiveError(
+        DirectiveError::InvalidName(".import".to_owned(), "".to_owned())
+    )));
+}
+
+#[test]
+fn test_directive_to_asm_string() {
+    for line in [".org 0d20", ".fill 0d2 $a+0d1", ".word 0d1, $b", ".reserve 0d4", ".export a, b", ".import c"] {
+        let parsed = parse_line(line, false).unwrap();
+        assert_eq!(parsed.to_asm_string(false), line);
+        assert!(parsed.is_data() != matches!(parsed, LineType::Origin(_) | LineType::Export(_) | LineType::Import(_)));
+    }
+}
+
+#[test]
+fn test_parse_ogn_line_numbers() {
+    let asm = "1  -5 to C\n2  Sub. 6 ; take one\n3  20 to CI\n20 to CI\n5  -3\n6\n7 foo";
+    let lines: Vec<(String, Result<LineType, LineParseError>)> = parse_source_lines(asm, true).into_iter()
+        .map(|l| match l {
+            Ok((s, l)) => (s.source(), Ok(l)),
+            Err((s, e)) => (s.source(), Err(e)),
+        })
+        .collect();
+    let origin = |n| Ok(LineType::Origin(Value::Value(n)));
+    let instruction = |i| Ok(LineType::Instruction(i));
+    assert_eq!(lines, vec![
+        ("1".to_owned(), origin(1)),
+        ("-5 to C".to_owned(), instruction(Instruction::Negate(Value::Value(5)))),
+        ("2".to_owned(), origin(2)),
+        ("Sub. 6".to_owned(), instruction(Instruction::Subtract(Value::Value(6)))),
+        ("3".to_owned(), origin(3)),
+        ("20 to CI".to_owned(), instruction(Instruction::Jump(Value::Value(20)))),
+        ("20 to CI".to_owned(), instruction(Instruction::Jump(Value::Value(20)))),
+        ("5".to_owned(), origin(5)),
+        ("-3".to_owned(), Ok(LineType::Absolute(Value::parse("-3").unwrap()))),
+        ("6".to_owned(), Ok(LineType::Absolute(Value::Value(6)))),
+        ("7".to_owned(), origin(7)),
+        ("foo".to_owned(), Err(LineParseError::InstructionError(InstructionError::UnkownInstruction("foo".to_owned())))),
+    ]);
+    let lines = parse_lines(vec!["1 Stop".to_owned(), "Test".to_owned()], true).ok().unwrap();
+    assert_eq!(lines, vec![LineType::Origin(Value::Value(1)), LineType::Instruction(Instruction::Stop), LineType::Instruction(Instruction::Test)]);
+    assert!(parse_source_lines("1 Stop", false)[0].is_err());
+}
@@ -44,3 +44,60 @@ fn test_parse() {
     assert_eq!(Value::parse("  $foo  "), Ok(Value::Tag("foo".to_owned())));
     assert_eq!(Value::parse("  sadfdsfsda  "), Err(ValueParseError::InvalidValue("sadfdsfsda".to_owned())));
 }
+
+#[test]
+fn test_parse_bit_patterns() {
+    let all_set = format!("0x{}", "F".repeat(WORD::BITS as usize / 4));
+    assert_eq!(Value::parse(&all_set), Ok(Value::Value(-1)));
+    assert_eq!(Value::parse(&format!("0b1{}", "0".repeat(WORD::BITS as usize - 1))), Ok(Value::Value(WORD::MIN)));
+    assert_eq!(Value::parse(&format!("{}0", all_set)), Err(ValueParseError::ValueOutOfRange(format!("{}0", &all_set[2..]))));
+    assert_eq!(Value::parse("0x-A"), Ok(Value::Value(-10)));
+    assert_eq!(Value::parse("0b-1010"), Ok(Value::Value(-10)));
+    assert_eq!(Value::parse_decimal(format!("{}", WORD::MAX as i128 + 1)), Err(ValueParseError::ValueOutOfRange(format!("{}", WORD::MAX as i128 + 1))));
+    assert_eq!(Value::parse_decimal(format!("{}", WORD::MIN)), Ok(Value::Value(WORD::MIN)));
+    assert_eq!(Value::parse_hex("+A".to_owned()), Err(ValueParseError::InvalidHex("+A".to_owned())));
+}
+
+#[test]
+fn test_parse_separators() {
+    assert_eq!(Value::parse("1_0"), Ok(Value::Value(10)));
+    assert_eq!(Value::parse("0b10_10"), Ok(Value::Value(10)));
+    assert_eq!(Value::parse("0d-1_0"), Ok(Value::Value(-10)));
+    assert_eq!(Value::parse("0x_A"), Err(ValueParseError::InvalidHex("_A".to_owned())));
+    assert_eq!(Value::parse("1__0"), Err(ValueParseError::InvalidDecimal("1__0".to_owned())));
+    assert_eq!(Value::parse("0o12_"), Err(ValueParseError::InvalidOctal("12_".to_owned())));
+    assert_eq!(Value::parse("12ab"), Err(ValueParseError::InvalidDecimal("12ab".to_owned())));
+}
+
+#[test]
+fn test_parse_lsb_binary() {
+    assert_eq!(Value::parse("0r0101"), Ok(Value::Value(10)));
+    assert_eq!(Value::parse("0r0101_0000"), Ok(Value::Value(10)));
+    assert_eq!(Value::parse(&format!("0r{}1", "0".repeat(WORD::BITS as usize - 1))), Ok(Value::Value(WORD::MIN)));
+    assert_eq!(Value::parse_lsb_binary("0120".to_owned()), Err(ValueParseError::InvalidLsbBinary("0120".to_owned())));
+    assert_eq!(Value::parse_lsb_binary("-01".to_owned()), Err(ValueParseError::InvalidLsbBinary("-01".to_owned())));
+    let long = "1".repeat(WORD::BITS as usize + 1);
+    assert_eq!(Value::parse_lsb_binary(long.clone()), Err(ValueParseError::ValueOutOfRange(long)));
+}
+
+#[test]
+fn test_parse_char() {
+    assert_eq!(Value::parse("'A'"), Ok(Value::Value(65)));
+    assert_eq!(Value::parse("' '"), Ok(Value::Value(32)));
+    assert_eq!(Value::parse("'+'+1"), Ok(Value::Binary(
+        expression::BinaryOperator::Add, Box::new(Value::Value(43)), Box::new(Value::Value(1))
+    )));
+    assert_eq!(Value::parse("'\\n'"), Ok(Value::Value(10)));
+    assert_eq!(Value::parse("'\\''"), Ok(Value::Value(39)));
+    assert_eq!(Value::parse("'ab'"), Err(ValueParseError::InvalidCharacter("'ab'".to_owned())));
+    assert_eq!(Value::parse("'a"), Err(ValueParseError::InvalidCharacter("'a".to_owned())));
+    assert_eq!(Value::parse("'\u{1F600}'").is_ok(), WORD::try_from(0x1F600).is_ok());
+}
+
+#[test]
+fn test_char_literals_in_lines() {
+    assert_eq!(parse_line("LDN 'A'", false), Ok(LineType::Instruction(Instruction::Negate(Value::Value(65)))));
+    assert_eq!(parse_line(".word ',', ';' ; comment", false), Ok(LineType::Words(vec![Value::Value(44), Value::Value(59)])));
+    assert_eq!(strip_comments("abs ';' ; comment"), "abs ';' ");
+    assert_eq!(split_source_lines("abs ';' ; comment")[0].source(), "abs ';'");
+}
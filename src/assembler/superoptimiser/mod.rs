@@ -0,0 +1,312 @@
+//! # Superoptimiser
+//!
+//! This module searches every Baby program up to a length for the shortest
+//! programs meeting a [Specification], which is feasible as the Baby only has
+//! a handful of instructions.
+//!
+//! A specification gives the words a program can use, which are inputs,
+//! which are outputs, and test cases of input values and the output values
+//! expected once the program stops. Programs are placed from address 0, so
+//! every word of the specification must come after the longest program
+//! searched.
+//!
+//! The main part of this module is [superoptimise][crate::assembler::superoptimiser::superoptimise],
+//! which builds programs one instruction at a time, shortest first, running
+//! every test case as each instruction is added. Two programs of any length
+//! that leave every test case in the same state behave the same whatever
+//! follows, so only the shorter, or the first found, is kept, up to
+//! [MAX_STATES]. The programs searched are made of the following, so control
+//! only moves forward, and each is only kept if none of its subtractions or
+//! negations overflow, and it only stores to words of the specification:
+//!
+//! * `ldn` and `sub` of a word of the specification.
+//! * `sto` through a word of the specification, which must hold the address
+//!   of a word of the specification, as `sto` stores indirectly.
+//! * `cmp` and `stp`.
+//!
+//! Every program found is checked by running it on each test case before it
+//! is returned. A [Superoptimised] result can also be turned into a puzzle for
+//! teaching, asking to beat a program written by hand, see [Superoptimised::puzzle].
+//!
+//! # Example
+//! ```
+//! use baby_emulator::assembler::superoptimiser::{superoptimise, Specification, TestCase};
+//! use baby_emulator::core::instructions::BabyInstruction;
+//!
+//! // y = -x, with `sto` storing through the word at 22 holding the address of y
+//! let specification = Specification {
+//!     words: vec![(22, 21)],
+//!     inputs: vec![20],
+//!     outputs: vec![21],
+//!     tests: vec![
+//!         TestCase { inputs: vec![3], outputs: vec![-3] },
+//!         TestCase { inputs: vec![-5], outputs: vec![5] },
+//!     ],
+//! };
+//! let result = superoptimise(&specification, 10).ok().unwrap();
+//! assert_eq!(result.programs, vec![vec![
+//!     BabyInstruction::Negate(20),
+//!     BabyInstruction::Store(22),
+//!     BabyInstruction::Stop,
+//! ]]);
+//! ```
+//!
+
+use std::collections::{BTreeSet, HashSet};
+use crate::core::{BabyModel, MEMORY_WORDS, WORD, errors::BabyErrors, instructions::BabyInstruction};
+
+
+#[cfg(test)]
+mod tests;
+
+
+/// The most states [superoptimise] keeps for one length of program before
+/// giving up.
+pub const MAX_STATES: usize = 1_000_000;
+
+/// A test case of a [Specification].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TestCase {
+    /// The value of each input, in the order of [Specification]`.inputs`.
+    pub inputs: Vec<WORD>,
+    /// The value expected in each output once the program stops, in the
+    /// order of [Specification]`.outputs`.
+    pub outputs: Vec<WORD>,
+}
+
+/// What a program searched for by [superoptimise] must do.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Specification {
+    /// The address and value of each word the program can use that is not an
+    /// input, such as constants and the addresses `sto` stores through, any
+    /// output not listed starts as 0.
+    pub words: Vec<(usize, WORD)>,
+    /// The address of each input.
+    pub inputs: Vec<usize>,
+    /// The address of each output.
+    pub outputs: Vec<usize>,
+    /// The test cases every program found must pass.
+    pub tests: Vec<TestCase>,
+}
+
+/// Possible errors thrown searching for programs.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SuperoptimiserError {
+    /// A word of the specification is outside of the store (address).
+    OutOfStore(usize),
+    /// A test case has the wrong number of inputs or outputs (test case).
+    WrongTestLength(usize),
+    /// More than [MAX_STATES] states were found for one length of program (length).
+    TooManyStates(usize),
+}
+
+impl SuperoptimiserError {
+
+    /// Returns a short string describing the error.
+    pub fn describe(&self) -> String {
+        match self {
+            SuperoptimiserError::OutOfStore(a) => format!("The word at `{}` is outside of the {} word store. ", a, MEMORY_WORDS),
+            SuperoptimiserError::WrongTestLength(t) => format!("Test case {} has the wrong number of inputs or outputs. ", t),
+            SuperoptimiserError::TooManyStates(l) => format!("Programs of {} instructions reach more than {} states. ", l, MAX_STATES),
+        }
+    }
+}
+
+/// The programs found by [superoptimise].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Superoptimised {
+    /// The shortest programs meeting the specification, all the same length,
+    /// none if there are none up to the length searched.
+    pub programs: Vec<Vec<BabyInstruction>>,
+    /// The number of programs run while searching.
+    pub searched: usize,
+}
+
+impl Superoptimised {
+
+    /// Returns the length of the shortest programs, if any were found.
+    pub fn length(&self) -> Option<usize> {
+        self.programs.first().map(|p| p.len())
+    }
+
+    /// Returns a puzzle asking for a program meeting a specification in
+    /// fewer instructions than a known program, if the shortest found is
+    /// shorter than it, otherwise [None].
+    ///
+    /// # Parameters
+    /// * `specification` - The specification the programs were found for.
+    /// * `beat` - The length of the known program, such as one written by hand.
+    ///
+    pub fn puzzle(&self, specification: &Specification, beat: usize) -> Option<String> {
+        if self.length()? >= beat { return None; }
+        let addresses = |a: &[usize]| a.iter().map(|a| a.to_string()).collect::<Vec<String>>().join(", ");
+        let values = |v: &[WORD]| v.iter().map(|v| v.to_string()).collect::<Vec<String>>().join(", ");
+        let mut puzzle = format!(
+            "Write a program, from address 0, that stops with the outputs at {} given the inputs at {}",
+            addresses(&specification.outputs), addresses(&specification.inputs)
+        );
+        if !specification.words.is_empty() {
+            let words: Vec<String> = specification.words.iter().map(|(a, v)| format!("{} at {}", v, a)).collect();
+            puzzle += &format!(", using {}", words.join(", "));
+        }
+        puzzle += ".\n";
+        for test in &specification.tests {
+            puzzle += &format!("{} -> {}\n", values(&test.inputs), values(&test.outputs));
+        }
+        puzzle += &format!("Can you beat {} instructions?", beat);
+        Some(puzzle)
+    }
+}
+
+/// Searches for the shortest programs meeting a specification, see
+/// [superoptimiser][crate::assembler::superoptimiser].
+///
+/// Returns [SuperoptimiserError::OutOfStore] or [SuperoptimiserError::WrongTestLength]
+/// if the specification is malformed, or [SuperoptimiserError::TooManyStates]
+/// if the search grows too large.
+///
+/// # Parameters
+/// * `specification` - What the programs must do.
+/// * `max_length` - The most instructions to search up to, this is also
+///   limited to the address of the first word of the specification.
+///
+pub fn superoptimise(specification: &Specification, max_length: usize) -> Result<Superoptimised, SuperoptimiserError> {
+    let space = Space::new(specification)?;
+    let max_length = max_length.min(*space.addresses.first().unwrap_or(&MEMORY_WORDS));
+    let candidates: Vec<BabyInstruction> = space.addresses.iter()
+        .flat_map(|a| {
+            let a = *a as u16;
+            [BabyInstruction::Negate(a), BabyInstruction::Subtract(a), BabyInstruction::Store(a)]
+        })
+        .chain([BabyInstruction::SkipNextIfNegative, BabyInstruction::Stop])
+        .collect();
+
+    let start = space.start();
+    let mut seen = HashSet::from([start.clone()]);
+    let mut level = vec![(vec![], start)];
+    let mut searched = 0;
+    for length in 1..=max_length {
+        let mut next = vec![];
+        let mut programs = vec![];
+        for (program, state) in &level {
+            for instruction in &candidates {
+                searched += 1;
+                let Some(state) = space.step(state, instruction) else { continue };
+                if !seen.insert(state.clone()) { continue; }
+                let program = [program.clone(), vec![*instruction]].concat();
+                match space.finished(&state) {
+                    Some(true) => if space.check(&program) { programs.push(program) },
+                    Some(false) => {},
+                    None => next.push((program, state)),
+                }
+            }
+        }
+        if !programs.is_empty() { return Ok(Superoptimised { programs, searched }); }
+        if next.len() > MAX_STATES { return Err(SuperoptimiserError::TooManyStates(length)); }
+        level = next;
+    }
+    Ok(Superoptimised { programs: vec![], searched })
+}
+
+/// Where a test case is within a program ending with the instruction just added.
+const RUNNING: WORD = 0;
+/// A test case whose next instruction was skipped, so is one past the
+/// instruction about to be added.
+const SKIPPING: WORD = 1;
+/// A test case that has stopped.
+const STOPPED: WORD = 2;
+
+/// The words a specification uses, with the state of each test case
+/// before a program runs.
+struct Space<'a> {
+    specification: &'a Specification,
+    addresses: Vec<usize>,
+    stores: Vec<[WORD; MEMORY_WORDS]>,
+}
+
+impl<'a> Space<'a> {
+
+    /// Checks a specification, collecting the words it uses.
+    fn new(specification: &'a Specification) -> Result<Space<'a>, SuperoptimiserError> {
+        let addresses: BTreeSet<usize> = specification.words.iter().map(|(a, _)| *a)
+            .chain(specification.inputs.iter().copied())
+            .chain(specification.outputs.iter().copied())
+            .collect();
+        if let Some(a) = addresses.iter().find(|a| **a >= MEMORY_WORDS) { return Err(SuperoptimiserError::OutOfStore(*a)); }
+        let mut store = [0; MEMORY_WORDS];
+        for (a, v) in &specification.words { store[*a] = *v; }
+        let mut stores = vec![];
+        for (i, test) in specification.tests.iter().enumerate() {
+            if test.inputs.len() != specification.inputs.len() || test.outputs.len() != specification.outputs.len() {
+                return Err(SuperoptimiserError::WrongTestLength(i));
+            }
+            let mut store = store;
+            for (a, v) in specification.inputs.iter().zip(&test.inputs) { store[*a] = *v; }
+            stores.push(store);
+        }
+        Ok(Space { specification, addresses: addresses.into_iter().collect(), stores })
+    }
+
+    /// The number of words in the state of each test case, its status,
+    /// accumulator and each word of the specification.
+    fn width(&self) -> usize {
+        self.addresses.len() + 2
+    }
+
+    /// Returns the state of every test case before a program runs.
+    fn start(&self) -> Vec<WORD> {
+        self.stores.iter()
+            .flat_map(|s| [RUNNING, 0].into_iter().chain(self.addresses.iter().map(|a| s[*a])))
+            .collect()
+    }
+
+    /// Returns the state of every test case once an instruction is added to
+    /// the program, [None] if it overflows or stores outside of the specification.
+    fn step(&self, state: &[WORD], instruction: &BabyInstruction) -> Option<Vec<WORD>> {
+        let mut state = state.to_vec();
+        for case in state.chunks_mut(self.width()) {
+            match case[0] {
+                STOPPED => continue,
+                SKIPPING => { case[0] = RUNNING; continue; },
+                _ => {},
+            }
+            let word = |o: &u16| self.addresses.iter().position(|a| *a == *o as usize).map(|i| i + 2);
+            match instruction {
+                BabyInstruction::Negate(o) => case[1] = case[word(o)?].checked_neg()?,
+                BabyInstruction::Subtract(o) => case[1] = case[1].checked_sub(case[word(o)?])?,
+                BabyInstruction::Store(o) => {
+                    let address = case[word(o)?] as usize & 0x1F;
+                    let i = self.addresses.iter().position(|a| *a == address)? + 2;
+                    case[i] = case[1];
+                },
+                BabyInstruction::SkipNextIfNegative => if case[1] < 0 { case[0] = SKIPPING },
+                _ => case[0] = STOPPED,
+            }
+        }
+        Some(state)
+    }
+
+    /// Returns whether every test case has stopped with the outputs expected,
+    /// [None] if some have not stopped.
+    fn finished(&self, state: &[WORD]) -> Option<bool> {
+        let mut passed = true;
+        for (case, test) in state.chunks(self.width()).zip(&self.specification.tests) {
+            if case[0] != STOPPED { return None; }
+            passed &= self.specification.outputs.iter().zip(&test.outputs)
+                .all(|(a, v)| self.addresses.iter().position(|b| b == a).map(|i| case[i + 2]) == Some(*v));
+        }
+        Some(passed)
+    }
+
+    /// Runs a program on every test case, returning true if each stops with
+    /// the outputs expected.
+    fn check(&self, program: &[BabyInstruction]) -> bool {
+        self.stores.iter().zip(&self.specification.tests).all(|(store, test)| {
+            let mut store = *store;
+            for (i, instruction) in program.iter().enumerate() { store[i] = instruction.to_number(); }
+            let (model, end) = BabyModel::new_with_program(store).run_loop(program.len());
+            matches!(end, BabyErrors::Stop(_))
+                && self.specification.outputs.iter().zip(&test.outputs).all(|(a, v)| model.main_store[*a] == *v)
+        })
+    }
+}
@@ -0,0 +1,95 @@
+use super::*;
+
+
+/// y = a - b, with `sto` storing through `to_t` and `to_y`.
+fn subtraction() -> Specification {
+    let (a, b, y, t, to_t, to_y) = (20, 21, 22, 23, 24, 25);
+    Specification {
+        words: vec![(to_t, t as WORD), (to_y, y as WORD)],
+        inputs: vec![a, b],
+        outputs: vec![y],
+        tests: vec![
+            TestCase { inputs: vec![5, 3], outputs: vec![2] },
+            TestCase { inputs: vec![-4, 6], outputs: vec![-10] },
+            TestCase { inputs: vec![0, -7], outputs: vec![7] },
+        ],
+    }
+}
+
+#[test]
+fn test_subtraction() {
+    let specification = subtraction();
+    let result = superoptimise(&specification, 8).ok().unwrap();
+    assert_eq!(result.length(), Some(6));
+    assert_eq!(result.programs, vec![vec![
+        BabyInstruction::Negate(20),
+        BabyInstruction::Store(25),
+        BabyInstruction::Negate(21),
+        BabyInstruction::Subtract(22),
+        BabyInstruction::Store(25),
+        BabyInstruction::Stop,
+    ]]);
+    assert_eq!(superoptimise(&specification, 5).ok().unwrap().programs, Vec::<Vec<BabyInstruction>>::new());
+}
+
+#[test]
+fn test_absolute_value() {
+    let (x, y, to_y) = (20, 21, 22);
+    let specification = Specification {
+        words: vec![(to_y, y as WORD)],
+        inputs: vec![x],
+        outputs: vec![y],
+        tests: [-9, -1, 0, 1, 12].iter().map(|v: &WORD| TestCase { inputs: vec![*v], outputs: vec![v.abs()] }).collect(),
+    };
+    let result = superoptimise(&specification, 8).ok().unwrap();
+    let length = result.length().unwrap();
+    for program in &result.programs {
+        assert_eq!(program.len(), length);
+        for test in &specification.tests {
+            let mut store = [0; MEMORY_WORDS];
+            for (i, instruction) in program.iter().enumerate() { store[i] = instruction.to_number(); }
+            store[x] = test.inputs[0];
+            store[to_y] = y as WORD;
+            let (model, end) = BabyModel::new_with_program(store).run_loop(length);
+            assert!(matches!(end, BabyErrors::Stop(_)));
+            assert_eq!(model.main_store[y], test.outputs[0]);
+        }
+    }
+    assert_eq!(result.programs, vec![vec![
+        BabyInstruction::Negate(x as u16),
+        BabyInstruction::Store(to_y as u16),
+        BabyInstruction::Negate(y as u16),
+        BabyInstruction::SkipNextIfNegative,
+        BabyInstruction::Store(to_y as u16),
+        BabyInstruction::Stop,
+    ]]);
+}
+
+#[test]
+fn test_puzzle() {
+    let specification = subtraction();
+    let result = superoptimise(&specification, 8).ok().unwrap();
+    assert_eq!(result.puzzle(&specification, 6), None);
+    assert_eq!(
+        result.puzzle(&specification, 7).unwrap(),
+        "Write a program, from address 0, that stops with the outputs at 22 given the inputs at 20, 21, using 23 at 24, 22 at 25.\n\
+         5, 3 -> 2\n-4, 6 -> -10\n0, -7 -> 7\nCan you beat 7 instructions?"
+    );
+    assert_eq!(Superoptimised { programs: vec![], searched: 0 }.puzzle(&specification, 7), None);
+}
+
+#[test]
+fn test_errors() {
+    let mut specification = subtraction();
+    specification.tests[1].outputs.push(0);
+    assert_eq!(superoptimise(&specification, 8), Err(SuperoptimiserError::WrongTestLength(1)));
+    specification.outputs = vec![40];
+    assert_eq!(superoptimise(&specification, 8), Err(SuperoptimiserError::OutOfStore(40)));
+    let overflowing = Specification {
+        words: vec![],
+        inputs: vec![20],
+        outputs: vec![20],
+        tests: vec![TestCase { inputs: vec![WORD::MIN], outputs: vec![0] }],
+    };
+    assert_eq!(superoptimise(&overflowing, 4).ok().unwrap().programs, Vec::<Vec<BabyInstruction>>::new());
+}
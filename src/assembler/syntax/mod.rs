@@ -0,0 +1,358 @@
+//! # Syntax
+//!
+//! This module contains a lossless syntax tree of asm source, unlike the
+//! [parser][crate::assembler::parser] nothing is thrown away, every comment,
+//! blank line and piece of whitespace is kept as a token, so the source can
+//! be rewritten safely and written back out exactly as it was.
+//!
+//! The main type in this module is [SyntaxTree][crate::assembler::syntax::SyntaxTree],
+//! a line for each line of the source, each holding its [Token]s and the result
+//! of parsing it. Joining the text of every token gives back the original source.
+//!
+//! Built on the tree are [SyntaxTree::format][crate::assembler::syntax::SyntaxTree::format],
+//! which lays out the source consistently, and [SyntaxTree::rename][crate::assembler::syntax::SyntaxTree::rename],
+//! which renames a tag or constant everywhere it is used.
+//!
+//! # Formatting
+//!
+//! * Mnemonics and directives are lowercased, macro names keep their case.
+//! * Instruction and `abs` operands line up in a column after the longest mnemonic.
+//! * Comments after code line up in a column after the longest line of code
+//!   with a comment, comment only lines start at the first column.
+//! * Runs of blank lines become a single blank line, and leading and trailing
+//!   blank lines are removed.
+//! * Original notation line numbers are right aligned.
+//! * Lines that do not parse are left as they are.
+//!
+//! # Example
+//! ```
+//! use baby_emulator::assembler::syntax::SyntaxTree;
+//!
+//! let asm = "  LDN   $value ; Load\n\n\n:value\nabs 0d5  ; Five\n";
+//! let tree = SyntaxTree::parse(asm, false);
+//! assert_eq!(tree.to_source(), asm);
+//! assert_eq!(tree.format(), "ldn $value ; Load\n\n:value\nabs 0d5    ; Five\n");
+//! ```
+//!
+
+use std::collections::HashSet;
+use super::parser::{LineType, RESERVED_NAMES, comment_start, macro_names, parse_line_with_macros, split_line_number};
+use super::parser::errors::LineParseError;
+
+
+#[cfg(test)]
+mod tests;
+
+
+/// Mnemonics lowercased when formatting original notation, instructions
+/// are written as phrases so are left as they are.
+const OG_KEYWORDS: [&str; 4] = ["abs", "equ", "macro", "endm"];
+
+/// The kind of a piece of source text.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TokenKind {
+    /// Spaces or tabs.
+    Whitespace,
+    /// The line ending, either `\n` or `\r\n`.
+    Newline,
+    /// A comment, including the `;`.
+    Comment,
+    /// A tag declaration, including the `:`.
+    Tag,
+    /// The first word of a line, an instruction, `abs`, `equ`, a directive
+    /// or a macro name.
+    Mnemonic,
+    /// Everything after the mnemonic, before any comment.
+    Operand,
+    /// An original notation instruction, written as a single phrase such as `-5 to C`.
+    Instruction,
+    /// An original notation line number at the start of a line.
+    LineNumber,
+}
+
+/// A piece of source text.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Token {
+    /// What the text is.
+    pub kind: TokenKind,
+    /// The source text.
+    pub text: String,
+}
+
+/// A single line of source.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyntaxLine {
+    /// Every piece of the line in order, including the line ending.
+    pub tokens: Vec<Token>,
+    /// The result of parsing the code on the line, [None] if it is blank
+    /// or only a comment. Any original notation line number is not included.
+    pub parsed: Option<Result<LineType, LineParseError>>,
+}
+
+impl SyntaxLine {
+
+    /// Returns the source text of the line, including the line ending.
+    pub fn text(&self) -> String {
+        self.tokens.iter().map(|t| t.text.as_str()).collect()
+    }
+
+    /// Returns the text of the first token of a kind, if there is one.
+    pub fn token(&self, kind: TokenKind) -> Option<&str> {
+        self.tokens.iter().find(|t| t.kind == kind).map(|t| t.text.as_str())
+    }
+
+    /// Returns true if the line has no code and no comment.
+    pub fn is_blank(&self) -> bool {
+        self.tokens.iter().all(|t| matches!(t.kind, TokenKind::Whitespace | TokenKind::Newline))
+    }
+}
+
+/// A lossless syntax tree of asm source.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyntaxTree {
+    /// Every line of the source, in order.
+    pub lines: Vec<SyntaxLine>,
+    /// If the source uses original notation.
+    pub og_notation: bool,
+}
+
+impl SyntaxTree {
+
+    /// Builds the syntax tree of some asm source, this never fails, any
+    /// line that does not parse holds the error.
+    ///
+    /// # Parameters
+    /// * `asm` - The asm source.
+    /// * `og_notation` - If true, the source uses original notation.
+    ///
+    pub fn parse(asm: &str, og_notation: bool) -> SyntaxTree {
+        let texts: Vec<&str> = asm.split_inclusive('\n').collect();
+        let sources: Vec<String> = texts.iter()
+            .map(|t| t.trim_end_matches(['\r', '\n']))
+            .map(|t| t[..comment_start(t).unwrap_or(t.len())].trim().to_owned())
+            .collect();
+        let macros = macro_names(&sources, og_notation);
+        let lines = texts.into_iter().map(|t| tokenise(t, og_notation, &macros)).collect();
+        SyntaxTree { lines, og_notation }
+    }
+
+    /// Returns the source text of the tree, exactly as it was parsed
+    /// unless the tree has been changed.
+    pub fn to_source(&self) -> String {
+        self.lines.iter().map(SyntaxLine::text).collect()
+    }
+
+    /// Returns the source formatted as described in the [module][crate::assembler::syntax]
+    /// documentation, with `\n` line endings.
+    pub fn format(&self) -> String {
+        let number_width = self.lines.iter()
+            .filter_map(|l| l.token(TokenKind::LineNumber))
+            .map(|n| n.chars().count())
+            .max();
+        let mnemonic_width = self.lines.iter()
+            .filter(|l| matches!(l.parsed, Some(Ok(LineType::Instruction(_) | LineType::Absolute(_)))))
+            .filter(|l| l.token(TokenKind::Operand).is_some())
+            .filter_map(|l| l.token(TokenKind::Mnemonic))
+            .map(|m| m.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        let rows: Vec<Row> = self.lines.iter()
+            .map(|l| match &l.parsed {
+                Some(Err(_)) => Row::Verbatim(l.text().trim_end().to_owned()),
+                _ if l.is_blank() => Row::Blank,
+                _ => Row::Code(self.format_code(l, number_width, mnemonic_width), l.token(TokenKind::Comment).map(str::trim_end)),
+            })
+            .collect();
+        let comment_column = rows.iter()
+            .filter_map(|r| match r {
+                Row::Code(c, Some(_)) if !c.is_empty() => Some(c.chars().count()),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0);
+
+        let mut res: Vec<String> = vec![];
+        for row in rows {
+            let line = match row {
+                Row::Blank if res.last().is_none_or(String::is_empty) => continue,
+                Row::Blank => String::new(),
+                Row::Verbatim(v) => v,
+                Row::Code(c, None) => c,
+                Row::Code(c, Some(comment)) if c.is_empty() => comment.to_owned(),
+                Row::Code(c, Some(comment)) => format!("{:<comment_column$} {}", c, comment),
+            };
+            res.push(line);
+        }
+        while res.last().is_some_and(String::is_empty) { res.pop(); }
+        res.into_iter().map(|l| l + "\n").collect()
+    }
+
+    /// Formats the code on a line, without any comment.
+    fn format_code(&self, line: &SyntaxLine, number_width: Option<usize>, mnemonic_width: usize) -> String {
+        let number = match (line.token(TokenKind::LineNumber), number_width) {
+            (Some(n), Some(w)) => format!("{:>w$} ", n),
+            (None, Some(w)) if line.parsed.is_some() => " ".repeat(w + 1),
+            _ => String::new(),
+        };
+        let code = match (line.token(TokenKind::Mnemonic), line.token(TokenKind::Operand)) {
+            (Some(m), Some(o)) if matches!(line.parsed, Some(Ok(LineType::Instruction(_) | LineType::Absolute(_)))) =>
+                format!("{:<mnemonic_width$} {}", self.normalise_mnemonic(m), o),
+            (Some(m), Some(o)) => format!("{} {}", self.normalise_mnemonic(m), o),
+            (Some(m), None) => self.normalise_mnemonic(m),
+            _ => line.token(TokenKind::Tag).or(line.token(TokenKind::Instruction)).unwrap_or("").to_owned(),
+        };
+        number + &code
+    }
+
+    /// Lowercases a mnemonic if it is an instruction, keyword or directive.
+    fn normalise_mnemonic(&self, mnemonic: &str) -> String {
+        let lower = mnemonic.to_lowercase();
+        let keyword = if self.og_notation { OG_KEYWORDS.contains(&lower.as_str()) }
+            else { RESERVED_NAMES.contains(&lower.as_str()) };
+        if keyword || lower.starts_with('.') { lower } else { mnemonic.to_owned() }
+    }
+
+    /// Renames a tag or constant everywhere it is declared or referenced,
+    /// including `.export` and `.import` lists, returning how many were renamed.
+    ///
+    /// Names in instruction operands are lowercased when parsed, so `$Foo` in
+    /// an instruction is renamed with `foo`, anywhere else the case must match.
+    ///
+    /// # Parameters
+    /// * `old` - The current name.
+    /// * `new` - The new name, which should be a valid tag name.
+    ///
+    /// # Example
+    /// ```
+    /// use baby_emulator::assembler::syntax::SyntaxTree;
+    ///
+    /// let mut tree = SyntaxTree::parse("ldn $x ; Load x\n:x\nabs $x+0d1", false);
+    /// assert_eq!(tree.rename("x", "value"), 3);
+    /// assert_eq!(tree.to_source(), "ldn $value ; Load x\n:value\nabs $value+0d1");
+    /// ```
+    ///
+    pub fn rename(&mut self, old: &str, new: &str) -> usize {
+        let mut count = 0;
+        for line in &mut self.lines {
+            let (declared, names, ignore_case) = match &line.parsed {
+                Some(Ok(LineType::Constant(n, _))) => (n == old && line.token(TokenKind::Operand).is_some_and(|o| o.starts_with(old)), false, false),
+                Some(Ok(LineType::Export(_) | LineType::Import(_))) => (false, true, false),
+                Some(Ok(LineType::Instruction(_))) => (false, false, true),
+                Some(Ok(_)) => (false, false, false),
+                _ => continue,
+            };
+            for token in &mut line.tokens {
+                let (text, n) = match token.kind {
+                    TokenKind::Tag if token.text == format!(":{}", old) => (format!(":{}", new), 1),
+                    TokenKind::Operand | TokenKind::Instruction => {
+                        let (mut text, mut n) = replace_name(&token.text, &format!("${}", old), &format!("${}", new), ignore_case);
+                        if declared {
+                            text = format!("{}{}", new, &text[old.len()..]);
+                            n += 1;
+                        }
+                        if names {
+                            let (t, m) = replace_name(&text, old, new, false);
+                            text = t;
+                            n += m;
+                        }
+                        (text, n)
+                    },
+                    _ => continue,
+                };
+                token.text = text;
+                count += n;
+            }
+        }
+        *self = SyntaxTree::parse(&self.to_source(), self.og_notation);
+        count
+    }
+}
+
+/// A formatted line before comments are aligned.
+enum Row<'a> {
+    /// A blank line.
+    Blank,
+    /// A line left as it is.
+    Verbatim(String),
+    /// A line of code, which may be empty, and any comment.
+    Code(String, Option<&'a str>),
+}
+
+/// Splits a line of source into tokens and parses it.
+fn tokenise(text: &str, og_notation: bool, macros: &HashSet<String>) -> SyntaxLine {
+    let content = text.trim_end_matches(['\r', '\n']);
+    let newline = &text[content.len()..];
+    let (code, comment) = content.split_at(comment_start(content).unwrap_or(content.len()));
+    let mut tokens = vec![];
+    let mut push = |kind: TokenKind, text: &str| if !text.is_empty() {
+        tokens.push(Token { kind, text: text.to_owned() });
+    };
+
+    let trimmed = code.trim_start();
+    push(TokenKind::Whitespace, &code[..code.len() - trimmed.len()]);
+    let expression = trimmed.trim_end();
+    let mut parsed = None;
+    if !expression.is_empty() {
+        let mut rest = expression;
+        let mut result = parse_line_with_macros(rest, og_notation, macros);
+        if let Some((_, after)) = split_line_number(rest).filter(|_| og_notation && result.is_err()) {
+            let number = &rest[..rest.len() - after.len()];
+            push(TokenKind::LineNumber, number.trim_end());
+            push(TokenKind::Whitespace, &number[number.trim_end().len()..]);
+            rest = after;
+            result = parse_line_with_macros(rest, og_notation, macros);
+        }
+        match &result {
+            _ if rest.starts_with(':') => push(TokenKind::Tag, rest),
+            Ok(LineType::Instruction(_)) if og_notation => push(TokenKind::Instruction, rest),
+            _ => match rest.split_once(char::is_whitespace) {
+                Some((mnemonic, operand)) => {
+                    push(TokenKind::Mnemonic, mnemonic);
+                    push(TokenKind::Whitespace, &rest[mnemonic.len()..rest.len() - operand.trim_start().len()]);
+                    push(TokenKind::Operand, operand.trim_start());
+                },
+                None => push(TokenKind::Mnemonic, rest),
+            },
+        }
+        parsed = Some(result);
+    }
+    push(TokenKind::Whitespace, &trimmed[expression.len()..]);
+    let comment_text = comment.trim_end();
+    push(TokenKind::Comment, comment_text);
+    push(TokenKind::Whitespace, &comment[comment_text.len()..]);
+    push(TokenKind::Newline, newline);
+    SyntaxLine { tokens, parsed }
+}
+
+/// Returns true if a character can be part of a name.
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Replaces every whole word occurrence of `old` in some text with `new`,
+/// matching the lowercased text if `ignore_case` is true, returning the
+/// new text and how many were replaced.
+fn replace_name(text: &str, old: &str, new: &str, ignore_case: bool) -> (String, usize) {
+    let matches = |s: &str| if ignore_case { s.to_lowercase() == old } else { s == old };
+    let mut res = String::new();
+    let mut count = 0;
+    let mut i = 0;
+    while i < text.len() {
+        let before = text[..i].chars().next_back();
+        let end = i + old.len();
+        let whole = end <= text.len() && text.is_char_boundary(end)
+            && !text[end..].starts_with(is_name_char)
+            && (old.starts_with('$') || !before.is_some_and(|c| is_name_char(c) || c == '$'));
+        if whole && matches(&text[i..end]) {
+            res.push_str(new);
+            count += 1;
+            i = end;
+        } else {
+            let c = text[i..].chars().next().unwrap_or_default();
+            res.push(c);
+            i += c.len_utf8();
+        }
+    }
+    (res, count)
+}
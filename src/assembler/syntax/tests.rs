@@ -0,0 +1,118 @@
+use super::*;
+use crate::assembler::assemble;
+
+
+const ASM: &str =
+"; Counts down from 10
+equ ONE 0d1
+  LDN   $start_value  ; Load 10
+:loop
+SUB $one ; Subtract
+cmp
+JMP $loop_start   ; Loop
+stp
+
+
+:loop_start
+abs $loop
+:one
+abs   $ONE
+:start_value
+abs 0d-10
+.word 0d1, ';'
+";
+
+const FORMATTED: &str =
+"; Counts down from 10
+equ ONE 0d1
+ldn $start_value ; Load 10
+:loop
+sub $one         ; Subtract
+cmp
+jmp $loop_start  ; Loop
+stp
+
+:loop_start
+abs $loop
+:one
+abs $ONE
+:start_value
+abs 0d-10
+.word 0d1, ';'
+";
+
+fn kinds(line: &SyntaxLine) -> Vec<TokenKind> {
+    line.tokens.iter().map(|t| t.kind).collect()
+}
+
+#[test]
+fn test_lossless() {
+    for asm in [ASM, FORMATTED, "", "\n\n", "stp", "ldn 0d1\r\n; x \r\nstp\t ;y\t", "foo bar ; not valid\n", "abs ';' ; c"] {
+        assert_eq!(SyntaxTree::parse(asm, false).to_source(), asm);
+    }
+    let og = "0 -24 to C ; Load\n1   Sub. 25\n  Stop\n\n24 -3";
+    assert_eq!(SyntaxTree::parse(og, true).to_source(), og);
+}
+
+#[test]
+fn test_tokens() {
+    let tree = SyntaxTree::parse(ASM, false);
+    assert_eq!(tree.lines.len(), 17);
+    assert_eq!(kinds(&tree.lines[0]), vec![TokenKind::Comment, TokenKind::Newline]);
+    assert_eq!(tree.lines[0].parsed, None);
+    assert_eq!(kinds(&tree.lines[2]), vec![
+        TokenKind::Whitespace, TokenKind::Mnemonic, TokenKind::Whitespace, TokenKind::Operand,
+        TokenKind::Whitespace, TokenKind::Comment, TokenKind::Newline,
+    ]);
+    assert_eq!(tree.lines[2].token(TokenKind::Operand), Some("$start_value"));
+    assert_eq!(tree.lines[3].token(TokenKind::Tag), Some(":loop"));
+    assert!(tree.lines[8].is_blank());
+    assert_eq!(tree.lines[16].token(TokenKind::Operand), Some("0d1, ';'"));
+    assert!(matches!(tree.lines[16].parsed, Some(Ok(LineType::Words(_)))));
+
+    let tree = SyntaxTree::parse("3 -24 to C ; Load\nbad line", true);
+    assert_eq!(kinds(&tree.lines[0]), vec![
+        TokenKind::LineNumber, TokenKind::Whitespace, TokenKind::Instruction,
+        TokenKind::Whitespace, TokenKind::Comment, TokenKind::Newline,
+    ]);
+    assert!(matches!(tree.lines[1].parsed, Some(Err(_))));
+}
+
+#[test]
+fn test_format() {
+    let formatted = SyntaxTree::parse(ASM, false).format();
+    assert_eq!(formatted, FORMATTED);
+    assert_eq!(SyntaxTree::parse(&formatted, false).format(), formatted);
+    assert_eq!(assemble(&formatted, false).ok().unwrap().0, assemble(ASM, false).ok().unwrap().0);
+    assert_eq!(SyntaxTree::parse("\n\nfoo  bar   ; bad\nstp\n\n", false).format(), "foo  bar   ; bad\nstp\n");
+    assert_eq!(SyntaxTree::parse("", false).format(), "");
+}
+
+#[test]
+fn test_format_og() {
+    let og = "0 -24 to C ; Load\n1   Sub. 25\n10  Stop\nabs 0d1";
+    let formatted = SyntaxTree::parse(og, true).format();
+    assert_eq!(formatted, " 0 -24 to C ; Load\n 1 Sub. 25\n10 Stop\n   abs 0d1\n");
+    assert_eq!(SyntaxTree::parse(&formatted, true).format(), formatted);
+}
+
+#[test]
+fn test_rename() {
+    let mut tree = SyntaxTree::parse(ASM, false);
+    assert_eq!(tree.rename("loop", "top"), 2);
+    assert_eq!(tree.lines[3].token(TokenKind::Tag), Some(":top"));
+    assert_eq!(tree.lines[11].token(TokenKind::Operand), Some("$top"));
+    assert_eq!(tree.lines[6].token(TokenKind::Operand), Some("$loop_start"));
+
+    assert_eq!(tree.rename("ONE", "STEP"), 2);
+    assert_eq!(tree.lines[1].token(TokenKind::Operand), Some("STEP 0d1"));
+    assert_eq!(tree.lines[13].token(TokenKind::Operand), Some("$STEP"));
+    assert_eq!(tree.lines[4].token(TokenKind::Operand), Some("$one"));
+    assert_eq!(assemble(&tree.to_source(), false).ok().unwrap().0, assemble(ASM, false).ok().unwrap().0);
+
+    let mut tree = SyntaxTree::parse(".export Count\nLDN $COUNT\n:count\nabs 0d1", false);
+    assert_eq!(tree.rename("count", "total"), 2);
+    assert_eq!(tree.to_source(), ".export Count\nLDN $total\n:total\nabs 0d1");
+    assert_eq!(tree.rename("Count", "Total"), 1);
+    assert_eq!(tree.lines[0].token(TokenKind::Operand), Some("Total"));
+}
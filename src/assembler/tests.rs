@@ -0,0 +1,98 @@
+use super::*;
+use crate::core::MEMORY_WORDS;
+use linker::errors::{LinkingError, TagError};
+
+
+#[test]
+fn test_assemble_all_correct() {
+    let asm = "ldn $foo\nstp\n:foo\nabs 0d-1";
+    match (assemble(asm, false), assemble_all(asm, false)) {
+        (Ok(LinkerData(a, _)), Ok(LinkerData(b, _))) => assert_eq!(a, b),
+        _ => panic!("Failed to assemble correct asm. ")
+    }
+}
+
+#[test]
+fn test_assemble_all_errors() {
+    let asm = "ldn $foo\nfoo 0d1\n:a b\n\nsub 0xZZ ; bad\njmp $bar\n:bar\nstp";
+    let errors = match assemble_all(asm, false) {
+        Err(e) => e,
+        Ok(_) => panic!("Incorrect asm assembled. ")
+    };
+    let lines: Vec<(usize, String)> = errors.iter()
+        .map(|e| (e.span().line, e.span().source()))
+        .collect();
+    assert_eq!(lines, vec![
+        (1, "$foo".to_owned()),
+        (2, "foo 0d1".to_owned()),
+        (3, ":a b".to_owned()),
+        (5, "0xZZ".to_owned()),
+    ]);
+    match &errors[0] {
+        AssemblyError::LinkerError(_, e) => 
+            assert_eq!(*e, LinkingError::TagError(TagError::UnknownTagName("foo".to_owned()))),
+        _ => panic!("Expected a linker error. ")
+    }
+    assert!(matches!(errors[1], AssemblyError::ParserError(_, _)));
+    match assemble(asm, false) {
+        Err(e) => assert_eq!(e.span().line, 2),
+        Ok(_) => panic!("Incorrect asm assembled. ")
+    }
+}
+
+#[test]
+fn test_assemble_all_keeps_positions() {
+    let asm = "jmp $end\nfoo\n:end\nstp";
+    let errors = match assemble_all(asm, false) {
+        Err(e) => e,
+        Ok(_) => panic!("Incorrect asm assembled. ")
+    };
+    assert_eq!(errors.len(), 1);
+    let asm = format!("{}\nfoo\njmp $nowhere", vec!["stp"; MEMORY_WORDS].join("\n"));
+    let lines: Vec<usize> = match assemble_all(&asm, false) {
+        Err(e) => e.iter().map(|e| e.span().line).collect(),
+        Ok(_) => panic!("Oversized program assembled. ")
+    };
+    assert_eq!(lines, vec![MEMORY_WORDS + 1, MEMORY_WORDS + 1, MEMORY_WORDS + 2]);
+}
+
+#[test]
+fn test_assemble_kilburn_notation() {
+    let asm = "\
+        ; Subtract one from minus minus three
+        0  -4 to C
+        1  Sub. 5
+        2  STP
+        4  -3
+        5  1
+    ";
+    let LinkerData(program, _) = match assemble(asm, true) {
+        Ok(v) => v,
+        Err(e) => panic!("{}", e.describe(true))
+    };
+    let model = crate::core::BabyModel::new_with_program(crate::core::instructions::BabyInstruction::to_numbers(program));
+    let (model, _) = model.run_loop(100);
+    assert_eq!(model.accumulator, 2);
+}
+
+#[test]
+fn test_assemble_with() {
+    let asm = "macro far\nsub 0d40\nendm\nfar\nstp\nldn 0d1";
+    let everything = AssemblyOptions { listing: true, warnings: true, deny_warnings: false };
+    let assembled = match assemble_with(asm, false, &everything) {
+        Ok(v) => v,
+        Err(e) => panic!("{}", e[0].describe(true))
+    };
+    let LinkerData(program, _) = assemble_all(asm, false).ok().unwrap();
+    assert_eq!(assembled.data.0, program);
+    let lines: Vec<usize> = assembled.warnings.iter().map(|w| w.span.line).collect();
+    assert_eq!(lines, vec![4, 6]);
+    let listing = assembled.listing.unwrap();
+    assert_eq!(listing.lines[3].words[0].address, 0);
+    assert_eq!(listing.lines[5].words[0].address, 2);
+
+    let plain = assemble_with(asm, false, &AssemblyOptions::default()).ok().unwrap();
+    assert!(plain.listing.is_none() && plain.warnings.is_empty());
+    let denied = AssemblyOptions { deny_warnings: true, ..everything };
+    assert!(matches!(assemble_with(asm, false, &denied), Err(e) if e.len() == 2));
+}
@@ -0,0 +1,236 @@
+//! # Warnings
+//!
+//! This module checks an assembled program for code that is valid but
+//! probably not what was meant, such as an operand that is silently masked
+//! to fit in an instruction, returning a list of warnings alongside the
+//! result of assembling.
+//!
+//! The main part of this module is [find_warnings][crate::assembler::warnings::find_warnings],
+//! which checks the expanded lines of a program that has linked successfully,
+//! each warning is a [Warning][crate::assembler::warnings::Warning].
+//!
+//! Warnings are looked for by [assemble_with][crate::assembler::assemble_with] when asked for,
+//! and can be made into errors with `deny_warnings`, so a program only assembles
+//! if it has no warnings at all.
+//!
+//! # Example
+//! ```
+//! use baby_emulator::assembler::{assemble_with, AssemblyOptions};
+//! use baby_emulator::assembler::warnings::Warning;
+//!
+//! let options = AssemblyOptions { warnings: true, ..Default::default() };
+//! let warnings = assemble_with("sub 0d40\nstp", false, &options).ok().unwrap().warnings;
+//! assert_eq!(warnings[0].warning, Warning::OperandOutOfRange("sub 0d40".to_owned(), 40));
+//! let denied = AssemblyOptions { deny_warnings: true, ..options };
+//! assert!(assemble_with("sub 0d40\nstp", false, &denied).is_err());
+//! ```
+//!
+
+use std::collections::HashMap;
+use crate::core::{MEMORY_WORDS, WORD};
+use super::diagnostics::SourceSpan;
+use super::linker::{collect_constants, resolve_constants, UnlinkedData};
+use super::parser::{LineType, Instruction, Value, expression::{UnaryOperator, BinaryOperator}};
+
+
+#[cfg(test)]
+mod tests;
+
+
+/// Possible warnings found in an assembled program.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Warning {
+    /// An instruction operand is not a main store address, so will be masked
+    /// to fit in the instruction (instruction, operand value).
+    OperandOutOfRange(String, WORD),
+    /// An absolute value does not fit in a [WORD] so has wrapped (value expression).
+    AbsoluteOverflow(String),
+    /// An instruction straight after a stop that is not skipped by a test,
+    /// with no tag for anything to jump to it.
+    UnreachableCode,
+    /// The program has no stop instruction.
+    NoStop,
+}
+
+impl Warning {
+
+    /// Returns a short string describing the warning.
+    pub fn describe(&self) -> String {
+        match self {
+            Warning::OperandOutOfRange(i, v) => format!(
+                "The operand of `{}` is {}, outside of the main store, it will be masked to {}. ",
+                i, v, v & (MEMORY_WORDS as WORD - 1)
+            ),
+            Warning::AbsoluteOverflow(v) => format!("The value `{}` does not fit in a word and has wrapped. ", v),
+            Warning::UnreachableCode => "The instruction can never be reached as it follows a stop. ".to_owned(),
+            Warning::NoStop => "The program has no stop instruction. ".to_owned(),
+        }
+    }
+}
+
+/// A warning along with where in the asm source it was found.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssemblyWarning {
+    /// Where in the asm source the warning was found.
+    pub span: SourceSpan,
+    /// The warning.
+    pub warning: Warning,
+}
+
+impl AssemblyWarning {
+
+    /// Returns a string describing the warning and where it was found.
+    pub fn describe(&self) -> String {
+        format!("A warning was found at {}. {}", self.span.describe(), self.warning.describe())
+    }
+
+    /// Renders the warning as a diagnostic showing the offending source
+    /// line with carets underneath the part that caused the warning.
+    ///
+    /// # Parameters
+    /// * `file` - The name of the source file, if there is one.
+    ///
+    pub fn render(&self, file: Option<&str>) -> String {
+        let message: Vec<String> = self.warning.describe().split_whitespace().map(str::to_owned).collect();
+        self.span.render(&format!("warning: {}", message.join(" ")), file)
+    }
+}
+
+/// Checks expanded lines that have linked successfully for any warnings.
+///
+/// Returns each warning along with the location of the line it was found
+/// on, in the order of the lines, [Warning::NoStop] takes the location of the
+/// last line placed in memory.
+///
+/// # Parameters
+/// * `lines` - The expanded lines each paired with a location.
+/// * `tags` - The tag names and values, as returned in [LinkerData][crate::assembler::linker::LinkerData].
+///
+/// # Example
+/// ```
+/// use std::collections::HashMap;
+/// use baby_emulator::assembler::parser::{LineType, Instruction, Value};
+/// use baby_emulator::assembler::warnings::{find_warnings, Warning};
+///
+/// let lines = vec![
+///     (1, LineType::Instruction(Instruction::Stop)),
+///     (2, LineType::Instruction(Instruction::Store(Value::Value(3)))),
+/// ];
+/// assert_eq!(find_warnings(&lines, &HashMap::new()), vec![(2, Warning::UnreachableCode)]);
+/// ```
+///
+pub fn find_warnings<L: Clone>(lines: &[(L, LineType)], tags: &HashMap<String, WORD>) -> Vec<(L, Warning)> {
+    let parsed: Vec<LineType> = lines.iter().map(|(_, l)| l.clone()).collect();
+    let mut symbols = resolve_constants(&collect_constants(&parsed), tags).0;
+    symbols.extend(tags.iter().map(|(t, v)| (t.clone(), *v)));
+
+    let mut warnings: Vec<(usize, Warning)> = vec![];
+    for (i, line) in parsed.iter().enumerate() {
+        let values: Vec<&Value> = match line {
+            LineType::Instruction(instr) => {
+                if let Some(v) = operand_out_of_range(instr, &symbols) {
+                    warnings.push((i, Warning::OperandOutOfRange(instr.to_asm_string(false), v)));
+                }
+                continue;
+            },
+            LineType::Absolute(v) => vec![v],
+            LineType::Fill(_, v) => vec![v],
+            LineType::Words(v) => v.iter().collect(),
+            _ => continue,
+        };
+        for value in values.into_iter().filter(|v| overflows(v, &symbols)) {
+            warnings.push((i, Warning::AbsoluteOverflow(value.to_asm_string())));
+        }
+    }
+    warnings.extend(find_unreachable(&parsed).into_iter().map(|i| (i, Warning::UnreachableCode)));
+
+    let has_stop = parsed.iter().any(|l| matches!(l, LineType::Instruction(Instruction::Stop)));
+    if let Some(last) = parsed.iter().rposition(|l| l.is_data()).filter(|_| !has_stop) {
+        warnings.push((last, Warning::NoStop));
+    }
+    warnings.sort_by_key(|(i, _)| *i);
+    warnings.into_iter().map(|(i, w)| (lines[i].0.clone(), w)).collect()
+}
+
+/// Checks expanded lines each paired with a [SourceSpan] for any warnings as
+/// per [find_warnings], narrowing the span of an operand warning to the operand.
+///
+/// # Parameters
+/// * `lines` - The expanded lines each paired with a source span.
+/// * `tags` - The tag names and values.
+///
+pub fn find_source_warnings(lines: &[(SourceSpan, LineType)], tags: &HashMap<String, WORD>) -> Vec<AssemblyWarning> {
+    find_warnings(lines, tags).into_iter()
+        .map(|(span, warning)| match &warning {
+            Warning::OperandOutOfRange(i, _) => {
+                let operand = i.split_once(' ').map_or("", |(_, o)| o);
+                AssemblyWarning { span: span.narrow(operand), warning }
+            },
+            _ => AssemblyWarning { span, warning },
+        })
+        .collect()
+}
+
+/// Returns the value of an instruction's operand if it is not a main store address.
+fn operand_out_of_range(instr: &Instruction, symbols: &HashMap<String, WORD>) -> Option<WORD> {
+    if matches!(instr, Instruction::Test | Instruction::Stop) { return None; }
+    match UnlinkedData::resolve_value(&instr.get_operand(), symbols) {
+        Ok(v) if !(0..MEMORY_WORDS as WORD).contains(&v) => Some(v),
+        _ => None,
+    }
+}
+
+/// Returns true if a value expression gives a result that does not fit in
+/// a [WORD] when worked out without wrapping.
+fn overflows(value: &Value, symbols: &HashMap<String, WORD>) -> bool {
+    exact_value(value, symbols).is_some_and(|v| WORD::try_from(v).is_err())
+}
+
+/// Works out a value expression without wrapping, returns [None] if it
+/// cannot be worked out, these are reported as errors by the linker.
+fn exact_value(value: &Value, symbols: &HashMap<String, WORD>) -> Option<i128> {
+    match value {
+        Value::Value(v) => Some(*v as i128),
+        Value::Tag(t) => symbols.get(t).map(|v| *v as i128),
+        Value::Unary(UnaryOperator::Negate, v) => Some(exact_value(v, symbols)?.saturating_neg()),
+        Value::Unary(UnaryOperator::Not, v) => Some(!exact_value(v, symbols)?),
+        Value::Binary(o, l, r) => {
+            let (l, r) = (exact_value(l, symbols)?, exact_value(r, symbols)?);
+            let shift = u32::try_from(r).ok().filter(|s| *s < WORD::BITS);
+            match o {
+                BinaryOperator::Or => Some(l | r),
+                BinaryOperator::Xor => Some(l ^ r),
+                BinaryOperator::And => Some(l & r),
+                BinaryOperator::ShiftLeft => shift.map(|s| l.saturating_mul(1 << s)),
+                BinaryOperator::ShiftRight => shift.map(|s| l >> s),
+                BinaryOperator::Add => Some(l.saturating_add(r)),
+                BinaryOperator::Subtract => Some(l.saturating_sub(r)),
+                BinaryOperator::Multiply => Some(l.saturating_mul(r)),
+                BinaryOperator::Divide => l.checked_div(r),
+            }
+        },
+    }
+}
+
+/// Finds the index of the first instruction of each run of instructions
+/// that follows a stop which is not straight after a test.
+///
+/// Tags and `.org` end a run as something could jump there, and so does
+/// any data, which is often placed straight after a stop.
+fn find_unreachable(lines: &[LineType]) -> Vec<usize> {
+    let mut res = vec![];
+    let mut after_stop = false;
+    let mut after_test = false;
+    for (i, line) in lines.iter().enumerate() {
+        match line {
+            LineType::Tag(_) => { after_stop = false; continue; },
+            LineType::Origin(_) => after_stop = false,
+            LineType::Instruction(_) if after_stop => { res.push(i); after_stop = false; },
+            LineType::Instruction(Instruction::Stop) => after_stop = !after_test,
+            l if l.is_data() => after_stop = false,
+            _ => continue,
+        }
+        after_test = matches!(line, LineType::Instruction(Instruction::Test));
+    }
+    res
+}
@@ -0,0 +1,81 @@
+use super::*;
+use crate::assembler::{assemble_with, AssemblyOptions, parser::parse_asm_string, expander::expand_macros};
+use crate::assembler::errors::AssemblyError;
+
+
+fn warnings(asm: &str) -> Vec<(usize, Warning)> {
+    let lines = expand_macros(parse_asm_string(asm, false).ok().unwrap()).ok().unwrap();
+    let located: Vec<(usize, LineType)> = lines.into_iter().enumerate().collect();
+    let tags = match crate::assembler::assemble(asm, false) {
+        Ok(d) => d.1,
+        Err(e) => panic!("{}", e.describe(true))
+    };
+    find_warnings(&located, &tags)
+}
+
+#[test]
+fn test_operand_out_of_range() {
+    assert_eq!(warnings("sub 0d40\nldn 0d31\nstp"), vec![
+        (0, Warning::OperandOutOfRange("sub 0d40".to_owned(), 40)),
+    ]);
+    assert_eq!(warnings("equ far 0d32\nsto $far-1\njmp $far\nstp"), vec![
+        (2, Warning::OperandOutOfRange("jmp $far".to_owned(), 32)),
+    ]);
+    assert_eq!(warnings("jrp 0d-1\nstp")[0].1, Warning::OperandOutOfRange("jrp 0d-1".to_owned(), -1));
+    assert!(Warning::OperandOutOfRange("sub 0d40".to_owned(), 40).describe().contains("masked to 8"));
+}
+
+#[test]
+fn test_absolute_overflow() {
+    let max = WORD::MAX;
+    let asm = format!("stp\nabs 0d{}+1\nabs 0d{}\n.word 0d1, 0d{}*2\nabs (0d{}+1)&0xF", max, max, max, max);
+    assert_eq!(warnings(&asm), vec![
+        (1, Warning::AbsoluteOverflow(format!("0d{}+0d1", max))),
+        (3, Warning::AbsoluteOverflow(format!("0d{}*0d2", max))),
+    ]);
+    assert_eq!(warnings("stp\nabs 0d-1-0d1\nabs ~0d0"), vec![]);
+}
+
+#[test]
+fn test_unreachable_code() {
+    assert_eq!(warnings("stp\nldn 0d1\nsub 0d1\nstp"), vec![(1, Warning::UnreachableCode)]);
+    assert_eq!(warnings("cmp\nstp\nldn 0d1\nstp"), vec![]);
+    assert_eq!(warnings("stp\n:a\nldn 0d1\nstp"), vec![]);
+    assert_eq!(warnings("stp\nabs 0d1\nldn 0d1\nstp"), vec![]);
+    assert_eq!(warnings("cmp\n:a\nstp\nstp\nstp"), vec![(4, Warning::UnreachableCode)]);
+}
+
+#[test]
+fn test_no_stop() {
+    assert_eq!(warnings("ldn 0d1\nabs 0d2\nequ a 0d1"), vec![(1, Warning::NoStop)]);
+    assert_eq!(warnings(""), vec![]);
+}
+
+fn assemble_with_warnings(asm: &str, deny_warnings: bool) -> Result<Vec<AssemblyWarning>, Vec<AssemblyError>> {
+    let options = AssemblyOptions { warnings: true, deny_warnings, ..Default::default() };
+    assemble_with(asm, false, &options).map(|a| a.warnings)
+}
+
+#[test]
+fn test_assemble_with_warnings() {
+    let asm = "macro far\nsub 0d40\nendm\nfar\nstp\nldn 0d1";
+    let found = match assemble_with_warnings(asm, false) {
+        Ok(w) => w,
+        Err(e) => panic!("{}", e[0].describe(true))
+    };
+    let lines: Vec<(usize, String)> = found.iter().map(|w| (w.span.line, w.span.source())).collect();
+    assert_eq!(lines, vec![(4, "far".to_owned()), (6, "ldn 0d1".to_owned())]);
+    let found = match assemble_with_warnings("sub 0d40\nstp", false) {
+        Ok(w) => w,
+        Err(_) => panic!()
+    };
+    assert_eq!(found[0].span.source(), "0d40");
+    assert!(found[0].render(None).starts_with("warning: The operand of `sub 0d40`"));
+
+    match assemble_with_warnings(asm, true) {
+        Err(e) => assert!(e.iter().all(|e| matches!(e, AssemblyError::Warning(_, _))) && e.len() == 2),
+        Ok(_) => panic!()
+    }
+    assert!(assemble_with_warnings("ldn $a\nstp\n:a\nabs 0d1", true).is_ok());
+    assert!(matches!(assemble_with_warnings("foo", true), Err(e) if matches!(e[0], AssemblyError::ParserError(_, _))));
+}
@@ -0,0 +1,87 @@
+//! # Arguments
+//!
+//! A minimal command line argument parser for the `baby` subcommands,
+//! splitting arguments into positional values, boolean flags such as
+//! `--og` and options taking a value such as `-o out`.
+//!
+
+use std::collections::HashMap;
+use crate::errors::CliError;
+
+
+/// The parsed arguments passed to a subcommand.
+#[derive(Debug, PartialEq)]
+pub struct Args {
+    /// Any arguments not starting with a `-`, in order.
+    pub positional: Vec<String>,
+    /// Any boolean flags present.
+    pub flags: Vec<String>,
+    /// Any options and their corresponding values.
+    pub options: HashMap<String, String>,
+}
+
+impl Args {
+
+    /// Parses a list of arguments.
+    ///
+    /// Returns [CliError::Usage] if an unknown flag is encountered or
+    /// an option is missing its value.
+    ///
+    /// # Parameters
+    /// * `args` - The arguments passed after the subcommand name.
+    /// * `flags` - The names of the accepted boolean flags.
+    /// * `options` - The names of the accepted options taking a value.
+    ///
+    pub fn parse(args: &[String], flags: &[&str], options: &[&str]) -> Result<Args, CliError> {
+        let mut res = Args { positional: vec![], flags: vec![], options: HashMap::new() };
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                a if flags.contains(&a) => res.flags.push(a.to_owned()),
+                a if options.contains(&a) => {
+                    let value = match iter.next() {
+                        Some(v) => v.clone(),
+                        None => return Err(CliError::Usage(format!("`{}` expects a value. ", a)))
+                    };
+                    res.options.insert(a.to_owned(), value);
+                },
+                a if a.starts_with('-') && a.len() > 1 =>
+                    return Err(CliError::Usage(format!("unknown argument `{}`. ", a))),
+                a => res.positional.push(a.to_owned()),
+            }
+        }
+        Ok(res)
+    }
+
+    /// Returns true if a flag was passed.
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|f| f == name)
+    }
+
+    /// Returns the value of an option if it was passed.
+    pub fn option(&self, name: &str) -> Option<&String> {
+        self.options.get(name)
+    }
+
+    /// Gets a required positional argument.
+    ///
+    /// Returns [CliError::Usage] naming the missing argument if absent.
+    pub fn positional(&self, index: usize, name: &str) -> Result<&String, CliError> {
+        match self.positional.get(index) {
+            Some(v) => Ok(v),
+            None => Err(CliError::Usage(format!("missing argument `{}`. ", name)))
+        }
+    }
+
+    /// Gets an option and parses it as a number.
+    ///
+    /// Returns the `default` if the option is absent, or [CliError::Usage]
+    /// if the value is not a valid number.
+    pub fn option_usize(&self, name: &str, default: usize) -> Result<usize, CliError> {
+        match self.option(name) {
+            Some(v) => v.parse::<usize>()
+                .map_err(|_| CliError::Usage(format!("`{}` expects a number, found `{}`. ", name, v))),
+            None => Ok(default)
+        }
+    }
+}
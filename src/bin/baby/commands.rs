@@ -0,0 +1,257 @@
+//! # Commands
+//!
+//! The implementations of each of the `baby` subcommands, each takes the
+//! arguments passed after the subcommand name and returns the text to be
+//! printed on success, or a [CliError] on failure.
+//!
+
+use std::fs;
+use std::path::Path;
+use baby_emulator::core::{BabyModel, MEMORY_WORDS, WORD, INSTR_MASK};
+use baby_emulator::core::instructions::BabyInstruction;
+use baby_emulator::core::errors::{BabyErrors, BabyError};
+use baby_emulator::assembler::AssemblyOptions;
+use baby_emulator::assembler::syntax::SyntaxTree;
+use baby_emulator::assembler::notation::convert_notation;
+use baby_emulator::compiler::compile_to_asm;
+use baby_emulator::analysis::cfg::ControlFlowGraph;
+use crate::args::Args;
+use crate::errors::{CliError, core_dump};
+use crate::image;
+use crate::debugger::{Debugger, terminal};
+use crate::gdb::{GdbStub, server, DEFAULT_MAX_STEPS as GDB_MAX_STEPS};
+use crate::lsp::{LanguageServer, server as lsp_server};
+use baby_emulator::assembler::listing::lsb_first;
+
+
+/// The default maximum number of instructions executed by `baby run`.
+pub const DEFAULT_MAX_STEPS: usize = 10_000;
+
+/// Assembles an asm file into a store image.
+///
+/// `baby asm <file.asm> [--og] [-o <out>] [--listing <out.lst>] [--sym <out.sym>] [--deny-warnings] [--object]`,
+/// the output path defaults to the input path with a `.bin` extension, a listing
+/// or symbol file of the assembled program is only written if a path is given for
+/// it. Any warnings are printed before the result, or fail the command if denied.
+///
+/// With `--object` a relocatable object file is written instead, with a
+/// `.obj` extension by default, to be linked with `baby link`.
+pub fn asm(args: &[String]) -> Result<String, CliError> {
+    let args = Args::parse(args, &["--og", "--deny-warnings", "--object"], &["-o", "--listing", "--sym"])?;
+    let input = args.positional(0, "file")?;
+    let og_notation = args.flag("--og");
+    let extension = if args.flag("--object") { "obj" } else { "bin" };
+    let output = match args.option("-o") {
+        Some(v) => v.clone(),
+        None => Path::new(input).with_extension(extension).to_string_lossy().into_owned()
+    };
+    if args.flag("--object") {
+        let object = image::load_object(input, og_notation)?;
+        fs::write(&output, object.to_text()).map_err(|e| CliError::Io(output.clone(), e))?;
+        return Ok(format!("Assembled `{}` to object `{}`. ", input, output));
+    }
+    let options = AssemblyOptions {
+        listing: args.option("--listing").is_some(), warnings: true, deny_warnings: args.flag("--deny-warnings"),
+    };
+    let ((store, tags), listing, warnings) = image::assemble_file_checked(input, og_notation, options)?;
+    image::write_store(&output, &store)?;
+    if let Some(sym_path) = args.option("--sym") { image::write_symbols(sym_path, &tags)?; }
+    let mut res: Vec<String> = warnings.iter().map(|w| w.render(Some(input))).collect();
+    match (args.option("--listing"), listing) {
+        (Some(listing_path), Some(listing)) => {
+            fs::write(listing_path, listing.render(og_notation))
+                .map_err(|e| CliError::Io(listing_path.clone(), e))?;
+            res.push(format!("Assembled `{}` to `{}` with listing `{}`. ", input, output, listing_path));
+        },
+        _ => res.push(format!("Assembled `{}` to `{}`. ", input, output)),
+    }
+    Ok(res.join("\n\n"))
+}
+
+/// Links object files into a store image.
+///
+/// `baby link <file>... [--og] [-o <out>] [--sym <out.sym>]`, each file can be
+/// an object file or an asm source file, placed in memory in the order given.
+/// The output path defaults to the first file with a `.bin` extension, a symbol
+/// file of the linked tags is only written if a path is given for it.
+pub fn link(args: &[String]) -> Result<String, CliError> {
+    let args = Args::parse(args, &["--og"], &["-o", "--sym"])?;
+    let first = args.positional(0, "file")?;
+    let output = match args.option("-o") {
+        Some(v) => v.clone(),
+        None => Path::new(first).with_extension("bin").to_string_lossy().into_owned()
+    };
+    let (store, tags) = image::link_files(&args.positional, args.flag("--og"))?;
+    image::write_store(&output, &store)?;
+    if let Some(sym_path) = args.option("--sym") { image::write_symbols(sym_path, &tags)?; }
+    Ok(format!("Linked {} files to `{}`. ", args.positional.len(), output))
+}
+
+/// Runs a program until it stops, printing the final core dump.
+///
+/// `baby run <file> [--og] [--max-steps <n>] [--sym <file.sym>]`, the file
+/// can be a store image or an asm source file, the core dump labels store
+/// lines with the tags of an asm source file, or those in the symbol file.
+pub fn run(args: &[String]) -> Result<String, CliError> {
+    let args = Args::parse(args, &["--og"], &["--max-steps", "--sym"])?;
+    let input = args.positional(0, "file")?;
+    let max_steps = args.option_usize("--max-steps", DEFAULT_MAX_STEPS)?;
+    let (store, tags) = image::load_program_symbols(input, args.flag("--og"), args.option("--sym"))?;
+    let model = BabyModel::new_with_program(store);
+    match model.run_loop(max_steps) {
+        (model, BabyErrors::Stop(s)) => Ok(format!("{}\n{}", core_dump(&model, &tags), s.get_descriptor())),
+        (_, err) => Err(CliError::Emulation(Box::new(err), tags))
+    }
+}
+
+/// Opens a program in the interactive debugger.
+///
+/// `baby debug <file> [--og] [--sym <file.sym>]`, the file can be a store
+/// image or an asm source file, tag names are only shown for asm source
+/// files or if a symbol file is given.
+pub fn debug(args: &[String]) -> Result<String, CliError> {
+    let args = Args::parse(args, &["--og"], &["--sym"])?;
+    let input = args.positional(0, "file")?;
+    let (store, tags) = image::load_program_symbols(input, args.flag("--og"), args.option("--sym"))?;
+    let mut debugger = Debugger::new(store, tags);
+    terminal::run(&mut debugger).map_err(|e| CliError::Io("terminal".to_owned(), e))?;
+    Ok(format!("Debugger exited after {} steps. ", debugger.steps))
+}
+
+/// The default address `baby gdb` listens on.
+pub const DEFAULT_GDB_ADDR: &str = "127.0.0.1:1234";
+
+/// Serves a program to a debugger over the GDB remote serial protocol.
+///
+/// `baby gdb <file> [--og] [--stdio] [--listen <addr>] [--max-steps <n>]`,
+/// listens on [DEFAULT_GDB_ADDR] unless `--stdio` is passed.
+pub fn gdb(args: &[String]) -> Result<String, CliError> {
+    let args = Args::parse(args, &["--og", "--stdio"], &["--listen", "--max-steps"])?;
+    let input = args.positional(0, "file")?;
+    let store = image::load_store(input, args.flag("--og"))?;
+    let mut stub = GdbStub::new(store);
+    stub.max_steps = args.option_usize("--max-steps", GDB_MAX_STEPS)?;
+    let res = if args.flag("--stdio") { server::serve(&mut stub, &mut server::Stdio::new()) }
+        else {
+            let addr = args.option("--listen").map(|a| a.as_str()).unwrap_or(DEFAULT_GDB_ADDR);
+            server::serve_tcp(&mut stub, addr)
+        };
+    res.map_err(|e| CliError::Io("gdb connection".to_owned(), e))?;
+    Ok(String::new())
+}
+
+/// Formats an asm file, see [SyntaxTree::format] for the layout.
+///
+/// `baby fmt <file.asm> [--og] [--write] [--check]`, prints the formatted
+/// source unless `--write` is given, which rewrites the file in place.
+/// With `--check` nothing is written, failing if the file is not formatted.
+pub fn fmt(args: &[String]) -> Result<String, CliError> {
+    let args = Args::parse(args, &["--og", "--write", "--check"], &[])?;
+    let input = args.positional(0, "file")?;
+    let source = image::read_source(input)?;
+    let formatted = SyntaxTree::parse(&source, args.flag("--og")).format();
+    if args.flag("--check") {
+        if formatted != source { return Err(CliError::Unformatted(input.clone())); }
+        return Ok(format!("`{}` is formatted. ", input));
+    }
+    if !args.flag("--write") { return Ok(formatted.trim_end().to_owned()); }
+    fs::write(input, &formatted).map_err(|e| CliError::Io(input.clone(), e))?;
+    Ok(format!("Formatted `{}`. ", input))
+}
+
+/// Converts an asm file between modern and original notation.
+///
+/// `baby convert <file.asm> [--og] [-o <out>]`, converts modern notation to
+/// original notation, or original to modern with `--og`, printing the result
+/// unless an output path is given. Fails without writing anything if any line
+/// cannot be converted.
+pub fn convert(args: &[String]) -> Result<String, CliError> {
+    let args = Args::parse(args, &["--og"], &["-o"])?;
+    let input = args.positional(0, "file")?;
+    let (converted, issues) = convert_notation(&image::read_source(input)?, args.flag("--og"));
+    if !issues.is_empty() { return Err(CliError::Conversion(input.clone(), issues)); }
+    match args.option("-o") {
+        Some(output) => {
+            fs::write(output, &converted).map_err(|e| CliError::Io(output.clone(), e))?;
+            Ok(format!("Converted `{}` to `{}`. ", input, output))
+        },
+        None => Ok(converted.trim_end().to_owned()),
+    }
+}
+
+/// Compiles a program to asm.
+///
+/// `baby compile <file> [-o <out.asm>]`, prints the generated asm unless an
+/// output path is given, see [compiler][baby_emulator::compiler] for the language.
+pub fn compile(args: &[String]) -> Result<String, CliError> {
+    let args = Args::parse(args, &[], &["-o"])?;
+    let input = args.positional(0, "file")?;
+    let source = fs::read_to_string(input).map_err(|e| CliError::Io(input.clone(), e))?;
+    let asm = compile_to_asm(&source).map_err(|e| CliError::Compile(input.clone(), e))?;
+    match args.option("-o") {
+        Some(output) => {
+            fs::write(output, &asm).map_err(|e| CliError::Io(output.clone(), e))?;
+            Ok(format!("Compiled `{}` to `{}`. ", input, output))
+        },
+        None => Ok(asm.trim_end().to_owned()),
+    }
+}
+
+/// Prints the control flow graph of a program as Graphviz DOT.
+///
+/// `baby cfg <file> [--og] [--sym <file.sym>] [-o <out.dot>]`, the file can
+/// be a store image or an asm source file, blocks are labelled with the tags
+/// of an asm source file, or those in the symbol file. Anything found building
+/// the graph is listed in comments at the top, and printed if written to a file.
+pub fn cfg(args: &[String]) -> Result<String, CliError> {
+    let args = Args::parse(args, &["--og"], &["-o", "--sym"])?;
+    let input = args.positional(0, "file")?;
+    let (store, tags) = image::load_program_symbols(input, args.flag("--og"), args.option("--sym"))?;
+    let graph = ControlFlowGraph::from_store(&store, &tags);
+    match args.option("-o") {
+        Some(output) => {
+            fs::write(output, graph.to_dot()).map_err(|e| CliError::Io(output.clone(), e))?;
+            let mut res: Vec<String> = graph.findings.iter().map(|f| f.describe()).collect();
+            res.push(format!("Wrote the control flow graph of `{}` to `{}`. ", input, output));
+            Ok(res.join("\n"))
+        },
+        None => Ok(graph.to_dot().trim_end().to_owned()),
+    }
+}
+
+/// Runs a language server for Baby asm over stdio.
+///
+/// `baby lsp [--og]`, documents use original notation if `--og` is
+/// given, unless the client's initialisation options say otherwise.
+pub fn lsp(args: &[String]) -> Result<String, CliError> {
+    let args = Args::parse(args, &["--og"], &[])?;
+    let mut server = LanguageServer::new(args.flag("--og"));
+    lsp_server::serve_stdio(&mut server).map_err(|e| CliError::Io("lsp connection".to_owned(), e))?;
+    Ok(String::new())
+}
+
+/// Prints the contents of a store image, one word per line.
+///
+/// `baby dump <file> [--og]`, the file can be a store image or an asm
+/// source file.
+pub fn dump(args: &[String]) -> Result<String, CliError> {
+    let args = Args::parse(args, &["--og"], &[])?;
+    let input = args.positional(0, "file")?;
+    let store = image::load_store(input, args.flag("--og"))?;
+    Ok(dump_store(&store))
+}
+
+/// Formats a main store, one word per line, showing the address, the
+/// word in hex, the word as LSB first binary (as shown on the Baby's
+/// display tube), and the word decoded as an instruction.
+pub fn dump_store(store: &[WORD; MEMORY_WORDS]) -> String {
+    store.iter().enumerate().map(|(addr, word)| {
+        let instr = BabyInstruction::from_number(*word as u16 & INSTR_MASK);
+        format!("{:#04x}: {:#010x} {} {} {}",
+            addr, word, lsb_first(*word), instr.get_instr_description(), instr.get_operand()
+        )
+    })
+    .collect::<Vec<String>>()
+    .join("\n")
+}
+
@@ -0,0 +1,289 @@
+//! # Debugger
+//!
+//! An interactive full screen debugger for Baby programs, started with
+//! `baby debug <file>`.
+//!
+//! The main type here is [Debugger][crate::debugger::Debugger] which holds
+//! the state of the debugging session around a [BabyModel], it is driven
+//! entirely through [Debugger::handle_key][crate::debugger::Debugger::handle_key]
+//! and [Debugger::tick][crate::debugger::Debugger::tick], and drawn by
+//! [render][crate::debugger::render], leaving the terminal handling to
+//! [terminal][crate::debugger::terminal].
+//!
+//! ## Keys
+//!
+//! | Key          | Action                                          |
+//! |--------------|-------------------------------------------------|
+//! | `s`          | Execute a single instruction                    |
+//! | `r`          | Run until a stop, breakpoint or pause           |
+//! | `p`, `space` | Pause a running program                         |
+//! | `b`          | Toggle a breakpoint on the selected store line  |
+//! | `e`, `enter` | Edit the selected store line                    |
+//! | `g`          | Set CI to the selected store line               |
+//! | `x`          | Reset the model to the loaded program           |
+//! | `↑`/`↓`      | Select a store line (also `k`/`j`)              |
+//! | `q`          | Quit                                            |
+//!
+//! Whilst editing, a store line can be given as any value (`0d-5`, `$tag`),
+//! an `abs` directive or a modern notation instruction (`ldn $tag`).
+//!
+
+use std::collections::{BTreeSet, HashMap};
+use baby_emulator::assembler::parser::{self, LineType, Value};
+use baby_emulator::assembler::linker::UnlinkedData;
+use baby_emulator::assembler::linker::errors::{LinkingError, LinkerError, TagError};
+use baby_emulator::assembler::disassembler::decode_word;
+use baby_emulator::assembler::parser::errors::ParseError;
+use baby_emulator::core::{BabyModel, MEMORY_WORDS, WORD, INSTR_MASK};
+use baby_emulator::core::errors::{BabyErrors, BabyError};
+
+
+/// Contains functionality for drawing the debugger to a string.
+pub mod render;
+/// Contains the terminal handling and event loop.
+pub mod terminal;
+#[cfg(test)]
+mod tests;
+
+
+/// A key press understood by the debugger.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Key {
+    /// A printable character.
+    Char(char),
+    /// The up arrow.
+    Up,
+    /// The down arrow.
+    Down,
+    /// The enter or return key.
+    Enter,
+    /// The backspace key.
+    Backspace,
+    /// The escape key.
+    Escape,
+}
+
+/// The current mode of the debugger.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Mode {
+    /// Waiting for a command.
+    Paused,
+    /// Continuously executing instructions.
+    Running,
+    /// The program has hit a stop instruction.
+    Stopped,
+    /// Editing the selected store line, holds the text typed so far.
+    Editing(String),
+}
+
+/// The state of a debugging session.
+pub struct Debugger {
+    /// The model being debugged.
+    pub model: BabyModel,
+    /// The model as originally loaded, used when resetting.
+    pub initial: BabyModel,
+    /// The tag names and values from assembling the program.
+    pub tags: HashMap<String, WORD>,
+    /// The store lines execution will pause at.
+    pub breakpoints: BTreeSet<usize>,
+    /// The selected store line.
+    pub cursor: usize,
+    /// The current mode.
+    pub mode: Mode,
+    /// A message describing the last thing that happened.
+    pub status: String,
+    /// The number of instructions executed since loading or resetting.
+    pub steps: usize,
+}
+
+impl Debugger {
+
+    /// Creates a new debugger with a program loaded.
+    ///
+    /// # Parameters
+    /// * `main_store` - The program to be debugged.
+    /// * `tags` - Any tag names and values to label the store with.
+    ///
+    pub fn new(main_store: [WORD; MEMORY_WORDS], tags: HashMap<String, WORD>) -> Debugger {
+        let model = BabyModel::new_with_program(main_store);
+        Debugger {
+            initial: model.clone(),
+            model,
+            tags,
+            breakpoints: BTreeSet::new(),
+            cursor: 0,
+            mode: Mode::Paused,
+            status: "Loaded. ".to_owned(),
+            steps: 0,
+        }
+    }
+
+    /// Handles a key press, returns false if the debugger should quit.
+    pub fn handle_key(&mut self, key: Key) -> bool {
+        if let Mode::Editing(text) = &self.mode {
+            let mut text = text.clone();
+            match key {
+                Key::Enter => { self.mode = Mode::Paused; self.commit_edit(&text); },
+                Key::Escape => { self.mode = Mode::Paused; self.status = "Edit cancelled. ".to_owned(); },
+                Key::Backspace => { text.pop(); self.mode = Mode::Editing(text); },
+                Key::Char(c) => { text.push(c); self.mode = Mode::Editing(text); },
+                _ => ()
+            }
+            return true;
+        }
+        match key {
+            Key::Char('q') => return false,
+            Key::Char('s') => { self.pause(); self.step(); },
+            Key::Char('r') if self.mode != Mode::Stopped => {
+                self.mode = Mode::Running;
+                self.status = "Running. ".to_owned();
+            },
+            Key::Char('p') | Key::Char(' ') => self.pause(),
+            Key::Char('b') => self.toggle_breakpoint(self.cursor),
+            Key::Char('e') | Key::Enter => {
+                self.pause();
+                self.mode = Mode::Editing(String::new());
+            },
+            Key::Char('g') => self.goto(self.cursor),
+            Key::Char('x') => self.reset(),
+            Key::Up | Key::Char('k') => self.cursor = (self.cursor + MEMORY_WORDS - 1) % MEMORY_WORDS,
+            Key::Down | Key::Char('j') => self.cursor = (self.cursor + 1) % MEMORY_WORDS,
+            _ => ()
+        }
+        true
+    }
+
+    /// Executes up to `steps` instructions if running, pausing at any
+    /// breakpoint or stopping at a stop instruction.
+    pub fn tick(&mut self, steps: usize) {
+        for _ in 0..steps {
+            if self.mode != Mode::Running { return; }
+            self.step();
+            if self.mode == Mode::Paused || self.mode == Mode::Running {
+                let addr = self.model.instruction_address as usize;
+                if self.breakpoints.contains(&addr) {
+                    self.mode = Mode::Paused;
+                    self.status = format!("Breakpoint hit at {:#04x}. ", addr);
+                }
+            }
+        }
+    }
+
+    /// Executes a single instruction.
+    pub fn step(&mut self) {
+        if self.mode == Mode::Stopped {
+            self.status = "The program has stopped, press `x` to reset. ".to_owned();
+            return;
+        }
+        match self.model.execute() {
+            Ok(m) => {
+                self.model = m;
+                self.steps += 1;
+                self.status = format!("Executed step {}. ", self.steps);
+            },
+            Err(BabyErrors::Stop(s)) => {
+                self.mode = Mode::Stopped;
+                self.status = s.get_descriptor().trim().to_owned();
+            },
+            Err(e) => {
+                self.mode = Mode::Paused;
+                self.status = e.get_descriptor().trim().to_owned();
+            }
+        }
+    }
+
+    /// Pauses a running program.
+    pub fn pause(&mut self) {
+        if self.mode == Mode::Running {
+            self.mode = Mode::Paused;
+            self.status = format!("Paused after {} steps. ", self.steps);
+        }
+    }
+
+    /// Adds a breakpoint to a store line, or removes it if already present.
+    pub fn toggle_breakpoint(&mut self, addr: usize) {
+        if !self.breakpoints.remove(&addr) {
+            self.breakpoints.insert(addr);
+        }
+    }
+
+    /// Sets CI to a store line, loading the instruction there.
+    pub fn goto(&mut self, addr: usize) {
+        self.model.instruction_address = addr as u16;
+        self.model.instruction = self.model.main_store[addr] as u16 & INSTR_MASK;
+        if self.mode == Mode::Stopped { self.mode = Mode::Paused; }
+        self.status = format!("CI set to {:#04x}. ", addr);
+    }
+
+    /// Resets the model to the program as originally loaded.
+    pub fn reset(&mut self) {
+        self.model = self.initial.clone();
+        self.mode = Mode::Paused;
+        self.steps = 0;
+        self.status = "Reset. ".to_owned();
+    }
+
+    /// Sets the selected store line from the text typed whilst editing.
+    fn commit_edit(&mut self, text: &str) {
+        match self.parse_word(text) {
+            Ok(word) => self.set_word(self.cursor, word),
+            Err(e) => self.status = e,
+        }
+    }
+
+    /// Sets a store line, reloading the instruction register if the line
+    /// is the current instruction.
+    pub fn set_word(&mut self, addr: usize, word: WORD) {
+        self.model.main_store[addr] = word;
+        if self.model.instruction_address as usize == addr {
+            self.model.instruction = word as u16 & INSTR_MASK;
+        }
+        self.status = format!("Set {:#04x} to {:#010x}. ", addr, word);
+    }
+
+    /// Parses an edited store line, this can either be a value, an
+    /// absolute value directive, or a modern notation instruction,
+    /// any tags are resolved against the loaded program's tags.
+    ///
+    /// Returns a string describing the problem if it cannot be parsed.
+    pub fn parse_word(&self, text: &str) -> Result<WORD, String> {
+        if let Ok(v) = Value::parse(text) {
+            return UnlinkedData::resolve_value(&v, &self.tags)
+                .map_err(|e| match e {
+                    LinkingError::TagError(TagError::UnknownTagName(t)) => format!("The tag `{}` is not known. ", t),
+                    e => e.describe(false),
+                });
+        }
+        let data = match parser::parse_line(text, false) {
+            Ok(LineType::Instruction(i)) => UnlinkedData::Instruction(i),
+            Ok(LineType::Absolute(v)) => UnlinkedData::Absolute(v),
+            Ok(LineType::Tag(_)) => return Err("Tags cannot be stored. ".to_owned()),
+            Ok(LineType::Constant(..)) => return Err("Constants cannot be stored. ".to_owned()),
+            Ok(LineType::Origin(_) | LineType::Fill(..) | LineType::Words(_) | LineType::Reserve(_) |
+                LineType::Export(_) | LineType::Import(_)) =>
+                return Err("Directives cannot be stored. ".to_owned()),
+            Ok(_) => return Err("Macros cannot be stored. ".to_owned()),
+            Err(e) => return Err(e.describe(false)),
+        };
+        match data.resolve(&self.tags) {
+            Ok(i) => Ok(i.to_number()),
+            Err(_) => Err(format!("Cannot resolve the tags in `{}`. ", text.trim())),
+        }
+    }
+
+    /// Gets the names of any tags at a store line.
+    pub fn tags_at(&self, addr: usize) -> Vec<&str> {
+        let mut tags: Vec<&str> = self.tags.iter()
+            .filter(|(_, v)| **v as usize == addr)
+            .map(|(k, _)| k.as_str())
+            .collect();
+        tags.sort();
+        tags
+    }
+
+    /// Disassembles a store line into modern notation, using tag names
+    /// for operands where a tag has the operand's value.
+    pub fn disassemble(&self, addr: usize) -> String {
+        decode_word(self.model.main_store[addr], &self.tags).to_asm_string(false)
+    }
+}
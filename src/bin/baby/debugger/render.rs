@@ -0,0 +1,56 @@
+//! # Render
+//!
+//! Draws the state of a [Debugger] to plain text lines, the store is drawn
+//! as a Williams tube would have shown it, each word LSB first with a dash
+//! for a 1 and a dot for a 0, alongside each word's disassembly.
+//!
+
+use baby_emulator::assembler::listing::lsb_first;
+use baby_emulator::core::{MEMORY_WORDS, WORD};
+use super::{Debugger, Mode};
+
+
+/// The index of the line the first store line is drawn on.
+pub const STORE_ROW: usize = 4;
+
+/// Draws a word as a Williams tube line, LSB first.
+pub fn tube_line(word: WORD) -> String {
+    lsb_first(word).chars().map(|b| if b == '1' { '-' } else { '.' }).collect()
+}
+
+/// Draws the full debugger screen, one string per line.
+pub fn render(debugger: &Debugger) -> Vec<String> {
+    let model = &debugger.model;
+    let mode = match &debugger.mode {
+        Mode::Paused => "PAUSED",
+        Mode::Running => "RUNNING",
+        Mode::Stopped => "STOPPED",
+        Mode::Editing(_) => "EDITING",
+    };
+    let mut lines = vec![
+        format!("Baby debugger [{}] steps: {}", mode, debugger.steps),
+        format!("ACC  {} {:#010x} ({})", tube_line(model.accumulator), model.accumulator, model.accumulator),
+        format!("CI   {:#04x}   PI {:#06x}", model.instruction_address, model.instruction),
+        String::new(),
+    ];
+    for addr in 0..MEMORY_WORDS {
+        let cursor = if debugger.cursor == addr { '>' } else { ' ' };
+        let current = if model.instruction_address as usize == addr { '*' } else { ' ' };
+        let breakpoint = if debugger.breakpoints.contains(&addr) { 'B' } else { ' ' };
+        let tags = debugger.tags_at(addr).iter()
+            .map(|t| format!(":{}", t))
+            .collect::<Vec<String>>()
+            .join(" ");
+        let word = model.main_store[addr];
+        lines.push(format!("{}{}{} {:#04x} {} {:#010x} {:<16} {}",
+            cursor, current, breakpoint, addr, tube_line(word), word, tags, debugger.disassemble(addr)
+        ));
+    }
+    lines.push(String::new());
+    lines.push(match &debugger.mode {
+        Mode::Editing(text) => format!("Set {:#04x} to: {}_", debugger.cursor, text),
+        _ => debugger.status.clone(),
+    });
+    lines.push("s step  r run  p pause  b breakpoint  e edit  g set CI  x reset  q quit".to_owned());
+    lines
+}
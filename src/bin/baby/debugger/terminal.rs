@@ -0,0 +1,133 @@
+//! # Terminal
+//!
+//! The terminal handling for the debugger, puts the terminal into raw
+//! mode using `stty`, reads key presses on a background thread, and
+//! redraws the screen with ANSI escape codes after each event.
+//!
+//! The terminal is always restored when [RawTerminal] is dropped.
+//!
+
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+use super::{Debugger, Key, Mode};
+use super::render::{render, STORE_ROW};
+
+
+/// How long to wait between executing instructions whilst running.
+pub const TICK: Duration = Duration::from_millis(20);
+/// How many instructions to execute each tick whilst running.
+pub const STEPS_PER_TICK: usize = 10;
+
+/// Holds the terminal in raw mode on the alternate screen, restoring
+/// the original settings when dropped.
+pub struct RawTerminal {
+    /// The terminal settings before entering raw mode.
+    saved: String,
+}
+
+impl RawTerminal {
+
+    /// Enters raw mode and switches to the alternate screen.
+    pub fn enable() -> io::Result<RawTerminal> {
+        let saved = stty(&["-g"])?;
+        stty(&["raw", "-echo"])?;
+        print!("\x1b[?1049h\x1b[?25l");
+        io::stdout().flush()?;
+        Ok(RawTerminal { saved: saved.trim().to_owned() })
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\x1b[?1049l");
+        let _ = io::stdout().flush();
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+/// Runs `stty` against the controlling terminal, returning its output.
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other("stty failed, is stdin a terminal? "));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Converts raw bytes read from the terminal into key presses.
+///
+/// `ctrl-c` is treated as `q` as raw mode stops it interrupting.
+pub fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        let key = match bytes[i] {
+            0x1b if bytes.get(i + 1) == Some(&b'[') => {
+                i += 2;
+                match bytes.get(i) {
+                    Some(b'A') => Some(Key::Up),
+                    Some(b'B') => Some(Key::Down),
+                    _ => None
+                }
+            },
+            0x1b => Some(Key::Escape),
+            b'\r' | b'\n' => Some(Key::Enter),
+            0x7f | 0x08 => Some(Key::Backspace),
+            0x03 => Some(Key::Char('q')),
+            b if b.is_ascii_graphic() || b == b' ' => Some(Key::Char(b as char)),
+            _ => None
+        };
+        keys.extend(key);
+        i += 1;
+    }
+    keys
+}
+
+/// Redraws the whole screen, highlighting the selected store line.
+fn draw(debugger: &Debugger) -> io::Result<()> {
+    let mut out = String::from("\x1b[H");
+    for (i, line) in render(debugger).iter().enumerate() {
+        if i == STORE_ROW + debugger.cursor {
+            out += &format!("\x1b[7m{}\x1b[0m\x1b[K\r\n", line);
+        } else {
+            out += &format!("{}\x1b[K\r\n", line);
+        }
+    }
+    out += "\x1b[J";
+    let mut stdout = io::stdout();
+    stdout.write_all(out.as_bytes())?;
+    stdout.flush()
+}
+
+/// Runs the debugger in the terminal until the user quits.
+pub fn run(debugger: &mut Debugger) -> io::Result<()> {
+    let _terminal = RawTerminal::enable()?;
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut stdin = io::stdin();
+        let mut buf = [0u8; 64];
+        while let Ok(n) = stdin.read(&mut buf) {
+            if n == 0 { return; }
+            for key in parse_keys(&buf[..n]) {
+                if tx.send(key).is_err() { return; }
+            }
+        }
+    });
+    draw(debugger)?;
+    loop {
+        let event = if debugger.mode == Mode::Running { rx.recv_timeout(TICK) }
+            else { rx.recv().map_err(|_| RecvTimeoutError::Disconnected) };
+        match event {
+            Ok(key) => if !debugger.handle_key(key) { return Ok(()); },
+            Err(RecvTimeoutError::Timeout) => debugger.tick(STEPS_PER_TICK),
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+        draw(debugger)?;
+    }
+}
@@ -0,0 +1,138 @@
+use super::*;
+use super::render::{render, tube_line, STORE_ROW};
+use super::terminal::parse_keys;
+use crate::image::assemble_store;
+use baby_emulator::assembler::{assemble, linker::LinkerData};
+use baby_emulator::core::instructions::BabyInstruction;
+
+
+const ASM: &str =
+"
+:start
+ldn $value
+sub $one
+sto $value
+stp
+:value
+abs 0d-3
+:one
+abs 0d1
+";
+
+fn get_debugger() -> Debugger {
+    let tags = match assemble(ASM, false) {
+        Ok(LinkerData(_, tags)) => tags,
+        Err(_) => panic!("Failed to assemble example. ")
+    };
+    let store = assemble_store(ASM, false).ok().unwrap();
+    Debugger::new(store, tags)
+}
+
+#[test]
+fn test_step() {
+    let mut debugger = get_debugger();
+    assert!(debugger.handle_key(Key::Char('s')));
+    assert_eq!(debugger.model.accumulator, 3);
+    assert_eq!(debugger.model.instruction_address, 1);
+    assert_eq!(debugger.steps, 1);
+}
+
+#[test]
+fn test_run_until_stop() {
+    let mut debugger = get_debugger();
+    debugger.handle_key(Key::Char('r'));
+    assert_eq!(debugger.mode, Mode::Running);
+    debugger.tick(100);
+    assert_eq!(debugger.mode, Mode::Stopped);
+    assert_eq!(debugger.steps, 3);
+    debugger.handle_key(Key::Char('s'));
+    assert_eq!(debugger.steps, 3);
+    debugger.handle_key(Key::Char('x'));
+    assert_eq!(debugger.mode, Mode::Paused);
+    assert_eq!(debugger.model, debugger.initial);
+}
+
+#[test]
+fn test_breakpoint() {
+    let mut debugger = get_debugger();
+    debugger.handle_key(Key::Down);
+    debugger.handle_key(Key::Down);
+    debugger.handle_key(Key::Char('b'));
+    assert!(debugger.breakpoints.contains(&2));
+    debugger.handle_key(Key::Char('r'));
+    debugger.tick(100);
+    assert_eq!(debugger.mode, Mode::Paused);
+    assert_eq!(debugger.model.instruction_address, 2);
+    debugger.handle_key(Key::Char('b'));
+    assert!(debugger.breakpoints.is_empty());
+}
+
+#[test]
+fn test_pause() {
+    let mut debugger = get_debugger();
+    debugger.handle_key(Key::Char('r'));
+    debugger.handle_key(Key::Char('p'));
+    assert_eq!(debugger.mode, Mode::Paused);
+    debugger.tick(100);
+    assert_eq!(debugger.steps, 0);
+}
+
+#[test]
+fn test_edit() {
+    let mut debugger = get_debugger();
+    debugger.handle_key(Key::Char('e'));
+    "sub $one".chars().for_each(|c| { debugger.handle_key(Key::Char(c)); });
+    debugger.handle_key(Key::Enter);
+    assert_eq!(debugger.model.main_store[0], BabyInstruction::Subtract(5).to_number());
+    assert_eq!(debugger.model.instruction, BabyInstruction::Subtract(5).to_number() as u16);
+
+    debugger.handle_key(Key::Down);
+    debugger.handle_key(Key::Enter);
+    "0d-7x".chars().for_each(|c| { debugger.handle_key(Key::Char(c)); });
+    debugger.handle_key(Key::Backspace);
+    debugger.handle_key(Key::Enter);
+    assert_eq!(debugger.model.main_store[1], -7);
+
+    debugger.handle_key(Key::Char('e'));
+    "foo".chars().for_each(|c| { debugger.handle_key(Key::Char(c)); });
+    debugger.handle_key(Key::Enter);
+    assert_eq!(debugger.model.main_store[1], -7);
+    assert_eq!(debugger.mode, Mode::Paused);
+}
+
+#[test]
+fn test_parse_word() {
+    let debugger = get_debugger();
+    assert_eq!(debugger.parse_word("$value"), Ok(4));
+    assert_eq!(debugger.parse_word("abs 0d9"), Ok(9));
+    assert_eq!(debugger.parse_word("stp"), Ok(BabyInstruction::Stop.to_number()));
+    assert!(debugger.parse_word("ldn $nothing").is_err());
+    assert!(debugger.parse_word(":tag").is_err());
+}
+
+#[test]
+fn test_disassemble() {
+    let debugger = get_debugger();
+    assert_eq!(debugger.disassemble(0), "ldn $value");
+    assert_eq!(debugger.disassemble(3), "stp");
+    assert_eq!(debugger.tags_at(0), vec!["start"]);
+}
+
+#[test]
+fn test_render() {
+    let debugger = get_debugger();
+    let lines = render(&debugger);
+    assert_eq!(lines.len(), STORE_ROW + MEMORY_WORDS + 3);
+    assert!(lines[STORE_ROW].starts_with(">* "));
+    assert!(lines[STORE_ROW].contains(":start"));
+    assert!(lines[STORE_ROW + 4].contains(&tube_line(-3)));
+    assert!(tube_line(1).starts_with("-."));
+}
+
+#[test]
+fn test_parse_keys() {
+    assert_eq!(parse_keys(b"s\x1b[A\x1b[B\r\x7f\x1b"), vec![
+        Key::Char('s'), Key::Up, Key::Down, Key::Enter, Key::Backspace, Key::Escape
+    ]);
+    assert_eq!(parse_keys(&[0x03]), vec![Key::Char('q')]);
+}
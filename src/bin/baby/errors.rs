@@ -0,0 +1,125 @@
+//! # CLI Errors
+//!
+//! Contains [CliError][crate::errors::CliError], an enum wrapping every
+//! error the `baby` binary can encounter, each branch maps to a distinct
+//! process exit code so scripts can tell the failures apart.
+//!
+//! | Code | Meaning                                   |
+//! |------|-------------------------------------------|
+//! | 0    | Success                                   |
+//! | 1    | Invalid command line usage                |
+//! | 2    | Failed to read or write a file            |
+//! | 3    | Error parsing, converting or compiling    |
+//! | 4    | Error thrown whilst linking asm or objects|
+//! | 5    | Emulation hit the maximum number of steps |
+//! | 6    | A store image or symbol file is malformed |
+//! | 7    | A file is not formatted (`fmt --check`)   |
+//!
+
+use baby_emulator::assembler::errors::AssemblyError;
+use baby_emulator::assembler::notation::ConversionIssue;
+use baby_emulator::compiler::CompileErrors;
+use baby_emulator::assembler::linker::errors::{LinkingError, LinkerError};
+use std::collections::HashMap;
+use baby_emulator::core::{BabyModel, WORD};
+use baby_emulator::core::errors::{BabyError, BabyErrors};
+
+
+/// Exit code for invalid command line usage.
+pub const EXIT_USAGE: u8 = 1;
+/// Exit code for a failure reading or writing a file.
+pub const EXIT_IO: u8 = 2;
+/// Exit code for an error thrown whilst parsing asm, expanding macros, converting notation
+/// or compiling a program.
+pub const EXIT_PARSE: u8 = 3;
+/// Exit code for an error thrown whilst linking asm, or a denied warning.
+pub const EXIT_LINK: u8 = 4;
+/// Exit code for an emulation that hit the maximum number of steps.
+pub const EXIT_ITERATIONS: u8 = 5;
+/// Exit code for a malformed store image or symbol file.
+pub const EXIT_IMAGE: u8 = 6;
+/// Exit code for an asm file that is not formatted when checked.
+pub const EXIT_UNFORMATTED: u8 = 7;
+
+/// Possible errors thrown by any of the `baby` subcommands.
+pub enum CliError {
+    /// The command line arguments are invalid.
+    Usage(String),
+    /// Failed to read or write a file (path, error).
+    Io(String, std::io::Error),
+    /// Assembling the asm source failed (path if read from a file, errors).
+    Assembly(Option<String>, Vec<AssemblyError>),
+    /// Linking object files failed (each error with the path of the object causing it).
+    Link(Vec<(String, LinkingError)>),
+    /// The emulation stopped with an error other than a stop instruction
+    /// (error, symbols to label the core dump with).
+    Emulation(Box<BabyErrors>, HashMap<String, WORD>),
+    /// A store image file is malformed (path, reason).
+    Image(String, String),
+    /// A symbol file is malformed (path, reason).
+    Symbols(String, String),
+    /// An asm file is not formatted when checked (path).
+    Unformatted(String),
+    /// Converting an asm file to the other notation found issues (path, issues).
+    Conversion(String, Vec<ConversionIssue>),
+    /// Compiling a program failed (path, errors).
+    Compile(String, CompileErrors),
+}
+
+impl CliError {
+
+    /// Returns a string describing the error.
+    pub fn describe(&self) -> String {
+        match self {
+            CliError::Usage(v) => format!("Invalid usage, {}", v),
+            CliError::Io(p, e) => format!("Failed to access `{}`, {}", p, e),
+            CliError::Assembly(p, e) => {
+                let rendered: Vec<String> = e.iter().map(|e| e.render(p.as_deref())).collect();
+                match e.len() {
+                    1 => rendered.join(""),
+                    n => format!("{}\n\n{} errors were found. ", rendered.join("\n\n"), n),
+                }
+            },
+            CliError::Link(e) => e.iter()
+                .map(|(p, e)| format!("error: {}\n --> {}", e.describe(false), p))
+                .collect::<Vec<String>>()
+                .join("\n\n"),
+            CliError::Emulation(e, s) => match e.as_ref() {
+                BabyErrors::IterationExceeded(e) => format!("{}\n{}", core_dump(&e.end_model, s), e.get_descriptor()),
+                e => e.get_descriptor(),
+            },
+            CliError::Image(p, r) => format!("The store image `{}` is invalid, {}", p, r),
+            CliError::Symbols(p, r) => format!("The symbol file `{}` is invalid, {}", p, r),
+            CliError::Conversion(p, i) => i.iter()
+                .map(|i| i.render(Some(p)))
+                .collect::<Vec<String>>()
+                .join("\n\n"),
+            CliError::Compile(p, e) => e.iter()
+                .map(|(s, e)| s.render(&format!("error: {}", e.describe().trim_end()), Some(p)))
+                .collect::<Vec<String>>()
+                .join("\n\n"),
+            CliError::Unformatted(p) => format!("The file `{}` is not formatted, run `baby fmt {} --write`. ", p, p),
+        }
+    }
+
+    /// Returns the process exit code corresponding to the error.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            CliError::Usage(_) => EXIT_USAGE,
+            CliError::Io(_, _) => EXIT_IO,
+            CliError::Assembly(_, e) if e.iter()
+                .any(|e| !matches!(e, AssemblyError::LinkerError(_, _) | AssemblyError::Warning(_, _))) => EXIT_PARSE,
+            CliError::Assembly(_, _) => EXIT_LINK,
+            CliError::Conversion(_, _) | CliError::Compile(_, _) => EXIT_PARSE,
+            CliError::Link(_) => EXIT_LINK,
+            CliError::Emulation(_, _) => EXIT_ITERATIONS,
+            CliError::Image(_, _) | CliError::Symbols(_, _) => EXIT_IMAGE,
+            CliError::Unformatted(_) => EXIT_UNFORMATTED,
+        }
+    }
+}
+
+/// Generates the core dump of a model, labelled with any symbols.
+pub fn core_dump(model: &BabyModel, symbols: &HashMap<String, WORD>) -> String {
+    model.core_dump_with_symbols(Some(symbols).filter(|s| !s.is_empty()))
+}
@@ -0,0 +1,326 @@
+//! # GDB Stub
+//!
+//! A GDB remote serial protocol stub, allowing standard debuggers and
+//! front ends to attach to the emulator with `baby gdb <file>`, either
+//! listening on a TCP socket (`target remote localhost:1234`) or talking
+//! over stdio (`target remote | baby gdb <file> --stdio`).
+//!
+//! The main type here is [GdbStub][crate::gdb::GdbStub] which holds a
+//! [BabyModel] and answers each packet through
+//! [GdbStub::handle_packet][crate::gdb::GdbStub::handle_packet], the
+//! transport is left to [server][crate::gdb::server].
+//!
+//! ## Target
+//!
+//! | Register | Number | Contents                                   |
+//! |----------|--------|--------------------------------------------|
+//! | `acc`    | 0      | The accumulator                            |
+//! | `ci`     | 1      | The control instruction (program counter)  |
+//!
+//! Both registers are one word wide. Memory is the main store, byte
+//! addressed with each word stored little endian, so store line `n` is
+//! at address `n * 4` for the default `i32` build, `ci` holds the byte
+//! address of the current store line so it matches breakpoint addresses.
+//!
+//! ## Supported packets
+//!
+//! `?`, `g`, `G`, `p`, `P`, `m`, `M`, `s`, `c`, `Z0`, `z0`, `k`, `D`,
+//! `qSupported`, `qAttached`, `qXfer:features:read`, `QStartNoAckMode`
+//! and `H`, anything else gets the empty "unsupported" reply.
+//!
+
+use std::collections::BTreeSet;
+use baby_emulator::core::{BabyModel, MEMORY_WORDS, WORD};
+use packet::{to_hex, from_hex};
+
+
+/// Contains framing for remote serial protocol packets.
+pub mod packet;
+/// Contains the TCP and stdio servers.
+pub mod server;
+#[cfg(test)]
+mod tests;
+
+
+/// The size of a word in target memory in bytes.
+pub const WORD_BYTES: usize = std::mem::size_of::<WORD>();
+/// The number of registers exposed to the debugger.
+pub const REGISTERS: usize = 2;
+/// The default maximum number of instructions a continue will execute
+/// before reporting back to the debugger.
+pub const DEFAULT_MAX_STEPS: usize = 1_000_000;
+/// The number of instructions executed between checks for an interrupt.
+pub const INTERRUPT_CHECK: usize = 1000;
+
+/// Stop reply for a single step or step limit (SIGTRAP).
+const STOP_TRAP: &str = "S05";
+/// Stop reply for a software breakpoint (SIGTRAP).
+const STOP_BREAKPOINT: &str = "T05swbreak:;";
+/// Stop reply for an interrupt from the debugger (SIGINT).
+const STOP_INTERRUPT: &str = "S02";
+/// Reply for a malformed packet.
+const ERR_MALFORMED: &str = "E01";
+/// Reply for an address outside the main store.
+const ERR_ADDRESS: &str = "E02";
+
+/// What the server should do after handling a packet.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Reply {
+    /// Send a packet containing the data.
+    Packet(String),
+    /// Send a packet containing the data, then close the connection.
+    Close(String),
+    /// Close the connection without replying.
+    Kill,
+}
+
+/// The state of a debugging session.
+pub struct GdbStub {
+    /// The model being debugged.
+    pub model: BabyModel,
+    /// The store lines with a software breakpoint set.
+    pub breakpoints: BTreeSet<usize>,
+    /// The maximum number of instructions a continue will execute.
+    pub max_steps: usize,
+    /// If packets should be acknowledged, turned off by `QStartNoAckMode`.
+    pub ack: bool,
+}
+
+impl GdbStub {
+
+    /// Creates a new stub with a program loaded.
+    pub fn new(main_store: [WORD; MEMORY_WORDS]) -> GdbStub {
+        GdbStub {
+            model: BabyModel::new_with_program(main_store),
+            breakpoints: BTreeSet::new(),
+            max_steps: DEFAULT_MAX_STEPS,
+            ack: true,
+        }
+    }
+
+    /// Handles a packet from the debugger, returning the reply.
+    ///
+    /// # Parameters
+    /// * `packet` - The unescaped packet data.
+    /// * `interrupted` - Polled whilst continuing, returns true if the
+    ///   debugger has asked to interrupt the program.
+    ///
+    pub fn handle_packet(&mut self, packet: &str, interrupted: &mut dyn FnMut() -> bool) -> Reply {
+        let reply = match packet {
+            "?" => STOP_TRAP.to_owned(),
+            "g" => self.read_registers(),
+            "k" => return Reply::Kill,
+            p if p.starts_with('D') => return Reply::Close("OK".to_owned()),
+            p if p.starts_with('G') => self.write_registers(&p[1..]),
+            p if p.starts_with('p') => self.read_register(&p[1..]),
+            p if p.starts_with('P') => self.write_register(&p[1..]),
+            p if p.starts_with('m') => self.read_memory(&p[1..]),
+            p if p.starts_with('M') => self.write_memory(&p[1..]),
+            p if p.starts_with('s') => self.step(&p[1..]),
+            p if p.starts_with('c') => self.resume(&p[1..], interrupted),
+            p if p.starts_with("Z0,") => self.set_breakpoint(&p[3..], true),
+            p if p.starts_with("z0,") => self.set_breakpoint(&p[3..], false),
+            p if p.starts_with('H') => "OK".to_owned(),
+            p if p.starts_with("qSupported") =>
+                "PacketSize=1000;qXfer:features:read+;QStartNoAckMode+;swbreak+".to_owned(),
+            "qAttached" => "1".to_owned(),
+            p if p.starts_with("qXfer:features:read:target.xml:") =>
+                Self::read_target_xml(&p["qXfer:features:read:target.xml:".len()..]),
+            "QStartNoAckMode" => { self.ack = false; "OK".to_owned() },
+            _ => String::new(),
+        };
+        Reply::Packet(reply)
+    }
+
+    /// Gets the value of a register by number.
+    pub fn register(&self, number: usize) -> Option<WORD> {
+        match number {
+            0 => Some(self.model.accumulator),
+            1 => Some((self.model.instruction_address as usize * WORD_BYTES) as WORD),
+            _ => None
+        }
+    }
+
+    /// Sets a register by number, setting CI also loads the instruction
+    /// at that address, as happens after a jump.
+    pub fn set_register(&mut self, number: usize, value: WORD) -> bool {
+        match number {
+            0 => self.model.accumulator = value,
+            1 => {
+                let addr = (value as usize / WORD_BYTES) & 0x1F;
+                self.model.instruction_address = addr as u16;
+                self.model.instruction = self.model.main_store[addr] as u16;
+            },
+            _ => return false
+        }
+        true
+    }
+
+    /// Handles `g`, all registers as hex.
+    fn read_registers(&self) -> String {
+        (0..REGISTERS).filter_map(|n| self.register(n))
+            .map(|v| to_hex(&v.to_le_bytes()))
+            .collect()
+    }
+
+    /// Handles `G<hex>`, setting all registers.
+    fn write_registers(&mut self, hex: &str) -> String {
+        let bytes = match from_hex(hex) {
+            Some(b) if b.len() == REGISTERS * WORD_BYTES => b,
+            _ => return ERR_MALFORMED.to_owned()
+        };
+        for (n, chunk) in bytes.chunks(WORD_BYTES).enumerate() {
+            self.set_register(n, Self::word_from_bytes(chunk));
+        }
+        "OK".to_owned()
+    }
+
+    /// Handles `p<n>`, a single register as hex.
+    fn read_register(&self, args: &str) -> String {
+        match usize::from_str_radix(args, 16).ok().and_then(|n| self.register(n)) {
+            Some(v) => to_hex(&v.to_le_bytes()),
+            None => ERR_MALFORMED.to_owned()
+        }
+    }
+
+    /// Handles `P<n>=<hex>`, setting a single register.
+    fn write_register(&mut self, args: &str) -> String {
+        let (n, hex) = match args.split_once('=') {
+            Some(v) => v,
+            None => return ERR_MALFORMED.to_owned()
+        };
+        let n = usize::from_str_radix(n, 16).ok();
+        let bytes = from_hex(hex).filter(|b| b.len() == WORD_BYTES);
+        match (n, bytes) {
+            (Some(n), Some(b)) if self.set_register(n, Self::word_from_bytes(&b)) => "OK".to_owned(),
+            _ => ERR_MALFORMED.to_owned()
+        }
+    }
+
+    /// Handles `m<addr>,<len>`, reading bytes from the main store.
+    fn read_memory(&self, args: &str) -> String {
+        let (addr, len) = match Self::parse_range(args) {
+            Some(v) => v,
+            None => return ERR_MALFORMED.to_owned()
+        };
+        let store: Vec<u8> = self.model.main_store.iter().flat_map(|w| w.to_le_bytes()).collect();
+        match addr.checked_add(len).and_then(|end| store.get(addr..end)) {
+            Some(b) => to_hex(b),
+            None => ERR_ADDRESS.to_owned()
+        }
+    }
+
+    /// Handles `M<addr>,<len>:<hex>`, writing bytes to the main store.
+    fn write_memory(&mut self, args: &str) -> String {
+        let (range, hex) = match args.split_once(':') {
+            Some(v) => v,
+            None => return ERR_MALFORMED.to_owned()
+        };
+        let (addr, len, bytes) = match (Self::parse_range(range), from_hex(hex)) {
+            (Some((a, l)), Some(b)) if b.len() == l => (a, l, b),
+            _ => return ERR_MALFORMED.to_owned()
+        };
+        let mut store: Vec<u8> = self.model.main_store.iter().flat_map(|w| w.to_le_bytes()).collect();
+        match addr.checked_add(len).and_then(|end| store.get_mut(addr..end)) {
+            Some(s) => s.copy_from_slice(&bytes),
+            None => return ERR_ADDRESS.to_owned()
+        }
+        for (i, chunk) in store.chunks(WORD_BYTES).enumerate() {
+            self.model.main_store[i] = Self::word_from_bytes(chunk);
+        }
+        let ci = self.model.instruction_address as usize;
+        self.model.instruction = self.model.main_store[ci] as u16;
+        "OK".to_owned()
+    }
+
+    /// Handles `s[addr]`, executing a single instruction.
+    fn step(&mut self, args: &str) -> String {
+        if !self.resume_at(args) { return ERR_MALFORMED.to_owned(); }
+        self.execute();
+        STOP_TRAP.to_owned()
+    }
+
+    /// Handles `c[addr]`, executing until a breakpoint, a stop instruction,
+    /// an interrupt or the step limit.
+    fn resume(&mut self, args: &str, interrupted: &mut dyn FnMut() -> bool) -> String {
+        if !self.resume_at(args) { return ERR_MALFORMED.to_owned(); }
+        for i in 0..self.max_steps {
+            if i % INTERRUPT_CHECK == INTERRUPT_CHECK - 1 && interrupted() {
+                return STOP_INTERRUPT.to_owned();
+            }
+            if !self.execute() { return STOP_TRAP.to_owned(); }
+            if self.breakpoints.contains(&(self.model.instruction_address as usize)) {
+                return STOP_BREAKPOINT.to_owned();
+            }
+        }
+        STOP_TRAP.to_owned()
+    }
+
+    /// Handles `Z0,<addr>,<kind>` and `z0,<addr>,<kind>`.
+    fn set_breakpoint(&mut self, args: &str, set: bool) -> String {
+        let addr = match args.split(',').next().and_then(|a| usize::from_str_radix(a, 16).ok()) {
+            Some(a) if a / WORD_BYTES < MEMORY_WORDS => a / WORD_BYTES,
+            _ => return ERR_ADDRESS.to_owned()
+        };
+        if set { self.breakpoints.insert(addr); }
+        else { self.breakpoints.remove(&addr); }
+        "OK".to_owned()
+    }
+
+    /// Sets CI if an address was given to `s` or `c`.
+    fn resume_at(&mut self, args: &str) -> bool {
+        if args.is_empty() { return true; }
+        match usize::from_str_radix(args, 16) {
+            Ok(a) => self.set_register(1, a as WORD),
+            Err(_) => false
+        }
+    }
+
+    /// Executes a single instruction, returns false if it was a stop
+    /// instruction, in which case the model is left at the stop.
+    fn execute(&mut self) -> bool {
+        match self.model.execute() {
+            Ok(m) => { self.model = m; true },
+            Err(_) => false,
+        }
+    }
+
+    /// Handles `qXfer:features:read:target.xml:<offset>,<length>`.
+    fn read_target_xml(args: &str) -> String {
+        let xml = target_xml();
+        let (offset, len) = match Self::parse_range(args) {
+            Some(v) => v,
+            None => return ERR_MALFORMED.to_owned()
+        };
+        let chunk: String = xml.chars().skip(offset).take(len).collect();
+        let more = offset + chunk.len() < xml.len();
+        format!("{}{}", if more { "m" } else { "l" }, chunk)
+    }
+
+    /// Parses `<addr>,<len>` in hex.
+    fn parse_range(args: &str) -> Option<(usize, usize)> {
+        let (addr, len) = args.split_once(',')?;
+        Some((usize::from_str_radix(addr, 16).ok()?, usize::from_str_radix(len, 16).ok()?))
+    }
+
+    /// Converts little endian bytes to a word.
+    fn word_from_bytes(bytes: &[u8]) -> WORD {
+        let mut word = [0u8; WORD_BYTES];
+        word.copy_from_slice(bytes);
+        WORD::from_le_bytes(word)
+    }
+}
+
+/// The target description sent to the debugger, naming the registers.
+pub fn target_xml() -> String {
+    format!(concat!(
+        "<?xml version=\"1.0\"?>",
+        "<!DOCTYPE target SYSTEM \"gdb-target.dtd\">",
+        "<target version=\"1.0\">",
+        "<feature name=\"org.ssem.baby\">",
+        "<reg name=\"acc\" bitsize=\"{bits}\" type=\"int\" regnum=\"0\"/>",
+        "<reg name=\"ci\" bitsize=\"{bits}\" type=\"code_ptr\" regnum=\"1\"/>",
+        "</feature>",
+        "</target>"
+    ), bits = WORD::BITS)
+}
@@ -0,0 +1,112 @@
+//! # Packets
+//!
+//! Framing for the GDB remote serial protocol, packets are sent as
+//! `$<data>#<checksum>` where the checksum is the sum of the data bytes
+//! modulo 256 as two hex digits, and acknowledged with a `+` or `-`.
+//!
+
+use std::io::{self, Read};
+
+
+/// Something received from the debugger.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Incoming {
+    /// A packet with a valid checksum, holds the unescaped data.
+    Packet(String),
+    /// A packet with an invalid checksum.
+    Corrupt,
+    /// A `+` acknowledging the last packet sent.
+    Ack,
+    /// A `-` asking for the last packet sent to be resent.
+    Nack,
+    /// A `ctrl-c` asking for a running program to be interrupted.
+    Interrupt,
+}
+
+/// Computes the checksum of some packet data.
+pub fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |acc, b| acc.wrapping_add(*b))
+}
+
+/// Frames packet data, escaping any bytes with special meaning.
+pub fn encode(data: &str) -> String {
+    let mut escaped: Vec<u8> = vec![];
+    for b in data.bytes() {
+        match b {
+            b'$' | b'#' | b'}' | b'*' => { escaped.push(b'}'); escaped.push(b ^ 0x20); },
+            b => escaped.push(b),
+        }
+    }
+    let sum = checksum(&escaped);
+    format!("${}#{:02x}", String::from_utf8_lossy(&escaped), sum)
+}
+
+/// Reads the next thing sent by the debugger from a stream.
+///
+/// Returns `Ok(None)` if the stream has closed.
+pub fn read_incoming<R: Read>(stream: &mut R) -> io::Result<Option<Incoming>> {
+    loop {
+        let b = match read_byte(stream)? {
+            Some(b) => b,
+            None => return Ok(None)
+        };
+        match b {
+            b'+' => return Ok(Some(Incoming::Ack)),
+            b'-' => return Ok(Some(Incoming::Nack)),
+            0x03 => return Ok(Some(Incoming::Interrupt)),
+            b'$' => return read_packet(stream).map(Some),
+            _ => continue,
+        }
+    }
+}
+
+/// Reads the remainder of a packet after the leading `$`.
+fn read_packet<R: Read>(stream: &mut R) -> io::Result<Incoming> {
+    let mut raw: Vec<u8> = vec![];
+    loop {
+        match read_byte(stream)? {
+            Some(b'#') => break,
+            Some(b) => raw.push(b),
+            None => return Err(io::ErrorKind::UnexpectedEof.into())
+        }
+    }
+    let mut sum = [0u8; 2];
+    stream.read_exact(&mut sum)?;
+    let expected = u8::from_str_radix(&String::from_utf8_lossy(&sum), 16).ok();
+    if expected != Some(checksum(&raw)) {
+        return Ok(Incoming::Corrupt);
+    }
+    let mut data: Vec<u8> = vec![];
+    let mut iter = raw.into_iter();
+    while let Some(b) = iter.next() {
+        match b {
+            b'}' => data.extend(iter.next().map(|e| e ^ 0x20)),
+            b => data.push(b),
+        }
+    }
+    Ok(Incoming::Packet(String::from_utf8_lossy(&data).into_owned()))
+}
+
+/// Reads a single byte, returning `None` if the stream has closed.
+fn read_byte<R: Read>(stream: &mut R) -> io::Result<Option<u8>> {
+    let mut b = [0u8; 1];
+    match stream.read(&mut b)? {
+        0 => Ok(None),
+        _ => Ok(Some(b[0])),
+    }
+}
+
+/// Encodes bytes as a string of hex digit pairs.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Decodes a string of hex digit pairs to bytes.
+///
+/// Returns `None` if the string is not valid hex.
+pub fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) { return None; }
+    (0..hex.len()).step_by(2)
+        .map(|i| hex.get(i..i + 2).and_then(|h| u8::from_str_radix(h, 16).ok()))
+        .collect()
+}
@@ -0,0 +1,105 @@
+//! # Server
+//!
+//! Serves a [GdbStub] over a connection, reading packets, acknowledging
+//! them, and writing back each reply, until the debugger detaches, kills
+//! the session or closes the connection.
+//!
+
+use std::io::{self, Read, Write};
+use std::net::TcpListener;
+use std::net::TcpStream;
+use super::GdbStub;
+use super::Reply;
+use super::packet::{encode, read_incoming, Incoming};
+
+
+/// A connection to a debugger.
+pub trait Connection: Read + Write {
+    /// Returns true if the debugger has sent an interrupt whilst the
+    /// program is running, consuming it, without blocking.
+    fn interrupt_pending(&mut self) -> bool {
+        false
+    }
+}
+
+impl Connection for TcpStream {
+    fn interrupt_pending(&mut self) -> bool {
+        let mut b = [0u8; 1];
+        if self.set_nonblocking(true).is_err() { return false; }
+        let pending = matches!(self.peek(&mut b), Ok(1)) && b[0] == 0x03;
+        let _ = self.set_nonblocking(false);
+        if pending { let _ = self.read_exact(&mut b); }
+        pending
+    }
+}
+
+/// A connection over the process's stdin and stdout.
+pub struct Stdio {
+    stdin: io::Stdin,
+    stdout: io::Stdout,
+}
+
+impl Stdio {
+    /// Creates a connection over stdin and stdout.
+    pub fn new() -> Stdio {
+        Stdio { stdin: io::stdin(), stdout: io::stdout() }
+    }
+}
+
+impl Default for Stdio {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Read for Stdio {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stdin.read(buf)
+    }
+}
+
+impl Write for Stdio {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stdout.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stdout.flush()
+    }
+}
+
+impl Connection for Stdio {}
+
+/// Serves a stub over a connection until the session ends.
+pub fn serve<C: Connection>(stub: &mut GdbStub, conn: &mut C) -> io::Result<()> {
+    let mut last = String::new();
+    loop {
+        let packet = match read_incoming(conn)? {
+            Some(Incoming::Packet(p)) => p,
+            Some(Incoming::Corrupt) => { conn.write_all(b"-")?; conn.flush()?; continue; },
+            Some(Incoming::Nack) => { conn.write_all(last.as_bytes())?; conn.flush()?; continue; },
+            Some(Incoming::Ack) | Some(Incoming::Interrupt) => continue,
+            None => return Ok(()),
+        };
+        if stub.ack { conn.write_all(b"+")?; }
+        let reply = stub.handle_packet(&packet, &mut || conn.interrupt_pending());
+        let (data, close) = match reply {
+            Reply::Packet(d) => (d, false),
+            Reply::Close(d) => (d, true),
+            Reply::Kill => { conn.flush()?; return Ok(()); },
+        };
+        last = encode(&data);
+        conn.write_all(last.as_bytes())?;
+        conn.flush()?;
+        if close { return Ok(()); }
+    }
+}
+
+/// Listens on a TCP address, serving the first debugger to connect.
+pub fn serve_tcp(stub: &mut GdbStub, addr: &str) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    eprintln!("Waiting for a debugger on {}. ", listener.local_addr()?);
+    let (mut stream, _) = listener.accept()?;
+    stream.set_nodelay(true)?;
+    serve(stub, &mut stream)
+}
@@ -0,0 +1,161 @@
+use super::*;
+use std::io::{Cursor, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use packet::{encode, read_incoming, checksum, Incoming};
+use crate::image::assemble_store;
+use baby_emulator::core::instructions::BabyInstruction;
+
+
+const ASM: &str =
+"
+ldn $value
+sub $one
+sub $one
+stp
+:value
+abs 0d-3
+:one
+abs 0d1
+";
+
+fn get_stub() -> GdbStub {
+    GdbStub::new(assemble_store(ASM, false).ok().unwrap())
+}
+
+fn send(stub: &mut GdbStub, packet: &str) -> String {
+    match stub.handle_packet(packet, &mut || false) {
+        Reply::Packet(v) | Reply::Close(v) => v,
+        Reply::Kill => String::new(),
+    }
+}
+
+fn word_hex(v: WORD) -> String {
+    to_hex(&v.to_le_bytes())
+}
+
+/// A minimal scripted RSP client.
+struct Client {
+    stream: TcpStream,
+}
+
+impl Client {
+    fn request(&mut self, packet: &str) -> String {
+        self.stream.write_all(encode(packet).as_bytes()).unwrap();
+        let mut ack = [0u8; 1];
+        self.stream.read_exact(&mut ack).unwrap();
+        assert_eq!(ack[0], b'+');
+        let reply = match read_incoming(&mut self.stream).unwrap() {
+            Some(Incoming::Packet(p)) => p,
+            r => panic!("Unexpected reply {:?}. ", r)
+        };
+        self.stream.write_all(b"+").unwrap();
+        reply
+    }
+}
+
+#[test]
+fn test_encode_decode() {
+    let encoded = encode("m0,4");
+    assert_eq!(encoded, format!("$m0,4#{:02x}", checksum(b"m0,4")));
+    let mut cursor = Cursor::new(format!("+{}", encode("a}b#c")).into_bytes());
+    assert_eq!(read_incoming(&mut cursor).unwrap(), Some(Incoming::Ack));
+    assert_eq!(read_incoming(&mut cursor).unwrap(), Some(Incoming::Packet("a}b#c".to_owned())));
+    assert_eq!(read_incoming(&mut cursor).unwrap(), None);
+    let mut corrupt = Cursor::new(b"$g#00".to_vec());
+    assert_eq!(read_incoming(&mut corrupt).unwrap(), Some(Incoming::Corrupt));
+}
+
+#[test]
+fn test_hex() {
+    assert_eq!(to_hex(&[0x01, 0xab]), "01ab");
+    assert_eq!(from_hex("01ab"), Some(vec![0x01, 0xab]));
+    assert_eq!(from_hex("0g"), None);
+    assert_eq!(from_hex("012"), None);
+}
+
+#[test]
+fn test_registers() {
+    let mut stub = get_stub();
+    assert_eq!(send(&mut stub, "g"), format!("{}{}", word_hex(0), word_hex(0)));
+    assert_eq!(send(&mut stub, &format!("P0={}", word_hex(-7))), "OK");
+    assert_eq!(send(&mut stub, "p0"), word_hex(-7));
+    assert_eq!(send(&mut stub, &format!("P1={}", word_hex(3 * WORD_BYTES as WORD))), "OK");
+    assert_eq!(stub.model.instruction_address, 3);
+    assert_eq!(stub.model.instruction, BabyInstruction::Stop.to_number() as u16);
+    assert_eq!(send(&mut stub, &format!("G{}{}", word_hex(2), word_hex(0))), "OK");
+    assert_eq!(stub.model.accumulator, 2);
+    assert_eq!(stub.model.instruction_address, 0);
+    assert_eq!(send(&mut stub, "p5"), ERR_MALFORMED);
+}
+
+#[test]
+fn test_memory() {
+    let mut stub = get_stub();
+    let addr = 4 * WORD_BYTES;
+    assert_eq!(send(&mut stub, &format!("m{:x},{:x}", addr, WORD_BYTES)), word_hex(-3));
+    assert_eq!(send(&mut stub, &format!("M{:x},{:x}:{}", addr, WORD_BYTES, word_hex(-9))), "OK");
+    assert_eq!(stub.model.main_store[4], -9);
+    assert_eq!(send(&mut stub, &format!("m{:x},1", MEMORY_WORDS * WORD_BYTES)), ERR_ADDRESS);
+    assert_eq!(send(&mut stub, "m0,1,"), ERR_MALFORMED);
+    assert_eq!(send(&mut stub, "mffffffffffffffff,1"), ERR_ADDRESS);
+    assert_eq!(send(&mut stub, "Mffffffffffffffff,1:00"), ERR_ADDRESS);
+}
+
+#[test]
+fn test_step_and_continue() {
+    let mut stub = get_stub();
+    assert_eq!(send(&mut stub, "s"), STOP_TRAP);
+    assert_eq!(stub.model.accumulator, 3);
+    assert_eq!(send(&mut stub, &format!("Z0,{:x},4", 2 * WORD_BYTES)), "OK");
+    assert_eq!(send(&mut stub, "c"), STOP_BREAKPOINT);
+    assert_eq!(stub.model.instruction_address, 2);
+    assert_eq!(send(&mut stub, &format!("z0,{:x},4", 2 * WORD_BYTES)), "OK");
+    assert_eq!(send(&mut stub, "c"), STOP_TRAP);
+    assert_eq!(stub.model.instruction_address, 3);
+    assert_eq!(stub.model.accumulator, 1);
+}
+
+#[test]
+fn test_interrupt() {
+    let mut stub = GdbStub::new([0; MEMORY_WORDS]);
+    let reply = stub.handle_packet("c", &mut || true);
+    assert_eq!(reply, Reply::Packet(STOP_INTERRUPT.to_owned()));
+    stub.max_steps = 10;
+    assert_eq!(send(&mut stub, "c"), STOP_TRAP);
+}
+
+#[test]
+fn test_queries() {
+    let mut stub = get_stub();
+    assert!(send(&mut stub, "qSupported:swbreak+").contains("qXfer:features:read+"));
+    let xml = send(&mut stub, "qXfer:features:read:target.xml:0,1000");
+    assert!(xml.starts_with('l'));
+    assert_eq!(&xml[1..], target_xml());
+    assert!(send(&mut stub, "qXfer:features:read:target.xml:0,10").starts_with('m'));
+    assert_eq!(send(&mut stub, "vMustReplyEmpty"), "");
+    assert_eq!(stub.handle_packet("k", &mut || false), Reply::Kill);
+}
+
+#[test]
+fn test_scripted_client() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut stub = get_stub();
+        server::serve(&mut stub, &mut stream).unwrap();
+        stub
+    });
+    let mut client = Client { stream: TcpStream::connect(addr).unwrap() };
+    assert!(client.request("qSupported").contains("swbreak+"));
+    assert_eq!(client.request("?"), STOP_TRAP);
+    assert_eq!(client.request(&format!("Z0,{:x},4", 3 * WORD_BYTES)), "OK");
+    assert_eq!(client.request("c"), STOP_BREAKPOINT);
+    assert_eq!(client.request("p1"), word_hex(3 * WORD_BYTES as WORD));
+    assert_eq!(client.request("p0"), word_hex(1));
+    assert_eq!(client.request(&format!("M{:x},{:x}:{}", 5 * WORD_BYTES, WORD_BYTES, word_hex(2))), "OK");
+    assert_eq!(client.request("D"), "OK");
+    let stub = server.join().unwrap();
+    assert_eq!(stub.model.main_store[5], 2);
+}
@@ -0,0 +1,197 @@
+//! # Store Images
+//!
+//! Reading and writing of store images, these are just the words of the
+//! Baby's main store written in order as little endian bytes, so an image
+//! for the default `i32` build is always 128 bytes.
+//!
+//! Any of the subcommands taking a program can also be given an asm
+//! source file (any file ending in `.asm`), which will be assembled first.
+//!
+//! Symbol files hold the tag names and values of an assembled program, see
+//! [symbols][baby_emulator::assembler::linker::symbols], so store images
+//! can still be shown in terms of the asm source's names.
+//!
+
+use std::fs;
+use std::path::Path;
+use std::collections::HashMap;
+use baby_emulator::assembler::{assemble_all, assemble_with, AssemblyOptions, Assembled, linker::LinkerData};
+use baby_emulator::assembler::{assemble_object, listing::Listing, warnings::AssemblyWarning};
+use baby_emulator::assembler::linker::{errors::{LinkingError, LinkerError}, object::{ObjectFile, link_objects}};
+use baby_emulator::assembler::linker::symbols::{to_symbol_file, parse_symbol_file};
+use baby_emulator::core::{instructions::BabyInstruction, MEMORY_WORDS, WORD};
+use crate::errors::CliError;
+
+
+/// The size of a single word in a store image in bytes.
+pub const WORD_BYTES: usize = std::mem::size_of::<WORD>();
+/// The size of a full store image in bytes.
+pub const IMAGE_BYTES: usize = MEMORY_WORDS * WORD_BYTES;
+
+/// A loaded main store along with its tag names and values.
+pub type Program = ([WORD; MEMORY_WORDS], HashMap<String, WORD>);
+
+/// Converts a main store into store image bytes.
+pub fn to_bytes(store: &[WORD; MEMORY_WORDS]) -> Vec<u8> {
+    store.iter().flat_map(|w| w.to_le_bytes()).collect()
+}
+
+/// Converts store image bytes into a main store.
+///
+/// Returns a string describing the problem if the image is not
+/// exactly [IMAGE_BYTES] long.
+pub fn from_bytes(bytes: &[u8]) -> Result<[WORD; MEMORY_WORDS], String> {
+    if bytes.len() != IMAGE_BYTES {
+        return Err(format!("expected {} bytes, found {}. ", IMAGE_BYTES, bytes.len()));
+    }
+    Ok(core::array::from_fn(|i| {
+        let mut word = [0u8; WORD_BYTES];
+        word.copy_from_slice(&bytes[i * WORD_BYTES..(i + 1) * WORD_BYTES]);
+        WORD::from_le_bytes(word)
+    }))
+}
+
+/// Returns true if the path is an asm source file.
+pub fn is_asm(path: &str) -> bool {
+    Path::new(path).extension().is_some_and(|e| e.eq_ignore_ascii_case("asm"))
+}
+
+/// Reads a file into a string.
+pub fn read_source(path: &str) -> Result<String, CliError> {
+    fs::read_to_string(path).map_err(|e| CliError::Io(path.to_owned(), e))
+}
+
+/// Assembles an asm string into a main store.
+#[cfg(test)]
+pub fn assemble_store(asm: &str, og_notation: bool) -> Result<[WORD; MEMORY_WORDS], CliError> {
+    match assemble_all(asm, og_notation) {
+        Ok(LinkerData(v, _)) => Ok(BabyInstruction::to_numbers(v)),
+        Err(e) => Err(CliError::Assembly(None, e))
+    }
+}
+
+/// Reads and assembles an asm source file into a main store, also
+/// returning the tag names and values, reporting every error found
+/// in the source if it fails.
+///
+/// # Parameters
+/// * `path` - The asm source file.
+/// * `og_notation` - If true, will use original notation.
+///
+pub fn assemble_file(path: &str, og_notation: bool) -> Result<Program, CliError> {
+    match assemble_all(&read_source(path)?, og_notation) {
+        Ok(LinkerData(v, tags)) => Ok((BabyInstruction::to_numbers(v), tags)),
+        Err(e) => Err(CliError::Assembly(Some(path.to_owned()), e))
+    }
+}
+
+/// Reads and assembles an asm source file into a main store as per
+/// [assemble_file], also returning the tag names and values, and the
+/// [Listing] and any warnings if asked for in `options`.
+///
+/// # Parameters
+/// * `path` - The asm source file.
+/// * `og_notation` - If true, will use original notation.
+/// * `options` - What to make on top of the assembled program.
+///
+pub fn assemble_file_checked(path: &str, og_notation: bool, options: AssemblyOptions) ->
+    Result<(Program, Option<Listing>, Vec<AssemblyWarning>), CliError> {
+    match assemble_with(&read_source(path)?, og_notation, &options) {
+        Ok(Assembled { data: LinkerData(v, tags), listing, warnings }) =>
+            Ok(((BabyInstruction::to_numbers(v), tags), listing, warnings)),
+        Err(e) => Err(CliError::Assembly(Some(path.to_owned()), e))
+    }
+}
+
+/// Loads an object file, either assembling it if it is an asm source
+/// file, or reading it as an object file.
+///
+/// # Parameters
+/// * `path` - The file to be loaded.
+/// * `og_notation` - If assembling, use original notation.
+///
+pub fn load_object(path: &str, og_notation: bool) -> Result<ObjectFile, CliError> {
+    let source = read_source(path)?;
+    if is_asm(path) {
+        return assemble_object(&source, og_notation).map_err(|e| CliError::Assembly(Some(path.to_owned()), e));
+    }
+    ObjectFile::parse(&source).map_err(|e| CliError::Link(vec![(path.to_owned(), LinkingError::ObjectError(e))]))
+}
+
+/// Loads and links object files in order into a main store, also
+/// returning the exported tags and the tags of the first object.
+///
+/// # Parameters
+/// * `paths` - The object or asm source files to be linked.
+/// * `og_notation` - If assembling, use original notation.
+///
+pub fn link_files(paths: &[String], og_notation: bool) -> Result<Program, CliError> {
+    let objects = paths.iter()
+        .map(|p| load_object(p, og_notation))
+        .collect::<Result<Vec<ObjectFile>, CliError>>()?;
+    match link_objects(&objects) {
+        Ok(LinkerData(v, tags)) => Ok((BabyInstruction::to_numbers(v), tags)),
+        Err(e) => Err(CliError::Link(e.into_iter().map(|(o, e)| (paths[o].clone(), e)).collect()))
+    }
+}
+
+/// Loads a main store from a file, either assembling it if it is an asm
+/// source file, or reading it as a store image.
+///
+/// # Parameters
+/// * `path` - The file to be loaded.
+/// * `og_notation` - If assembling, use original notation.
+///
+pub fn load_store(path: &str, og_notation: bool) -> Result<[WORD; MEMORY_WORDS], CliError> {
+    load_program(path, og_notation).map(|(store, _)| store)
+}
+
+/// Loads a main store from a file as per [load_store], also returning the
+/// tag names and values if it was assembled, store images have no tags.
+///
+/// # Parameters
+/// * `path` - The file to be loaded.
+/// * `og_notation` - If assembling, use original notation.
+///
+pub fn load_program(path: &str, og_notation: bool) -> Result<Program, CliError> {
+    if is_asm(path) {
+        return assemble_file(path, og_notation);
+    }
+    let bytes = fs::read(path).map_err(|e| CliError::Io(path.to_owned(), e))?;
+    match from_bytes(&bytes) {
+        Ok(store) => Ok((store, HashMap::new())),
+        Err(r) => Err(CliError::Image(path.to_owned(), r))
+    }
+}
+
+/// Loads a main store from a file as per [load_program], taking the tag
+/// names and values from a symbol file instead if one is given.
+///
+/// # Parameters
+/// * `path` - The file to be loaded.
+/// * `og_notation` - If assembling, use original notation.
+/// * `symbols` - The symbol file, if there is one.
+///
+pub fn load_program_symbols(path: &str, og_notation: bool, symbols: Option<&String>) -> Result<Program, CliError> {
+    let (store, tags) = load_program(path, og_notation)?;
+    match symbols {
+        Some(s) => Ok((store, load_symbols(s)?)),
+        None => Ok((store, tags)),
+    }
+}
+
+/// Reads a symbol file into tag names and values.
+pub fn load_symbols(path: &str) -> Result<HashMap<String, WORD>, CliError> {
+    parse_symbol_file(&read_source(path)?)
+        .map_err(|e| CliError::Symbols(path.to_owned(), e.describe(false)))
+}
+
+/// Writes tag names and values to a file as a symbol file.
+pub fn write_symbols(path: &str, symbols: &HashMap<String, WORD>) -> Result<(), CliError> {
+    fs::write(path, to_symbol_file(symbols)).map_err(|e| CliError::Io(path.to_owned(), e))
+}
+
+/// Writes a main store to a file as a store image.
+pub fn write_store(path: &str, store: &[WORD; MEMORY_WORDS]) -> Result<(), CliError> {
+    fs::write(path, to_bytes(store)).map_err(|e| CliError::Io(path.to_owned(), e))
+}
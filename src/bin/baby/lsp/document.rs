@@ -0,0 +1,249 @@
+//! # Document
+//!
+//! The analysis of a single open asm document, worked out once each time
+//! the text changes, holding the diagnostics, every occurrence of each tag
+//! or constant name, the value of each name, and the listing of the
+//! assembled program, so requests can be answered without assembling again.
+//!
+//! Positions here are character columns on 0 based lines, converting to
+//! and from the UTF-16 columns used by the protocol is left to the server.
+//!
+
+use std::collections::{HashMap, HashSet};
+use baby_emulator::core::WORD;
+use baby_emulator::assembler::{assemble_with, AssemblyOptions, Assembled};
+use baby_emulator::assembler::diagnostics::SourceSpan;
+use baby_emulator::assembler::listing::Listing;
+use baby_emulator::assembler::linker::{collect_constants, resolve_constants};
+use baby_emulator::assembler::parser::{parse_source_lines, LineType, Value};
+
+
+/// How serious a diagnostic is, numbered as in the protocol.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    /// The document does not assemble.
+    Error = 1,
+    /// The document assembles but is probably not what was meant.
+    Warning = 2,
+}
+
+/// A single error or warning found in the document.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    /// Where in the document it was found.
+    pub span: SourceSpan,
+    /// How serious it is.
+    pub severity: Severity,
+    /// A single line describing it.
+    pub message: String,
+}
+
+/// A single use of a tag or constant name in the document.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Occurrence {
+    /// The name, as the assembler sees it.
+    pub name: String,
+    /// Where the name is written, including any `:` or `$`.
+    pub span: SourceSpan,
+    /// True if this declares the name rather than referencing it.
+    pub declaration: bool,
+}
+
+/// What a name in the document refers to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SymbolKind {
+    /// A `:tag`, the value is an address.
+    Tag,
+    /// An `equ` constant.
+    Constant,
+}
+
+/// The analysis of an open asm document.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Document {
+    /// The full text of the document.
+    pub text: String,
+    /// Every error or warning, in order.
+    pub diagnostics: Vec<Diagnostic>,
+    /// Every use of a tag or constant name, in order.
+    pub occurrences: Vec<Occurrence>,
+    /// The kind of each declared name.
+    pub kinds: HashMap<String, SymbolKind>,
+    /// The value of each tag and constant, if the document assembles.
+    pub values: HashMap<String, WORD>,
+    /// The listing of the assembled program, if the document assembles.
+    pub listing: Option<Listing>,
+    /// The number of store words holding assembled values, if the document
+    /// assembles, gaps left by `.org` and words set aside by `.reserve` are
+    /// not counted.
+    pub words_used: Option<usize>,
+}
+
+impl Document {
+
+    /// Analyses the text of a document.
+    ///
+    /// # Parameters
+    /// * `text` - The full text of the document.
+    /// * `og_notation` - If true, the document uses original notation.
+    ///
+    pub fn new(text: &str, og_notation: bool) -> Document {
+        let lines: Vec<(SourceSpan, LineType)> = parse_source_lines(text, og_notation)
+            .into_iter().filter_map(Result::ok).collect();
+        let (occurrences, kinds) = find_occurrences(&lines);
+
+        let options = AssemblyOptions { listing: true, warnings: true, deny_warnings: false };
+        let (diagnostics, tags, listing) = match assemble_with(text, og_notation, &options) {
+            Err(errors) => (errors.iter().map(|e| Diagnostic {
+                span: e.span().clone(), severity: Severity::Error, message: e.message(),
+            }).collect(), None, None),
+            Ok(Assembled { data, listing, warnings }) => (warnings.into_iter().map(|w| Diagnostic {
+                message: w.warning.describe().split_whitespace().collect::<Vec<&str>>().join(" "),
+                span: w.span, severity: Severity::Warning,
+            }).collect(), Some(data.1), listing),
+        };
+        let values = match &tags {
+            Some(tags) => {
+                let parsed: Vec<LineType> = lines.iter().map(|(_, l)| l.clone()).collect();
+                resolve_constants(&collect_constants(&parsed), tags).0
+            },
+            None => HashMap::new(),
+        };
+        let reserved: HashSet<usize> = lines.iter()
+            .filter(|(_, l)| matches!(l, LineType::Reserve(_)))
+            .map(|(s, _)| s.line)
+            .collect();
+        let words_used = listing.as_ref().map(|l| l.lines.iter()
+            .filter(|l| !reserved.contains(&l.line))
+            .flat_map(|l| l.words.iter().map(|w| w.address))
+            .collect::<HashSet<usize>>()
+            .len());
+        Document { text: text.to_owned(), diagnostics, occurrences, kinds, values, listing, words_used }
+    }
+
+    /// Returns the occurrence of a name at a position, if there is one.
+    ///
+    /// # Parameters
+    /// * `line` - The line, starting at 0.
+    /// * `column` - The character column, starting at 0.
+    ///
+    pub fn occurrence_at(&self, line: usize, column: usize) -> Option<&Occurrence> {
+        self.occurrences.iter()
+            .find(|o| o.span.line == line + 1 && (o.span.start..=o.span.end).contains(&column))
+    }
+
+    /// Returns every occurrence of a name, optionally leaving out its declaration.
+    ///
+    /// # Parameters
+    /// * `name` - The tag or constant name.
+    /// * `declaration` - If true, the declaration is included.
+    ///
+    pub fn occurrences_of(&self, name: &str, declaration: bool) -> Vec<&Occurrence> {
+        self.occurrences.iter().filter(|o| o.name == name && (declaration || !o.declaration)).collect()
+    }
+
+    /// Returns markdown describing what is at a position, the value of the
+    /// name under the cursor, or otherwise the words the line assembled to.
+    ///
+    /// # Parameters
+    /// * `line` - The line, starting at 0.
+    /// * `column` - The character column, starting at 0.
+    /// * `og_notation` - If true, decoded words use original notation.
+    ///
+    pub fn hover(&self, line: usize, column: usize, og_notation: bool) -> Option<String> {
+        if let Some(o) = self.occurrence_at(line, column) {
+            let kind = match self.kinds.get(&o.name)? {
+                SymbolKind::Tag => "tag",
+                SymbolKind::Constant => "constant",
+            };
+            return Some(match self.values.get(&o.name) {
+                Some(v) => format!("{} `{}` = {} ({:#x})", kind, o.name, v, v),
+                None => format!("{} `{}`", kind, o.name),
+            });
+        }
+        let listing = self.listing.as_ref()?.lines.get(line)?;
+        if listing.words.is_empty() { return None; }
+        let hex_width = WORD::BITS as usize / 4;
+        let rows: Vec<String> = listing.words.iter()
+            .map(|w| format!("{:02}: {:#0width$x}  {}", w.address, w.word, w.decoded.to_asm_string(og_notation), width = hex_width + 2))
+            .collect();
+        Some(format!("```text\n{}\n```", rows.join("\n")))
+    }
+}
+
+/// Finds every declaration of and reference to a tag or constant name in
+/// parsed lines, returning them along with the kind of each declared name.
+///
+/// References to a macro's parameters in its body are left out, as are
+/// declarations of a name that has already been declared.
+fn find_occurrences(lines: &[(SourceSpan, LineType)]) -> (Vec<Occurrence>, HashMap<String, SymbolKind>) {
+    let mut occurrences = vec![];
+    let mut kinds = HashMap::new();
+    let mut parameters: HashSet<&str> = HashSet::new();
+    for (span, line) in lines {
+        let mut declare = |name: &str, prefix: &str, kind: SymbolKind| {
+            if kinds.contains_key(name) { return; }
+            kinds.insert(name.to_owned(), kind);
+            if let Some(span) = find_name(span, prefix, name).into_iter().next() {
+                occurrences.push(Occurrence { name: name.to_owned(), span, declaration: true });
+            }
+        };
+        let references: Vec<&str> = match line {
+            LineType::Tag(t) => { declare(t, ":", SymbolKind::Tag); vec![] },
+            LineType::Constant(n, v) => { declare(n, "", SymbolKind::Constant); v.tags() },
+            LineType::Macro(_, p) => { parameters = p.iter().map(String::as_str).collect(); vec![] },
+            LineType::EndMacro => { parameters.clear(); vec![] },
+            LineType::Instruction(i) => {
+                let operand = i.get_operand();
+                for name in operand.tags().into_iter().filter(|n| !parameters.contains(n)) {
+                    push_references(&mut occurrences, span, name);
+                }
+                vec![]
+            },
+            LineType::Absolute(v) | LineType::Origin(v) | LineType::Reserve(v) => v.tags(),
+            LineType::Fill(n, v) => n.tags().into_iter().chain(v.tags()).collect(),
+            LineType::Words(v) | LineType::MacroCall(_, v) => v.iter().flat_map(Value::tags).collect(),
+            LineType::Export(n) | LineType::Import(n) => {
+                for name in n {
+                    for span in find_name(span, "", name) {
+                        occurrences.push(Occurrence { name: name.clone(), span, declaration: false });
+                    }
+                }
+                vec![]
+            },
+        };
+        for name in references.into_iter().filter(|n| !parameters.contains(n)) {
+            push_references(&mut occurrences, span, name);
+        }
+    }
+    (occurrences, kinds)
+}
+
+/// Adds each `$name` reference on a line that has not already been added.
+fn push_references(occurrences: &mut Vec<Occurrence>, span: &SourceSpan, name: &str) {
+    for span in find_name(span, "$", name) {
+        if occurrences.iter().any(|o| o.span == span) { continue; }
+        occurrences.push(Occurrence { name: name.to_owned(), span, declaration: false });
+    }
+}
+
+/// Finds each whole word occurrence of a name with a prefix within a
+/// span, ignoring case, returning a span for each covering the prefix and name.
+fn find_name(span: &SourceSpan, prefix: &str, name: &str) -> Vec<SourceSpan> {
+    let chars: Vec<char> = span.text.chars().map(|c| c.to_ascii_lowercase()).collect();
+    let needle: Vec<char> = prefix.chars().chain(name.chars()).map(|c| c.to_ascii_lowercase()).collect();
+    let is_name = |c: Option<&char>| c.is_some_and(|c| c.is_alphanumeric() || *c == '_');
+    let mut res = vec![];
+    let mut i = span.start;
+    while i + needle.len() <= span.end {
+        let end = i + needle.len();
+        let whole = (!prefix.is_empty() || i == 0 || !is_name(chars.get(i - 1))) && !is_name(chars.get(end));
+        if chars[i..end] == needle[..] && whole {
+            res.push(SourceSpan { start: i, end, ..span.clone() });
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+    res
+}
@@ -0,0 +1,291 @@
+//! # JSON
+//!
+//! A minimal JSON value, parser and writer, just enough for the JSON-RPC
+//! messages of the language server protocol. Object keys keep the order
+//! they were inserted or read in, so written messages are predictable.
+//!
+
+use std::fmt;
+
+
+/// The deepest arrays and objects can nest, any deeper document is
+/// treated as malformed rather than overflowing the stack.
+pub const MAX_DEPTH: usize = 128;
+
+/// A JSON value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    /// `null`.
+    Null,
+    /// `true` or `false`.
+    Bool(bool),
+    /// Any number, integers are written without a fraction.
+    Number(f64),
+    /// A string.
+    String(String),
+    /// An array of values.
+    Array(Vec<Json>),
+    /// An object, each key and value in order.
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+
+    /// Creates an object from keys and values.
+    pub fn object(entries: Vec<(&str, Json)>) -> Json {
+        Json::Object(entries.into_iter().map(|(k, v)| (k.to_owned(), v)).collect())
+    }
+
+    /// Creates a string value.
+    pub fn str(value: &str) -> Json {
+        Json::String(value.to_owned())
+    }
+
+    /// Creates a number value from an integer.
+    pub fn int(value: usize) -> Json {
+        Json::Number(value as f64)
+    }
+
+    /// Gets the value of a key if this is an object with that key.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Gets a value by following a path of object keys.
+    pub fn path(&self, keys: &[&str]) -> Option<&Json> {
+        keys.iter().try_fold(self, |v, k| v.get(k))
+    }
+
+    /// Returns the string if this is a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the number as a [usize] if this is a whole, positive number.
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            Json::Number(n) if *n >= 0.0 && n.fract() == 0.0 => Some(*n as usize),
+            _ => None,
+        }
+    }
+
+    /// Returns the boolean if this is a boolean.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Returns the items if this is an array.
+    pub fn as_array(&self) -> Option<&Vec<Json>> {
+        match self {
+            Json::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Parses a JSON document, returning [None] if it is malformed or
+    /// nests deeper than [MAX_DEPTH].
+    ///
+    /// # Example
+    /// ```ignore
+    /// let value = Json::parse(r#"{"id": 1, "params": [true, null]}"#).unwrap();
+    /// assert_eq!(value.get("id"), Some(&Json::int(1)));
+    /// ```
+    ///
+    pub fn parse(text: &str) -> Option<Json> {
+        let mut parser = Parser { chars: text.chars().collect(), position: 0, depth: 0 };
+        let value = parser.value()?;
+        parser.whitespace();
+        if parser.position == parser.chars.len() { Some(value) } else { None }
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(s) => write_string(f, s),
+            Json::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 { write!(f, ",")?; }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            },
+            Json::Object(entries) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 { write!(f, ",")?; }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            },
+        }
+    }
+}
+
+/// Writes a string with quotes, escaping any characters that need it.
+fn write_string(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in value.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+/// Recursive descent parser over the characters of a JSON document.
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    depth: usize,
+}
+
+impl Parser {
+
+    /// Skips any whitespace.
+    fn whitespace(&mut self) {
+        while self.chars.get(self.position).is_some_and(|c| c.is_whitespace()) { self.position += 1; }
+    }
+
+    /// Consumes a literal word such as `true` if it is next.
+    fn literal(&mut self, word: &str) -> bool {
+        let end = self.position + word.chars().count();
+        if self.chars.get(self.position..end).is_some_and(|c| c.iter().copied().eq(word.chars())) {
+            self.position = end;
+            return true;
+        }
+        false
+    }
+
+    /// Parses any value.
+    fn value(&mut self) -> Option<Json> {
+        self.whitespace();
+        let next = *self.chars.get(self.position)?;
+        match next {
+            '{' | '[' if self.depth >= MAX_DEPTH => None,
+            '{' => self.nested(Self::object),
+            '[' => self.nested(Self::array),
+            '"' => self.string().map(Json::String),
+            _ if self.literal("null") => Some(Json::Null),
+            _ if self.literal("true") => Some(Json::Bool(true)),
+            _ if self.literal("false") => Some(Json::Bool(false)),
+            _ => self.number(),
+        }
+    }
+
+    /// Parses an array or object one level deeper.
+    fn nested(&mut self, parse: fn(&mut Self) -> Option<Json>) -> Option<Json> {
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    /// Parses an object, the next character being `{`.
+    fn object(&mut self) -> Option<Json> {
+        self.position += 1;
+        let mut entries = vec![];
+        self.whitespace();
+        if self.literal("}") { return Some(Json::Object(entries)); }
+        loop {
+            self.whitespace();
+            if self.chars.get(self.position) != Some(&'"') { return None; }
+            let key = self.string()?;
+            self.whitespace();
+            if !self.literal(":") { return None; }
+            entries.push((key, self.value()?));
+            self.whitespace();
+            if self.literal("}") { return Some(Json::Object(entries)); }
+            if !self.literal(",") { return None; }
+        }
+    }
+
+    /// Parses an array, the next character being `[`.
+    fn array(&mut self) -> Option<Json> {
+        self.position += 1;
+        let mut items = vec![];
+        self.whitespace();
+        if self.literal("]") { return Some(Json::Array(items)); }
+        loop {
+            items.push(self.value()?);
+            self.whitespace();
+            if self.literal("]") { return Some(Json::Array(items)); }
+            if !self.literal(",") { return None; }
+        }
+    }
+
+    /// Parses a string, the next character being `"`.
+    fn string(&mut self) -> Option<String> {
+        self.position += 1;
+        let mut res = String::new();
+        loop {
+            let c = *self.chars.get(self.position)?;
+            self.position += 1;
+            match c {
+                '"' => return Some(res),
+                '\\' => {
+                    let escape = *self.chars.get(self.position)?;
+                    self.position += 1;
+                    res.push(match escape {
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'u' => self.unicode()?,
+                        c @ ('"' | '\\' | '/') => c,
+                        _ => return None,
+                    });
+                },
+                c => res.push(c),
+            }
+        }
+    }
+
+    /// Parses the hex digits of a `\u` escape, joining surrogate pairs.
+    fn unicode(&mut self) -> Option<char> {
+        let high = self.hex4()?;
+        if !(0xD800..0xDC00).contains(&high) { return char::from_u32(high); }
+        if !self.literal("\\u") { return None; }
+        let low = self.hex4()?;
+        char::from_u32(0x10000 + ((high - 0xD800) << 10) + low.checked_sub(0xDC00)?)
+    }
+
+    /// Parses four hex digits.
+    fn hex4(&mut self) -> Option<u32> {
+        let digits: String = self.chars.get(self.position..self.position + 4)?.iter().collect();
+        self.position += 4;
+        u32::from_str_radix(&digits, 16).ok()
+    }
+
+    /// Parses a number.
+    fn number(&mut self) -> Option<Json> {
+        let start = self.position;
+        while self.chars.get(self.position).is_some_and(|c| "+-.eE".contains(*c) || c.is_ascii_digit()) {
+            self.position += 1;
+        }
+        let text: String = self.chars[start..self.position].iter().collect();
+        text.parse().ok().map(Json::Number)
+    }
+}
@@ -0,0 +1,354 @@
+//! # Language Server
+//!
+//! A language server protocol server for Baby asm, so editors can show
+//! errors as a program is written, run with `baby lsp` and spoken to
+//! over stdio.
+//!
+//! The main type here is [LanguageServer][crate::lsp::LanguageServer]
+//! which holds the open documents and answers each JSON-RPC message
+//! through [LanguageServer::handle][crate::lsp::LanguageServer::handle],
+//! the framing is left to [server][crate::lsp::server].
+//!
+//! ## Features
+//!
+//! | Method                            | Provides                                                     |
+//! |-----------------------------------|--------------------------------------------------------------|
+//! | `textDocument/publishDiagnostics` | Every error, or every warning once the document assembles    |
+//! | `textDocument/definition`         | The `:tag` or `equ` declaring the name under the cursor      |
+//! | `textDocument/references`         | Every `$name` use of the name under the cursor               |
+//! | `textDocument/hover`              | A name's value, or the words a line assembled to             |
+//! | `textDocument/completion`         | Mnemonics and directives for the notation, and `$` names     |
+//! | `baby/wordsUsed`                  | A notification of how many of the 32 words the program uses  |
+//!
+//! Documents use modern notation unless `baby lsp --og` is run, or the
+//! client passes `{"ogNotation": true}` as its initialisation options.
+//! Only full document syncing is supported.
+//!
+
+use std::collections::HashMap;
+use baby_emulator::core::MEMORY_WORDS;
+use baby_emulator::assembler::diagnostics::SourceSpan;
+use document::Document;
+use json::Json;
+
+
+/// Contains the analysis of an open document.
+pub mod document;
+/// Contains a minimal JSON value, parser and writer.
+pub mod json;
+/// Contains the message framing and stdio server.
+pub mod server;
+#[cfg(test)]
+mod tests;
+
+
+/// Error code for a message that is not valid JSON.
+pub const PARSE_ERROR: i64 = -32700;
+/// Error code for a message that is not a valid request.
+pub const INVALID_REQUEST: i64 = -32600;
+/// Error code for a method the server does not know.
+pub const METHOD_NOT_FOUND: i64 = -32601;
+/// Error code for a request sent before `initialize`.
+pub const SERVER_NOT_INITIALIZED: i64 = -32002;
+
+/// Completion item kind for a keyword.
+const KIND_KEYWORD: usize = 14;
+/// Completion item kind for a constant.
+const KIND_CONSTANT: usize = 21;
+/// Completion item kind for a reference.
+const KIND_REFERENCE: usize = 18;
+
+/// The mnemonics offered for modern notation, each with a description.
+const MODERN_COMPLETIONS: [(&str, &str); 7] = [
+    ("jmp", "Jump to the address held at an address"),
+    ("jrp", "Jump relative by the value held at an address"),
+    ("ldn", "Load the negated value at an address"),
+    ("sto", "Store the accumulator at an address"),
+    ("sub", "Subtract the value at an address"),
+    ("cmp", "Skip the next instruction if the accumulator is negative"),
+    ("stp", "Stop"),
+];
+
+/// The instruction forms offered for original notation, each with a description.
+const OG_COMPLETIONS: [(&str, &str); 7] = [
+    ("to CI", "Jump to the address held at an address, `# to CI`"),
+    ("Add", "Jump relative by the value held at an address, `Add # to CI`"),
+    ("to C", "Load the negated value at an address, `-# to C`"),
+    ("c to", "Store the accumulator at an address, `c to #`"),
+    ("Sub.", "Subtract the value at an address"),
+    ("Test", "Skip the next instruction if the accumulator is negative"),
+    ("Stop", "Stop"),
+];
+
+/// The keywords and directives offered for both notations, each with a description.
+const DIRECTIVE_COMPLETIONS: [(&str, &str); 10] = [
+    ("abs", "An absolute value"),
+    ("equ", "Declare a constant"),
+    ("macro", "Start a macro definition"),
+    ("endm", "End a macro definition"),
+    (".org", "Place the next line at an address"),
+    (".fill", "Fill a number of words with a value"),
+    (".word", "A list of absolute values"),
+    (".reserve", "Reserve a number of zeroed words"),
+    (".export", "Export names from an object"),
+    (".import", "Import names from another object"),
+];
+
+/// The state of a language server session.
+pub struct LanguageServer {
+    /// If documents use original notation.
+    pub og_notation: bool,
+    /// If `initialize` has been received.
+    pub initialized: bool,
+    /// If `shutdown` has been received.
+    pub shut_down: bool,
+    /// If `exit` has been received, the server should stop.
+    pub exited: bool,
+    /// The open documents by URI.
+    pub documents: HashMap<String, Document>,
+}
+
+impl LanguageServer {
+
+    /// Creates a new server with no open documents.
+    ///
+    /// # Parameters
+    /// * `og_notation` - If documents use original notation, unless changed by the client.
+    ///
+    pub fn new(og_notation: bool) -> LanguageServer {
+        LanguageServer { og_notation, initialized: false, shut_down: false, exited: false, documents: HashMap::new() }
+    }
+
+    /// Handles a message from the client, returning every message to send back.
+    ///
+    /// Requests get a single response, notifications may cause any number
+    /// of notifications to be sent, such as the diagnostics of a document.
+    ///
+    /// # Parameters
+    /// * `message` - The text of the message, without its header.
+    ///
+    pub fn handle(&mut self, message: &str) -> Vec<Json> {
+        let message = match Json::parse(message) {
+            Some(m) => m,
+            None => return vec![error(Json::Null, PARSE_ERROR, "The message is not valid JSON. ")],
+        };
+        let id = message.get("id").cloned();
+        let params = message.get("params").cloned().unwrap_or(Json::Null);
+        let method = match message.get("method").and_then(Json::as_str) {
+            Some(m) => m.to_owned(),
+            None => return match id {
+                Some(id) => vec![error(id, INVALID_REQUEST, "The message has no method. ")],
+                None => vec![],
+            },
+        };
+        let id = match id {
+            Some(id) => id,
+            None => return self.notification(&method, &params),
+        };
+        if !self.initialized && method != "initialize" {
+            return vec![error(id, SERVER_NOT_INITIALIZED, "The server has not been initialised. ")];
+        }
+        let result = match method.as_str() {
+            "initialize" => self.initialize(&params),
+            "shutdown" => { self.shut_down = true; Json::Null },
+            "textDocument/definition" => self.definition(&params),
+            "textDocument/references" => self.references(&params),
+            "textDocument/hover" => self.hover(&params),
+            "textDocument/completion" => self.completion(&params),
+            _ => return vec![error(id, METHOD_NOT_FOUND, &format!("The method `{}` is not supported. ", method))],
+        };
+        vec![Json::object(vec![("jsonrpc", Json::str("2.0")), ("id", id), ("result", result)])]
+    }
+
+    /// Handles a notification, returning any notifications to send back.
+    fn notification(&mut self, method: &str, params: &Json) -> Vec<Json> {
+        let uri = params.path(&["textDocument", "uri"]).and_then(Json::as_str).unwrap_or("").to_owned();
+        let text = match method {
+            "exit" => { self.exited = true; return vec![]; },
+            _ if !self.initialized => return vec![],
+            "textDocument/didOpen" => params.path(&["textDocument", "text"]),
+            "textDocument/didChange" => params.get("contentChanges").and_then(Json::as_array)
+                .and_then(|c| c.last()).and_then(|c| c.get("text")),
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                return vec![publish_diagnostics(&uri, vec![])];
+            },
+            _ => return vec![],
+        };
+        let Some(text) = text.and_then(Json::as_str) else { return vec![] };
+        let document = Document::new(text, self.og_notation);
+        let diagnostics = document.diagnostics.iter()
+            .map(|d| Json::object(vec![
+                ("range", range(&d.span)),
+                ("severity", Json::int(d.severity as usize)),
+                ("source", Json::str("baby")),
+                ("message", Json::str(&d.message)),
+            ]))
+            .collect();
+        let words_used = words_used(&uri, document.words_used);
+        self.documents.insert(uri.clone(), document);
+        vec![publish_diagnostics(&uri, diagnostics), words_used]
+    }
+
+    /// Answers `initialize`, returning the server's capabilities.
+    fn initialize(&mut self, params: &Json) -> Json {
+        self.initialized = true;
+        if let Some(og) = params.path(&["initializationOptions", "ogNotation"]).and_then(Json::as_bool) {
+            self.og_notation = og;
+        }
+        Json::object(vec![
+            ("capabilities", Json::object(vec![
+                ("textDocumentSync", Json::int(1)),
+                ("definitionProvider", Json::Bool(true)),
+                ("referencesProvider", Json::Bool(true)),
+                ("hoverProvider", Json::Bool(true)),
+                ("completionProvider", Json::object(vec![("triggerCharacters", Json::Array(vec![Json::str("$")]))])),
+            ])),
+            ("serverInfo", Json::object(vec![
+                ("name", Json::str("baby")),
+                ("version", Json::str(env!("CARGO_PKG_VERSION"))),
+            ])),
+        ])
+    }
+
+    /// Returns the document and the line and character column of a position request.
+    fn position(&self, params: &Json) -> Option<(&Document, usize, usize)> {
+        let uri = params.path(&["textDocument", "uri"])?.as_str()?;
+        let document = self.documents.get(uri)?;
+        let line = params.path(&["position", "line"])?.as_usize()?;
+        let character = params.path(&["position", "character"])?.as_usize()?;
+        let text = document.text.lines().nth(line).unwrap_or("");
+        Some((document, line, char_column(text, character)))
+    }
+
+    /// Answers `textDocument/definition` with the location of the declaration.
+    fn definition(&self, params: &Json) -> Json {
+        let Some((document, line, column)) = self.position(params) else { return Json::Null };
+        let uri = params.path(&["textDocument", "uri"]).cloned().unwrap_or(Json::Null);
+        document.occurrence_at(line, column)
+            .and_then(|o| document.occurrences_of(&o.name, true).into_iter().find(|o| o.declaration))
+            .map_or(Json::Null, |o| location(&uri, &o.span))
+    }
+
+    /// Answers `textDocument/references` with the location of every use.
+    fn references(&self, params: &Json) -> Json {
+        let Some((document, line, column)) = self.position(params) else { return Json::Null };
+        let uri = params.path(&["textDocument", "uri"]).cloned().unwrap_or(Json::Null);
+        let declaration = params.path(&["context", "includeDeclaration"]).and_then(Json::as_bool).unwrap_or(true);
+        match document.occurrence_at(line, column) {
+            Some(o) => Json::Array(document.occurrences_of(&o.name, declaration).into_iter()
+                .map(|o| location(&uri, &o.span))
+                .collect()),
+            None => Json::Null,
+        }
+    }
+
+    /// Answers `textDocument/hover` with markdown describing the position.
+    fn hover(&self, params: &Json) -> Json {
+        let Some((document, line, column)) = self.position(params) else { return Json::Null };
+        match document.hover(line, column, self.og_notation) {
+            Some(h) => Json::object(vec![("contents", Json::object(vec![
+                ("kind", Json::str("markdown")),
+                ("value", Json::str(&h)),
+            ]))]),
+            None => Json::Null,
+        }
+    }
+
+    /// Answers `textDocument/completion`, with names after a `$`, otherwise
+    /// the mnemonics and directives of the notation in use.
+    fn completion(&self, params: &Json) -> Json {
+        let Some((document, line, column)) = self.position(params) else { return Json::Array(vec![]) };
+        let before: String = document.text.lines().nth(line).unwrap_or("").chars().take(column).collect();
+        let word = before.rsplit(|c: char| c.is_whitespace() || "(,+-*/&|^~".contains(c)).next().unwrap_or("");
+        let items = if word.starts_with('$') {
+            let mut names: Vec<(&String, _)> = document.kinds.iter().collect();
+            names.sort_by_key(|(name, _)| *name);
+            names.into_iter().map(|(name, kind)| {
+                let kind = match kind {
+                    document::SymbolKind::Tag => KIND_REFERENCE,
+                    document::SymbolKind::Constant => KIND_CONSTANT,
+                };
+                let detail = document.values.get(name).map_or(String::new(), |v| v.to_string());
+                completion_item(name, kind, &detail)
+            }).collect()
+        } else {
+            let (items, notation) = if self.og_notation { (&OG_COMPLETIONS[..], "original notation") }
+                else { (&MODERN_COMPLETIONS[..], "modern notation") };
+            items.iter()
+                .map(|(label, detail)| completion_item(label, KIND_KEYWORD, &format!("{} ({})", detail, notation)))
+                .chain(DIRECTIVE_COMPLETIONS.iter().map(|(label, detail)| completion_item(label, KIND_KEYWORD, detail)))
+                .collect()
+        };
+        Json::Array(items)
+    }
+}
+
+/// Creates an error response.
+fn error(id: Json, code: i64, message: &str) -> Json {
+    Json::object(vec![
+        ("jsonrpc", Json::str("2.0")),
+        ("id", id),
+        ("error", Json::object(vec![
+            ("code", Json::Number(code as f64)),
+            ("message", Json::str(message.trim())),
+        ])),
+    ])
+}
+
+/// Creates a notification.
+fn notification(method: &str, params: Json) -> Json {
+    Json::object(vec![("jsonrpc", Json::str("2.0")), ("method", Json::str(method)), ("params", params)])
+}
+
+/// Creates a `textDocument/publishDiagnostics` notification.
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Json>) -> Json {
+    notification("textDocument/publishDiagnostics", Json::object(vec![
+        ("uri", Json::str(uri)),
+        ("diagnostics", Json::Array(diagnostics)),
+    ]))
+}
+
+/// Creates a `baby/wordsUsed` notification, with a message such as
+/// `12 / 32 words used` for a status bar.
+fn words_used(uri: &str, words: Option<usize>) -> Json {
+    let message = match words {
+        Some(w) => format!("{} / {} words used", w, MEMORY_WORDS),
+        None => format!("? / {} words used", MEMORY_WORDS),
+    };
+    notification("baby/wordsUsed", Json::object(vec![
+        ("uri", Json::str(uri)),
+        ("wordsUsed", words.map_or(Json::Null, Json::int)),
+        ("memoryWords", Json::int(MEMORY_WORDS)),
+        ("message", Json::str(&message)),
+    ]))
+}
+
+/// Creates a completion item.
+fn completion_item(label: &str, kind: usize, detail: &str) -> Json {
+    Json::object(vec![("label", Json::str(label)), ("kind", Json::int(kind)), ("detail", Json::str(detail))])
+}
+
+/// Creates a location from a span.
+fn location(uri: &Json, span: &SourceSpan) -> Json {
+    Json::object(vec![("uri", uri.clone()), ("range", range(span))])
+}
+
+/// Creates a range from a span, converting character columns to UTF-16 columns.
+fn range(span: &SourceSpan) -> Json {
+    let position = |column: usize| Json::object(vec![
+        ("line", Json::int(span.line.saturating_sub(1))),
+        ("character", Json::int(span.text.chars().take(column).map(char::len_utf16).sum())),
+    ]);
+    Json::object(vec![("start", position(span.start)), ("end", position(span.end))])
+}
+
+/// Converts a UTF-16 column on a line to a character column.
+fn char_column(line: &str, utf16: usize) -> usize {
+    let mut units = 0;
+    for (i, c) in line.chars().enumerate() {
+        if units >= utf16 { return i; }
+        units += c.len_utf16();
+    }
+    line.chars().count()
+}
@@ -0,0 +1,68 @@
+//! # Server
+//!
+//! Serves a [LanguageServer] over a stream, each message is sent as a
+//! `Content-Length: <n>` header, a blank line, then `n` bytes of JSON,
+//! reading each message and writing back every reply until the client
+//! sends `exit` or closes the stream.
+//!
+
+use std::io::{self, BufRead, Write};
+use super::LanguageServer;
+use super::json::Json;
+
+
+/// The largest message body accepted, in bytes.
+pub const MAX_MESSAGE_BYTES: usize = 8 * 1024 * 1024;
+
+/// Reads the next message from a stream, returning the JSON text.
+///
+/// Returns `Ok(None)` if the stream has closed, or an error if the body
+/// is longer than [MAX_MESSAGE_BYTES], any headers other than
+/// `Content-Length` are ignored.
+pub fn read_message<R: BufRead>(stream: &mut R) -> io::Result<Option<String>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if stream.read_line(&mut header)? == 0 { return Ok(None); }
+        let header = header.trim_end();
+        if header.is_empty() {
+            if length.is_some() { break; }
+            continue;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let length = length.unwrap_or(0);
+    if length > MAX_MESSAGE_BYTES {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("message of {} bytes is too long", length)));
+    }
+    let mut body = vec![0u8; length];
+    stream.read_exact(&mut body)?;
+    Ok(Some(String::from_utf8_lossy(&body).into_owned()))
+}
+
+/// Writes a message to a stream with its header.
+pub fn write_message<W: Write>(stream: &mut W, message: &Json) -> io::Result<()> {
+    let body = message.to_string();
+    write!(stream, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    stream.flush()
+}
+
+/// Serves a language server over a stream until the session ends.
+pub fn serve<R: BufRead, W: Write>(server: &mut LanguageServer, input: &mut R, output: &mut W) -> io::Result<()> {
+    while let Some(message) = read_message(input)? {
+        for reply in server.handle(&message) {
+            write_message(output, &reply)?;
+        }
+        if server.exited { break; }
+    }
+    Ok(())
+}
+
+/// Serves a language server over the process's stdin and stdout.
+pub fn serve_stdio(server: &mut LanguageServer) -> io::Result<()> {
+    serve(server, &mut io::stdin().lock(), &mut io::stdout().lock())
+}
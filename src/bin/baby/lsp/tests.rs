@@ -0,0 +1,213 @@
+use super::*;
+use std::io::Cursor;
+use server::{read_message, serve};
+
+
+const URI: &str = "file:///prog.asm";
+
+const ASM: &str =
+"equ size 0d2
+ldn $value ; Load
+sub $one
+sto $value
+stp
+:value
+abs 0d-3
+:one
+abs $size-0d1";
+
+const INITIALIZE: &str = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"capabilities":{}}}"#;
+const SHUTDOWN: &str = r#"{"jsonrpc":"2.0","id":99,"method":"shutdown"}"#;
+const EXIT: &str = r#"{"jsonrpc":"2.0","method":"exit"}"#;
+
+/// Frames each message with its header, as a client sends them.
+fn frame(messages: &[String]) -> Vec<u8> {
+    messages.iter()
+        .map(|m| format!("Content-Length: {}\r\n\r\n{}", m.len(), m))
+        .collect::<String>()
+        .into_bytes()
+}
+
+/// Serves a session of messages, returning every message sent back.
+fn session(og_notation: bool, messages: &[String]) -> Vec<Json> {
+    let mut input = Cursor::new(frame(messages));
+    let mut output = vec![];
+    serve(&mut LanguageServer::new(og_notation), &mut input, &mut output).unwrap();
+    let mut output = Cursor::new(output);
+    let mut res = vec![];
+    while let Some(m) = read_message(&mut output).unwrap() {
+        res.push(Json::parse(&m).expect("The server sent invalid JSON. "));
+    }
+    res
+}
+
+fn did_open(text: &str) -> String {
+    format!(
+        r#"{{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{{"textDocument":{{"uri":"{}","languageId":"baby","version":1,"text":{}}}}}}}"#,
+        URI, Json::str(text)
+    )
+}
+
+fn request(id: usize, method: &str, line: usize, character: usize, extra: &str) -> String {
+    format!(
+        r#"{{"jsonrpc":"2.0","id":{},"method":"{}","params":{{"textDocument":{{"uri":"{}"}},"position":{{"line":{},"character":{}}}{}}}}}"#,
+        id, method, URI, line, character, extra
+    )
+}
+
+/// Serves a session opening [ASM] with the requests, returning the result of each.
+fn results(requests: &[String]) -> Vec<Json> {
+    let mut messages = vec![INITIALIZE.to_owned(), did_open(ASM)];
+    messages.extend(requests.iter().cloned());
+    session(false, &messages).into_iter()
+        .filter(|m| m.get("id").is_some_and(|id| *id != Json::int(1)))
+        .map(|m| m.get("result").cloned().unwrap())
+        .collect()
+}
+
+fn range(line: usize, start: usize, end: usize) -> Json {
+    Json::parse(&format!(
+        r#"{{"start":{{"line":{},"character":{}}},"end":{{"line":{},"character":{}}}}}"#,
+        line, start, line, end
+    )).unwrap()
+}
+
+fn labels(items: &Json) -> Vec<&str> {
+    items.as_array().unwrap().iter().map(|i| i.get("label").and_then(Json::as_str).unwrap()).collect()
+}
+
+#[test]
+fn test_json() {
+    let text = r#"{"a":[1,-2.5,true,false,null],"b":"x\"\\\né😀","c":{}}"#;
+    let value = Json::parse(text).unwrap();
+    assert_eq!(value.path(&["b"]).and_then(Json::as_str), Some("x\"\\\né😀"));
+    assert_eq!(value.get("a").and_then(Json::as_array).map(Vec::len), Some(5));
+    assert_eq!(Json::parse(&value.to_string()), Some(value));
+    assert_eq!(Json::int(12).to_string(), "12");
+    assert_eq!(Json::parse("{\"a\":}"), None);
+    assert_eq!(Json::parse("[1] 2"), None);
+    let nested = |n| format!("{}{}", "[".repeat(n), "]".repeat(n));
+    assert!(Json::parse(&nested(json::MAX_DEPTH)).is_some());
+    assert_eq!(Json::parse(&nested(json::MAX_DEPTH + 1)), None);
+    assert_eq!(Json::parse(&"[".repeat(300_000)), None);
+}
+
+#[test]
+fn test_words_used() {
+    let words_used = |text| document::Document::new(text, false).words_used;
+    assert_eq!(words_used(ASM), Some(6));
+    assert_eq!(words_used("ldn $a\nstp\n.org 0d20\n:a\nabs 0d1\n.reserve 0d4\n.word 0d1, 0d2"), Some(5));
+    assert_eq!(words_used("ldn $nope"), None);
+}
+
+#[test]
+fn test_lifecycle() {
+    let replies = session(false, &[
+        r#"{"jsonrpc":"2.0","id":0,"method":"textDocument/hover","params":{}}"#.to_owned(),
+        INITIALIZE.to_owned(),
+        r#"{"jsonrpc":"2.0","id":2,"method":"foo/bar"}"#.to_owned(),
+        r#"{"jsonrpc":"2.0","method":"foo/bar"}"#.to_owned(),
+        "{not json".to_owned(),
+        SHUTDOWN.to_owned(),
+        EXIT.to_owned(),
+        INITIALIZE.to_owned(),
+    ]);
+    assert_eq!(replies.len(), 5);
+    assert_eq!(replies[0].path(&["error", "code"]), Some(&Json::Number(SERVER_NOT_INITIALIZED as f64)));
+    let capabilities = replies[1].path(&["result", "capabilities"]).unwrap();
+    assert_eq!(capabilities.get("textDocumentSync"), Some(&Json::int(1)));
+    assert_eq!(capabilities.get("definitionProvider"), Some(&Json::Bool(true)));
+    assert_eq!(replies[2].path(&["error", "code"]), Some(&Json::Number(METHOD_NOT_FOUND as f64)));
+    assert_eq!(replies[3].path(&["error", "code"]), Some(&Json::Number(PARSE_ERROR as f64)));
+    assert_eq!(replies[4].get("id"), Some(&Json::int(99)));
+    assert_eq!(replies[4].get("result"), Some(&Json::Null));
+    let mut huge = Cursor::new(b"Content-Length: 18446744073709551615\r\n\r\n{}".to_vec());
+    assert_eq!(read_message(&mut huge).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    let replies = session(false, &["[".repeat(300_000), INITIALIZE.to_owned()]);
+    assert_eq!(replies[0].path(&["error", "code"]), Some(&Json::Number(PARSE_ERROR as f64)));
+}
+
+#[test]
+fn test_diagnostics() {
+    let change = format!(
+        r#"{{"jsonrpc":"2.0","method":"textDocument/didChange","params":{{"textDocument":{{"uri":"{}","version":2}},"contentChanges":[{{"text":"ldn $nope\nsub 0d1"}}]}}}}"#,
+        URI
+    );
+    let close = format!(r#"{{"jsonrpc":"2.0","method":"textDocument/didClose","params":{{"textDocument":{{"uri":"{}"}}}}}}"#, URI);
+    let replies = session(false, &[INITIALIZE.to_owned(), did_open(ASM), change, close]);
+    let methods: Vec<&str> = replies.iter().filter_map(|m| m.get("method").and_then(Json::as_str)).collect();
+    assert_eq!(methods, vec![
+        "textDocument/publishDiagnostics", "baby/wordsUsed",
+        "textDocument/publishDiagnostics", "baby/wordsUsed",
+        "textDocument/publishDiagnostics",
+    ]);
+    assert_eq!(replies[1].path(&["params", "diagnostics"]), Some(&Json::Array(vec![])));
+    assert_eq!(replies[2].path(&["params", "message"]).and_then(Json::as_str), Some("6 / 32 words used"));
+    assert_eq!(replies[2].path(&["params", "wordsUsed"]), Some(&Json::int(6)));
+
+    let diagnostics = replies[3].path(&["params", "diagnostics"]).and_then(Json::as_array).unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].get("range"), Some(&range(0, 4, 9)));
+    assert_eq!(diagnostics[0].get("severity"), Some(&Json::int(1)));
+    assert!(diagnostics[0].get("message").and_then(Json::as_str).unwrap().contains("nope"));
+    assert_eq!(replies[4].path(&["params", "wordsUsed"]), Some(&Json::Null));
+    assert_eq!(replies[5].path(&["params", "diagnostics"]), Some(&Json::Array(vec![])));
+
+    let replies = session(false, &[INITIALIZE.to_owned(), did_open("ldn 0d1")]);
+    let diagnostics = replies[1].path(&["params", "diagnostics"]).and_then(Json::as_array).unwrap();
+    assert_eq!(diagnostics[0].get("severity"), Some(&Json::int(2)));
+}
+
+#[test]
+fn test_definition_and_references() {
+    let results = results(&[
+        request(2, "textDocument/definition", 2, 6, ""),
+        request(3, "textDocument/references", 1, 5, r#","context":{"includeDeclaration":true}"#),
+        request(4, "textDocument/references", 1, 5, r#","context":{"includeDeclaration":false}"#),
+        request(5, "textDocument/definition", 8, 6, ""),
+        request(6, "textDocument/definition", 4, 1, ""),
+    ]);
+    assert_eq!(results[0].get("range"), Some(&range(7, 0, 4)));
+    assert_eq!(results[0].get("uri"), Some(&Json::str(URI)));
+    let ranges = |r: &Json| r.as_array().unwrap().iter().map(|l| l.get("range").cloned().unwrap()).collect::<Vec<Json>>();
+    assert_eq!(ranges(&results[1]), vec![range(1, 4, 10), range(3, 4, 10), range(5, 0, 6)]);
+    assert_eq!(ranges(&results[2]), vec![range(1, 4, 10), range(3, 4, 10)]);
+    assert_eq!(results[3].get("range"), Some(&range(0, 4, 8)));
+    assert_eq!(results[4], Json::Null);
+}
+
+#[test]
+fn test_hover() {
+    let results = results(&[
+        request(2, "textDocument/hover", 5, 2, ""),
+        request(3, "textDocument/hover", 8, 6, ""),
+        request(4, "textDocument/hover", 4, 0, ""),
+        request(5, "textDocument/hover", 0, 0, ""),
+    ]);
+    let value = |r: &Json| r.path(&["contents", "value"]).and_then(Json::as_str).unwrap().to_owned();
+    assert_eq!(value(&results[0]), "tag `value` = 4 (0x4)");
+    assert_eq!(value(&results[1]), "constant `size` = 2 (0x2)");
+    assert!(value(&results[2]).contains("03: ") && value(&results[2]).contains("stp"));
+    assert_eq!(results[3], Json::Null);
+}
+
+#[test]
+fn test_completion() {
+    let results = results(&[
+        request(2, "textDocument/completion", 2, 0, ""),
+        request(3, "textDocument/completion", 2, 5, ""),
+    ]);
+    let keywords = labels(&results[0]);
+    assert!(keywords.contains(&"ldn") && keywords.contains(&".org") && !keywords.contains(&"Stop"));
+    assert_eq!(labels(&results[1]), vec!["one", "size", "value"]);
+    assert_eq!(results[1].as_array().unwrap()[2].get("detail"), Some(&Json::str("4")));
+
+    let replies = session(false, &[
+        r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"initializationOptions":{"ogNotation":true}}}"#.to_owned(),
+        did_open("-2 to C\nStop\n3"),
+        request(2, "textDocument/completion", 0, 0, ""),
+    ]);
+    let keywords = labels(replies.last().unwrap().get("result").unwrap());
+    assert!(keywords.contains(&"Stop") && keywords.contains(&"to CI") && !keywords.contains(&"ldn"));
+    assert_eq!(replies[2].path(&["params", "message"]).and_then(Json::as_str), Some("3 / 32 words used"));
+}
@@ -0,0 +1,123 @@
+//! # Baby
+//!
+//! The `baby` command line tool, a thin front end over the
+//! [baby_emulator] library for assembling, running and inspecting
+//! Baby programs without writing any Rust.
+//!
+//! ```text
+//! baby asm <file.asm> [--og] [-o <out>]        Assemble to a store image,
+//!     [--listing <out.lst>] [--deny-warnings]  optionally writing a listing
+//!     [--sym <out.sym>] [--object]             or symbols, or to an object file
+//! baby link <file>... [--og] [-o <out>]        Link object files to a store image
+//!     [--sym <out.sym>]
+//! baby run <file> [--og] [--max-steps <n>]     Run and print the core dump
+//!     [--sym <file.sym>]
+//! baby dump <file> [--og]                      Print a store image
+//! baby debug <file> [--og] [--sym <file.sym>]  Open the interactive debugger
+//! baby gdb <file> [--og] [--stdio]             Serve to a GDB remote debugger
+//! baby fmt <file.asm> [--og] [--write]         Format an asm file
+//!     [--check]
+//! baby convert <file.asm> [--og] [-o <out>]    Convert between modern and original notation
+//! baby compile <file> [-o <out.asm>]           Compile a program to asm
+//! baby cfg <file> [--og] [-o <out.dot>]        Print the control flow graph as DOT
+//!     [--sym <file.sym>]
+//! baby lsp [--og]                              Run a language server over stdio
+//! ```
+//!
+//! See [errors][crate::errors] for the exit codes returned on failure.
+//!
+
+use std::process::ExitCode;
+use errors::{CliError, EXIT_USAGE};
+
+
+/// Contains a minimal command line argument parser.
+mod args;
+/// Contains the implementations of each subcommand.
+mod commands;
+/// Contains the interactive terminal debugger.
+mod debugger;
+/// Contains the errors thrown by the subcommands and their exit codes.
+mod errors;
+/// Contains the GDB remote serial protocol stub.
+mod gdb;
+/// Contains functionality for reading and writing store images.
+mod image;
+/// Contains the language server for asm source files.
+mod lsp;
+#[cfg(test)]
+mod tests;
+
+
+/// The usage text printed for `baby help` or invalid usage.
+const USAGE: &str =
+"Usage: baby <command> [arguments]
+
+Commands:
+    asm <file.asm> [--og] [-o <out>]        Assemble asm to a store image,
+        [--listing <out.lst>]               also writing a listing if given,
+        [--sym <out.sym>]                   and a symbol file if given,
+        [--deny-warnings] [--object]        failing on any warnings if denied,
+                                            or to an object file with `--object`
+    link <file>... [--og] [-o <out>]        Link object files to a store image,
+        [--sym <out.sym>]                   also writing a symbol file if given
+    run <file> [--og] [--max-steps <n>]     Run a program, printing the core dump
+        [--sym <file.sym>]                  labelled with the symbol file's names
+    dump <file> [--og]                      Print each word of a store image
+    debug <file> [--og] [--sym <file.sym>]  Open the interactive debugger
+    gdb <file> [--og] [--stdio]             Serve a program to a GDB remote debugger,
+        [--listen <addr>] [--max-steps <n>] on 127.0.0.1:1234 unless `--stdio`
+    fmt <file.asm> [--og] [--write]         Format asm, printing the result unless
+        [--check]                           `--write`, or failing if unformatted
+    convert <file.asm> [--og] [-o <out>]    Convert modern notation asm to original
+                                            notation, or original to modern with `--og`
+    compile <file> [-o <out.asm>]           Compile a program in the high level language
+                                            to asm, printing the result unless `-o`
+    cfg <file> [--og] [-o <out.dot>]        Print a program's control flow graph as
+        [--sym <file.sym>]                  Graphviz DOT, labelled with any symbols
+    lsp [--og]                              Run a language server for asm over stdio
+    help                                    Print this message
+
+<file> can be a store image or an asm source file ending in `.asm`,
+or an object file or asm source file for `link`, `--og` assembles
+using original notation. ";
+
+/// Dispatches the arguments to the named subcommand.
+fn dispatch(args: &[String]) -> Result<String, CliError> {
+    let (command, rest) = match args.split_first() {
+        Some((c, r)) => (c.as_str(), r),
+        None => return Err(CliError::Usage("no command specified. ".to_owned()))
+    };
+    match command {
+        "asm" => commands::asm(rest),
+        "link" => commands::link(rest),
+        "run" => commands::run(rest),
+        "dump" => commands::dump(rest),
+        "debug" => commands::debug(rest),
+        "gdb" => commands::gdb(rest),
+        "fmt" => commands::fmt(rest),
+        "convert" => commands::convert(rest),
+        "compile" => commands::compile(rest),
+        "cfg" => commands::cfg(rest),
+        "lsp" => commands::lsp(rest),
+        "help" | "--help" | "-h" => Ok(USAGE.to_owned()),
+        c => Err(CliError::Usage(format!("unknown command `{}`. ", c)))
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match dispatch(&args) {
+        Ok(v) => {
+            if !v.is_empty() { println!("{}", v); }
+            ExitCode::SUCCESS
+        },
+        Err(e) => {
+            eprintln!("{}", e.describe());
+            if e.exit_code() == EXIT_USAGE {
+                eprintln!("\n{}", USAGE);
+            }
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
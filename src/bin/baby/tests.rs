@@ -0,0 +1,286 @@
+use super::*;
+use std::fs;
+use std::path::PathBuf;
+use baby_emulator::core::{BabyModel, MEMORY_WORDS, WORD, instructions::BabyInstruction};
+use args::Args;
+use errors::{EXIT_PARSE, EXIT_LINK, EXIT_IO, EXIT_ITERATIONS, EXIT_IMAGE, EXIT_UNFORMATTED};
+
+
+const ASM: &str =
+"
+ldn $start_value
+:loop_start_value
+sub $subtract_val
+cmp
+jmp $loop_start
+stp
+:loop_start
+abs $loop_start_value
+:subtract_val
+abs 0d1
+:start_value
+abs 0d-10
+";
+
+fn to_args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|a| a.to_string()).collect()
+}
+
+/// A temporary directory for the files of a single test, removed along
+/// with everything in it when dropped, even if the test fails.
+struct TempDir(PathBuf);
+
+impl TempDir {
+
+    fn new(test: &str) -> TempDir {
+        let dir = std::env::temp_dir().join(format!("baby-cli-{}-{}", test, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        TempDir(dir)
+    }
+
+    fn path(&self, name: &str) -> String {
+        self.0.join(name).to_string_lossy().into_owned()
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn exit_code(res: Result<String, CliError>) -> u8 {
+    match res {
+        Ok(_) => 0,
+        Err(e) => e.exit_code()
+    }
+}
+
+#[test]
+fn test_args_parse() {
+    let args = Args::parse(&to_args(&["a.asm", "--og", "-o", "out"]), &["--og"], &["-o"]).ok().unwrap();
+    assert_eq!(args.positional, vec!["a.asm".to_owned()]);
+    assert!(args.flag("--og"));
+    assert_eq!(args.option("-o"), Some(&"out".to_owned()));
+    assert!(Args::parse(&to_args(&["-o"]), &[], &["-o"]).is_err());
+    assert!(Args::parse(&to_args(&["--foo"]), &[], &[]).is_err());
+}
+
+#[test]
+fn test_args_option_usize() {
+    let args = Args::parse(&to_args(&["--max-steps", "12"]), &[], &["--max-steps"]).ok().unwrap();
+    assert_eq!(args.option_usize("--max-steps", 5).ok(), Some(12));
+    assert_eq!(args.option_usize("--other", 5).ok(), Some(5));
+    let args = Args::parse(&to_args(&["--max-steps", "x"]), &[], &["--max-steps"]).ok().unwrap();
+    assert!(args.option_usize("--max-steps", 5).is_err());
+}
+
+#[test]
+fn test_image_round_trip() {
+    let store: [WORD; MEMORY_WORDS] = core::array::from_fn(|i| i as WORD - 5);
+    let bytes = image::to_bytes(&store);
+    assert_eq!(bytes.len(), image::IMAGE_BYTES);
+    assert_eq!(image::from_bytes(&bytes), Ok(store));
+    assert!(image::from_bytes(&bytes[1..]).is_err());
+}
+
+#[test]
+fn test_asm_run_dump() {
+    let dir = TempDir::new("asm_run_dump");
+    let source = dir.path("loop.asm");
+    let output = dir.path("loop.bin");
+    fs::write(&source, ASM).unwrap();
+    assert_eq!(exit_code(dispatch(&to_args(&["asm", &source, "-o", &output]))), 0);
+    assert_eq!(fs::read(&output).unwrap().len(), image::IMAGE_BYTES);
+    assert_eq!(exit_code(dispatch(&to_args(&["run", &output]))), 0);
+    assert_eq!(exit_code(dispatch(&to_args(&["run", &source]))), 0);
+    let dump = dispatch(&to_args(&["dump", &output])).ok().unwrap();
+    assert_eq!(dump.lines().count(), MEMORY_WORDS);
+}
+
+#[test]
+fn test_asm_listing() {
+    let dir = TempDir::new("asm_listing");
+    let source = dir.path("listing.asm");
+    let output = dir.path("listing.bin");
+    let listing = dir.path("listing.lst");
+    fs::write(&source, ASM).unwrap();
+    let args = to_args(&["asm", &source, "-o", &output, "--listing", &listing]);
+    assert_eq!(exit_code(dispatch(&args)), 0);
+    assert_eq!(fs::read(&output).unwrap().len(), image::IMAGE_BYTES);
+    let text = fs::read_to_string(&listing).unwrap();
+    assert!(text.lines().nth(2).is_some_and(|l| l.starts_with("0x00") && l.ends_with("ldn $start_value")));
+    assert!(text.contains("SYMBOLS\nloop_start_value  0x01"));
+}
+
+#[test]
+fn test_fmt() {
+    let dir = TempDir::new("fmt");
+    let source = dir.path("fmt.asm");
+    fs::write(&source, "LDN  $x ; Load\n\n\n:x\nabs 0d1 ; One").unwrap();
+    let formatted = "ldn $x  ; Load\n\n:x\nabs 0d1 ; One\n";
+    assert_eq!(dispatch(&to_args(&["fmt", &source])).ok(), Some(formatted.trim_end().to_owned()));
+    assert_eq!(exit_code(dispatch(&to_args(&["fmt", &source, "--check"]))), EXIT_UNFORMATTED);
+    assert_eq!(exit_code(dispatch(&to_args(&["fmt", &source, "--write"]))), 0);
+    assert_eq!(fs::read_to_string(&source).unwrap(), formatted);
+    assert_eq!(exit_code(dispatch(&to_args(&["fmt", &source, "--check"]))), 0);
+}
+
+#[test]
+fn test_convert() {
+    let dir = TempDir::new("convert");
+    let source = dir.path("convert.asm");
+    let output = dir.path("convert_og.asm");
+    fs::write(&source, ASM).unwrap();
+    assert_eq!(exit_code(dispatch(&to_args(&["convert", &source, "-o", &output]))), 0);
+    let og = fs::read_to_string(&output).unwrap();
+    assert!(og.contains("-$start_value, C\n"));
+    let original = image::assemble_store(ASM, false).ok().unwrap();
+    assert!(image::assemble_store(&og, true).ok() == Some(original));
+    let back = dispatch(&to_args(&["convert", &output, "--og"])).ok().unwrap();
+    assert_eq!(back, ASM.trim_end());
+
+    fs::write(&source, "macro stop\nendm").unwrap();
+    assert_eq!(exit_code(dispatch(&to_args(&["convert", &source]))), EXIT_PARSE);
+}
+
+#[test]
+fn test_cfg() {
+    let dir = TempDir::new("cfg");
+    let source = dir.path("cfg.asm");
+    let output = dir.path("cfg.dot");
+    fs::write(&source, ASM).unwrap();
+    let dot = dispatch(&to_args(&["cfg", &source])).ok().unwrap();
+    assert!(dot.starts_with("digraph program {"));
+    assert!(dot.contains("ldn $start_value"));
+    assert_eq!(exit_code(dispatch(&to_args(&["cfg", &source, "-o", &output]))), 0);
+    assert_eq!(fs::read_to_string(&output).unwrap().trim_end(), dot);
+}
+
+#[test]
+fn test_compile() {
+    let dir = TempDir::new("compile");
+    let source = dir.path("compile.baby");
+    let output = dir.path("compile.asm");
+    fs::write(&source, "var x = 3\nvar y\ny = x + x\n").unwrap();
+    assert_eq!(exit_code(dispatch(&to_args(&["compile", &source, "-o", &output]))), 0);
+    let store = image::assemble_store(&fs::read_to_string(&output).unwrap(), false).ok().unwrap();
+    let (model, _) = BabyModel::new_with_program(store).run_loop(100);
+    let tags = baby_emulator::compiler::compile_and_link("var x = 3\nvar y\ny = x + x").ok().unwrap().1;
+    assert_eq!(model.main_store[tags["y"] as usize], 6);
+    assert!(dispatch(&to_args(&["compile", &source])).ok().unwrap().starts_with("ldn $x"));
+
+    fs::write(&source, "var x\nx = y").unwrap();
+    let error = dispatch(&to_args(&["compile", &source])).err().unwrap();
+    assert_eq!(error.exit_code(), EXIT_PARSE);
+    assert!(error.describe().contains("The variable `y` is not declared."));
+}
+
+#[test]
+fn test_asm_symbols() {
+    let dir = TempDir::new("asm_symbols");
+    let source = dir.path("symbols.asm");
+    let output = dir.path("symbols.bin");
+    let symbols = dir.path("symbols.sym");
+    fs::write(&source, ASM).unwrap();
+    assert_eq!(exit_code(dispatch(&to_args(&["asm", &source, "-o", &output, "--sym", &symbols]))), 0);
+    assert!(fs::read_to_string(&symbols).unwrap().contains("\nloop_start_value 1\n"));
+    let dump = dispatch(&to_args(&["run", &output, "--sym", &symbols])).ok().unwrap();
+    assert!(dump.contains("0x07 (start_value): "));
+    let plain = dispatch(&to_args(&["run", &output])).ok().unwrap();
+    assert!(plain.contains("0x07: ") && !plain.contains("start_value"));
+    assert!(dispatch(&to_args(&["run", &source])).ok().unwrap().contains("0x01 (loop_start_value): "));
+
+    let looping = dir.path("symbols_loop.asm");
+    fs::write(&looping, ":start\njmp $back\n:back\nabs 0d0").unwrap();
+    let error = match dispatch(&to_args(&["run", &looping, "--max-steps", "10"])) {
+        Err(e) => e,
+        Ok(_) => panic!("Looping program stopped. ")
+    };
+    assert_eq!(error.exit_code(), EXIT_ITERATIONS);
+    assert!(error.describe().contains("0x01 (back): "));
+    let bad_symbols = dir.path("bad.sym");
+    fs::write(&bad_symbols, "start_value seven").unwrap();
+    assert_eq!(exit_code(dispatch(&to_args(&["run", &output, "--sym", &bad_symbols]))), EXIT_IMAGE);
+}
+
+#[test]
+fn test_asm_warnings() {
+    let dir = TempDir::new("asm_warnings");
+    let source = dir.path("warnings.asm");
+    let output = dir.path("warnings.bin");
+    fs::write(&source, "sub 0d40\nstp").unwrap();
+    let message = dispatch(&to_args(&["asm", &source, "-o", &output])).ok().unwrap();
+    assert!(message.starts_with("warning: The operand of `sub 0d40`"));
+    assert!(message.ends_with(&format!("Assembled `{}` to `{}`. ", source, output)));
+    let denied = dispatch(&to_args(&["asm", &source, "-o", &output, "--deny-warnings"]));
+    assert_eq!(exit_code(denied), EXIT_LINK);
+}
+
+#[test]
+fn test_asm_objects_link() {
+    let dir = TempDir::new("asm_objects_link");
+    let main = dir.path("main.asm");
+    let library = dir.path("library.asm");
+    let library_object = dir.path("library.obj");
+    let output = dir.path("linked.bin");
+    fs::write(&main, ".import value\nldn $value\nstp").unwrap();
+    fs::write(&library, ".export value\n:value\nabs 0d-7").unwrap();
+    assert_eq!(exit_code(dispatch(&to_args(&["asm", &library, "--object"]))), 0);
+    assert!(fs::read_to_string(&library_object).unwrap().starts_with("babyobj 1\nexport value"));
+    assert_eq!(exit_code(dispatch(&to_args(&["link", &main, &library_object, "-o", &output]))), 0);
+    let store = image::load_store(&output, false).ok().unwrap();
+    assert_eq!(store[0], BabyInstruction::Negate(2).to_number());
+    assert_eq!(store[2], -7);
+    assert_eq!(exit_code(dispatch(&to_args(&["link", &main, "-o", &output]))), EXIT_LINK);
+    assert_eq!(exit_code(dispatch(&to_args(&["link", &main, &main, "-o", &output]))), EXIT_LINK);
+    assert_eq!(exit_code(dispatch(&to_args(&["link"]))), EXIT_USAGE);
+}
+
+#[test]
+fn test_exit_codes() {
+    let dir = TempDir::new("exit_codes");
+    let parse_err = dir.path("parse_err.asm");
+    let link_err = dir.path("link_err.asm");
+    let bad_image = dir.path("bad.bin");
+    let looping = dir.path("looping.asm");
+    fs::write(&parse_err, "foo 0d1").unwrap();
+    fs::write(&link_err, "jmp $nowhere").unwrap();
+    fs::write(&bad_image, [0u8; 3]).unwrap();
+    fs::write(&looping, "jmp 0d1\nabs 0d0").unwrap();
+    assert_eq!(exit_code(dispatch(&to_args(&["asm", &parse_err]))), EXIT_PARSE);
+    assert_eq!(exit_code(dispatch(&to_args(&["asm", &link_err]))), EXIT_LINK);
+    assert_eq!(exit_code(dispatch(&to_args(&["run", &bad_image]))), EXIT_IMAGE);
+    assert_eq!(exit_code(dispatch(&to_args(&["run", &looping, "--max-steps", "10"]))), EXIT_ITERATIONS);
+    assert_eq!(exit_code(dispatch(&to_args(&["dump", &dir.path("missing.bin")]))), EXIT_IO);
+    assert_eq!(exit_code(dispatch(&to_args(&["foo"]))), EXIT_USAGE);
+    assert_eq!(exit_code(dispatch(&[])), EXIT_USAGE);
+}
+
+#[test]
+fn test_assembly_diagnostic() {
+    let dir = TempDir::new("assembly_diagnostic");
+    let source = dir.path("diagnostic.asm");
+    fs::write(&source, "; header\nstp\n\nsub 0xZZ").unwrap();
+    let message = match dispatch(&to_args(&["asm", &source])) {
+        Err(e) => e.describe(),
+        Ok(_) => panic!("Incorrect source assembled. ")
+    };
+    assert!(message.contains(&format!("--> {}:4:5", source)));
+    assert!(message.ends_with("  |     ^^^^"));
+}
+
+#[test]
+fn test_assembly_multiple_errors() {
+    let dir = TempDir::new("assembly_multiple_errors");
+    let source = dir.path("multiple.asm");
+    fs::write(&source, "jmp $nowhere\nfoo\nstp").unwrap();
+    let error = match dispatch(&to_args(&["asm", &source])) {
+        Err(e) => e,
+        Ok(_) => panic!("Incorrect source assembled. ")
+    };
+    assert_eq!(error.exit_code(), EXIT_PARSE);
+    assert!(error.describe().contains(&format!("--> {}:1:5", source)));
+    assert!(error.describe().contains(&format!("--> {}:2:1", source)));
+    assert!(error.describe().ends_with("2 errors were found. "));
+}
@@ -0,0 +1,84 @@
+//! # Compiler Errors
+//!
+//! This module includes all the possible errors that can be thrown whilst
+//! compiling a program, each is a branch of [CompilationError][crate::compiler::errors::CompilationError],
+//! returned alongside the [SourceSpan][crate::assembler::diagnostics::SourceSpan]
+//! of the statement it was found on.
+//!
+
+use crate::core::MEMORY_WORDS;
+use crate::assembler::linker::errors::{LinkingError, LinkerError};
+
+
+/// The number of words a compiled program needs, split by what they are for.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProgramSize {
+    /// Words of instructions.
+    pub code: usize,
+    /// Words holding variables.
+    pub variables: usize,
+    /// Words holding literal values used by expressions.
+    pub constants: usize,
+    /// Words holding the address of a variable to store to, or of a line to jump to.
+    pub pointers: usize,
+    /// Words holding intermediate results.
+    pub temporaries: usize,
+}
+
+impl ProgramSize {
+
+    /// Returns the total number of words.
+    pub fn total(&self) -> usize {
+        self.code + self.variables + self.constants + self.pointers + self.temporaries
+    }
+}
+
+/// Possible errors thrown compiling a program.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CompilationError {
+    /// A line is not any known statement (the line).
+    InvalidStatement(String),
+    /// An expression or condition is malformed (the expression).
+    InvalidExpression(String),
+    /// A variable name is not a valid name, or is a keyword (the name).
+    InvalidName(String),
+    /// A literal does not fit in a word (the literal).
+    LiteralOutOfRange(String),
+    /// A variable is used but not declared (the name).
+    UnknownVariable(String),
+    /// A variable is declared more than once (the name).
+    DuplicateVariable(String),
+    /// A variable is declared inside an `if` or `while` block (the name).
+    NestedDeclaration(String),
+    /// A `}` or `} else {` with no block for it to close.
+    UnexpectedClose,
+    /// A block that is never closed with a `}`.
+    UnclosedBlock,
+    /// The compiled program does not fit in the store (the words needed).
+    ProgramTooLarge(ProgramSize),
+    /// Error linking the compiled program.
+    LinkerError(LinkingError),
+}
+
+impl CompilationError {
+
+    /// Returns a short string describing the error.
+    pub fn describe(&self) -> String {
+        match self {
+            CompilationError::InvalidStatement(s) => format!("The line `{}` is not a statement. ", s),
+            CompilationError::InvalidExpression(s) => format!("The expression `{}` is not valid, expressions can only add and subtract variables and numbers. ", s),
+            CompilationError::InvalidName(s) => format!("The name `{}` is not a valid variable name. ", s),
+            CompilationError::LiteralOutOfRange(s) => format!("The number `{}` does not fit in a word. ", s),
+            CompilationError::UnknownVariable(s) => format!("The variable `{}` is not declared. ", s),
+            CompilationError::DuplicateVariable(s) => format!("The variable `{}` is already declared. ", s),
+            CompilationError::NestedDeclaration(s) => format!("The variable `{}` is declared inside a block, variables can only be declared at the top level. ", s),
+            CompilationError::UnexpectedClose => "The `}` has no block to close. ".to_owned(),
+            CompilationError::UnclosedBlock => "The block is never closed with a `}`. ".to_owned(),
+            CompilationError::ProgramTooLarge(s) => format!(
+                "The program needs {} words but the store only has {MEMORY_WORDS}, {} of instructions, {} of variables, {} of constants, {} of pointers and {} of temporaries. ",
+                s.total(), s.code, s.variables, s.constants, s.pointers, s.temporaries
+            ),
+            CompilationError::LinkerError(l) => format!("There was an error linking the compiled program. {}", l.describe(false)),
+        }
+    }
+}
@@ -0,0 +1,645 @@
+//! # Compiler
+//!
+//! This module compiles a small structured language to Baby asm, giving a
+//! step between high level code and the machine code of the Baby, as even
+//! `a = b + c` takes double negation and a temporary word in asm.
+//!
+//! The main part of this module is [compile][crate::compiler::compile], which
+//! parses a program and generates the [LineType][crate::assembler::parser::LineType]
+//! sequence for it, the same as the [parser][crate::assembler::parser] gives for
+//! asm, so it is linked by the existing [linker][crate::assembler::linker], as done
+//! by [compile_and_link][crate::compiler::compile_and_link]. [compile_to_asm][crate::compiler::compile_to_asm]
+//! gives the generated asm source with each statement alongside its code.
+//!
+//! # Language
+//! Each statement is on its own line, anything after a `#` is a comment and
+//! names are case insensitive.
+//!
+//! | Statement                  | Description                                                        |
+//! |----------------------------|--------------------------------------------------------------------|
+//! | `var x` or `var x = 5`     | Declares a variable, holding 0 or the value given when loaded      |
+//! | `x = a + b - 1`            | Sets a variable to variables and numbers added and subtracted      |
+//! | `if a < b {`               | Runs the block up to `}` if the condition is true                  |
+//! | `} else {`                 | Ends an `if` block, running the block up to `}` if it was false    |
+//! | `while a >= 0 {`           | Runs the block up to `}` for as long as the condition is true      |
+//! | `halt`                     | Stops the program                                                  |
+//!
+//! Conditions compare two expressions with `<`, `>`, `<=` or `>=`. Variables
+//! are declared at the top level before they are used, and the program stops
+//! once it runs off the end.
+//!
+//! # Generated code
+//! Code is placed from address 0, followed by the variables, a temporary for
+//! expressions that add a variable, the numbers used by expressions and then
+//! pointer words. The emulator stores to the address held in the operand word
+//! (see [BabyModel::store][crate::core::BabyModel::store]), and jumps go to the
+//! address held in the operand word, so each variable assigned to and each line
+//! jumped to has a pointer word holding its address. Generated names contain a
+//! `.` so never clash with variable names.
+//!
+//! # Example
+//! ```
+//! use baby_emulator::compiler::compile_and_link;
+//! use baby_emulator::assembler::linker::LinkerData;
+//! use baby_emulator::core::{BabyModel, errors::BabyErrors, instructions::BabyInstruction};
+//!
+//! const PROGRAM: &str =
+//! "
+//! var n = 5
+//! var total
+//! while n > 0 {
+//!     total = total + n   # Add up 5 + 4 + 3 + 2 + 1
+//!     n = n - 1
+//! }
+//! ";
+//!
+//! let LinkerData(program, tags) = compile_and_link(PROGRAM).ok().unwrap();
+//! let model = BabyModel::new_with_program(BabyInstruction::to_numbers(program));
+//! let (model, error) = model.run_loop(1000);
+//! assert!(matches!(error, BabyErrors::Stop(_)));
+//! assert_eq!(model.main_store[tags["total"] as usize], 15);
+//! ```
+//!
+
+use crate::core::{MEMORY_WORDS, WORD};
+use crate::assembler::diagnostics::SourceSpan;
+use crate::assembler::linker::{self, LinkerData};
+use crate::assembler::parser::{LineType, Instruction, Value};
+use errors::{CompilationError, ProgramSize};
+
+
+/// Contains types for handling errors found whilst compiling.
+pub mod errors;
+#[cfg(test)]
+mod tests;
+
+
+/// Words that cannot be used as variable names.
+pub const KEYWORDS: [&str; 5] = ["var", "if", "else", "while", "halt"];
+
+/// A statement along with where in the source it was found, each block
+/// of a program being a list of these.
+pub type Block = Vec<(SourceSpan, Statement)>;
+
+/// Errors found compiling a program, each with where in the source it was found.
+pub type CompileErrors = Vec<(SourceSpan, CompilationError)>;
+
+/// Generated lines, each with the span of the statement it was generated from.
+type Lines = Vec<(SourceSpan, LineType)>;
+
+/// A variable or number in an expression.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operand {
+    /// A variable (variable name).
+    Variable(String),
+    /// A number.
+    Literal(WORD),
+}
+
+/// An operand of an expression along with whether it is added or subtracted,
+/// an expression being a list of these.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Term {
+    /// True if the operand is subtracted.
+    pub subtract: bool,
+    /// The operand.
+    pub operand: Operand,
+}
+
+/// How the two sides of a condition are compared.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Comparison {
+    /// `<`
+    Less,
+    /// `>`
+    Greater,
+    /// `<=`
+    LessEqual,
+    /// `>=`
+    GreaterEqual,
+}
+
+/// A condition of an `if` or `while` statement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Condition {
+    /// The expression on the left of the comparison.
+    pub left: Vec<Term>,
+    /// How the two sides are compared.
+    pub comparison: Comparison,
+    /// The expression on the right of the comparison.
+    pub right: Vec<Term>,
+}
+
+impl Condition {
+
+    /// Returns an expression that is negative exactly when the condition
+    /// is true, as tested by `cmp`.
+    pub fn difference(&self) -> Vec<Term> {
+        let negated = |terms: &[Term]| terms.iter()
+            .map(|t| Term { subtract: !t.subtract, operand: t.operand.clone() })
+            .collect::<Vec<Term>>();
+        let one = Term { subtract: true, operand: Operand::Literal(1) };
+        match self.comparison {
+            Comparison::Less => [self.left.clone(), negated(&self.right)].concat(),
+            Comparison::Greater => [self.right.clone(), negated(&self.left)].concat(),
+            Comparison::LessEqual => [self.left.clone(), negated(&self.right), vec![one]].concat(),
+            Comparison::GreaterEqual => [self.right.clone(), negated(&self.left), vec![one]].concat(),
+        }
+    }
+}
+
+/// Represents all the possible statements.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Statement {
+    /// Declares a variable (variable name, initial value).
+    Var(String, WORD),
+    /// Sets a variable to the value of an expression (variable name, expression).
+    Assign(String, Vec<Term>),
+    /// Runs the first block if the condition is true, otherwise the second.
+    If(Condition, Block, Block),
+    /// Runs the block for as long as the condition is true.
+    While(Condition, Block),
+    /// Stops the program.
+    Halt,
+}
+
+/// A block that is still open whilst parsing.
+struct OpenBlock {
+    span: SourceSpan,
+    kind: OpenKind,
+    body: Block,
+}
+
+/// The statement an open block belongs to.
+enum OpenKind {
+    If(Condition, Option<Block>),
+    While(Condition),
+}
+
+/// Parses the source of a program into its statements.
+///
+/// Returns every error found parsing, rather than just the first.
+///
+/// # Parameters
+/// * `source` - The program source.
+///
+/// # Example
+/// ```
+/// use baby_emulator::compiler::{parse_program, Statement};
+///
+/// let program = parse_program("var x = 3\nhalt").ok().unwrap();
+/// assert_eq!(program[0].1, Statement::Var("x".to_owned(), 3));
+/// assert_eq!(program[1].0.line, 2);
+/// ```
+///
+pub fn parse_program(source: &str) -> Result<Block, CompileErrors> {
+    let mut errors = vec![];
+    let mut root = vec![];
+    let mut open: Vec<OpenBlock> = vec![];
+    for (i, line) in source.lines().enumerate() {
+        let code = line.split('#').next().unwrap_or("").trim().to_lowercase();
+        if code.is_empty() { continue; }
+        let span = SourceSpan::new(i + 1, line).trim();
+        let words = code.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+        let statement = match words.as_str() {
+            "}" => match open.pop() {
+                Some(block) => Ok(Some(close_block(block))),
+                None => Err(CompilationError::UnexpectedClose),
+            },
+            "} else {" => {
+                match open.last_mut() {
+                    Some(OpenBlock { kind: OpenKind::If(_, then @ None), body, .. }) =>
+                        *then = Some(std::mem::take(body)),
+                    _ => errors.push((span, CompilationError::UnexpectedClose)),
+                }
+                continue;
+            },
+            "halt" => Ok(Some((span.clone(), Statement::Halt))),
+            _ => match opened_block(&words) {
+                Some(Ok(kind)) => { open.push(OpenBlock { span, kind, body: vec![] }); continue; },
+                Some(Err(e)) => Err(e),
+                None => parse_statement(&words, !open.is_empty()).map(|s| Some((span.clone(), s))),
+            },
+        };
+        match statement {
+            Ok(Some(s)) => open.last_mut().map_or(&mut root, |b| &mut b.body).push(s),
+            Ok(None) => (),
+            Err(e) => errors.push((span, e)),
+        }
+    }
+    errors.extend(open.into_iter().map(|b| (b.span, CompilationError::UnclosedBlock)));
+    if errors.is_empty() { Ok(root) } else { Err(errors) }
+}
+
+/// Tries to parse a line opening a block, returning [None] if it is not `if` or `while`.
+fn opened_block(line: &str) -> Option<Result<OpenKind, CompilationError>> {
+    let (keyword, rest) = line.split_once(' ')?;
+    let condition = rest.strip_suffix('{').map(|c| c.trim());
+    let kind: fn(Condition) -> OpenKind = match keyword {
+        "if" => |c| OpenKind::If(c, None),
+        "while" => OpenKind::While,
+        _ => return None,
+    };
+    Some(match condition {
+        Some(c) => parse_condition(c).map(kind),
+        None => Err(CompilationError::InvalidStatement(line.to_owned())),
+    })
+}
+
+/// Returns the statement for a block that has been closed.
+fn close_block(block: OpenBlock) -> (SourceSpan, Statement) {
+    let statement = match block.kind {
+        OpenKind::If(c, Some(then)) => Statement::If(c, then, block.body),
+        OpenKind::If(c, None) => Statement::If(c, block.body, vec![]),
+        OpenKind::While(c) => Statement::While(c, block.body),
+    };
+    (block.span, statement)
+}
+
+/// Parses a variable declaration or an assignment.
+fn parse_statement(line: &str, nested: bool) -> Result<Statement, CompilationError> {
+    if let Some(rest) = line.strip_prefix("var ") {
+        let (name, value) = match rest.split_once('=') {
+            Some((n, v)) => (n.trim(), constant_value(v.trim())?),
+            None => (rest.trim(), 0),
+        };
+        let name = parse_name(name)?;
+        if nested { return Err(CompilationError::NestedDeclaration(name)); }
+        return Ok(Statement::Var(name, value));
+    }
+    match line.split_once('=') {
+        Some((name, expression)) => Ok(Statement::Assign(parse_name(name.trim())?, parse_expression(expression.trim())?)),
+        None => Err(CompilationError::InvalidStatement(line.to_owned())),
+    }
+}
+
+/// Parses an expression that can only contain numbers, folding it to a single value.
+fn constant_value(expression: &str) -> Result<WORD, CompilationError> {
+    parse_expression(expression)?.iter().try_fold(0 as WORD, |acc, t| match (&t.operand, t.subtract) {
+        (Operand::Literal(v), false) => Ok(acc.wrapping_add(*v)),
+        (Operand::Literal(v), true) => Ok(acc.wrapping_sub(*v)),
+        (Operand::Variable(_), _) => Err(CompilationError::InvalidExpression(expression.to_owned())),
+    })
+}
+
+/// Parses a condition, two expressions either side of a comparison.
+fn parse_condition(condition: &str) -> Result<Condition, CompilationError> {
+    let comparisons = [
+        ("<=", Comparison::LessEqual), (">=", Comparison::GreaterEqual),
+        ("<", Comparison::Less), (">", Comparison::Greater),
+    ];
+    let (symbol, comparison) = comparisons.iter()
+        .find(|(s, _)| condition.contains(s))
+        .ok_or_else(|| CompilationError::InvalidExpression(condition.to_owned()))?;
+    let (left, right) = condition.split_once(symbol).unwrap_or_default();
+    Ok(Condition { left: parse_expression(left.trim())?, comparison: *comparison, right: parse_expression(right.trim())? })
+}
+
+/// Parses an expression, operands added or subtracted, the first optionally
+/// starting with a `-`.
+///
+/// # Example
+/// ```
+/// use baby_emulator::compiler::{parse_expression, Term, Operand};
+///
+/// assert_eq!(parse_expression("-x + 2"), Ok(vec![
+///     Term { subtract: true, operand: Operand::Variable("x".to_owned()) },
+///     Term { subtract: false, operand: Operand::Literal(2) },
+/// ]));
+/// assert!(parse_expression("x * 2").is_err());
+/// ```
+///
+pub fn parse_expression(expression: &str) -> Result<Vec<Term>, CompilationError> {
+    let invalid = || CompilationError::InvalidExpression(expression.to_owned());
+    let mut terms = vec![];
+    let mut rest = expression.trim_start();
+    let mut subtract = match rest.strip_prefix('-') {
+        Some(r) => { rest = r; true },
+        None => false,
+    };
+    loop {
+        rest = rest.trim_start();
+        let end = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+        if end == 0 { return Err(invalid()); }
+        let word = &rest[..end];
+        let operand = if word.starts_with(|c: char| c.is_ascii_digit()) {
+            match word.parse::<WORD>() {
+                Ok(v) => Operand::Literal(v),
+                Err(_) if word.chars().all(|c| c.is_ascii_digit()) => return Err(CompilationError::LiteralOutOfRange(word.to_owned())),
+                Err(_) => return Err(invalid()),
+            }
+        } else {
+            Operand::Variable(parse_name(word)?)
+        };
+        terms.push(Term { subtract, operand });
+        rest = rest[end..].trim_start();
+        subtract = match rest.chars().next() {
+            None => return Ok(terms),
+            Some('+') => false,
+            Some('-') => true,
+            Some(_) => return Err(invalid()),
+        };
+        rest = &rest[1..];
+    }
+}
+
+/// Checks a variable name is only letters, digits and underscores, not
+/// starting with a digit, and is not a keyword.
+fn parse_name(name: &str) -> Result<String, CompilationError> {
+    let valid = name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&name);
+    if valid { Ok(name.to_owned()) } else { Err(CompilationError::InvalidName(name.to_owned())) }
+}
+
+/// Compiles a program to the parsed lines of the asm for it, each with the
+/// span of the statement it was generated from.
+///
+/// Returns every error found parsing the program or using variables, or
+/// [CompilationError::ProgramTooLarge] pointing at the statement that
+/// does not fit if the program needs more than [MEMORY_WORDS] words.
+///
+/// # Parameters
+/// * `source` - The program source.
+///
+/// # Example
+/// ```
+/// use baby_emulator::compiler::{compile, errors::CompilationError};
+/// use baby_emulator::assembler::parser::LineType;
+///
+/// let lines = compile("var x\nx = 0 - x").ok().unwrap();
+/// assert!(matches!(lines.last(), Some((_, LineType::Absolute(_)))));
+/// let errors = compile("x = 1").err().unwrap();
+/// assert_eq!(errors[0].1, CompilationError::UnknownVariable("x".to_owned()));
+/// ```
+///
+pub fn compile(source: &str) -> Result<Vec<(SourceSpan, LineType)>, CompileErrors> {
+    generate(source).map(|(code, data)| [code, data].concat())
+}
+
+/// Compiles a program as per [compile], then links it with the
+/// [linker][crate::assembler::linker], ready to run.
+///
+/// # Parameters
+/// * `source` - The program source.
+///
+pub fn compile_and_link(source: &str) -> Result<LinkerData, CompileErrors> {
+    linker::link_source_lines_all(compile(source)?).map_err(|e| e.into_iter()
+        .map(|(s, e)| (s, CompilationError::LinkerError(e)))
+        .collect())
+}
+
+/// Compiles a program as per [compile], returning the asm source, each
+/// statement commented alongside the first line of its code.
+///
+/// # Parameters
+/// * `source` - The program source.
+///
+/// # Example
+/// ```
+/// use baby_emulator::compiler::compile_to_asm;
+///
+/// let asm = compile_to_asm("var x = 2\nx = 0 - x").ok().unwrap();
+/// assert!(asm.starts_with("ldn $const.0          ; x = 0 - x\nsub $x\nsto $ptr.x\n"));
+/// ```
+///
+pub fn compile_to_asm(source: &str) -> Result<String, CompileErrors> {
+    let (code, data) = generate(source)?;
+    let mut res = String::new();
+    let mut last_line = 0;
+    for (span, line) in &code {
+        let asm = line.to_asm_string(false);
+        if span.line == last_line || matches!(line, LineType::Tag(_)) {
+            res.push_str(&format!("{}\n", asm));
+        } else {
+            res.push_str(&format!("{:<21} ; {}\n", asm, span.text.trim()));
+            last_line = span.line;
+        }
+    }
+    for (_, line) in &data {
+        res.push_str(&format!("{}\n", line.to_asm_string(false)));
+    }
+    Ok(res)
+}
+
+/// Parses, checks and generates the code and data lines of a program,
+/// checking they fit in memory.
+fn generate(source: &str) -> Result<(Lines, Lines), CompileErrors> {
+    let program = parse_program(source)?;
+    let mut errors = vec![];
+    check_variables(&program, &mut vec![], &mut errors);
+    if !errors.is_empty() { return Err(errors); }
+
+    let mut generator = Generator::default();
+    generator.block(&program);
+    if !matches!(generator.code.last(), Some((_, LineType::Instruction(Instruction::Stop)))) {
+        let span = program.last().map_or_else(|| SourceSpan::new(1, ""), |(s, _)| s.clone());
+        generator.code.push((span, LineType::Instruction(Instruction::Stop)));
+    }
+    let (code, data, size) = generator.finish();
+    if size.total() <= MEMORY_WORDS { return Ok((code, data)); }
+
+    let first_beyond = code.iter().chain(data.iter())
+        .filter(|(_, l)| l.is_data())
+        .nth(MEMORY_WORDS)
+        .map(|(s, _)| s.clone())
+        .unwrap_or_else(|| SourceSpan::new(1, ""));
+    Err(vec![(first_beyond, CompilationError::ProgramTooLarge(size))])
+}
+
+/// Checks every variable is declared once, before it is used.
+fn check_variables(block: &Block, declared: &mut Vec<String>, errors: &mut CompileErrors) {
+    let check_terms = |terms: &[Term], declared: &Vec<String>, errors: &mut CompileErrors, span: &SourceSpan| {
+        for t in terms {
+            if let Operand::Variable(v) = &t.operand {
+                if !declared.contains(v) { errors.push((span.narrow(v), CompilationError::UnknownVariable(v.clone()))); }
+            }
+        }
+    };
+    for (span, statement) in block {
+        match statement {
+            Statement::Var(n, _) if declared.contains(n) => errors.push((span.narrow(n), CompilationError::DuplicateVariable(n.clone()))),
+            Statement::Var(n, _) => declared.push(n.clone()),
+            Statement::Assign(n, e) => {
+                if !declared.contains(n) { errors.push((span.narrow(n), CompilationError::UnknownVariable(n.clone()))); }
+                check_terms(e, declared, errors, span);
+            },
+            Statement::If(c, then, otherwise) => {
+                check_terms(&[c.left.clone(), c.right.clone()].concat(), declared, errors, span);
+                check_variables(then, declared, errors);
+                check_variables(otherwise, declared, errors);
+            },
+            Statement::While(c, body) => {
+                check_terms(&[c.left.clone(), c.right.clone()].concat(), declared, errors, span);
+                check_variables(body, declared, errors);
+            },
+            Statement::Halt => (),
+        }
+    }
+}
+
+/// Generates the code for statements, keeping track of the data words the
+/// code needs, each along with the span of the statement that first needed it.
+#[derive(Default)]
+struct Generator {
+    code: Lines,
+    variables: Vec<(SourceSpan, String, WORD)>,
+    temporary: Option<SourceSpan>,
+    constants: Vec<(SourceSpan, WORD)>,
+    pointers: Vec<(SourceSpan, String)>,
+    labels: usize,
+}
+
+/// The name of the temporary word.
+const TEMPORARY: &str = "temp.0";
+
+impl Generator {
+
+    /// Generates the code for each statement in a block.
+    fn block(&mut self, block: &Block) {
+        for (span, statement) in block {
+            self.statement(span, statement);
+        }
+    }
+
+    /// Generates the code for a statement.
+    fn statement(&mut self, span: &SourceSpan, statement: &Statement) {
+        match statement {
+            Statement::Var(n, v) => self.variables.push((span.clone(), n.clone(), *v)),
+            Statement::Assign(n, e) => {
+                self.load(span, e);
+                let pointer = self.pointer(span, n);
+                self.instruction(span, Instruction::Store(pointer));
+            },
+            Statement::If(c, then, otherwise) => {
+                let label = self.label("if");
+                let (other, end) = (format!("{}.else", label), format!("{}.end", label));
+                self.branch_unless(span, c, if otherwise.is_empty() { &end } else { &other });
+                self.block(then);
+                if !otherwise.is_empty() {
+                    self.jump(span, &end);
+                    self.code.push((span.clone(), LineType::Tag(other)));
+                    self.block(otherwise);
+                }
+                self.code.push((span.clone(), LineType::Tag(end)));
+            },
+            Statement::While(c, body) => {
+                let label = self.label("while");
+                let end = format!("{}.end", label);
+                self.code.push((span.clone(), LineType::Tag(label.clone())));
+                self.branch_unless(span, c, &end);
+                self.block(body);
+                self.jump(span, &label);
+                self.code.push((span.clone(), LineType::Tag(end)));
+            },
+            Statement::Halt => self.instruction(span, Instruction::Stop),
+        }
+    }
+
+    /// Returns a new label name for a statement.
+    fn label(&mut self, statement: &str) -> String {
+        self.labels += 1;
+        format!("{}.{}", statement, self.labels - 1)
+    }
+
+    /// Adds an instruction to the code.
+    fn instruction(&mut self, span: &SourceSpan, instruction: Instruction) {
+        self.code.push((span.clone(), LineType::Instruction(instruction)));
+    }
+
+    /// Returns a reference to a word holding a number.
+    fn constant(&mut self, span: &SourceSpan, value: WORD) -> Value {
+        if !self.constants.iter().any(|(_, v)| *v == value) {
+            self.constants.push((span.clone(), value));
+        }
+        Value::Tag(format!("const.{}", value).replace('-', "n"))
+    }
+
+    /// Returns a reference to a word holding the address of a tag.
+    fn pointer(&mut self, span: &SourceSpan, tag: &str) -> Value {
+        if !self.pointers.iter().any(|(_, t)| t == tag) {
+            self.pointers.push((span.clone(), tag.to_owned()));
+        }
+        Value::Tag(format!("ptr.{}", tag))
+    }
+
+    /// Adds a jump to a label.
+    fn jump(&mut self, span: &SourceSpan, label: &str) {
+        let pointer = self.pointer(span, label);
+        self.instruction(span, Instruction::Jump(pointer));
+    }
+
+    /// Adds code jumping to a label if a condition is false, carrying on
+    /// to the next line if it is true.
+    fn branch_unless(&mut self, span: &SourceSpan, condition: &Condition, label: &str) {
+        self.load(span, &condition.difference());
+        self.instruction(span, Instruction::Test);
+        self.jump(span, label);
+    }
+
+    /// Adds code loading the value of an expression into the accumulator.
+    ///
+    /// Numbers are added up into one constant, variables subtracted are
+    /// subtracted directly, but variables added need negating, so are
+    /// subtracted from a negated load then stored to the temporary and
+    /// loaded again.
+    fn load(&mut self, span: &SourceSpan, expression: &[Term]) {
+        let literal = expression.iter().fold(0 as WORD, |acc, t| match (&t.operand, t.subtract) {
+            (Operand::Literal(v), false) => acc.wrapping_add(*v),
+            (Operand::Literal(v), true) => acc.wrapping_sub(*v),
+            _ => acc,
+        });
+        let variables = |subtract: bool| expression.iter()
+            .filter_map(|t| match &t.operand {
+                Operand::Variable(v) if t.subtract == subtract => Some(Value::Tag(v.clone())),
+                _ => None,
+            })
+            .collect::<Vec<Value>>();
+        let (added, subtracted) = (variables(false), variables(true));
+
+        match added.split_first() {
+            None => {
+                let negated = self.constant(span, literal.wrapping_neg());
+                self.instruction(span, Instruction::Negate(negated));
+            },
+            Some((first, rest)) => {
+                self.instruction(span, Instruction::Negate(first.clone()));
+                for v in rest { self.instruction(span, Instruction::Subtract(v.clone())); }
+                if literal != 0 {
+                    let constant = self.constant(span, literal);
+                    self.instruction(span, Instruction::Subtract(constant));
+                }
+                let pointer = self.pointer(span, TEMPORARY);
+                self.instruction(span, Instruction::Store(pointer));
+                self.instruction(span, Instruction::Negate(Value::Tag(TEMPORARY.to_owned())));
+                self.temporary.get_or_insert_with(|| span.clone());
+            },
+        }
+        for v in subtracted { self.instruction(span, Instruction::Subtract(v)); }
+    }
+
+    /// Returns the code and the data lines, along with the size of the program.
+    fn finish(self) -> (Lines, Lines, ProgramSize) {
+        let mut data = vec![];
+        let mut word = |span: &SourceSpan, tag: String, value: Value| {
+            data.push((span.clone(), LineType::Tag(tag)));
+            data.push((span.clone(), LineType::Absolute(value)));
+        };
+        for (span, name, value) in &self.variables { word(span, name.clone(), Value::Value(*value)); }
+        if let Some(span) = &self.temporary { word(span, TEMPORARY.to_owned(), Value::Value(0)); }
+        for (span, value) in &self.constants {
+            word(span, format!("const.{}", value).replace('-', "n"), Value::Value(*value));
+        }
+        for (span, tag) in &self.pointers { word(span, format!("ptr.{}", tag), Value::Tag(tag.clone())); }
+
+        let size = ProgramSize {
+            code: self.code.iter().filter(|(_, l)| l.is_data()).count(),
+            variables: self.variables.len(),
+            constants: self.constants.len(),
+            pointers: self.pointers.len(),
+            temporaries: usize::from(self.temporary.is_some()),
+        };
+        (self.code, data, size)
+    }
+}
@@ -0,0 +1,130 @@
+use super::*;
+use crate::core::{BabyModel, errors::BabyErrors, instructions::BabyInstruction};
+use crate::assembler::assemble;
+
+
+/// Compiles, links and runs a program, returning the final value of each
+/// variable asked for.
+fn run(source: &str, variables: &[&str]) -> Vec<WORD> {
+    let LinkerData(program, tags) = match compile_and_link(source) {
+        Ok(v) => v,
+        Err(e) => panic!("{}", e[0].1.describe()),
+    };
+    let model = BabyModel::new_with_program(BabyInstruction::to_numbers(program));
+    let (model, error) = model.run_loop(10_000);
+    assert!(matches!(error, BabyErrors::Stop(_)));
+    variables.iter().map(|v| model.main_store[tags[*v] as usize]).collect()
+}
+
+fn errors(source: &str) -> Vec<(usize, CompilationError)> {
+    compile(source).err().unwrap().into_iter().map(|(s, e)| (s.line, e)).collect()
+}
+
+#[test]
+fn test_parse() {
+    let program = parse_program("var A = 3 - 5 # comment\n\nIF a >= b + 1 {\n  halt\n} else {\n a = -a\n}").ok().unwrap();
+    assert_eq!(program.len(), 2);
+    assert_eq!(program[0].1, Statement::Var("a".to_owned(), -2));
+    let Statement::If(condition, then, otherwise) = &program[1].1 else { panic!() };
+    assert_eq!(program[1].0.line, 3);
+    assert_eq!(condition.comparison, Comparison::GreaterEqual);
+    assert_eq!(condition.right.len(), 2);
+    assert_eq!(then[0].1, Statement::Halt);
+    assert_eq!(otherwise[0].0.line, 6);
+    assert_eq!(parse_expression("a+1-b"), Ok(vec![
+        Term { subtract: false, operand: Operand::Variable("a".to_owned()) },
+        Term { subtract: false, operand: Operand::Literal(1) },
+        Term { subtract: true, operand: Operand::Variable("b".to_owned()) },
+    ]));
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(errors("var x\nx = x * 2\nfoo\n}\nwhile x {\nwhile x < 0 {\nvar y\n"), vec![
+        (2, CompilationError::InvalidExpression("x * 2".to_owned())),
+        (3, CompilationError::InvalidStatement("foo".to_owned())),
+        (4, CompilationError::UnexpectedClose),
+        (5, CompilationError::InvalidExpression("x".to_owned())),
+        (7, CompilationError::NestedDeclaration("y".to_owned())),
+        (6, CompilationError::UnclosedBlock),
+    ]);
+    assert_eq!(errors("var while\nvar 2x\nvar y = 99999999999"), vec![
+        (1, CompilationError::InvalidName("while".to_owned())),
+        (2, CompilationError::InvalidName("2x".to_owned())),
+        (3, CompilationError::LiteralOutOfRange("99999999999".to_owned())),
+    ]);
+    assert_eq!(errors("if 1 < 2 {\n} else {\n} else {"), vec![
+        (3, CompilationError::UnexpectedClose),
+        (1, CompilationError::UnclosedBlock),
+    ]);
+}
+
+#[test]
+fn test_variables() {
+    assert_eq!(errors("var x\nx = y\nvar x\nwhile z < 0 {\n}"), vec![
+        (2, CompilationError::UnknownVariable("y".to_owned())),
+        (3, CompilationError::DuplicateVariable("x".to_owned())),
+        (4, CompilationError::UnknownVariable("z".to_owned())),
+    ]);
+    let span = &compile("var x\nx = x + y").err().unwrap()[0].0;
+    assert_eq!((span.start, span.end), (8, 9));
+}
+
+#[test]
+fn test_expressions() {
+    let source = "var a = 7\nvar b = -3\nvar c\nvar d\nc = a + b\nd = b - a - 10";
+    assert_eq!(run(source, &["a", "b", "c", "d"]), vec![7, -3, 4, -20]);
+    let source = "var a = 7\nvar b = -3\nvar e\nvar f\ne = -a\nf = 5 + a + a - b";
+    assert_eq!(run(source, &["e", "f"]), vec![-7, 22]);
+}
+
+#[test]
+fn test_conditions() {
+    let source = |comparison: &str, left: WORD, right: WORD| format!(
+        "var l = {}\nvar r = {}\nvar res\nif l {} r {{\n  res = 1\n}} else {{\n  res = 2\n}}", left, right, comparison
+    );
+    let cases = [("<", [1, 2, 2]), (">", [2, 2, 1]), ("<=", [1, 1, 2]), (">=", [2, 1, 1])];
+    for (comparison, expected) in cases {
+        let results: Vec<WORD> = [(1, 2), (2, 2), (3, 2)].iter()
+            .map(|(l, r)| run(&source(comparison, *l, *r), &["res"])[0])
+            .collect();
+        assert_eq!(results, expected, "{}", comparison);
+    }
+}
+
+#[test]
+fn test_loops() {
+    let source =
+"var a = 6
+var b = 7
+var product
+while a > 0 {      # Multiply by adding
+    product = product + b
+    a = a - 1
+}";
+    assert_eq!(run(source, &["product", "a"]), vec![42, 0]);
+    assert_eq!(run("var x = 3\nhalt\nx = 4", &["x"]), vec![3]);
+    assert_eq!(run("", &[]), vec![]);
+}
+
+#[test]
+fn test_asm() {
+    let asm = compile_to_asm("var x = 2\nvar y\ny = x + 1\nwhile y > 0 {\n y = y - x\n}").ok().unwrap();
+    assert!(asm.contains("ldn $x                ; y = x + 1\nsub $const.1\nsto $ptr.temp.0\nldn $temp.0\nsto $ptr.y\n"));
+    assert!(asm.contains(":while.0\nldn $const.0          ; while y > 0 {\nsub $y\ncmp\njmp $ptr.while.0.end\n"));
+    assert!(asm.contains(":ptr.while.0.end\nabs $while.0.end\n"));
+    let LinkerData(program, _) = assemble(&asm, false).ok().unwrap();
+    assert_eq!(program, compile_and_link("var x = 2\nvar y\ny = x + 1\nwhile y > 0 {\n y = y - x\n}").ok().unwrap().0);
+}
+
+#[test]
+fn test_too_large() {
+    let mut source = "var x\n".to_owned();
+    source.push_str(&"x = x - 1\n".repeat(8));
+    let errors = compile(&source).err().unwrap();
+    let CompilationError::ProgramTooLarge(size) = &errors[0].1 else { panic!() };
+    assert_eq!(size, &ProgramSize { code: 41, variables: 1, constants: 1, pointers: 2, temporaries: 1 });
+    assert_eq!(errors[0].0.line, 8);
+    assert!(errors[0].1.describe().contains("needs 46 words"));
+    assert!(compile(&format!("var x\n{}", "x = x - 1\n".repeat(5))).is_ok());
+}
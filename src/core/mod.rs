@@ -103,6 +103,7 @@
 //! ```
 //! 
 
+use std::collections::HashMap;
 use std::ops::Neg;
 use errors::{Stop, BabyErrors, IterationsExceeded};
 use instructions::BabyInstruction;
@@ -511,23 +512,73 @@ impl BabyModel {
     /// }
     /// ```
     pub fn core_dump(&self) -> String {
+        self.core_dump_with_symbols(None)
+    }
+
+    /// Generates a string representation of current state of the model,
+    /// labelling addresses with symbol names. 
+    /// 
+    /// Same as [BabyModel::core_dump] except when a symbol table is given,
+    /// where the instruction address, the operand address of the instruction
+    /// register and each main store line are followed by the names of any
+    /// symbols with that value, for example `0x05 (counter)`. 
+    /// 
+    /// # Parameters 
+    /// * `symbols` - The symbol names and values, such as the tags returned by the assembler. 
+    /// 
+    /// # Example 
+    /// ```
+    /// use std::collections::HashMap;
+    /// use baby_emulator::core::BabyModel;
+    /// 
+    /// let model = BabyModel::new_example_program();
+    /// let symbols = HashMap::from([("counter".to_owned(), 5)]);
+    /// assert!(model.core_dump_with_symbols(Some(&symbols)).contains("0x05 (counter): "));
+    /// ```
+    pub fn core_dump_with_symbols(&self, symbols: Option<&HashMap<String, WORD>>) -> String {
         let instr_desc = BabyInstruction::from_number(self.instruction).get_instr_description();
         let operand_addr = BabyInstruction::from_number(self.instruction).get_operand();
         let operand = self.main_store[operand_addr];
+        let instr_desc = match symbols {
+            Some(s) => format!("{} {}", instr_desc, address_label(operand_addr, 4, s)),
+            None => instr_desc,
+        };
         let mut res = format!("Accumulator: {:#010x}; Instruction Register: {:#06x} ({} - {});\n", 
             self.accumulator, self.instruction, instr_desc, operand
         );
-        res += &format!("Instruction Address: {:#06x}; Main Store: \n", self.instruction_address);
+        let instr_addr = match symbols {
+            Some(s) => address_label(self.instruction_address as usize, 6, s),
+            None => format!("{:#06x}", self.instruction_address),
+        };
+        res += &format!("Instruction Address: {}; Main Store: \n", instr_addr);
         
         for i in 0..(MEMORY_WORDS / 4) {
             let offset = i * 4;
             for i2 in 0..4 {
                 let addr = i2 + offset;
-                res += &format!("{:#04x}: {:#010x}; ", addr, self.main_store[addr]);
+                let label = match symbols {
+                    Some(s) => address_label(addr, 4, s),
+                    None => format!("{:#04x}", addr),
+                };
+                res += &format!("{}: {:#010x}; ", label, self.main_store[addr]);
             }
             res += if i == (MEMORY_WORDS / 4) - 1 { "" }
                 else { "\n" };
         }
         return res;
     }
+}
+
+/// Formats an address in hex followed by the sorted names of any symbols 
+/// with that value in brackets, such as `0x05 (counter, total)`. 
+fn address_label(addr: usize, width: usize, symbols: &HashMap<String, WORD>) -> String {
+    let mut names: Vec<&str> = symbols.iter()
+        .filter(|(_, v)| usize::try_from(**v).is_ok_and(|v| v == addr))
+        .map(|(n, _)| n.as_str())
+        .collect();
+    names.sort();
+    match names.is_empty() {
+        true => format!("{:#0width$x}", addr),
+        false => format!("{:#0width$x} ({})", addr, names.join(", ")),
+    }
 }
\ No newline at end of file
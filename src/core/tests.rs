@@ -281,3 +281,19 @@ fn test_test_positive_dispatch() {
     assert_eq!(new_model.instruction, 0);
     assert_eq!(new_model.instruction_address, 0);
 }
+
+#[test]
+fn test_core_dump_with_symbols() {
+    let model = BabyModel::new_example_program();
+    assert_eq!(model.core_dump_with_symbols(None), model.core_dump());
+    let symbols = HashMap::from([
+        ("start".to_owned(), 0), ("total".to_owned(), 5), ("counter".to_owned(), 5), ("big".to_owned(), 40),
+    ]);
+    let dump = model.core_dump_with_symbols(Some(&symbols));
+    assert!(dump.contains("Instruction Address: 0x0000 (start); "));
+    assert!(dump.contains("0x05 (counter, total): "));
+    assert!(dump.contains("0x06: "));
+    assert!(!dump.contains("big"));
+    let plain = model.core_dump_with_symbols(Some(&HashMap::new()));
+    assert_eq!(plain.lines().skip(1).collect::<Vec<_>>(), model.core_dump().lines().skip(1).collect::<Vec<_>>());
+}
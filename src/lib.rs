@@ -150,3 +150,7 @@
 pub mod core;
 /// Contains types and functionality for assembling Baby asm. 
 pub mod assembler;
+/// Contains a compiler from a small structured language to Baby asm. 
+pub mod compiler;
+/// Contains static analyses of Baby programs. 
+pub mod analysis;